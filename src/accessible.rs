@@ -0,0 +1,209 @@
+//! `--accessible`: a line-oriented mode for terminal screen readers. There
+//! is no full-screen redraw, box drawing or abbreviated unit; each report is
+//! a few sentences in a fixed order ("CPU 43 percent."), printed when asked
+//! for, so nothing is read out while the user isn't listening.
+//!
+//! Commands are read a line at a time: Enter repeats the summary, `p` lists
+//! the busiest processes, `d` the disks, `h` the commands and `q` quits.
+
+use crate::sys::{Collector, SysCache};
+use std::io::{self, BufRead, Write};
+use std::time::Duration;
+
+/// Processes listed by `p`.
+const TOP_PROCS: usize = 10;
+
+const HELP: &str = "Commands: Enter for the summary, p for processes, d for disks, \
+                    h for help, q to quit.";
+
+/// `bytes` with the unit spelled out, e.g. "1.5 gigabytes".
+pub fn spoken_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["bytes", "kilobytes", "megabytes", "gigabytes", "terabytes"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} bytes", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// A duration in words, e.g. "2 days, 3 hours, 5 minutes", to the minute
+/// (to the second below one).
+pub fn spoken_duration(secs: u64) -> String {
+    if secs < 60 {
+        return plural(secs, "second");
+    }
+    let parts: Vec<String> = [
+        (secs / 86_400, "day"),
+        (secs / 3600 % 24, "hour"),
+        (secs / 60 % 60, "minute"),
+    ]
+    .into_iter()
+    .filter(|&(n, _)| n > 0)
+    .map(|(n, unit)| plural(n, unit))
+    .collect();
+    parts.join(", ")
+}
+
+fn plural(n: u64, unit: &str) -> String {
+    if n == 1 {
+        format!("1 {}", unit)
+    } else {
+        format!("{} {}s", n, unit)
+    }
+}
+
+fn percent(part: u64, total: u64) -> f64 {
+    if total > 0 {
+        part as f64 / total as f64 * 100.0
+    } else {
+        0.0
+    }
+}
+
+/// The machine in a few labelled sentences, always in the same order;
+/// metrics the host doesn't report are left out.
+pub fn summary(sys: &SysCache) -> Vec<String> {
+    let mut lines = Vec::new();
+    if !sys.host.is_empty() {
+        lines.push(format!("Host {}.", sys.host));
+    }
+    lines.push(format!("Up {}.", spoken_duration(sys.uptime)));
+    lines.push(format!("CPU {:.0} percent.", sys.cpu_global));
+    if sys.cpu_temp > 0.0 {
+        lines.push(format!(
+            "CPU temperature {:.0} degrees Celsius.",
+            sys.cpu_temp
+        ));
+    }
+    if sys.total_mem > 0 {
+        lines.push(format!(
+            "Memory {:.0} percent, {} of {} used.",
+            percent(sys.used_mem, sys.total_mem),
+            spoken_bytes(sys.used_mem),
+            spoken_bytes(sys.total_mem)
+        ));
+    }
+    lines.push(format!(
+        "Network receiving {} per second, sending {} per second.",
+        spoken_bytes(sys.rx_rate),
+        spoken_bytes(sys.tx_rate)
+    ));
+    if let Some(bat) = &sys.battery {
+        let state = if bat.charging {
+            "charging"
+        } else {
+            "discharging"
+        };
+        lines.push(format!("Battery {:.0} percent, {}.", bat.percent, state));
+    }
+    if let Some(err) = &sys.offline {
+        lines.push(format!("Offline: {}.", err));
+    }
+    lines
+}
+
+/// The busiest processes, one sentence each.
+pub fn processes(sys: &SysCache) -> Vec<String> {
+    let procs = sys.processes();
+    if procs.is_empty() {
+        return vec!["No processes.".into()];
+    }
+    let mut lines = vec![format!(
+        "Top {} processes by CPU.",
+        procs.len().min(TOP_PROCS)
+    )];
+    lines.extend(procs.iter().take(TOP_PROCS).enumerate().map(|(i, p)| {
+        format!(
+            "{}. {}, PID {}, user {}, CPU {:.0} percent, memory {}.",
+            i + 1,
+            p.name,
+            p.pid,
+            p.user,
+            p.cpu,
+            spoken_bytes(p.mem_bytes)
+        )
+    }));
+    lines
+}
+
+/// Each disk by mount point, one sentence each.
+pub fn disks(sys: &SysCache) -> Vec<String> {
+    let disks = sys.disks();
+    if disks.is_empty() {
+        return vec!["No disks.".into()];
+    }
+    disks
+        .iter()
+        .map(|d| {
+            let used = d.total.saturating_sub(d.available);
+            format!(
+                "Disk {} {:.0} percent full, {} free.",
+                d.mount_point,
+                percent(used, d.total),
+                spoken_bytes(d.available)
+            )
+        })
+        .collect()
+}
+
+/// Answers commands from stdin until `q` or end of input. Each answer is
+/// collected fresh, so CPU usage is averaged since the previous one.
+pub fn run(warmup: Duration) -> io::Result<()> {
+    let mut collector = Collector::new();
+    collector.collect();
+    std::thread::sleep(warmup);
+
+    let mut out = io::stdout().lock();
+    let report = |out: &mut io::StdoutLock, lines: Vec<String>| {
+        for line in lines {
+            writeln!(out, "{}", line)?;
+        }
+        out.flush()
+    };
+    report(&mut out, summary(&collector.collect()))?;
+    report(&mut out, vec![HELP.into()])?;
+
+    for line in io::stdin().lock().lines() {
+        let lines = match line?.trim() {
+            "" => summary(&collector.collect()),
+            "p" => processes(&collector.collect()),
+            "d" => disks(&collector.collect()),
+            "q" => return Ok(()),
+            _ => vec![HELP.into()],
+        };
+        report(&mut out, lines)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary() {
+        let mut sys = SysCache::default();
+        sys.cpu_global = 43.2;
+        sys.total_mem = 16 << 30;
+        sys.used_mem = 4 << 30;
+        sys.rx_rate = 2048;
+        assert_eq!(
+            summary(&sys),
+            [
+                "Up 0 seconds.",
+                "CPU 43 percent.",
+                "Memory 25 percent, 4.0 gigabytes of 16.0 gigabytes used.",
+                "Network receiving 2.0 kilobytes per second, sending 0 bytes per second.",
+            ]
+        );
+        assert_eq!(spoken_bytes(512), "512 bytes");
+        assert_eq!(spoken_duration(90_061), "1 day, 1 hour, 1 minute");
+        assert_eq!(spoken_duration(7_200), "2 hours");
+    }
+}
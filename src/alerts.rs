@@ -0,0 +1,945 @@
+//! Threshold alerts evaluated every tick, loaded from
+//! `~/.config/sysdash/alerts.toml`:
+//!
+//! ```toml
+//! [[rule]]
+//! metric = "cpu"          # cpu, mem, temp or disk:<mount>
+//! op = ">"                # >, >=, <, <=
+//! threshold = 90
+//! clear = 80              # resolve only once back past this, default threshold
+//! for = 30                # seconds the condition must hold, default 0
+//! severity = "critical"   # or "warning" (default)
+//! ```
+//!
+//! The same file holds `[[watch]]` entries for the process [`Watchdog`] and a
+//! `[disk_space]` section (`threshold`, `clear`, `for`, `severity`, `enabled`) that
+//! applies to every mounted filesystem; it defaults to a warning at 90%.
+//! A top-level `on_alert = "<command>"` runs on every fired/resolved alert,
+//! see [`crate::sinks::CommandSink`]. `bell = true` rings the terminal bell
+//! and `flash = true` flashes the top bar while a critical alert is active.
+//! `notify = true` shows a desktop notification when an alert fires (cargo
+//! feature "notify"), at most once every `notify_interval` seconds (default
+//! 300) per rule. `webhook = "<url>"` POSTs every fired/resolved alert as
+//! JSON to the URL (cargo feature "webhook"), see
+//! [`crate::sinks::WebhookSink`].
+//! On a Raspberry Pi, under-voltage or throttling raises a warning unless
+//! `throttling = false`. A network interface going down or disappearing
+//! raises a warning, resolved when it comes back, unless `links = false`;
+//! the virtual interfaces of containers and VMs are left out.
+//!
+//! An active alert can be acknowledged, which stops the flashing until it
+//! resolves, or snoozed for `snooze` seconds (default 900), which hides it and
+//! suppresses its events.
+
+use crate::check::{Comparator, Metric};
+use crate::sbc;
+use crate::sinks::CommandSink;
+use crate::sys::{Interface, SysCache};
+use crate::watchdog::{RawWatch, Watch, Watchdog};
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, PartialEq, Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    #[default]
+    Warning,
+    Critical,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Warning => "warning",
+            Severity::Critical => "critical",
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RawRule {
+    name: Option<String>,
+    metric: String,
+    #[serde(default = "default_op")]
+    op: String,
+    threshold: f64,
+    clear: Option<f64>,
+    #[serde(rename = "for", default)]
+    duration: u64,
+    #[serde(default)]
+    severity: Severity,
+}
+
+fn default_op() -> String {
+    ">".into()
+}
+
+#[derive(Deserialize, Default)]
+struct AlertsFile {
+    #[serde(default)]
+    rule: Vec<RawRule>,
+    #[serde(default)]
+    watch: Vec<RawWatch>,
+    #[serde(default)]
+    disk_space: DiskSpace,
+    on_alert: Option<String>,
+    #[serde(default)]
+    bell: bool,
+    #[serde(default)]
+    flash: bool,
+    #[serde(default)]
+    notify: bool,
+    webhook: Option<String>,
+    #[serde(default = "default_notify_interval")]
+    notify_interval: u64,
+    #[serde(default = "default_snooze")]
+    snooze: u64,
+    #[serde(default = "default_throttling")]
+    throttling: bool,
+    #[serde(default = "default_throttling")]
+    links: bool,
+}
+
+fn default_throttling() -> bool {
+    true
+}
+
+/// Prefixes of interfaces that come and go with containers and VMs.
+const VIRTUAL_LINKS: [&str; 8] = [
+    "lo", "veth", "docker", "br-", "virbr", "vnet", "cali", "flannel",
+];
+
+/// Name of the built-in alert for an interface that is down.
+pub fn link_alert(interface: &str) -> String {
+    format!("{} down", interface)
+}
+
+/// Name of the built-in alert for a throttled single-board computer.
+pub const THROTTLE_ALERT: &str = "SoC throttled";
+
+fn default_snooze() -> u64 {
+    15 * 60
+}
+
+fn default_notify_interval() -> u64 {
+    5 * 60
+}
+
+/// Everything `alerts.toml` configures.
+pub struct AlertConfig {
+    pub rules: Vec<AlertRule>,
+    pub watches: Vec<Watch>,
+    pub disk_space: DiskSpace,
+    pub on_alert: Option<String>,
+    pub bell: bool,
+    pub flash: bool,
+    /// Minimum time between desktop notifications for one rule; `None`
+    /// when they are off.
+    pub notify: Option<Duration>,
+    pub webhook: Option<String>,
+    pub snooze: Duration,
+    pub throttling: bool,
+    pub links: bool,
+}
+
+/// Usage threshold applied to every filesystem, independent of `[[rule]]`s.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct DiskSpace {
+    pub enabled: bool,
+    pub threshold: f64,
+    pub clear: Option<f64>,
+    #[serde(rename = "for")]
+    pub duration: u64,
+    pub severity: Severity,
+}
+
+impl Default for DiskSpace {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            threshold: 90.0,
+            clear: None,
+            duration: 0,
+            severity: Severity::Warning,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct AlertRule {
+    pub name: String,
+    pub metric: Metric,
+    pub cmp: Comparator,
+    pub threshold: f64,
+    /// Level the value must get back past before the alert resolves.
+    pub clear: f64,
+    pub duration: Duration,
+    pub severity: Severity,
+}
+
+impl AlertRule {
+    /// Whether the condition holds: against `threshold` to fire, and against
+    /// `clear` once active, so a value hovering at the threshold doesn't flap.
+    pub fn breached(&self, value: f64, active: bool) -> bool {
+        let level = if active { self.clear } else { self.threshold };
+        self.cmp.matches(value, level)
+    }
+}
+
+/// Checks that `clear` is on the non-firing side of `threshold`.
+fn check_clear(cmp: Comparator, threshold: f64, clear: f64) -> Result<(), String> {
+    let ok = match cmp {
+        Comparator::Greater | Comparator::GreaterEq => clear <= threshold,
+        Comparator::Less | Comparator::LessEq => clear >= threshold,
+    };
+    if ok {
+        Ok(())
+    } else {
+        Err(format!(
+            "clear {} is past threshold {}; it must be on the other side",
+            clear, threshold
+        ))
+    }
+}
+
+impl TryFrom<RawRule> for AlertRule {
+    type Error = String;
+
+    fn try_from(raw: RawRule) -> Result<Self, String> {
+        let metric = Metric::parse(&raw.metric)?;
+        let cmp = match Comparator::split(raw.op.trim()) {
+            Some((cmp, "")) => cmp,
+            _ => return Err(format!("invalid op '{}'", raw.op)),
+        };
+        let clear = raw.clear.unwrap_or(raw.threshold);
+        check_clear(cmp, raw.threshold, clear)?;
+        let name = raw
+            .name
+            .unwrap_or_else(|| format!("{} {} {}", metric, raw.op.trim(), raw.threshold));
+        Ok(AlertRule {
+            name,
+            metric,
+            cmp,
+            threshold: raw.threshold,
+            clear,
+            duration: Duration::from_secs(raw.duration),
+            severity: raw.severity,
+        })
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum AlertKind {
+    Fired,
+    Resolved,
+}
+
+impl AlertKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AlertKind::Fired => "fired",
+            AlertKind::Resolved => "resolved",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct AlertEvent {
+    pub rule: String,
+    /// Metric label such as `cpu`, `disk:/` or `proc:nginx`.
+    pub metric: String,
+    pub value: f64,
+    pub threshold: f64,
+    pub severity: Severity,
+    pub kind: AlertKind,
+    pub at: chrono::DateTime<chrono::Local>,
+}
+
+/// Receives every fired/resolved transition; implemented by notification,
+/// webhook and command back ends.
+pub trait AlertSink {
+    fn send(&mut self, event: &AlertEvent);
+}
+
+/// Tracks how long a condition has held and whether it has fired.
+#[derive(Default)]
+pub(crate) struct Hold {
+    since: Option<Instant>,
+    pub(crate) active: bool,
+}
+
+impl Hold {
+    /// Returns a transition once `breached` has held for `duration`, or as
+    /// soon as it stops holding after having fired.
+    pub(crate) fn update(
+        &mut self,
+        breached: bool,
+        now: Instant,
+        duration: Duration,
+    ) -> Option<AlertKind> {
+        if breached {
+            let since = *self.since.get_or_insert(now);
+            if !self.active && now.duration_since(since) >= duration {
+                self.active = true;
+                return Some(AlertKind::Fired);
+            }
+        } else {
+            self.since = None;
+            if self.active {
+                self.active = false;
+                return Some(AlertKind::Resolved);
+            }
+        }
+        None
+    }
+}
+
+#[derive(Default)]
+struct RuleState {
+    hold: Hold,
+    value: f64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ActiveAlert {
+    pub name: String,
+    pub metric: String,
+    pub severity: Severity,
+    pub value: Option<f64>,
+    pub acked: bool,
+}
+
+pub struct AlertEngine {
+    rules: Vec<AlertRule>,
+    state: Vec<RuleState>,
+    sinks: Vec<Box<dyn AlertSink>>,
+    pub watchdog: Watchdog,
+    pub disk_space: DiskSpace,
+    disk_state: HashMap<String, RuleState>,
+    pub throttling: bool,
+    throttle_state: RuleState,
+    pub links: bool,
+    /// Whether each interface was up at the last evaluation; ones that
+    /// disappeared stay in as down. `None` before the first.
+    link_state: Option<BTreeMap<String, bool>>,
+    /// Interfaces that went down since startup and haven't come back.
+    links_down: BTreeSet<String>,
+    pub bell: bool,
+    pub flash: bool,
+    pub snooze: Duration,
+    acked: HashSet<String>,
+    snoozed: HashMap<String, Instant>,
+    pub errors: Vec<String>,
+}
+
+impl Default for AlertEngine {
+    fn default() -> Self {
+        Self {
+            rules: Vec::new(),
+            state: Vec::new(),
+            sinks: Vec::new(),
+            watchdog: Watchdog::default(),
+            disk_space: DiskSpace::default(),
+            disk_state: HashMap::new(),
+            throttling: true,
+            throttle_state: RuleState::default(),
+            links: true,
+            link_state: None,
+            links_down: BTreeSet::new(),
+            bell: false,
+            flash: false,
+            snooze: Duration::from_secs(default_snooze()),
+            acked: HashSet::new(),
+            snoozed: HashMap::new(),
+            errors: Vec::new(),
+        }
+    }
+}
+
+pub fn alerts_path() -> PathBuf {
+    crate::plugin::config_dir().join("alerts.toml")
+}
+
+pub fn parse_rules(text: &str) -> Result<Vec<AlertRule>, String> {
+    parse_config(text).map(|c| c.rules)
+}
+
+pub fn parse_config(text: &str) -> Result<AlertConfig, String> {
+    let file: AlertsFile = toml::from_str(text).map_err(|e| e.to_string())?;
+    if let Some(clear) = file.disk_space.clear {
+        check_clear(Comparator::GreaterEq, file.disk_space.threshold, clear)?;
+    }
+    let rules = file
+        .rule
+        .into_iter()
+        .map(AlertRule::try_from)
+        .collect::<Result<_, _>>()?;
+    let watches = file
+        .watch
+        .into_iter()
+        .map(Watch::try_from)
+        .collect::<Result<_, _>>()?;
+    Ok(AlertConfig {
+        rules,
+        watches,
+        disk_space: file.disk_space,
+        on_alert: file.on_alert,
+        bell: file.bell,
+        flash: file.flash,
+        notify: file
+            .notify
+            .then(|| Duration::from_secs(file.notify_interval)),
+        webhook: file.webhook,
+        snooze: Duration::from_secs(file.snooze),
+        throttling: file.throttling,
+        links: file.links,
+    })
+}
+
+impl AlertEngine {
+    pub fn new(rules: Vec<AlertRule>) -> Self {
+        let state = rules.iter().map(|_| RuleState::default()).collect();
+        Self {
+            rules,
+            state,
+            ..Self::default()
+        }
+    }
+
+    /// Loads rules from `path`; a missing file yields an engine with no rules.
+    pub fn load(path: &Path) -> Self {
+        let Ok(text) = std::fs::read_to_string(path) else {
+            return Self::default();
+        };
+        match parse_config(&text) {
+            Ok(config) => {
+                let mut engine = Self::new(config.rules);
+                engine.watchdog.watches = config.watches;
+                engine.disk_space = config.disk_space;
+                engine.bell = config.bell;
+                engine.flash = config.flash;
+                engine.snooze = config.snooze;
+                engine.throttling = config.throttling;
+                engine.links = config.links;
+                if let Some(command) = config.on_alert {
+                    engine.add_sink(Box::new(CommandSink::new(command)));
+                }
+                #[cfg(feature = "notify")]
+                if let Some(interval) = config.notify {
+                    engine.add_sink(Box::new(crate::sinks::NotifySink::new(interval)));
+                }
+                #[cfg(not(feature = "notify"))]
+                if config.notify.is_some() {
+                    engine
+                        .errors
+                        .push("alerts.toml: notify needs a build with --features notify".into());
+                }
+                #[cfg(feature = "webhook")]
+                if let Some(url) = config.webhook {
+                    engine.add_sink(Box::new(crate::sinks::WebhookSink::new(url)));
+                }
+                #[cfg(not(feature = "webhook"))]
+                if config.webhook.is_some() {
+                    engine
+                        .errors
+                        .push("alerts.toml: webhook needs a build with --features webhook".into());
+                }
+                engine
+            }
+            Err(e) => Self {
+                errors: vec![format!("alerts.toml: {}", e)],
+                ..Self::default()
+            },
+        }
+    }
+
+    pub fn add_sink(&mut self, sink: Box<dyn AlertSink>) {
+        self.sinks.push(sink);
+    }
+
+    /// Evaluates threshold rules and process watches, returning every
+    /// transition after forwarding it to the sinks.
+    pub fn evaluate(&mut self, sys: &SysCache, now: Instant) -> Vec<AlertEvent> {
+        let mut events = self.evaluate_with(|m| m.value(sys), now);
+        let mut more = self.watchdog.evaluate(sys.processes(), now);
+        if self.disk_space.enabled {
+            let usage: Vec<(String, f64)> = sys
+                .disks()
+                .iter()
+                .filter(|d| d.total > 0)
+                .map(|d| {
+                    let used = d.total.saturating_sub(d.available);
+                    (d.mount_point.clone(), used as f64 / d.total as f64 * 100.0)
+                })
+                .collect();
+            more.extend(self.evaluate_disk_space(&usage, now));
+        }
+        if let Some(flags) = sys.sbc.as_ref().and_then(|s| s.throttled) {
+            if self.throttling {
+                more.extend(self.evaluate_throttling(flags, now));
+            }
+        }
+        if self.links {
+            more.extend(self.evaluate_links(&sys.interfaces));
+        }
+        self.dispatch(&mut more, now);
+        events.extend(more);
+        events
+    }
+
+    /// Fires `<name> down` when an interface goes down or disappears and
+    /// resolves it when the interface is up again; one that appears already
+    /// up counts as coming up. The first call only records the state.
+    pub fn evaluate_links(&mut self, interfaces: &[Interface]) -> Vec<AlertEvent> {
+        let mut current: BTreeMap<String, bool> = interfaces
+            .iter()
+            .filter(|i| !VIRTUAL_LINKS.iter().any(|p| i.name.starts_with(p)))
+            .map(|i| (i.name.clone(), i.up))
+            .collect();
+        let Some(before) = self.link_state.take() else {
+            self.link_state = Some(current);
+            return Vec::new();
+        };
+        for (name, _) in before.iter() {
+            current.entry(name.clone()).or_insert(false);
+        }
+        let events: Vec<AlertEvent> = current
+            .iter()
+            .filter(|&(name, &up)| before.get(name).map_or(up, |&was| was != up))
+            .map(|(name, &up)| {
+                if up {
+                    self.links_down.remove(name);
+                } else {
+                    self.links_down.insert(name.clone());
+                }
+                AlertEvent {
+                    rule: link_alert(name),
+                    metric: format!("link:{}", name),
+                    value: if up { 1.0 } else { 0.0 },
+                    threshold: 0.0,
+                    severity: Severity::Warning,
+                    kind: if up {
+                        AlertKind::Resolved
+                    } else {
+                        AlertKind::Fired
+                    },
+                    at: chrono::Local::now(),
+                }
+            })
+            .collect();
+        self.link_state = Some(current);
+        events
+    }
+
+    /// Forgets the links, disks and throttling seen so far, which belong to
+    /// the host that was on screen, so the next one doesn't fire on the
+    /// difference. Its first evaluation only records its state again.
+    pub fn forget_host(&mut self) {
+        self.link_state = None;
+        self.links_down.clear();
+        self.disk_state.clear();
+        self.throttle_state = RuleState::default();
+    }
+
+    /// Applies the `[disk_space]` threshold to `(mount, percent used)` pairs.
+    pub fn evaluate_disk_space(
+        &mut self,
+        usage: &[(String, f64)],
+        now: Instant,
+    ) -> Vec<AlertEvent> {
+        let rule = &self.disk_space;
+        let duration = Duration::from_secs(rule.duration);
+        let mut events = Vec::new();
+        for (mount, percent) in usage {
+            let state = self.disk_state.entry(mount.clone()).or_default();
+            state.value = *percent;
+            let level = match rule.clear {
+                Some(clear) if state.hold.active => clear,
+                _ => rule.threshold,
+            };
+            if let Some(kind) = state.hold.update(*percent >= level, now, duration) {
+                events.push(AlertEvent {
+                    rule: format!("disk:{} above {}%", mount, rule.threshold),
+                    metric: format!("disk:{}", mount),
+                    value: *percent,
+                    threshold: rule.threshold,
+                    severity: rule.severity,
+                    kind,
+                    at: chrono::Local::now(),
+                });
+            }
+        }
+        events
+    }
+
+    /// Fires while the firmware reports under-voltage, capping or throttling
+    /// and resolves once all of those clear.
+    pub fn evaluate_throttling(&mut self, flags: u32, now: Instant) -> Option<AlertEvent> {
+        let state = &mut self.throttle_state;
+        state.value = flags as f64;
+        let throttled = !sbc::throttle_reasons(flags).is_empty();
+        let kind = state.hold.update(throttled, now, Duration::ZERO)?;
+        Some(AlertEvent {
+            rule: THROTTLE_ALERT.into(),
+            metric: "throttled".into(),
+            value: flags as f64,
+            threshold: 0.0,
+            severity: Severity::Warning,
+            kind,
+            at: chrono::Local::now(),
+        })
+    }
+
+    /// Drops events of snoozed alerts, forgets acknowledgements of resolved
+    /// ones and forwards the rest to every sink.
+    fn dispatch(&mut self, events: &mut Vec<AlertEvent>, now: Instant) {
+        self.snoozed.retain(|_, until| *until > now);
+        events.retain(|e| !self.snoozed.contains_key(&e.rule));
+        for event in events.iter() {
+            if event.kind == AlertKind::Resolved {
+                self.acked.remove(&event.rule);
+            }
+            for sink in self.sinks.iter_mut() {
+                sink.send(event);
+            }
+        }
+    }
+
+    /// Advances every rule with the value from `lookup` and returns the
+    /// transitions, which are also forwarded to every sink.
+    pub fn evaluate_with<F>(&mut self, lookup: F, now: Instant) -> Vec<AlertEvent>
+    where
+        F: Fn(&Metric) -> Option<f64>,
+    {
+        let mut events = Vec::new();
+        for (rule, state) in self.rules.iter().zip(self.state.iter_mut()) {
+            let Some(value) = lookup(&rule.metric) else {
+                continue;
+            };
+            state.value = value;
+
+            let breached = rule.breached(value, state.hold.active);
+            if let Some(kind) = state.hold.update(breached, now, rule.duration) {
+                events.push(AlertEvent {
+                    rule: rule.name.clone(),
+                    metric: rule.metric.to_string(),
+                    value,
+                    threshold: rule.threshold,
+                    severity: rule.severity,
+                    kind,
+                    at: chrono::Local::now(),
+                });
+            }
+        }
+
+        self.dispatch(&mut events, now);
+        events
+    }
+
+    /// Active rule and watchdog alerts, most severe first.
+    pub fn active(&self) -> Vec<ActiveAlert> {
+        let mut active: Vec<ActiveAlert> = self
+            .rules
+            .iter()
+            .zip(self.state.iter())
+            .filter(|(_, s)| s.hold.active)
+            .map(|(r, s)| ActiveAlert {
+                name: r.name.clone(),
+                metric: r.metric.to_string(),
+                severity: r.severity,
+                value: Some(s.value),
+                acked: false,
+            })
+            .collect();
+        for (mount, state) in &self.disk_state {
+            if state.hold.active {
+                active.push(ActiveAlert {
+                    name: format!("disk:{} above {}%", mount, self.disk_space.threshold),
+                    metric: format!("disk:{}", mount),
+                    severity: self.disk_space.severity,
+                    value: Some(state.value),
+                    acked: false,
+                });
+            }
+        }
+        if self.throttle_state.hold.active {
+            active.push(ActiveAlert {
+                name: THROTTLE_ALERT.into(),
+                metric: "throttled".into(),
+                severity: Severity::Warning,
+                value: None,
+                acked: false,
+            });
+        }
+        for name in &self.links_down {
+            active.push(ActiveAlert {
+                name: link_alert(name),
+                metric: format!("link:{}", name),
+                severity: Severity::Warning,
+                value: None,
+                acked: false,
+            });
+        }
+        active.extend(self.watchdog.active());
+        active.retain(|a| !self.snoozed.contains_key(&a.name));
+        for alert in active.iter_mut() {
+            alert.acked = self.acked.contains(&alert.name);
+        }
+        active.sort_by_key(|a| a.severity != Severity::Critical);
+        active
+    }
+
+    /// Whether a critical alert is active and not yet acknowledged.
+    pub fn has_critical(&self) -> bool {
+        self.active()
+            .iter()
+            .any(|a| a.severity == Severity::Critical && !a.acked)
+    }
+
+    /// Marks the active alert `name` as seen; it stays listed until resolved.
+    pub fn acknowledge(&mut self, name: &str) {
+        self.acked.insert(name.to_string());
+    }
+
+    /// Hides `name` and suppresses its events for the configured duration.
+    pub fn snooze(&mut self, name: &str, now: Instant) {
+        self.snoozed.insert(name.to_string(), now + self.snooze);
+    }
+
+    /// Snoozed alert names with the time they wake up, soonest first.
+    pub fn snoozed(&self) -> Vec<(&str, Instant)> {
+        let mut out: Vec<(&str, Instant)> =
+            self.snoozed.iter().map(|(n, t)| (n.as_str(), *t)).collect();
+        out.sort_by_key(|(_, t)| *t);
+        out
+    }
+
+    /// Whether any active alert watches `metric`; `Disk("")` matches any disk.
+    pub fn is_firing(&self, metric: &Metric) -> bool {
+        let label = metric.to_string();
+        self.active().iter().any(|a| match metric {
+            Metric::Disk(m) if m.is_empty() => a.metric.starts_with("disk:"),
+            _ => a.metric == label,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    struct Recorder(Rc<RefCell<Vec<AlertKind>>>);
+
+    impl AlertSink for Recorder {
+        fn send(&mut self, event: &AlertEvent) {
+            self.0.borrow_mut().push(event.kind);
+        }
+    }
+
+    #[test]
+    fn test_parse_rules() {
+        let rules = parse_rules(
+            r#"
+            [[rule]]
+            metric = "cpu"
+            threshold = 90
+            for = 30
+            severity = "critical"
+
+            [[rule]]
+            name = "root full"
+            metric = "disk:/"
+            op = ">="
+            threshold = 95
+            "#,
+        )
+        .unwrap();
+        assert_eq!(rules[0].name, "cpu > 90");
+        assert_eq!(rules[0].duration, Duration::from_secs(30));
+        assert_eq!(rules[0].severity, Severity::Critical);
+        assert_eq!(rules[1].metric, Metric::Disk("/".into()));
+        assert_eq!(rules[1].cmp, Comparator::GreaterEq);
+        assert_eq!(rules[1].severity, Severity::Warning);
+
+        assert!(parse_rules("[[rule]]\nmetric = \"gpu\"\nthreshold = 1").is_err());
+        assert!(parse_rules("[[rule]]\nmetric = \"cpu\"\nop = \"!\"\nthreshold = 1").is_err());
+        assert!(parse_rules("[[rule]]\nmetric = \"cpu\"\nthreshold = 90\nclear = 95").is_err());
+        assert!(parse_config("[disk_space]\nthreshold = 90\nclear = 95").is_err());
+    }
+
+    #[test]
+    fn test_clear_threshold_hysteresis() {
+        let mut engine = AlertEngine::new(
+            parse_rules("[[rule]]\nmetric = \"cpu\"\nthreshold = 90\nclear = 80").unwrap(),
+        );
+        let t0 = Instant::now();
+        assert_eq!(
+            engine.evaluate_with(|_| Some(91.0), t0)[0].kind,
+            AlertKind::Fired
+        );
+        assert!(engine.evaluate_with(|_| Some(85.0), t0).is_empty());
+        assert!(engine.evaluate_with(|_| Some(92.0), t0).is_empty());
+        assert_eq!(
+            engine.evaluate_with(|_| Some(80.0), t0)[0].kind,
+            AlertKind::Resolved
+        );
+        assert!(engine.evaluate_with(|_| Some(85.0), t0).is_empty());
+    }
+
+    #[test]
+    fn test_fire_after_duration_and_resolve() {
+        let mut engine = AlertEngine::new(
+            parse_rules("[[rule]]\nmetric = \"cpu\"\nthreshold = 90\nfor = 10").unwrap(),
+        );
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        engine.add_sink(Box::new(Recorder(Rc::clone(&seen))));
+
+        let t0 = Instant::now();
+        assert!(engine.evaluate_with(|_| Some(95.0), t0).is_empty());
+        assert!(engine
+            .evaluate_with(|_| Some(95.0), t0 + Duration::from_secs(5))
+            .is_empty());
+        let fired = engine.evaluate_with(|_| Some(96.0), t0 + Duration::from_secs(10));
+        assert_eq!(fired[0].kind, AlertKind::Fired);
+        assert!(engine.is_firing(&Metric::Cpu));
+        assert_eq!(engine.active()[0].value, Some(96.0));
+
+        let resolved = engine.evaluate_with(|_| Some(50.0), t0 + Duration::from_secs(11));
+        assert_eq!(resolved[0].kind, AlertKind::Resolved);
+        assert!(engine.active().is_empty());
+        assert_eq!(*seen.borrow(), vec![AlertKind::Fired, AlertKind::Resolved]);
+    }
+
+    #[test]
+    fn test_disk_space_threshold() {
+        let mut engine = AlertEngine::load(Path::new("/nonexistent/alerts.toml"));
+        assert!(engine.disk_space.enabled);
+        let t0 = Instant::now();
+        let usage = vec![("/".to_string(), 95.0), ("/home".to_string(), 40.0)];
+        let events = engine.evaluate_disk_space(&usage, t0);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].metric, "disk:/");
+        assert!(engine.is_firing(&Metric::Disk(String::new())));
+        assert!(!engine.is_firing(&Metric::Disk("/home".into())));
+
+        let disk = parse_config("[disk_space]\nthreshold = 80\nfor = 60")
+            .unwrap()
+            .disk_space;
+        assert_eq!(disk.threshold, 80.0);
+        assert_eq!(disk.duration, 60);
+        assert!(disk.enabled);
+    }
+
+    #[test]
+    fn test_throttling_alert() {
+        let mut engine = AlertEngine::default();
+        let t0 = Instant::now();
+        // Under-voltage happened earlier but is over: nothing to report.
+        assert!(engine.evaluate_throttling(0x10000, t0).is_none());
+        let fired = engine.evaluate_throttling(0x50005, t0).unwrap();
+        assert_eq!(fired.kind, AlertKind::Fired);
+        assert_eq!(engine.active()[0].name, THROTTLE_ALERT);
+        let resolved = engine.evaluate_throttling(0x50000, t0).unwrap();
+        assert_eq!(resolved.kind, AlertKind::Resolved);
+        assert!(engine.active().is_empty());
+
+        assert!(!parse_config("throttling = false").unwrap().throttling);
+    }
+
+    #[test]
+    fn test_notify_config() {
+        assert_eq!(parse_config("").unwrap().notify, None);
+        let config = parse_config("notify = true\nnotify_interval = 60").unwrap();
+        assert_eq!(config.notify, Some(Duration::from_secs(60)));
+        assert_eq!(
+            parse_config("notify = true").unwrap().notify,
+            Some(Duration::from_secs(300))
+        );
+    }
+
+    #[test]
+    fn test_link_alerts() {
+        let link = |name: &str, up| Interface {
+            name: name.into(),
+            up,
+        };
+        let mut engine = AlertEngine::default();
+        // Down at startup isn't news.
+        assert!(engine
+            .evaluate_links(&[link("eth0", false), link("wlan0", true)])
+            .is_empty());
+
+        let events = engine.evaluate_links(&[link("eth0", false), link("veth1", true)]);
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            (events[0].rule.as_str(), events[0].kind),
+            ("wlan0 down", AlertKind::Fired)
+        );
+        assert_eq!(engine.active()[0].name, "wlan0 down");
+
+        let events = engine.evaluate_links(&[link("eth0", true), link("wlan0", true)]);
+        let kinds: Vec<_> = events.iter().map(|e| (e.rule.as_str(), e.kind)).collect();
+        assert_eq!(
+            kinds,
+            [
+                ("eth0 down", AlertKind::Resolved),
+                ("wlan0 down", AlertKind::Resolved)
+            ]
+        );
+        assert!(engine.active().is_empty());
+
+        engine.evaluate_links(&[link("eth0", false)]);
+        assert_eq!(engine.active().len(), 2);
+        engine.forget_host();
+        assert!(engine.active().is_empty());
+        assert!(engine.evaluate_links(&[link("wlan0", true)]).is_empty());
+        assert!(engine.evaluate_links(&[link("wlan0", true)]).is_empty());
+    }
+
+    #[test]
+    fn test_spike_shorter_than_duration_does_not_fire() {
+        let mut engine = AlertEngine::new(
+            parse_rules("[[rule]]\nmetric = \"disk:/\"\nthreshold = 90\nfor = 10").unwrap(),
+        );
+        let t0 = Instant::now();
+        engine.evaluate_with(|_| Some(99.0), t0);
+        engine.evaluate_with(|_| Some(10.0), t0 + Duration::from_secs(5));
+        let events = engine.evaluate_with(|_| Some(99.0), t0 + Duration::from_secs(12));
+        assert!(events.is_empty());
+        assert!(!engine.is_firing(&Metric::Disk(String::new())));
+    }
+
+    #[test]
+    fn test_acknowledge_and_snooze() {
+        let mut engine = AlertEngine::new(
+            parse_rules("[[rule]]\nname = \"hot\"\nmetric = \"cpu\"\nthreshold = 90\nseverity = \"critical\"")
+                .unwrap(),
+        );
+        let t0 = Instant::now();
+        engine.evaluate_with(|_| Some(95.0), t0);
+        assert!(engine.has_critical());
+        engine.acknowledge("hot");
+        assert!(!engine.has_critical());
+        assert!(engine.active()[0].acked);
+
+        // Resolving clears the acknowledgement, so the next firing flashes.
+        engine.evaluate_with(|_| Some(10.0), t0);
+        engine.evaluate_with(|_| Some(95.0), t0);
+        assert!(engine.has_critical());
+
+        engine.snooze("hot", t0);
+        assert!(engine.active().is_empty());
+        assert!(engine.evaluate_with(|_| Some(10.0), t0).is_empty());
+        assert!(engine
+            .evaluate_with(|_| Some(95.0), t0 + Duration::from_secs(60))
+            .is_empty());
+        let woke = engine.evaluate_with(|_| Some(10.0), t0 + Duration::from_secs(900));
+        assert!(engine.snoozed().is_empty());
+        assert_eq!(woke[0].kind, AlertKind::Resolved);
+    }
+}
@@ -0,0 +1,158 @@
+//! `--api <addr>` answers HTTP requests with the dashboard's latest
+//! snapshot of the local machine while the TUI runs, for scripts and web
+//! frontends:
+//!
+//! - `GET /api/v1/system`: CPU, cores, memory, swap, disks, network and the
+//!   rest of [`SysCache`], without the process list
+//! - `GET /api/v1/processes`: the process list, busiest first
+//!
+//! Both are JSON with the field names of [`SysCache`] and
+//! [`ProcessInfo`](crate::sys::ProcessInfo). There is no authentication, so
+//! bind to `127.0.0.1` unless the network is trusted.
+
+use crate::sys::SysCache;
+use serde_json::{json, Value};
+use std::io;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+use tokio::time;
+
+/// How long a client may take to send its request.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Binds `addr` and serves from a task; each snapshot sent on the returned
+/// channel replaces the one served. Must be called from within a tokio
+/// runtime.
+pub async fn spawn(addr: &str) -> io::Result<watch::Sender<SysCache>> {
+    let listener = TcpListener::bind(addr).await?;
+    let (tx, rx) = watch::channel(SysCache::default());
+    tokio::spawn(serve(listener, rx));
+    Ok(tx)
+}
+
+async fn serve(listener: TcpListener, rx: watch::Receiver<SysCache>) {
+    while let Ok((stream, _)) = listener.accept().await {
+        let rx = rx.clone();
+        tokio::spawn(async move {
+            let _ = time::timeout(TIMEOUT, handle(stream, rx)).await;
+        });
+    }
+}
+
+/// Answers one request and closes the connection.
+async fn handle(stream: TcpStream, rx: watch::Receiver<SysCache>) -> io::Result<()> {
+    let (read, mut write) = stream.into_split();
+    let mut lines = BufReader::new(read).lines();
+    let Some(request) = lines.next_line().await? else {
+        return Ok(());
+    };
+    // The headers don't matter, but are read so the client sees no reset.
+    while let Some(line) = lines.next_line().await? {
+        if line.is_empty() {
+            break;
+        }
+    }
+    let (status, body) = route(&request, &rx.borrow());
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    write.write_all(response.as_bytes()).await?;
+    write.shutdown().await
+}
+
+/// The status and body for a request line such as
+/// `GET /api/v1/system HTTP/1.1`.
+fn route(request: &str, sys: &SysCache) -> (&'static str, String) {
+    let mut parts = request.split_whitespace();
+    let (method, target) = (parts.next(), parts.next().unwrap_or_default());
+    let path = target.split('?').next().unwrap_or_default();
+    let error = |status, message: &str| (status, json!({ "error": message }).to_string());
+    if method != Some("GET") {
+        return error("405 Method Not Allowed", "only GET is supported");
+    }
+    let body = match path.trim_end_matches('/') {
+        "/api/v1/system" => serde_json::to_value(sys).map(|mut value| {
+            if let Value::Object(map) = &mut value {
+                map.remove("procs");
+            }
+            value
+        }),
+        "/api/v1/processes" => serde_json::to_value(sys.processes()),
+        _ => return error("404 Not Found", "not found"),
+    };
+    match body {
+        Ok(body) => ("200 OK", body.to_string()),
+        Err(e) => error("500 Internal Server Error", &e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sys::ProcessInfo;
+    use tokio::io::AsyncReadExt;
+
+    fn sys() -> SysCache {
+        let mut sys = SysCache::default().with_processes(vec![ProcessInfo {
+            pid: 42,
+            name: "postgres".into(),
+            user: "pg".into(),
+            cmd: "postgres -D /data".into(),
+            cpu: 12.5,
+            mem_bytes: 1024,
+            parent: Some(1),
+        }]);
+        sys.cpu_global = 37.5;
+        sys
+    }
+
+    #[test]
+    fn test_route() {
+        let sys = sys();
+        let (status, body) = route("GET /api/v1/system HTTP/1.1", &sys);
+        assert_eq!(status, "200 OK");
+        let value: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(value["cpu_global"], 37.5);
+        assert!(value.get("procs").is_none());
+
+        let (status, body) = route("GET /api/v1/processes?x=1 HTTP/1.1", &sys);
+        assert_eq!(status, "200 OK");
+        let value: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(value[0]["pid"], 42);
+        assert_eq!(value[0]["parent"], 1);
+
+        assert_eq!(route("GET /api/v2/system HTTP/1.1", &sys).0, "404 Not Found");
+        assert_eq!(
+            route("POST /api/v1/system HTTP/1.1", &sys).0,
+            "405 Method Not Allowed"
+        );
+        assert_eq!(route("", &sys).0, "405 Method Not Allowed");
+    }
+
+    #[tokio::test]
+    async fn test_serves_latest_snapshot() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (tx, rx) = watch::channel(SysCache::default());
+        tokio::spawn(serve(listener, rx));
+        tx.send_replace(sys());
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(b"GET /api/v1/processes HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        let (_, body) = response.split_once("\r\n\r\n").unwrap();
+        let value: Value = serde_json::from_str(body).unwrap();
+        assert_eq!(value[0]["name"], "postgres");
+    }
+}
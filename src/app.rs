@@ -1,7 +1,31 @@
-use crate::sys::SysCache;
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crate::alerts::{self, AlertEngine, AlertEvent, AlertKind, Severity};
+use crate::cgroups;
+use crate::clock::Clock;
+use crate::command::{self, CommandWidget};
+use crate::config::{Colors, Config};
+use crate::containers::{self, ContainerAction, ContainerRequest, Containers, RateHistory};
+use crate::delta::{Baseline, DeltaSort};
+use crate::focus::Focus;
+use crate::host::Host;
+use crate::keymap::Action;
+use crate::plugin::{self, PluginHost};
+use crate::remote::Agent;
+use crate::replay::Replay;
+use crate::screensaver::Screensaver;
+use crate::snapshot;
+use crate::sys::{ProcessDetail, ProcessInfo, SysCache};
+use crate::theme::{self, Theme};
+use crossterm::event::{KeyCode, KeyEvent, MouseEvent, MouseEventKind};
+use ratatui::layout::Rect;
 use ratatui::widgets::TableState;
-use std::time::Duration;
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::ops::Range;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot::{self, error::TryRecvError};
 
 #[derive(PartialEq, Debug)]
 pub enum InputMode {
@@ -10,6 +34,80 @@ pub enum InputMode {
 }
 
 #[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Popup {
+    AlertLog,
+    Process,
+    /// The monitor's own overhead.
+    Perf,
+    /// Agents discovered on the network, to connect to.
+    Connect,
+    /// Containers of the Docker daemon.
+    Containers,
+    /// The cgroup hierarchy of the host on screen.
+    Cgroups,
+    /// Kubernetes pods on the host on screen, from its cgroups.
+    Pods,
+    /// Min/avg/max/p95 since startup.
+    Stats,
+    /// Changes since the baseline marked with `b`.
+    Delta,
+    /// Processes that exited.
+    Exits,
+    /// The key bindings in effect.
+    Help,
+}
+
+/// A panel that the arrow keys and the mouse wheel scroll; `f` moves the
+/// focus between them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Panel {
+    #[default]
+    Processes,
+    Cores,
+    Disks,
+    /// The processes listed in the GPU panel, which the process keys act
+    /// on while it has the focus.
+    Gpu,
+    Sensors,
+}
+
+/// The rows of the Sensors panel: CPU power and its history, the battery
+/// with up to two lines of details, and the fans under a heading.
+pub fn sensor_rows(sys: &SysCache) -> usize {
+    let power = if sys.cpu_power.is_some() { 2 } else { 0 };
+    let battery = sys.battery.as_ref().map_or(0, |bat| {
+        let rate = bat.power.is_some() || bat.time_left.is_some();
+        let wear = bat.health.is_some() || bat.cycle_count.is_some() || bat.temperature.is_some();
+        1 + usize::from(rate) + usize::from(wear)
+    });
+    let fans = if sys.fans.is_empty() { 0 } else { 1 + sys.fans.len() };
+    power + battery + fans
+}
+
+/// Bounds for changing the update interval with `+` / `-`.
+pub const MIN_TICK_RATE: Duration = Duration::from_millis(250);
+pub const MAX_TICK_RATE: Duration = Duration::from_secs(16);
+
+/// Span of time covered by the CPU and network charts.
+pub const HISTORY_WINDOW: Duration = Duration::from_secs(100);
+
+/// Span of disk usage samples used for the "full in" projection.
+pub const DISK_TREND_WINDOW: Duration = Duration::from_secs(30 * 60);
+
+/// Span of memory samples used for the "exhausted in" projection.
+pub const MEM_TREND_WINDOW: Duration = Duration::from_secs(10 * 60);
+
+/// The CPU caps `L` steps through, in percent of one core.
+pub const CPU_LIMITS: [u32; 3] = [50, 25, 10];
+
+/// Rows PageUp / PageDown move the process selection by.
+const PAGE: usize = 20;
+
+/// How many fired/resolved alert events are kept for the history popup.
+pub const ALERT_LOG_LEN: usize = 200;
+
+#[derive(Clone, Copy, PartialEq, Debug, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
 pub enum SortColumn {
     Pid,
     Name,
@@ -19,92 +117,736 @@ pub enum SortColumn {
 }
 
 pub struct App {
-    sys: SysCache,
-    _tick_rate: Duration,
+    /// Every monitored machine; the one on screen is `hosts[active]`.
+    pub hosts: Vec<Host>,
+    active: usize,
+    /// Showing the grid of all hosts instead of one host's dashboard.
+    pub overview: bool,
+    /// Highlighted card in the overview.
+    pub overview_selected: usize,
+    /// Agents announced on the network, newest last.
+    pub discovered: Vec<Agent>,
+    /// Selected row in the connect popup.
+    pub connect_selected: usize,
+    /// The last poll of the Docker daemon, once there was one.
+    pub containers: Option<Containers>,
+    pub container_rates: RateHistory,
+    /// Selected row in the containers popup.
+    pub container_selected: usize,
+    /// A stop or kill waiting for y in the containers popup.
+    pub container_confirm: Option<ContainerRequest>,
+    /// The polling task, which carries out confirmed requests.
+    pub container_control: Option<containers::Control>,
+    /// Selected row in the cgroup view, and the cgroups unfolded there.
+    pub cgroup_selected: usize,
+    pub cgroup_expanded: HashSet<String>,
+    /// The host whose worker reads cgroups for the open cgroup or pod view.
+    cgroups_host: Option<usize>,
+    tick_rate: Duration,
+    tick_rate_changed: bool,
+    /// Slower tick used while unfocused or without input for `idle_after`.
+    idle_rate: Option<Duration>,
+    idle_after: Duration,
+    focused: bool,
+    last_input: Instant,
+    idle: bool,
+    pub screensaver: Screensaver,
+    /// The screensaver is on; the next key only wakes it.
+    dimmed: bool,
+    /// Snapshots are dropped so the screen holds still; toggled with `p`.
+    paused: bool,
+    /// Playing back a recording instead of watching the machine.
+    replay: Option<Replay>,
     should_quit: bool,
     pub table_state: TableState,
 
-    pub cpu_history: Vec<u64>,
-    pub net_rx_history: Vec<u64>,
-    pub net_tx_history: Vec<u64>,
-
     pub search_query: String,
     pub input_mode: InputMode,
 
     pub sort_col: SortColumn,
     pub sort_desc: bool,
-    pub _tree_view: bool,
+    /// Indexes into `sys.processes()` that match the search, in sort order.
+    /// Rebuilt by [`App::update_visible`] whenever any of those change.
+    visible: Vec<usize>,
+    /// Indentation of each visible row; empty outside the tree view.
+    depths: Vec<usize>,
+    /// Processes matching the search before `process_limit` cut them.
+    matched: usize,
+    /// At most this many rows, the first in sort order, unless `show_all`.
+    pub process_limit: Option<usize>,
+    pub show_all: bool,
+    /// First row drawn in the process table; see [`App::process_window`].
+    process_offset: usize,
+    pub tree_view: bool,
+    /// In the flat view, count each process's descendants into its CPU and
+    /// memory, toggled with `c`.
+    pub aggregate: bool,
+    /// Subtree CPU and memory by PID while `aggregate` applies.
+    totals: HashMap<u32, (f32, u64)>,
+    /// Draw memory and its projected trend on the history chart.
+    pub projection: bool,
+    /// Show the event log panel, toggled with `e`.
+    pub show_events: bool,
+    /// Set by `--pid` / `--name`: the single-process dashboard replaces the
+    /// machine view.
+    pub focus: Option<Focus>,
+    /// How the top bar shows the time.
+    pub clock: Clock,
+
+    pub plugins: PluginHost,
+    pub commands: Vec<CommandWidget>,
+    pub alerts: AlertEngine,
+    pub alert_log: VecDeque<AlertEvent>,
+
+    pub popup: Option<Popup>,
+    /// Details for the process popup, once the worker has fetched them.
+    pub detail: Option<ProcessDetail>,
+    detail_rx: Option<oneshot::Receiver<Option<ProcessDetail>>>,
+    /// Selected row among the active alerts in the alert popup.
+    pub alert_selected: usize,
+    /// The last [`SysCache::notice`] of the host on screen, e.g. a refused
+    /// remote action; cleared by the next key press.
+    pub notice: Option<String>,
+    /// Order of the delta popup, toggled there with `m`.
+    pub delta_sort: DeltaSort,
+    /// The panel the arrow keys scroll.
+    pub panel: Panel,
+    /// First row shown in the Cores, Disks and Sensors panels; the UI clamps
+    /// them to the last full page.
+    pub cores_scroll: usize,
+    pub disks_scroll: usize,
+    pub sensors_scroll: usize,
+    /// The selected row among the processes of all GPUs, in panel order.
+    pub gpu_selected: usize,
+    /// Where each scrollable panel was drawn, for the mouse wheel.
+    pub panel_areas: Vec<(Panel, Rect)>,
+    bell_pending: bool,
+    snapshot_pending: bool,
+    frame_time: Duration,
+    /// `config.toml`; the panels are read while drawing.
+    pub config: Config,
+    themes: Vec<Theme>,
+    /// The theme drawn with, cycled with Ctrl+t.
+    theme: usize,
 }
 
 impl App {
-    pub fn new(tick_rate: Duration) -> Self {
+    pub fn new(config: Config) -> Self {
+        Self::with_hosts(config, vec![String::new()])
+    }
+
+    /// An app watching one host per name; an empty name is the local machine.
+    /// It starts without plugins, command widgets or alert rules, so tests
+    /// don't depend on the config directory; see [`App::load_user_files`].
+    pub fn with_hosts(config: Config, names: Vec<String>) -> Self {
+        let tick_rate = config.tick_rate();
+        let themes = theme::list(&config);
+        let theme = theme::start(&config, &themes).unwrap_or_default();
         let mut table_state = TableState::default();
         table_state.select(Some(0));
         Self {
-            sys: SysCache::new(),
-            _tick_rate: tick_rate,
+            hosts: names
+                .into_iter()
+                .map(|name| Host::new(name, tick_rate))
+                .collect(),
+            active: 0,
+            overview: false,
+            overview_selected: 0,
+            discovered: Vec::new(),
+            connect_selected: 0,
+            containers: None,
+            container_rates: RateHistory::default(),
+            container_selected: 0,
+            container_confirm: None,
+            container_control: None,
+            cgroup_selected: 0,
+            cgroup_expanded: HashSet::new(),
+            cgroups_host: None,
+            tick_rate,
+            tick_rate_changed: false,
+            idle_rate: None,
+            idle_after: Duration::from_secs(120),
+            focused: true,
+            last_input: Instant::now(),
+            idle: false,
+            screensaver: Screensaver::default(),
+            dimmed: false,
+            paused: false,
+            replay: None,
             should_quit: false,
             table_state,
-            cpu_history: vec![0; 100],
-            net_rx_history: vec![0; 100],
-            net_tx_history: vec![0; 100],
             search_query: String::new(),
             input_mode: InputMode::Normal,
-            sort_col: SortColumn::Cpu,
-            sort_desc: true,
-            _tree_view: false,
+            sort_col: config.sort,
+            sort_desc: config.descending,
+            visible: Vec::new(),
+            depths: Vec::new(),
+            matched: 0,
+            process_limit: None,
+            show_all: false,
+            process_offset: 0,
+            tree_view: false,
+            aggregate: false,
+            totals: HashMap::new(),
+            projection: false,
+            show_events: false,
+            focus: None,
+            clock: Clock::default(),
+            plugins: PluginHost::default(),
+            commands: Vec::new(),
+            alerts: AlertEngine::default(),
+            alert_log: VecDeque::new(),
+            popup: None,
+            detail: None,
+            detail_rx: None,
+            alert_selected: 0,
+            notice: None,
+            delta_sort: DeltaSort::default(),
+            panel: Panel::default(),
+            cores_scroll: 0,
+            disks_scroll: 0,
+            sensors_scroll: 0,
+            gpu_selected: 0,
+            panel_areas: Vec::new(),
+            bell_pending: false,
+            snapshot_pending: false,
+            frame_time: Duration::ZERO,
+            config,
+            themes,
+            theme,
+        }
+    }
+
+    /// Loads the plugins, command widgets and alert rules from the user's
+    /// config directory, replacing the ones the app has.
+    pub fn load_user_files(&mut self) {
+        self.plugins = PluginHost::load(&plugin::plugins_dir());
+        self.commands = command::load(&command::widgets_path());
+        self.alerts = AlertEngine::load(&alerts::alerts_path());
+    }
+
+    /// Applies a snapshot for the host on screen.
+    pub fn on_refresh(&mut self, sys: SysCache) {
+        self.on_host_refresh(self.active, sys);
+    }
+
+    /// Applies a snapshot from a host's collector and updates everything
+    /// derived from it: histories and trends, and for the host on screen
+    /// also the process list, plugins and alerts. Paused, it drops them.
+    pub fn on_host_refresh(&mut self, host: usize, sys: SysCache) {
+        if !self.paused {
+            self.apply_snapshot(host, sys, Instant::now());
+        }
+    }
+
+    fn apply_snapshot(&mut self, host: usize, sys: SysCache, now: Instant) {
+        let Some(h) = self.hosts.get_mut(host) else {
+            return;
+        };
+        let notice = sys.notice.clone();
+        h.apply(sys, now);
+        if host != self.active {
+            return;
+        }
+        if notice.is_some() {
+            self.notice = notice;
+        }
+        if let Some(focus) = &mut self.focus {
+            let h = &self.hosts[self.active];
+            // Also until the collector reports on it, e.g. right after start.
+            let changed = focus.update(&h.sys, now);
+            if changed || (focus.pid.is_some() && h.sys.focus.is_none()) {
+                if let Some(worker) = &h.worker {
+                    worker.set_focus(focus.pid);
+                }
+            }
+        }
+        self.update_visible();
+
+        let sys = &self.hosts[self.active].sys;
+        self.plugins.run(sys);
+        let events = self.alerts.evaluate(sys, now);
+        let link = events.iter().rev().find_map(|e| {
+            let name = e.metric.strip_prefix("link:")?;
+            Some(match e.kind {
+                AlertKind::Fired => format!("{} went down", name),
+                AlertKind::Resolved => format!("{} came up", name),
+            })
+        });
+        if link.is_some() {
+            self.notice = link;
+        }
+        self.log_alerts(events);
+    }
+
+    /// Plays `replay` on the host on screen, from its first frame.
+    pub fn start_replay(&mut self, replay: Replay) {
+        self.replay = Some(replay);
+        self.show_frame(false);
+    }
+
+    pub fn replay(&self) -> Option<&Replay> {
+        self.replay.as_ref()
+    }
+
+    /// Steps the replay and shows the frame it lands on. Stepping back
+    /// rebuilds the histories from the frames before it, so the charts look
+    /// as they did at that point.
+    fn step_replay(&mut self, forward: bool) {
+        if self.replay.as_mut().is_some_and(|r| r.step(forward)) {
+            self.show_frame(!forward);
         }
     }
 
-    pub fn on_tick(&mut self) {
-        self.sys.refresh();
+    fn show_frame(&mut self, rebuild: bool) {
+        let Some(replay) = &self.replay else {
+            return;
+        };
+        let position = replay.position();
+        if rebuild {
+            let host = &mut self.hosts[self.active];
+            let mut fresh = Host::new(std::mem::take(&mut host.name), self.tick_rate);
+            for i in *replay.window(HISTORY_WINDOW).start()..position {
+                fresh.apply(replay.frame(i).sys.clone(), replay.instant(i));
+            }
+            *host = fresh;
+        }
+        let (sys, now) = (replay.current().sys.clone(), replay.instant(position));
+        self.apply_snapshot(self.active, sys, now);
+    }
+
+    pub fn host(&self) -> &Host {
+        &self.hosts[self.active]
+    }
+
+    pub fn active_host(&self) -> usize {
+        self.active
+    }
 
-        self.cpu_history.remove(0);
-        self.cpu_history.push(self.sys.cpu_global as u64);
+    /// Shows host `index`, wrapping around; the selection starts at the top.
+    pub fn select_host(&mut self, index: usize) {
+        if self.hosts.is_empty() {
+            return;
+        }
+        let index = index % self.hosts.len();
+        if index != self.active {
+            self.active = index;
+            self.alerts.forget_host();
+            self.popup = None;
+            self.detail = None;
+            self.detail_rx = None;
+            self.table_state.select(Some(0));
+            self.update_visible();
+            self.sync_cgroups();
+        }
+    }
 
-        self.net_rx_history.remove(0);
-        self.net_rx_history.push(self.sys.rx_rate);
+    fn log_alerts(&mut self, events: Vec<AlertEvent>) {
+        if self.alerts.bell
+            && events
+                .iter()
+                .any(|e| e.kind == AlertKind::Fired && e.severity == Severity::Critical)
+        {
+            self.bell_pending = true;
+        }
+        for event in events {
+            self.hosts[self.active].events.alert(&event);
+            if self.alert_log.len() == ALERT_LOG_LEN {
+                self.alert_log.pop_front();
+            }
+            self.alert_log.push_back(event);
+        }
+    }
 
-        self.net_tx_history.remove(0);
-        self.net_tx_history.push(self.sys.tx_rate);
+    fn toggle_popup(&mut self, popup: Popup) {
+        self.popup = if self.popup == Some(popup) {
+            None
+        } else {
+            Some(popup)
+        };
     }
 
     pub fn on_key(&mut self, key: KeyEvent) {
+        let waking = self.dimmed;
+        self.last_input = Instant::now();
+        self.update_idle(self.last_input);
+        if waking {
+            return;
+        }
+        self.notice = None;
+        if self.popup == Some(Popup::AlertLog) && self.on_alert_key(key.code) {
+            return;
+        }
+        if self.popup == Some(Popup::Connect) && self.on_connect_key(key.code) {
+            return;
+        }
+        if self.popup == Some(Popup::Delta) && self.on_delta_key(key.code) {
+            return;
+        }
+        if self.popup == Some(Popup::Containers) && self.on_container_key(key.code) {
+            return;
+        }
+        if self.popup == Some(Popup::Cgroups) && self.on_cgroup_key(key.code) {
+            return;
+        }
+        if self.overview && self.input_mode == InputMode::Normal && self.on_overview_key(key.code)
+        {
+            return;
+        }
+        if self.replay.is_some() && self.input_mode == InputMode::Normal {
+            match key.code {
+                KeyCode::Left => return self.step_replay(false),
+                KeyCode::Right => return self.step_replay(true),
+                _ => {}
+            }
+        }
         match self.input_mode {
-            InputMode::Normal => match key.code {
-                KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::F(10) => self.should_quit = true,
-                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                    self.should_quit = true
-                }
-                KeyCode::Down | KeyCode::Char('n') => self.next(),
-                KeyCode::Up | KeyCode::Char('p') => self.previous(),
-                KeyCode::Char('k') | KeyCode::F(9) => self.kill(),
-                KeyCode::Char('/') | KeyCode::F(3) => self.input_mode = InputMode::Editing,
-                KeyCode::F(6) => {
-                    self.cycle_sort();
+            InputMode::Normal => {
+                if let Some(action) = self.config.keys.action(&key) {
+                    self.run(action);
+                } else if let KeyCode::Char(c @ '0'..='9') = key.code {
+                    self.select_tab(c as usize - '0' as usize);
                 }
-                KeyCode::Char('I') => {
-                    self.sort_desc = !self.sort_desc;
-                }
-                KeyCode::Tab => {
-                    self.cycle_sort();
-                }
-                _ => {}
-            },
+            }
             InputMode::Editing => match key.code {
                 KeyCode::Esc => self.input_mode = InputMode::Normal,
                 KeyCode::Enter => self.input_mode = InputMode::Normal,
                 KeyCode::Backspace => {
                     self.search_query.pop();
+                    self.update_visible();
                 }
                 KeyCode::Char(c) => {
                     self.search_query.push(c);
+                    self.update_visible();
                 }
                 _ => {}
             },
         }
     }
 
+    fn run(&mut self, action: Action) {
+        match action {
+            Action::Quit => self.should_quit = true,
+            Action::Down => self.scroll(self.panel, true),
+            Action::Up => self.scroll(self.panel, false),
+            Action::PageDown => self.select_process(self.selected_row() + PAGE),
+            Action::PageUp => self.select_process(self.selected_row().saturating_sub(PAGE)),
+            Action::Home => self.select_process(0),
+            Action::End => self.select_process(usize::MAX),
+            Action::ShowAll if self.process_limit.is_some() => {
+                self.show_all = !self.show_all;
+                self.update_visible();
+            }
+            Action::ShowAll => {}
+            Action::NextPanel => self.cycle_panel(),
+            Action::Kill => self.kill(),
+            Action::NiceUp => self.renice(1),
+            Action::NiceDown => self.renice(-1),
+            Action::Watch => self.toggle_watch(),
+            Action::Limit => self.cycle_limit(),
+            Action::Search => self.input_mode = InputMode::Editing,
+            Action::Sort => self.cycle_sort(),
+            Action::Reverse => {
+                self.sort_desc = !self.sort_desc;
+                self.update_visible();
+            }
+            Action::Tree => {
+                self.tree_view = !self.tree_view;
+                self.update_visible();
+            }
+            Action::Aggregate => {
+                self.aggregate = !self.aggregate;
+                self.update_visible();
+            }
+            Action::Projection => self.projection = !self.projection,
+            Action::Events => self.show_events = !self.show_events,
+            Action::Alerts => self.toggle_popup(Popup::AlertLog),
+            Action::Perf => self.toggle_popup(Popup::Perf),
+            Action::Connect => self.toggle_popup(Popup::Connect),
+            Action::Containers => self.toggle_popup(Popup::Containers),
+            Action::Cgroups => self.toggle_popup(Popup::Cgroups),
+            Action::Pods => self.toggle_popup(Popup::Pods),
+            Action::Stats => self.toggle_popup(Popup::Stats),
+            Action::Baseline => self.mark_baseline(),
+            Action::Delta => self.toggle_popup(Popup::Delta),
+            Action::Exits => self.toggle_popup(Popup::Exits),
+            Action::Help => self.toggle_popup(Popup::Help),
+            Action::Pause => self.paused = !self.paused,
+            Action::Snapshot => self.snapshot_pending = true,
+            Action::Export => self.export_json(),
+            Action::Overview if self.hosts.len() > 1 => {
+                self.overview = !self.overview;
+                self.overview_selected = self.active;
+            }
+            Action::Overview => {}
+            Action::NextHost => self.select_host(self.active + 1),
+            Action::PrevHost => {
+                self.select_host(self.active + self.hosts.len().saturating_sub(1))
+            }
+            Action::Details => self.open_detail(),
+            Action::Theme => {
+                self.theme = (self.theme + 1) % self.themes.len();
+                self.notice = Some(format!("theme {}", self.themes[self.theme].name));
+            }
+            Action::Faster => self.change_tick_rate(false),
+            Action::Slower => self.change_tick_rate(true),
+            Action::Close => {
+                self.popup = None;
+                self.panel = Panel::Processes;
+            }
+        }
+        self.sync_cgroups();
+    }
+
+    /// Has the worker of the host on screen read cgroups while the cgroup
+    /// or pod view is open, and no worker otherwise.
+    fn sync_cgroups(&mut self) {
+        let open = matches!(self.popup, Some(Popup::Cgroups | Popup::Pods));
+        let wanted = open.then_some(self.active);
+        if wanted == self.cgroups_host {
+            return;
+        }
+        for (host, on) in [(self.cgroups_host, false), (wanted, true)] {
+            let worker = host
+                .and_then(|h| self.hosts.get(h))
+                .and_then(|h| h.worker.as_ref());
+            if let Some(worker) = worker {
+                worker.set_cgroups(on);
+            }
+        }
+        self.cgroups_host = wanted;
+    }
+
+    /// Keys handled while the cgroup view is open: Enter unfolds or folds
+    /// the selected cgroup, Right unfolds it and Left folds it, or moves to
+    /// its parent if it is folded.
+    fn on_cgroup_key(&mut self, code: KeyCode) -> bool {
+        let list = &self.hosts[self.active].sys.cgroups;
+        let rows = cgroups::rows(list, &self.cgroup_expanded);
+        let selected = self.cgroup_selected.min(rows.len().saturating_sub(1));
+        let Some(row) = rows.get(selected) else {
+            return false;
+        };
+        let path = list[row.index].path.clone();
+        match code {
+            KeyCode::Down | KeyCode::Char('n') => {
+                self.cgroup_selected = (selected + 1).min(rows.len() - 1);
+            }
+            KeyCode::Up | KeyCode::Char('p') => {
+                self.cgroup_selected = selected.saturating_sub(1);
+            }
+            KeyCode::Enter if row.has_children => {
+                if !self.cgroup_expanded.remove(&path) {
+                    self.cgroup_expanded.insert(path);
+                }
+            }
+            KeyCode::Right if row.has_children => {
+                self.cgroup_expanded.insert(path);
+            }
+            KeyCode::Left => {
+                if !self.cgroup_expanded.remove(&path) {
+                    let parent = path.rsplit_once('/').map(|(parent, _)| parent);
+                    if let Some(i) = rows
+                        .iter()
+                        .position(|r| Some(list[r.index].path.as_str()) == parent)
+                    {
+                        self.cgroup_selected = i;
+                    }
+                }
+            }
+            KeyCode::Enter | KeyCode::Right => {}
+            _ => return false,
+        }
+        true
+    }
+
+    /// Keys handled while the alert popup is open; returns false for keys it
+    /// leaves to the normal bindings.
+    fn on_alert_key(&mut self, code: KeyCode) -> bool {
+        let active = self.alerts.active();
+        match code {
+            KeyCode::Down | KeyCode::Char('n') => {
+                self.alert_selected = (self.alert_selected + 1).min(active.len().saturating_sub(1));
+            }
+            KeyCode::Up | KeyCode::Char('p') => {
+                self.alert_selected = self.alert_selected.saturating_sub(1);
+            }
+            KeyCode::Char('x') => {
+                if let Some(alert) = active.get(self.alert_selected) {
+                    self.alerts.acknowledge(&alert.name);
+                }
+            }
+            KeyCode::Char('s') => {
+                if let Some(alert) = active.get(self.alert_selected) {
+                    self.alerts.snooze(&alert.name, Instant::now());
+                    self.alert_selected = self.alert_selected.saturating_sub(1);
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Keys handled while the overview is shown: move between host cards
+    /// and drill into one with Enter.
+    /// The host tab numbered `tab`: 0 is the overview, 1 the first host.
+    /// Only with several hosts, like the tabs.
+    fn select_tab(&mut self, tab: usize) {
+        if self.hosts.len() < 2 || tab > self.hosts.len() {
+            return;
+        }
+        self.overview = tab == 0;
+        if let Some(host) = tab.checked_sub(1) {
+            self.select_host(host);
+        }
+        self.overview_selected = self.active;
+    }
+
+    fn on_overview_key(&mut self, code: KeyCode) -> bool {
+        let last = self.hosts.len().saturating_sub(1);
+        match code {
+            KeyCode::Right | KeyCode::Down | KeyCode::Char('n') => {
+                self.overview_selected = (self.overview_selected + 1).min(last);
+            }
+            KeyCode::Left | KeyCode::Up | KeyCode::Char('p') => {
+                self.overview_selected = self.overview_selected.saturating_sub(1);
+            }
+            KeyCode::Enter => {
+                self.overview = false;
+                self.select_host(self.overview_selected);
+            }
+            KeyCode::Esc => self.overview = false,
+            _ => return false,
+        }
+        true
+    }
+
+    /// Keys handled while the delta popup is open.
+    fn on_delta_key(&mut self, code: KeyCode) -> bool {
+        match code {
+            KeyCode::Char('m') => {
+                self.delta_sort = match self.delta_sort {
+                    DeltaSort::CpuTime => DeltaSort::MemGrowth,
+                    DeltaSort::MemGrowth => DeltaSort::CpuTime,
+                };
+            }
+            KeyCode::Char('b') => self.mark_baseline(),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Saves the snapshot of the host on screen as JSON in the working
+    /// directory.
+    fn export_json(&mut self) {
+        self.notice = Some(match snapshot::save_json(self.sys(), Path::new(".")) {
+            Ok(path) => format!("Saved {}", path.display()),
+            Err(e) => format!("Export failed: {}", e),
+        });
+    }
+
+    /// Starts comparing the host on screen against its current snapshot.
+    fn mark_baseline(&mut self) {
+        let host = &mut self.hosts[self.active];
+        let baseline = Baseline::new(&host.sys, Instant::now());
+        self.notice = Some(format!(
+            "baseline marked at {}, B shows the changes",
+            baseline.at.format("%H:%M:%S")
+        ));
+        host.baseline = Some(baseline);
+    }
+
+    /// Keys handled while the connect popup is open.
+    fn on_connect_key(&mut self, code: KeyCode) -> bool {
+        match code {
+            KeyCode::Down | KeyCode::Char('n') => {
+                self.connect_selected =
+                    (self.connect_selected + 1).min(self.discovered.len().saturating_sub(1));
+            }
+            KeyCode::Up | KeyCode::Char('p') => {
+                self.connect_selected = self.connect_selected.saturating_sub(1);
+            }
+            KeyCode::Enter => {
+                if let Some(agent) = self.discovered.get(self.connect_selected) {
+                    let addr = agent.addr.clone();
+                    let host = self.connect(addr);
+                    self.popup = None;
+                    self.select_host(host);
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Keys handled while the containers popup is open: `s` and `k` ask to
+    /// stop or kill the selected container, and while asking, `y` confirms
+    /// and any other key cancels.
+    fn on_container_key(&mut self, code: KeyCode) -> bool {
+        if let Some(request) = self.container_confirm.take() {
+            if code == KeyCode::Char('y') {
+                if let Some(control) = &self.container_control {
+                    let _ = control.send(request);
+                }
+            }
+            return true;
+        }
+        let len = self.containers.as_ref().map_or(0, |c| c.list.len());
+        let action = match code {
+            KeyCode::Down | KeyCode::Char('n') => {
+                self.container_selected = (self.container_selected + 1).min(len.saturating_sub(1));
+                return true;
+            }
+            KeyCode::Up | KeyCode::Char('p') => {
+                self.container_selected = self.container_selected.saturating_sub(1);
+                return true;
+            }
+            KeyCode::Char('s') => ContainerAction::Stop,
+            KeyCode::Char('k') => ContainerAction::Kill,
+            _ => return false,
+        };
+        let selected = self
+            .containers
+            .as_ref()
+            .and_then(|c| c.list.get(self.container_selected));
+        if let Some(container) = selected {
+            self.container_confirm = Some(ContainerRequest {
+                action,
+                id: container.id.clone(),
+                name: container.name.clone(),
+            });
+        }
+        true
+    }
+
+    pub fn on_containers(&mut self, containers: Containers) {
+        self.container_rates.record(&containers.list);
+        self.container_selected = self
+            .container_selected
+            .min(containers.list.len().saturating_sub(1));
+        if containers.notice.is_some() {
+            self.notice = containers.notice.clone();
+        }
+        self.containers = Some(containers);
+    }
+
+    /// Remembers an announced agent, once per address.
+    pub fn on_discovered(&mut self, agent: Agent) {
+        if !self.discovered.iter().any(|a| a.addr == agent.addr) {
+            self.discovered.push(agent);
+        }
+    }
+
+    /// Adds a host for the agent at `addr` unless one exists, and returns its
+    /// index. The caller starts a worker for every host that lacks one.
+    pub fn connect(&mut self, addr: String) -> usize {
+        if let Some(i) = self.hosts.iter().position(|h| h.name == addr) {
+            return i;
+        }
+        self.hosts.push(Host::new(addr, self.tick_rate));
+        self.hosts.len() - 1
+    }
+
     fn cycle_sort(&mut self) {
         self.sort_col = match self.sort_col {
             SortColumn::Pid => SortColumn::Name,
@@ -113,11 +855,71 @@ impl App {
             SortColumn::Cpu => SortColumn::Mem,
             SortColumn::Mem => SortColumn::Pid,
         };
+        self.update_visible();
+    }
+
+    /// Moves the focus to the next panel, skipping Cores where there are no
+    /// per-core figures, the GPU panel where it lists no processes and the
+    /// Sensors panel where there is nothing to show.
+    fn cycle_panel(&mut self) {
+        let gpu = self.config.panels.gpu && !self.gpu_pids().is_empty();
+        let sensors = self.config.panels.sensors && sensor_rows(self.sys()) > 0;
+        self.panel = match self.panel {
+            Panel::Processes if !self.sys().cpu_cores.is_empty() => Panel::Cores,
+            Panel::Processes | Panel::Cores => Panel::Disks,
+            Panel::Disks if gpu => Panel::Gpu,
+            Panel::Disks | Panel::Gpu if sensors => Panel::Sensors,
+            Panel::Disks | Panel::Gpu | Panel::Sensors => Panel::Processes,
+        };
+    }
+
+    /// The processes of every GPU, as the GPU panel lists them.
+    pub fn gpu_pids(&self) -> Vec<u32> {
+        let gpus = &self.sys().gpus;
+        gpus.iter().flat_map(|gpu| gpu.processes.iter().map(|p| p.pid)).collect()
+    }
+
+    /// One row down or up in `panel`: the selection for processes, the
+    /// first row shown for the others.
+    fn scroll(&mut self, panel: Panel, down: bool) {
+        let (offset, len) = match panel {
+            Panel::Processes if down => return self.next(),
+            Panel::Processes => return self.previous(),
+            Panel::Cores => (&mut self.cores_scroll, self.hosts[self.active].sys.cpu_cores.len()),
+            Panel::Disks => (&mut self.disks_scroll, self.hosts[self.active].sys.disks().len()),
+            Panel::Sensors => (&mut self.sensors_scroll, sensor_rows(&self.hosts[self.active].sys)),
+            Panel::Gpu => {
+                let gpus = &self.hosts[self.active].sys.gpus;
+                (&mut self.gpu_selected, gpus.iter().map(|g| g.processes.len()).sum())
+            }
+        };
+        *offset = if down {
+            (*offset + 1).min(len.saturating_sub(1))
+        } else {
+            offset.saturating_sub(1)
+        };
+    }
+
+    /// Scrolls the panel under the mouse wheel.
+    pub fn on_mouse(&mut self, event: MouseEvent) {
+        let down = match event.kind {
+            MouseEventKind::ScrollDown => true,
+            MouseEventKind::ScrollUp => false,
+            _ => return,
+        };
+        let hit = self.panel_areas.iter().find(|(_, area)| {
+            (area.left()..area.right()).contains(&event.column)
+                && (area.top()..area.bottom()).contains(&event.row)
+        });
+        if let Some(&(panel, _)) = hit {
+            self.scroll(panel, down);
+        }
     }
 
     fn next(&mut self) {
+        let last = self.visible.len().saturating_sub(1);
         let i = match self.table_state.selected() {
-            Some(i) => i + 1,
+            Some(i) => (i + 1).min(last),
             None => 0,
         };
         self.table_state.select(Some(i));
@@ -137,22 +939,371 @@ impl App {
         self.table_state.select(Some(i));
     }
 
-    fn kill(&mut self) {
+    fn selected_row(&self) -> usize {
+        self.table_state.selected().unwrap_or(0)
+    }
+
+    /// Selects row `i` of the table, or the last row past the end.
+    fn select_process(&mut self, i: usize) {
+        self.table_state
+            .select(Some(i.min(self.visible.len().saturating_sub(1))));
+    }
+
+    /// How many processes matched the search; more than
+    /// [`visible_processes`](Self::visible_processes) when the limit hides some.
+    pub fn matched_processes(&self) -> usize {
+        self.matched
+    }
+
+    /// The rows of the process table that fit in `rows` lines, scrolled just
+    /// enough to keep the selection in view. Only these rows are built each
+    /// frame, so drawing costs the same with 100 processes or 10,000.
+    pub fn process_window(&mut self, rows: usize) -> Range<usize> {
+        let len = self.visible.len();
+        let selected = self.selected_row();
+        let mut offset = self.process_offset.min(len.saturating_sub(rows));
+        if selected < offset {
+            offset = selected;
+        } else if rows > 0 && selected >= offset + rows {
+            offset = selected + 1 - rows;
+        }
+        self.process_offset = offset;
+        offset..(offset + rows).min(len)
+    }
+
+    /// Processes matching the search query, in the current sort order; the
+    /// table selection indexes into this list.
+    pub fn visible_processes(&self) -> Vec<&ProcessInfo> {
+        let procs = self.hosts[self.active].sys.processes();
+        self.visible.iter().map(|&i| &procs[i]).collect()
+    }
+
+    /// Indentation of each row of [`visible_processes`](Self::visible_processes)
+    /// in the tree view; empty otherwise.
+    pub fn visible_depths(&self) -> &[usize] {
+        &self.depths
+    }
+
+    /// Recomputes the filtered, sorted process list and keeps the selection
+    /// within it. In the tree view children follow their parent and only
+    /// siblings are sorted.
+    fn update_visible(&mut self) {
+        let query = self.search_query.to_lowercase();
+        let procs = self.hosts[self.active].sys.processes();
+        let matches = |p: &ProcessInfo| {
+            p.name.to_lowercase().contains(&query) || p.pid.to_string().contains(&query)
+        };
+
+        self.totals = if self.aggregating() {
+            self.hosts[self.active].tree.subtree_totals(procs)
+        } else {
+            HashMap::new()
+        };
+
+        // Reuse the buffers; taken out so the sort closures can borrow self.
+        let mut visible = std::mem::take(&mut self.visible);
+        let mut depths = std::mem::take(&mut self.depths);
+        visible.clear();
+        depths.clear();
+        if self.tree_view {
+            let index: HashMap<u32, usize> =
+                procs.iter().enumerate().map(|(i, p)| (p.pid, i)).collect();
+            self.hosts[self.active].tree.walk(
+                |a, b| match (index.get(&a), index.get(&b)) {
+                    (Some(&a), Some(&b)) => self.compare(&procs[a], &procs[b]),
+                    _ => a.cmp(&b),
+                },
+                |pid, depth| {
+                    if let Some(&i) = index.get(&pid) {
+                        if matches(&procs[i]) {
+                            visible.push(i);
+                            depths.push(depth);
+                        }
+                    }
+                },
+            );
+        } else {
+            visible.extend((0..procs.len()).filter(|&i| matches(&procs[i])));
+            visible.sort_by(|&a, &b| self.compare(&procs[a], &procs[b]));
+        }
+        self.matched = visible.len();
+        if let Some(limit) = self.process_limit.filter(|_| !self.show_all) {
+            visible.truncate(limit);
+            depths.truncate(limit);
+        }
+        self.visible = visible;
+        self.depths = depths;
+
         if let Some(i) = self.table_state.selected() {
-            if let Some(proc) = self.sys.processes().get(i) {
-                self.sys.kill_process(proc.pid);
+            self.table_state
+                .select(Some(i.min(self.visible.len().saturating_sub(1))));
+        }
+    }
+
+    /// Whether the table shows subtree totals: `c` is on and the tree view,
+    /// which shows the children themselves, is off.
+    pub fn aggregating(&self) -> bool {
+        self.aggregate && !self.tree_view
+    }
+
+    /// The CPU and memory the table shows for `p`, its subtree's while
+    /// [`aggregating`](Self::aggregating).
+    pub fn usage(&self, p: &ProcessInfo) -> (f32, u64) {
+        self.totals
+            .get(&p.pid)
+            .copied()
+            .unwrap_or((p.cpu, p.mem_bytes))
+    }
+
+    fn compare(&self, a: &ProcessInfo, b: &ProcessInfo) -> Ordering {
+        let ((a_cpu, a_mem), (b_cpu, b_mem)) = (self.usage(a), self.usage(b));
+        let ord = match self.sort_col {
+            SortColumn::Pid => a.pid.cmp(&b.pid),
+            SortColumn::Name => a.name.cmp(&b.name),
+            SortColumn::User => a.user.cmp(&b.user),
+            SortColumn::Cpu => a_cpu.partial_cmp(&b_cpu).unwrap_or(Ordering::Equal),
+            SortColumn::Mem => a_mem.cmp(&b_mem),
+        };
+        if self.sort_desc {
+            ord.reverse()
+        } else {
+            ord
+        }
+    }
+
+    /// The selected row of the process table, or of the GPU panel while it
+    /// has the focus. A GPU process outside the process list, e.g. in
+    /// another PID namespace, can't be acted on.
+    fn selected_process(&self) -> Option<ProcessInfo> {
+        if self.panel == Panel::Gpu {
+            let pid = *self.gpu_pids().get(self.gpu_selected)?;
+            return self.host().sys.processes().iter().find(|p| p.pid == pid).cloned();
+        }
+        let i = *self.visible.get(self.table_state.selected()?)?;
+        self.host().sys.processes().get(i).cloned()
+    }
+
+    fn kill(&mut self) {
+        if let (Some(proc), Some(worker)) = (self.selected_process(), &self.host().worker) {
+            worker.kill_process(proc.pid);
+        }
+    }
+
+    /// Raises (positive) or lowers the selected process's nice value.
+    fn renice(&mut self, delta: i32) {
+        if let (Some(proc), Some(worker)) = (self.selected_process(), &self.host().worker) {
+            worker.renice_process(proc.pid, delta);
+        }
+    }
+
+    /// Steps the selected process's CPU cap through [`CPU_LIMITS`], then
+    /// lifts it.
+    fn cycle_limit(&mut self) {
+        let (Some(proc), Some(worker)) = (self.selected_process(), &self.host().worker) else {
+            return;
+        };
+        let current = self.host().sys.limits.get(&proc.pid).copied();
+        let next = match current {
+            None => Some(CPU_LIMITS[0]),
+            Some(limit) => CPU_LIMITS.iter().copied().find(|&l| l < limit),
+        };
+        worker.limit_process(proc.pid, next);
+    }
+
+    /// Opens the detail popup for the selected process and asks the worker
+    /// for its command line and environment.
+    fn open_detail(&mut self) {
+        let Some(proc) = self.selected_process() else {
+            return;
+        };
+        self.popup = Some(Popup::Process);
+        self.detail = Some(ProcessDetail {
+            pid: proc.pid,
+            name: proc.name.to_string(),
+            exe: proc.cmd.to_string(),
+            ..ProcessDetail::default()
+        });
+        self.detail_rx = self
+            .host()
+            .worker
+            .as_ref()
+            .map(|w| w.process_detail(proc.pid));
+    }
+
+    /// Picks up a detail answer from the worker, if one has arrived.
+    pub fn poll_detail(&mut self) {
+        let Some(rx) = &mut self.detail_rx else {
+            return;
+        };
+        match rx.try_recv() {
+            Ok(detail) => {
+                self.detail_rx = None;
+                if let Some(detail) = detail {
+                    self.detail = Some(detail);
+                }
+            }
+            Err(TryRecvError::Empty) => {}
+            Err(TryRecvError::Closed) => self.detail_rx = None,
+        }
+    }
+
+    /// Whether details for the popup are still being fetched.
+    pub fn detail_pending(&self) -> bool {
+        self.detail_rx.is_some()
+    }
+
+    fn toggle_watch(&mut self) {
+        if let Some(proc) = self.selected_process() {
+            self.alerts.watchdog.toggle(&proc);
+        }
+    }
+
+    /// Doubles (`slower`) or halves the update interval within
+    /// [`MIN_TICK_RATE`]..=[`MAX_TICK_RATE`].
+    fn change_tick_rate(&mut self, slower: bool) {
+        let rate = if slower {
+            self.tick_rate * 2
+        } else {
+            self.tick_rate / 2
+        };
+        let rate = rate.clamp(MIN_TICK_RATE, MAX_TICK_RATE);
+        if rate != self.tick_rate {
+            self.tick_rate = rate;
+            self.tick_rate_changed = true;
+        }
+    }
+
+    pub fn tick_rate(&self) -> Duration {
+        self.tick_rate
+    }
+
+    /// The interval actually in use: the chosen one, or the idle rate if
+    /// that is slower and the user is away.
+    pub fn effective_tick_rate(&self) -> Duration {
+        let mut rate = self.tick_rate;
+        if let Some(idle) = self.idle_rate.filter(|_| self.idle) {
+            rate = rate.max(idle);
+        }
+        if self.dimmed {
+            rate = rate.max(self.screensaver.refresh());
+        }
+        rate
+    }
+
+    /// The new update interval if it changed since the last call; the caller
+    /// passes it on to the timer and the collector.
+    pub fn take_tick_rate_change(&mut self) -> Option<Duration> {
+        std::mem::take(&mut self.tick_rate_changed).then(|| self.effective_tick_rate())
+    }
+
+    /// Slows the tick to `rate` while the terminal is unfocused or has had
+    /// no input for `after`; `None` disables the slowdown.
+    pub fn set_idle_rate(&mut self, rate: Option<Duration>, after: Duration) {
+        self.idle_rate = rate;
+        self.idle_after = after;
+    }
+
+    pub fn on_focus(&mut self, focused: bool) {
+        self.focused = focused;
+        self.update_idle(Instant::now());
+    }
+
+    /// Called on every tick to notice when input has stopped, and to play
+    /// the next frame of a replay.
+    pub fn on_tick(&mut self, now: Instant) {
+        self.update_idle(now);
+        if !self.paused && self.replay.is_some() {
+            self.step_replay(true);
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.idle
+    }
+
+    /// Whether the screensaver has dimmed the screen.
+    pub fn is_dimmed(&self) -> bool {
+        self.dimmed
+    }
+
+    fn update_idle(&mut self, now: Instant) {
+        let quiet = now.duration_since(self.last_input);
+        let idle = self.idle_rate.is_some() && (!self.focused || quiet >= self.idle_after);
+        let dimmed = self.screensaver.after().is_some_and(|after| quiet >= after);
+        if (idle, dimmed) != (self.idle, self.dimmed) {
+            let before = self.effective_tick_rate();
+            self.idle = idle;
+            self.dimmed = dimmed;
+            if self.effective_tick_rate() != before {
+                self.tick_rate_changed = true;
             }
         }
     }
 
+    /// Whether a critical alert fired since the last call; the caller rings
+    /// the terminal bell.
+    /// The palette of the current theme, in 16 colours unless the terminal
+    /// shows 24-bit ones.
+    pub fn colors(&self) -> Colors {
+        let colors = self.themes[self.theme].colors;
+        if self.truecolor() {
+            colors
+        } else {
+            theme::ansi16(colors)
+        }
+    }
+
+    pub fn truecolor(&self) -> bool {
+        self.config.truecolor != Some(false)
+    }
+
+    pub fn take_bell(&mut self) -> bool {
+        std::mem::take(&mut self.bell_pending)
+    }
+
+    /// Whether `X` asked for the frame to be saved since the last call; the
+    /// caller does it once the frame is drawn.
+    pub fn take_snapshot(&mut self) -> bool {
+        std::mem::take(&mut self.snapshot_pending)
+    }
+
+    /// Whether the top bar should flash: `flash` is enabled and a critical
+    /// alert is active and unacknowledged.
+    pub fn is_flashing(&self) -> bool {
+        self.alerts.flash && self.alerts.has_critical()
+    }
+
     pub fn request_quit(&mut self) {
         self.should_quit = true;
     }
     pub fn should_quit(&self) -> bool {
         self.should_quit
     }
+    /// Records how long the last frame took to draw, for the perf popup.
+    pub fn record_frame(&mut self, elapsed: Duration) {
+        self.frame_time = elapsed;
+    }
+
+    pub fn frame_time(&self) -> Duration {
+        self.frame_time
+    }
+
+    /// This process as of the last refresh, for its own CPU and memory use;
+    /// `None` while a remote host is on screen.
+    pub fn own_process(&self) -> Option<&ProcessInfo> {
+        let pid = std::process::id();
+        let host = self.host();
+        host.is_local()
+            .then(|| host.sys.processes().iter().find(|p| p.pid == pid))
+            .flatten()
+    }
+
     pub fn sys(&self) -> &SysCache {
-        &self.sys
+        &self.host().sys
     }
 }
 
@@ -164,7 +1315,7 @@ mod tests {
 
     #[test]
     fn test_app_new() {
-        let app = App::new(Duration::from_millis(100));
+        let app = App::new(Config::default());
         assert_eq!(app.input_mode, InputMode::Normal);
         assert!(!app.should_quit());
         assert_eq!(app.sort_col, SortColumn::Cpu);
@@ -172,7 +1323,7 @@ mod tests {
 
     #[test]
     fn test_cycle_sort() {
-        let mut app = App::new(Duration::from_millis(100));
+        let mut app = App::new(Config::default());
         assert_eq!(app.sort_col, SortColumn::Cpu); // Initial
         app.cycle_sort();
         assert_eq!(app.sort_col, SortColumn::Mem);
@@ -180,9 +1331,491 @@ mod tests {
         assert_eq!(app.sort_col, SortColumn::Pid);
     }
 
+    #[test]
+    fn test_alert_log_popup() {
+        let mut app = App::new(Config::default());
+        let key = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::empty());
+        app.on_key(key('a'));
+        assert_eq!(app.popup, Some(Popup::AlertLog));
+        app.on_key(key('a'));
+        assert_eq!(app.popup, None);
+        app.on_key(key('a'));
+        app.on_key(KeyEvent::new(KeyCode::Esc, KeyModifiers::empty()));
+        assert_eq!(app.popup, None);
+    }
+
+    #[test]
+    fn test_event_log_panel() {
+        let mut app = App::new(Config::default());
+        let key = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::empty());
+        app.on_key(key('e'));
+        assert!(app.show_events);
+        assert_eq!(app.popup, None);
+        app.on_key(key('e'));
+        assert!(!app.show_events);
+    }
+
+    #[test]
+    fn test_bell_on_critical_alert() {
+        let mut app = App::new(Config::default());
+        app.alerts = AlertEngine::new(
+            alerts::parse_rules(
+                "[[rule]]\nmetric = \"cpu\"\nthreshold = 90\nseverity = \"critical\"",
+            )
+            .unwrap(),
+        );
+        app.alerts.bell = true;
+        app.alerts.flash = true;
+        let events = app
+            .alerts
+            .evaluate_with(|_| Some(99.0), std::time::Instant::now());
+        app.log_alerts(events);
+        assert!(app.take_bell());
+        assert!(!app.take_bell());
+        assert!(app.is_flashing());
+        assert_eq!(app.host().events.entries.len(), 1);
+
+        app.on_key(KeyEvent::new(KeyCode::Char('a'), KeyModifiers::empty()));
+        app.on_key(KeyEvent::new(KeyCode::Char('x'), KeyModifiers::empty()));
+        assert!(!app.is_flashing());
+        assert_eq!(app.alerts.active().len(), 1);
+        app.on_key(KeyEvent::new(KeyCode::Char('s'), KeyModifiers::empty()));
+        assert!(app.alerts.active().is_empty());
+    }
+
+    #[test]
+    fn test_visible_processes_follow_search_and_sort() {
+        let mut app = App::new(Config::default());
+        app.on_refresh(SysCache::new());
+        let all = app.visible_processes().len();
+        assert_eq!(all, app.sys().processes().len());
+        app.table_state.select(Some(all - 1));
+
+        let pid = std::process::id().to_string();
+        app.on_key(KeyEvent::new(KeyCode::Char('/'), KeyModifiers::empty()));
+        for c in pid.chars() {
+            app.on_key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::empty()));
+        }
+        let visible = app.visible_processes();
+        assert!(visible.iter().all(|p| p.pid.to_string().contains(&pid)));
+        assert!(visible.iter().any(|p| p.pid.to_string() == pid));
+        assert!(app.table_state.selected().unwrap() < visible.len());
+
+        app.search_query.clear();
+        app.sort_col = SortColumn::Mem;
+        app.cycle_sort();
+        let pids: Vec<u32> = app.visible_processes().iter().map(|p| p.pid).collect();
+        assert!(pids.windows(2).all(|w| w[0] >= w[1]));
+    }
+
+    #[test]
+    fn test_process_limit_and_window() {
+        let procs = (1..=30)
+            .map(|pid| ProcessInfo {
+                pid,
+                name: format!("p{}", pid).into(),
+                user: "u".into(),
+                cmd: "".into(),
+                cpu: pid as f32,
+                mem_bytes: 0,
+                parent: None,
+            })
+            .collect();
+        let mut app = App::new(Config::default());
+        app.process_limit = Some(10);
+        app.on_refresh(SysCache::default().with_processes(procs));
+        assert_eq!(app.visible_processes().len(), 10);
+        assert_eq!(app.matched_processes(), 30);
+        assert_eq!(app.visible_processes()[0].pid, 30);
+
+        app.on_key(KeyEvent::new(KeyCode::Char('A'), KeyModifiers::empty()));
+        assert_eq!(app.visible_processes().len(), 30);
+        assert_eq!(app.process_window(8), 0..8);
+        app.on_key(KeyEvent::new(KeyCode::End, KeyModifiers::empty()));
+        assert_eq!(app.process_window(8), 22..30);
+        app.on_key(KeyEvent::new(KeyCode::PageUp, KeyModifiers::empty()));
+        assert_eq!(app.process_window(8), 9..17);
+    }
+
+    #[test]
+    fn test_tree_view_orders_children_after_parents() {
+        let proc = |pid, parent, cpu| ProcessInfo {
+            pid,
+            name: format!("p{}", pid).into(),
+            user: "u".into(),
+            cmd: "".into(),
+            cpu,
+            mem_bytes: 0,
+            parent,
+        };
+        let mut app = App::new(Config::default());
+        app.on_refresh(SysCache::default().with_processes(vec![
+            proc(1, None, 0.0),
+            proc(2, Some(1), 5.0),
+            proc(3, Some(2), 1.0),
+            proc(4, Some(1), 9.0),
+        ]));
+        let pids =
+            |app: &App| -> Vec<u32> { app.visible_processes().iter().map(|p| p.pid).collect() };
+        assert_eq!(pids(&app), vec![4, 2, 3, 1]);
+
+        app.on_key(KeyEvent::new(KeyCode::Char('t'), KeyModifiers::empty()));
+        assert_eq!(pids(&app), vec![1, 4, 2, 3]);
+        assert_eq!(app.visible_depths(), &[0, 1, 1, 2]);
+
+        // Flat again, with each subtree's CPU folded into its root.
+        app.on_key(KeyEvent::new(KeyCode::Char('t'), KeyModifiers::empty()));
+        app.on_key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::empty()));
+        assert_eq!(pids(&app), vec![1, 4, 2, 3]);
+        assert_eq!(app.usage(app.visible_processes()[0]), (15.0, 0));
+    }
+
+    #[test]
+    fn test_perf_popup() {
+        let mut app = App::new(Config::default());
+        app.on_refresh(SysCache::new());
+        app.on_key(KeyEvent::new(KeyCode::Char('P'), KeyModifiers::SHIFT));
+        assert_eq!(app.popup, Some(Popup::Perf));
+        assert!(app.own_process().is_some());
+        assert!(app.sys().timings.total >= app.sys().timings.processes);
+    }
+
+    #[test]
+    fn test_hosts_keep_their_own_state() {
+        let mut app = App::with_hosts(Config::default(), vec!["a:1".into(), "b:1".into()]);
+        let mut sys = SysCache::default();
+        sys.cpu_global = 40.0;
+        app.on_host_refresh(1, sys);
+        assert_eq!(app.sys().cpu_global, 0.0);
+        assert_eq!(app.host().label(), "a:1");
+
+        app.on_key(KeyEvent::new(KeyCode::Char(']'), KeyModifiers::empty()));
+        assert_eq!(app.active_host(), 1);
+        assert_eq!(app.sys().cpu_global, 40.0);
+        assert_eq!(app.host().cpu_history.latest(), 40);
+        app.on_key(KeyEvent::new(KeyCode::Char(']'), KeyModifiers::empty()));
+        assert_eq!(app.active_host(), 0);
+        app.on_key(KeyEvent::new(KeyCode::Char('['), KeyModifiers::empty()));
+        assert_eq!(app.active_host(), 1);
+    }
+
+    #[test]
+    fn test_host_switch_keeps_links_apart() {
+        use crate::sys::Interface;
+
+        let mut app = App::with_hosts(Config::default(), vec!["a:1".into(), "b:1".into()]);
+        let with_link = |name: &str| {
+            let mut sys = SysCache::default();
+            sys.interfaces = vec![Interface {
+                name: name.into(),
+                up: true,
+            }];
+            sys
+        };
+        app.on_host_refresh(0, with_link("eth0"));
+        app.on_host_refresh(0, with_link("eth0"));
+        app.select_host(1);
+        app.on_host_refresh(1, with_link("wlan0"));
+        app.on_host_refresh(1, with_link("wlan0"));
+        app.select_host(0);
+        app.on_host_refresh(0, with_link("eth0"));
+        assert_eq!(app.notice, None);
+        assert!(app.alerts.active().is_empty());
+    }
+
+    #[test]
+    fn test_notice_until_next_key() {
+        let mut app = App::with_hosts(Config::default(), vec!["a:1".into(), "b:1".into()]);
+        let mut sys = SysCache::default();
+        sys.notice = Some("kill is not allowed on this agent".into());
+        app.on_host_refresh(1, sys.clone());
+        assert_eq!(app.notice, None);
+        app.on_host_refresh(0, sys);
+        assert_eq!(
+            app.notice.as_deref(),
+            Some("kill is not allowed on this agent")
+        );
+        app.on_host_refresh(0, SysCache::default());
+        assert!(app.notice.is_some());
+        app.on_key(KeyEvent::new(KeyCode::Down, KeyModifiers::empty()));
+        assert_eq!(app.notice, None);
+    }
+
+    #[test]
+    fn test_overview_drill_down() {
+        let mut app = App::with_hosts(Config::default(), vec!["a:1".into(), "b:1".into()]);
+        let key = |c| KeyEvent::new(c, KeyModifiers::empty());
+        app.on_key(key(KeyCode::Char('o')));
+        assert!(app.overview);
+        app.on_key(key(KeyCode::Right));
+        app.on_key(key(KeyCode::Right));
+        assert_eq!(app.overview_selected, 1);
+        app.on_key(key(KeyCode::Enter));
+        assert!(!app.overview);
+        assert_eq!(app.active_host(), 1);
+
+        let mut single = App::new(Config::default());
+        single.on_key(key(KeyCode::Char('o')));
+        assert!(!single.overview);
+        single.on_key(key(KeyCode::Char('0')));
+        assert!(!single.overview);
+    }
+
+    #[test]
+    fn test_number_keys_select_hosts() {
+        let mut app = App::with_hosts(Config::default(), vec!["a:1".into(), "b:1".into()]);
+        let key = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::empty());
+        app.on_key(key('2'));
+        assert_eq!(app.active_host(), 1);
+        app.on_key(key('0'));
+        assert!(app.overview);
+        assert_eq!(app.overview_selected, 1);
+        app.on_key(key('1'));
+        assert!(!app.overview);
+        assert_eq!(app.active_host(), 0);
+        app.on_key(key('3'));
+        assert_eq!(app.active_host(), 0);
+    }
+
+    #[test]
+    fn test_stop_container() {
+        use crate::containers::ContainerInfo;
+
+        let mut app = App::new(Config::default());
+        let (control, mut requests) = tokio::sync::mpsc::unbounded_channel();
+        app.container_control = Some(control);
+        let container = |id: &str| ContainerInfo {
+            id: id.into(),
+            name: format!("{}-name", id),
+            ..ContainerInfo::default()
+        };
+        app.on_containers(Containers {
+            host: "local".into(),
+            list: vec![container("a"), container("b")],
+            ..Containers::default()
+        });
+        let key = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::empty());
+        app.on_key(key('D'));
+        app.on_key(key('n'));
+        app.on_key(key('s'));
+        let asked = app.container_confirm.clone().unwrap();
+        assert_eq!((asked.action, asked.id.as_str()), (ContainerAction::Stop, "b"));
+
+        // Anything but y cancels, without leaving the popup.
+        app.on_key(key('q'));
+        assert!(app.container_confirm.is_none() && !app.should_quit());
+        assert!(requests.try_recv().is_err());
+
+        app.on_key(key('k'));
+        app.on_key(key('y'));
+        let sent = requests.try_recv().unwrap();
+        assert_eq!((sent.action, sent.name.as_str()), (ContainerAction::Kill, "b-name"));
+        assert_eq!(app.popup, Some(Popup::Containers));
+
+        app.on_containers(Containers {
+            list: vec![container("a")],
+            notice: Some("killed b-name".into()),
+            ..Containers::default()
+        });
+        assert_eq!(app.container_selected, 0);
+        assert_eq!(app.notice.as_deref(), Some("killed b-name"));
+    }
+
+    #[test]
+    fn test_gpu_process_focus() {
+        use crate::sys::gpu::{GpuInfo, GpuProcess};
+        use crate::sys::ProcessInfo;
+
+        let proc = |pid| ProcessInfo {
+            pid,
+            name: "job".into(),
+            user: "me".into(),
+            cmd: "job".into(),
+            cpu: 0.0,
+            mem_bytes: 0,
+            parent: None,
+        };
+        let mut sys = SysCache::default().with_processes(vec![proc(1), proc(40), proc(41)]);
+        sys.gpus = [40, 41]
+            .map(|pid| GpuInfo {
+                processes: vec![GpuProcess { pid, mem: None }],
+                ..GpuInfo::default()
+            })
+            .to_vec();
+        let mut app = App::new(Config::default());
+        app.on_refresh(sys);
+        let key = |c| KeyEvent::new(c, KeyModifiers::empty());
+        app.on_key(key(KeyCode::Char('f')));
+        app.on_key(key(KeyCode::Char('f')));
+        assert_eq!(app.panel, Panel::Gpu);
+        assert_eq!(app.selected_process().map(|p| p.pid), Some(40));
+        // Across GPUs, and no further than the last process.
+        app.on_key(key(KeyCode::Down));
+        app.on_key(key(KeyCode::Down));
+        assert_eq!(app.selected_process().map(|p| p.pid), Some(41));
+
+        app.on_key(key(KeyCode::Char('f')));
+        assert_eq!(app.panel, Panel::Processes);
+        app.config.panels.gpu = false;
+        app.on_key(key(KeyCode::Char('f')));
+        app.on_key(key(KeyCode::Char('f')));
+        assert_eq!(app.panel, Panel::Processes);
+    }
+
+    #[test]
+    fn test_sensors_focus() {
+        use crate::sys::FanInfo;
+
+        let mut sys = SysCache::default();
+        sys.fans = (0..3)
+            .map(|i| FanInfo {
+                label: format!("fan{}", i),
+                rpm: 1000,
+            })
+            .collect();
+        let mut app = App::new(Config::default());
+        app.on_refresh(sys);
+        let key = |c| KeyEvent::new(c, KeyModifiers::empty());
+        app.on_key(key(KeyCode::Char('f')));
+        assert_eq!(app.panel, Panel::Disks);
+        app.on_key(key(KeyCode::Char('f')));
+        assert_eq!(app.panel, Panel::Sensors);
+        for _ in 0..10 {
+            app.on_key(key(KeyCode::Down));
+        }
+        // The heading and three fans.
+        assert_eq!(app.sensors_scroll, 3);
+        app.on_key(key(KeyCode::Char('f')));
+        assert_eq!(app.panel, Panel::Processes);
+    }
+
+    #[test]
+    fn test_cgroup_tree_keys() {
+        use crate::cgroups::CgroupInfo;
+
+        let mut sys = SysCache::default();
+        sys.cgroups = ["system.slice", "system.slice/sshd.service", "user.slice"]
+            .into_iter()
+            .map(|path| CgroupInfo {
+                path: path.into(),
+                ..CgroupInfo::default()
+            })
+            .collect();
+        let mut app = App::new(Config::default());
+        app.on_refresh(sys);
+        let key = |c| KeyEvent::new(c, KeyModifiers::empty());
+        app.on_key(key(KeyCode::Char('G')));
+        assert_eq!(app.popup, Some(Popup::Cgroups));
+
+        app.on_key(key(KeyCode::Enter));
+        assert!(app.cgroup_expanded.contains("system.slice"));
+        app.on_key(key(KeyCode::Down));
+        assert_eq!(app.cgroup_selected, 1);
+        // A leaf folds nothing, so Left goes to its parent, then folds it.
+        app.on_key(key(KeyCode::Left));
+        assert_eq!(app.cgroup_selected, 0);
+        app.on_key(key(KeyCode::Left));
+        assert!(app.cgroup_expanded.is_empty());
+        app.on_key(key(KeyCode::Down));
+        assert_eq!(app.cgroup_selected, 1);
+
+        app.on_key(key(KeyCode::Esc));
+        assert_eq!(app.popup, None);
+    }
+
+    #[test]
+    fn test_connect_to_discovered_agent() {
+        let mut app = App::new(Config::default());
+        let agent = Agent {
+            name: "nas".into(),
+            addr: "192.168.1.5:7879".into(),
+        };
+        app.on_discovered(agent.clone());
+        app.on_discovered(agent);
+        assert_eq!(app.discovered.len(), 1);
+
+        app.on_key(KeyEvent::new(KeyCode::Char('C'), KeyModifiers::SHIFT));
+        assert_eq!(app.popup, Some(Popup::Connect));
+        app.on_key(KeyEvent::new(KeyCode::Enter, KeyModifiers::empty()));
+        assert_eq!(app.popup, None);
+        assert_eq!(app.hosts.len(), 2);
+        assert_eq!(app.active_host(), 1);
+        assert_eq!(app.connect("192.168.1.5:7879".into()), 1);
+    }
+
+    #[test]
+    fn test_change_tick_rate() {
+        let mut app = App::new(Config::default());
+        assert_eq!(app.take_tick_rate_change(), None);
+        app.on_key(KeyEvent::new(KeyCode::Char('-'), KeyModifiers::empty()));
+        assert_eq!(app.take_tick_rate_change(), Some(Duration::from_secs(2)));
+        assert_eq!(app.take_tick_rate_change(), None);
+        for _ in 0..10 {
+            app.on_key(KeyEvent::new(KeyCode::Char('+'), KeyModifiers::empty()));
+        }
+        assert_eq!(app.tick_rate(), MIN_TICK_RATE);
+    }
+
+    #[test]
+    fn test_plus_updates_faster() {
+        let mut app = App::new(Config::default());
+        let before = app.tick_rate();
+        app.on_key(KeyEvent::new(KeyCode::Char('+'), KeyModifiers::empty()));
+        assert!(app.tick_rate() < before);
+    }
+
+    #[test]
+    fn test_idle_slows_tick() {
+        let mut app = App::new(Config::default());
+        app.set_idle_rate(Some(Duration::from_secs(5)), Duration::from_secs(60));
+        let start = Instant::now();
+        app.on_tick(start + Duration::from_secs(10));
+        assert!(!app.is_idle());
+        app.on_tick(start + Duration::from_secs(61));
+        assert!(app.is_idle());
+        assert_eq!(app.take_tick_rate_change(), Some(Duration::from_secs(5)));
+
+        app.on_key(KeyEvent::new(KeyCode::Char('j'), KeyModifiers::empty()));
+        assert_eq!(app.take_tick_rate_change(), Some(Duration::from_secs(1)));
+        app.on_focus(false);
+        assert_eq!(app.take_tick_rate_change(), Some(Duration::from_secs(5)));
+        app.on_focus(true);
+        assert_eq!(app.take_tick_rate_change(), Some(Duration::from_secs(1)));
+        assert_eq!(app.tick_rate(), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_screensaver_wakes_on_key() {
+        let mut app = App::new(Config::default());
+        app.screensaver.after = 300;
+        let start = Instant::now();
+        app.on_tick(start + Duration::from_secs(301));
+        assert!(app.is_dimmed());
+        assert_eq!(app.take_tick_rate_change(), Some(Duration::from_secs(10)));
+
+        // The waking key isn't acted on.
+        app.on_key(KeyEvent::new(KeyCode::Char('q'), KeyModifiers::empty()));
+        assert!(!app.is_dimmed());
+        assert!(!app.should_quit());
+        assert_eq!(app.take_tick_rate_change(), Some(Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn test_process_detail_popup() {
+        let mut app = App::new(Config::default());
+        app.on_refresh(SysCache::new());
+        app.on_key(KeyEvent::new(KeyCode::Enter, KeyModifiers::empty()));
+        assert_eq!(app.popup, Some(Popup::Process));
+        let selected = app.visible_processes()[0].pid;
+        assert_eq!(app.detail.as_ref().unwrap().pid, selected);
+        assert!(!app.detail_pending());
+        app.on_key(KeyEvent::new(KeyCode::Esc, KeyModifiers::empty()));
+        assert_eq!(app.popup, None);
+    }
+
     #[test]
     fn test_input_mode_switching() {
-        let mut app = App::new(Duration::from_millis(100));
+        let mut app = App::new(Config::default());
         
         let slash = KeyEvent{ code: KeyCode::Char('/'), modifiers: KeyModifiers::empty(), kind: crossterm::event::KeyEventKind::Press, state: crossterm::event::KeyEventState::NONE };
         app.on_key(slash);
@@ -196,4 +1829,70 @@ mod tests {
         app.on_key(esc);
         assert_eq!(app.input_mode, InputMode::Normal);
     }
+
+    #[test]
+    fn test_cycle_theme() {
+        let config = Config {
+            theme: Some("monochrome".into()),
+            ..Config::default()
+        };
+        let mut app = App::new(config);
+        assert_eq!(app.colors(), theme::built_in("monochrome").unwrap());
+        app.on_key(KeyEvent::new(KeyCode::Char('t'), KeyModifiers::CONTROL));
+        assert_eq!(app.colors(), Colors::default());
+        assert_eq!(app.notice.as_deref(), Some("theme dark"));
+        assert!(!app.tree_view);
+    }
+
+    #[test]
+    fn test_pause() {
+        let mut app = App::new(Config::default());
+        let mut sys = SysCache::default();
+        sys.cpu_global = 10.0;
+        app.on_refresh(sys.clone());
+
+        app.on_key(KeyEvent::new(KeyCode::Char('p'), KeyModifiers::empty()));
+        assert!(app.is_paused());
+        sys.cpu_global = 90.0;
+        app.on_refresh(sys.clone());
+        assert_eq!(app.sys().cpu_global, 10.0);
+
+        app.on_key(KeyEvent::new(KeyCode::Char('p'), KeyModifiers::empty()));
+        app.on_refresh(sys);
+        assert_eq!(app.sys().cpu_global, 90.0);
+    }
+
+    #[test]
+    fn test_replay() {
+        use crate::replay::{Frame, Replay};
+
+        let frames = (0..3)
+            .map(|i| {
+                let mut sys = SysCache::default();
+                sys.cpu_global = 10.0 * (i + 1) as f32;
+                Frame { at: i * 1000, sys }
+            })
+            .collect();
+        let mut app = App::new(Config::default());
+        app.start_replay(Replay::new(frames).unwrap());
+        assert_eq!(app.sys().cpu_global, 10.0);
+
+        let now = Instant::now();
+        app.on_tick(now);
+        app.on_tick(now);
+        app.on_tick(now);
+        assert_eq!(app.sys().cpu_global, 30.0);
+        assert_eq!(app.host().cpu_history.latest(), 30);
+
+        app.on_key(KeyEvent::new(KeyCode::Left, KeyModifiers::empty()));
+        assert_eq!(app.sys().cpu_global, 20.0);
+        assert_eq!(app.host().cpu_history.latest(), 20);
+        assert_eq!(app.replay().unwrap().position(), 1);
+
+        app.on_key(KeyEvent::new(KeyCode::Char('p'), KeyModifiers::empty()));
+        app.on_tick(now);
+        assert_eq!(app.sys().cpu_global, 20.0);
+        app.on_key(KeyEvent::new(KeyCode::Right, KeyModifiers::empty()));
+        assert_eq!(app.sys().cpu_global, 30.0);
+    }
 }
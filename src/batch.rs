@@ -0,0 +1,151 @@
+use crate::sys::{format_bytes, format_duration_secs, Collector, SysCache};
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::time::Duration;
+
+const BATCH_PROCS: usize = 20;
+
+/// How each frame is printed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Format {
+    /// The summary and the busiest processes, frames separated by a blank
+    /// line.
+    #[default]
+    Text,
+    /// The whole [`SysCache`] as one JSON object per line.
+    Json,
+}
+
+/// Renders one plain-text frame: a short summary followed by the busiest processes.
+pub fn render(sys: &SysCache) -> String {
+    let mut out = String::new();
+    let now = chrono::Local::now().format("%H:%M:%S");
+    let mem_pct = if sys.total_mem > 0 {
+        sys.used_mem as f64 / sys.total_mem as f64 * 100.0
+    } else {
+        0.0
+    };
+
+    let _ = writeln!(
+        out,
+        "sysdash - {} up {}",
+        now,
+        format_duration_secs(sys.uptime)
+    );
+    let _ = writeln!(
+        out,
+        "CPU: {:.1}%  Temp: {:.1}C",
+        sys.cpu_global, sys.cpu_temp
+    );
+    let _ = writeln!(
+        out,
+        "Mem: {}/{} ({:.1}%)",
+        format_bytes(sys.used_mem),
+        format_bytes(sys.total_mem),
+        mem_pct
+    );
+    let _ = writeln!(
+        out,
+        "Net: RX {}/s  TX {}/s",
+        format_bytes(sys.rx_rate),
+        format_bytes(sys.tx_rate)
+    );
+    let _ = writeln!(out);
+    let _ = writeln!(
+        out,
+        "{:>7} {:<12} {:>6} {:>8} COMMAND",
+        "PID", "USER", "CPU%", "MEM"
+    );
+    for p in sys.processes().iter().take(BATCH_PROCS) {
+        let _ = writeln!(
+            out,
+            "{:>7} {:<12} {:>6.1} {:>8} {}",
+            p.pid,
+            p.user.chars().take(12).collect::<String>(),
+            p.cpu,
+            format_bytes(p.mem_bytes),
+            p.name
+        );
+    }
+    out
+}
+
+/// The snapshot as one line of JSON: CPU, memory, disks, network rates and
+/// every process, with the field names of [`SysCache`].
+pub fn to_json(sys: &SysCache) -> io::Result<String> {
+    let mut line = serde_json::to_string(sys)?;
+    line.push('\n');
+    Ok(line)
+}
+
+/// Prints a frame every `tick_rate` without touching the terminal mode.
+/// Runs forever unless `iterations` is given; stops quietly if stdout closes.
+pub fn run(tick_rate: Duration, iterations: Option<u64>, format: Format) -> io::Result<()> {
+    let mut collector = Collector::new();
+    let stdout = io::stdout();
+    let mut count = 0u64;
+
+    loop {
+        std::thread::sleep(tick_rate);
+        let sys = collector.collect();
+
+        let frame = match format {
+            Format::Text => render(&sys),
+            Format::Json => to_json(&sys)?,
+        };
+        let mut out = stdout.lock();
+        let separate = count > 0 && format == Format::Text;
+        let written = if separate { writeln!(out) } else { Ok(()) }
+            .and_then(|_| out.write_all(frame.as_bytes()))
+            .and_then(|_| out.flush());
+        match written {
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+            other => other?,
+        }
+
+        count += 1;
+        if iterations.is_some_and(|n| count >= n) {
+            return Ok(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sys::ProcessInfo;
+
+    #[test]
+    fn test_render_layout() {
+        let sys = SysCache::new();
+        let text = render(&sys);
+        let lines: Vec<&str> = text.lines().collect();
+        assert!(lines[0].starts_with("sysdash - "));
+        assert!(lines[1].starts_with("CPU: "));
+        assert!(lines[2].starts_with("Mem: "));
+        assert!(text.contains("PID"));
+        assert!(lines.len() <= 6 + BATCH_PROCS);
+    }
+
+    #[test]
+    fn test_to_json() {
+        let sys = SysCache::default().with_processes(vec![ProcessInfo {
+            pid: 42,
+            name: "postgres".into(),
+            user: "pg".into(),
+            cmd: "postgres -D /data".into(),
+            cpu: 12.5,
+            mem_bytes: 1024,
+            parent: None,
+        }]);
+        let line = to_json(&sys).unwrap();
+        assert!(line.ends_with('\n'));
+        assert_eq!(line.lines().count(), 1);
+
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(value["cpu_global"], 0.0);
+        assert_eq!(value["procs"][0]["pid"], 42);
+        assert_eq!(value["procs"][0]["name"], "postgres");
+        assert!(value["disks"].is_array());
+    }
+}
@@ -0,0 +1,396 @@
+//! The cgroup v2 hierarchy, for the cgroup view (`G`): CPU and memory per
+//! slice, scope and service. On systemd machines every service, session
+//! and container has a cgroup of its own, so this shows what a machine is
+//! busy with better than a list of PIDs. The tree is only read while the
+//! view is open, on the process schedule.
+//!
+//! On a Kubernetes node the kubelet puts every pod, and every container in
+//! it, in a cgroup under `kubepods`; [`pods`] picks those out for the pod
+//! view (`K`).
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// Counters older than this are not compared with, so reopening the view
+/// shows current usage rather than the average since it was closed.
+const STALE: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct CgroupInfo {
+    /// Below the root, e.g. `system.slice/sshd.service`.
+    pub path: String,
+    /// In percent of one core, including the cgroups below; 0 until read
+    /// twice.
+    pub cpu: f32,
+    /// Including the cgroups below and their page cache.
+    pub mem_bytes: u64,
+    /// The processes in this cgroup itself, not the ones below.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pids: Vec<u32>,
+}
+
+impl CgroupInfo {
+    pub fn name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or(&self.path)
+    }
+
+    pub fn depth(&self) -> usize {
+        self.path.matches('/').count()
+    }
+}
+
+/// Reads the hierarchy under `root`, remembering each cgroup's CPU time
+/// for the next read.
+pub struct CgroupReader {
+    root: PathBuf,
+    /// `usage_usec` by path at the previous read.
+    usage: HashMap<String, u64>,
+    at: Option<Instant>,
+}
+
+impl CgroupReader {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            usage: HashMap::new(),
+            at: None,
+        }
+    }
+
+    /// Every cgroup below the root, each before the ones below it; `None`
+    /// where `root` isn't a cgroup v2 hierarchy.
+    pub fn read(&mut self, now: Instant) -> Option<Vec<CgroupInfo>> {
+        if !self.root.join("cgroup.controllers").exists() {
+            return None;
+        }
+        let elapsed = self
+            .at
+            .map(|at| now.duration_since(at))
+            .filter(|e| !e.is_zero() && *e <= STALE);
+        let mut usage = HashMap::new();
+        let mut list = Vec::new();
+        let mut dirs = vec![String::new()];
+        while let Some(path) = dirs.pop() {
+            let dir = self.root.join(&path);
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                    let name = entry.file_name().to_string_lossy().into_owned();
+                    dirs.push(if path.is_empty() {
+                        name
+                    } else {
+                        format!("{}/{}", path, name)
+                    });
+                }
+            }
+            // The root's counters are the whole machine's.
+            if path.is_empty() {
+                continue;
+            }
+            let usec = fs::read_to_string(dir.join("cpu.stat"))
+                .ok()
+                .and_then(|stat| usage_usec(&stat));
+            let mut info = CgroupInfo {
+                mem_bytes: fs::read_to_string(dir.join("memory.current"))
+                    .ok()
+                    .and_then(|m| m.trim().parse().ok())
+                    .unwrap_or(0),
+                pids: fs::read_to_string(dir.join("cgroup.procs"))
+                    .map(|procs| procs.lines().filter_map(|l| l.parse().ok()).collect())
+                    .unwrap_or_default(),
+                ..CgroupInfo::default()
+            };
+            if let Some(usec) = usec {
+                if let (Some(elapsed), Some(&before)) = (elapsed, self.usage.get(&path)) {
+                    let used = usec.saturating_sub(before) as f64;
+                    info.cpu = (used / elapsed.as_micros() as f64 * 100.0) as f32;
+                }
+                usage.insert(path.clone(), usec);
+            }
+            info.path = path;
+            list.push(info);
+        }
+        self.usage = usage;
+        self.at = Some(now);
+        // By component, so `a.slice/b` follows `a.slice` before `a.slice-c`.
+        list.sort_by(|a, b| a.path.split('/').cmp(b.path.split('/')));
+        Some(list)
+    }
+}
+
+fn usage_usec(stat: &str) -> Option<u64> {
+    stat.lines()
+        .find_map(|line| line.strip_prefix("usage_usec "))?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// A row of the tree view.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TreeRow {
+    /// Into the list the rows were built from.
+    pub index: usize,
+    pub has_children: bool,
+}
+
+/// The rows shown of `list`, which is in [`CgroupReader::read`] order: the
+/// top-level cgroups, and the children of those in `expanded` whose
+/// parents are shown too.
+pub fn rows(list: &[CgroupInfo], expanded: &HashSet<String>) -> Vec<TreeRow> {
+    let mut rows = Vec::new();
+    // The cgroup whose descendants are being skipped.
+    let mut folded: Option<&str> = None;
+    for (index, cgroup) in list.iter().enumerate() {
+        let below = |parent: &str| {
+            cgroup
+                .path
+                .strip_prefix(parent)
+                .is_some_and(|rest| rest.starts_with('/'))
+        };
+        if folded.is_some_and(below) {
+            continue;
+        }
+        let has_children = list.get(index + 1).is_some_and(|next| {
+            next.path
+                .strip_prefix(&cgroup.path)
+                .is_some_and(|rest| rest.starts_with('/'))
+        });
+        folded = (!expanded.contains(&cgroup.path)).then_some(cgroup.path.as_str());
+        rows.push(TreeRow {
+            index,
+            has_children,
+        });
+    }
+    rows
+}
+
+/// A Kubernetes pod, from the cgroups the kubelet made for it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pod {
+    pub uid: String,
+    /// `guaranteed`, `burstable` or `besteffort`.
+    pub qos: &'static str,
+    pub cpu: f32,
+    pub mem_bytes: u64,
+    /// Busiest first; the sandbox (pause) container is one of them.
+    pub containers: Vec<PodContainer>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PodContainer {
+    /// The runtime's container ID, shortened to 12 characters.
+    pub id: String,
+    pub cpu: f32,
+    pub mem_bytes: u64,
+    pub pids: Vec<u32>,
+}
+
+/// The pods among `list`, busiest first. Both cgroup drivers are
+/// understood: systemd's `kubepods.slice/kubepods-burstable.slice/
+/// kubepods-burstable-pod<uid>.slice/cri-containerd-<id>.scope` and
+/// cgroupfs's `kubepods/burstable/pod<uid>/<id>`.
+pub fn pods(list: &[CgroupInfo]) -> Vec<Pod> {
+    let mut pods: Vec<Pod> = Vec::new();
+    for cgroup in list {
+        let mut parts = cgroup.path.split('/');
+        // kubeadm and k3s may nest kubepods below a slice of their own.
+        if !parts.any(|part| part.starts_with("kubepods")) {
+            continue;
+        }
+        let mut qos = "guaranteed";
+        let mut uid = None;
+        let mut container = None;
+        for part in parts.by_ref() {
+            let part = part.trim_end_matches(".slice").trim_end_matches(".scope");
+            if uid.is_some() {
+                container = Some(part);
+                break;
+            }
+            let pod = part
+                .strip_prefix("pod")
+                .or_else(|| part.rsplit_once("-pod").map(|(_, id)| id));
+            let last = part.rsplit('-').next();
+            if let Some(id) = pod {
+                // systemd escapes the dashes of the UID.
+                uid = Some(id.replace('_', "-"));
+            } else if let Some(class) = ["burstable", "besteffort"]
+                .into_iter()
+                .find(|c| last == Some(c))
+            {
+                qos = class;
+            }
+        }
+        // Cgroups a container made below its own count towards it already.
+        let (Some(uid), None) = (uid, parts.next()) else {
+            continue;
+        };
+        let pod = match pods.iter().position(|p| p.uid == uid) {
+            Some(i) => &mut pods[i],
+            None => {
+                pods.push(Pod {
+                    uid,
+                    qos,
+                    cpu: 0.0,
+                    mem_bytes: 0,
+                    containers: Vec::new(),
+                });
+                pods.last_mut().expect("just pushed")
+            }
+        };
+        match container {
+            None => {
+                pod.cpu = cgroup.cpu;
+                pod.mem_bytes = cgroup.mem_bytes;
+            }
+            Some(name) => {
+                let id = name.rsplit('-').next().unwrap_or(name);
+                pod.containers.push(PodContainer {
+                    id: id.chars().take(12).collect(),
+                    cpu: cgroup.cpu,
+                    mem_bytes: cgroup.mem_bytes,
+                    pids: cgroup.pids.clone(),
+                });
+            }
+        }
+    }
+    let busiest = |a: f32, b: f32| b.partial_cmp(&a).unwrap_or(std::cmp::Ordering::Equal);
+    for pod in &mut pods {
+        pod.containers.sort_by(|a, b| busiest(a.cpu, b.cpu));
+    }
+    pods.sort_by(|a, b| busiest(a.cpu, b.cpu));
+    pods
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn write(path: &Path, contents: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn test_read() {
+        let root = std::env::temp_dir().join(format!("sysdash-cgroup-{}", std::process::id()));
+        write(&root.join("cgroup.controllers"), "cpu memory\n");
+        write(&root.join("cpu.stat"), "usage_usec 999999\n");
+        for (path, usec) in [
+            ("system.slice", 1_000_000),
+            ("system.slice/sshd.service", 200_000),
+            ("system.slice-extra", 0),
+            ("user.slice", 500_000),
+        ] {
+            write(
+                &root.join(path).join("cpu.stat"),
+                &format!("usage_usec {}\nuser_usec 0\n", usec),
+            );
+            write(&root.join(path).join("memory.current"), "4096\n");
+        }
+
+        let mut reader = CgroupReader::new(&root);
+        let start = Instant::now();
+        let first = reader.read(start).unwrap();
+        let paths: Vec<_> = first.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            [
+                "system.slice",
+                "system.slice/sshd.service",
+                "system.slice-extra",
+                "user.slice"
+            ]
+        );
+        assert!(first.iter().all(|c| c.cpu == 0.0 && c.mem_bytes == 4096));
+
+        write(&root.join("system.slice/cpu.stat"), "usage_usec 1500000\n");
+        let second = reader.read(start + Duration::from_secs(1)).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(second[0].cpu, 50.0);
+        assert_eq!(second[1].cpu, 0.0);
+        assert_eq!((second[1].name(), second[1].depth()), ("sshd.service", 1));
+        assert!(reader.read(start).is_none());
+    }
+
+    #[test]
+    fn test_rows() {
+        let list: Vec<CgroupInfo> = [
+            "a.slice",
+            "a.slice/b.service",
+            "a.slice/b.service/c",
+            "d.scope",
+        ]
+        .into_iter()
+        .map(|path| CgroupInfo {
+            path: path.into(),
+            ..CgroupInfo::default()
+        })
+        .collect();
+        let shown = |expanded: &[&str]| -> Vec<usize> {
+            let expanded = expanded.iter().map(|p| p.to_string()).collect();
+            rows(&list, &expanded).iter().map(|r| r.index).collect()
+        };
+        assert_eq!(shown(&[]), [0, 3]);
+        assert_eq!(shown(&["a.slice"]), [0, 1, 3]);
+        assert_eq!(shown(&["a.slice", "a.slice/b.service"]), [0, 1, 2, 3]);
+        // Hidden with its parent folded, even if expanded itself.
+        assert_eq!(shown(&["a.slice/b.service"]), [0, 3]);
+        let rows = rows(&list, &HashSet::new());
+        assert!(rows[0].has_children && !rows[1].has_children);
+    }
+
+    #[test]
+    fn test_pods() {
+        let cgroup = |path: &str, cpu: f32, pids: &[u32]| CgroupInfo {
+            path: path.into(),
+            cpu,
+            mem_bytes: 1024,
+            pids: pids.to_vec(),
+        };
+        let systemd =
+            "kubepods.slice/kubepods-burstable.slice/kubepods-burstable-poda1b2_c3d4.slice";
+        let list = [
+            cgroup("kubepods.slice", 90.0, &[]),
+            cgroup("kubepods.slice/kubepods-burstable.slice", 40.0, &[]),
+            cgroup(systemd, 40.0, &[]),
+            cgroup(
+                &format!("{}/cri-containerd-0123456789abcdef.scope", systemd),
+                39.0,
+                &[7, 8],
+            ),
+            cgroup(
+                &format!("{}/cri-containerd-fedcba9876543210.scope", systemd),
+                1.0,
+                &[5],
+            ),
+            cgroup(
+                &format!("{}/cri-containerd-fedcba9876543210.scope/nested", systemd),
+                1.0,
+                &[],
+            ),
+            cgroup("kubepods/pod99-aa", 50.0, &[]),
+            cgroup("kubepods/pod99-aa/abcdef", 50.0, &[3]),
+            cgroup("system.slice/kubelet.service", 5.0, &[2]),
+        ];
+        let pods = pods(&list);
+        assert_eq!(pods.len(), 2);
+        assert_eq!((pods[0].uid.as_str(), pods[0].qos), ("99-aa", "guaranteed"));
+        assert_eq!(pods[0].containers[0].pids, [3]);
+
+        let pod = &pods[1];
+        assert_eq!(
+            (pod.uid.as_str(), pod.qos, pod.cpu),
+            ("a1b2-c3d4", "burstable", 40.0)
+        );
+        let ids: Vec<_> = pod.containers.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, ["0123456789ab", "fedcba987654"]);
+        assert_eq!(pod.containers[0].pids, [7, 8]);
+    }
+}
@@ -0,0 +1,334 @@
+use crate::sys::metrics::registry;
+use crate::sys::{Collector, SysCache};
+use std::fmt;
+
+/// Ordered by severity, so the worst result of several checks is the max.
+#[derive(Clone, Copy, PartialEq, PartialOrd, Debug)]
+pub enum Status {
+    Ok,
+    Warning,
+    Unknown,
+    Critical,
+}
+
+impl Status {
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Status::Ok => 0,
+            Status::Warning => 1,
+            Status::Critical => 2,
+            Status::Unknown => 3,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Status::Ok => "OK",
+            Status::Warning => "WARNING",
+            Status::Critical => "CRITICAL",
+            Status::Unknown => "UNKNOWN",
+        }
+    }
+}
+
+/// What a rule watches: the short names for the usual metrics, or any
+/// metric in the [registry](crate::sys::metrics) by name, with the label of
+/// one of its series after a colon, e.g. `swap.io.rate:in`.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Metric {
+    Cpu,
+    Mem,
+    Temp,
+    Disk(String),
+    Registered(String, Option<String>),
+}
+
+impl Metric {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.trim() {
+            "cpu" => Ok(Metric::Cpu),
+            "mem" => Ok(Metric::Mem),
+            "temp" => Ok(Metric::Temp),
+            other => match other.strip_prefix("disk:") {
+                Some(mount) if !mount.is_empty() => Ok(Metric::Disk(mount.to_string())),
+                Some(_) => Err(format!("unknown metric '{}'", other)),
+                None => Self::registered(other),
+            },
+        }
+    }
+
+    fn registered(s: &str) -> Result<Self, String> {
+        let (name, label) = match s.split_once(':') {
+            Some((name, label)) => (name, Some(label.to_string())),
+            None => (s, None),
+        };
+        let def = registry()
+            .get(name)
+            .ok_or_else(|| format!("unknown metric '{}'", s))?;
+        match (def.label, &label) {
+            (Some(key), None) => Err(format!(
+                "'{}' needs a {}, e.g. {}:<{}>",
+                name, key, name, key
+            )),
+            (None, Some(_)) => Err(format!("'{}' has a single series", name)),
+            _ => Ok(Metric::Registered(name.to_string(), label)),
+        }
+    }
+
+    /// The registered metric and series this stands for.
+    pub fn series(&self) -> (&str, Option<&str>) {
+        match self {
+            Metric::Cpu => ("cpu.utilization", None),
+            Metric::Mem => ("memory.utilization", None),
+            Metric::Temp => ("cpu.temperature", None),
+            Metric::Disk(mount) => ("filesystem.utilization", Some(mount)),
+            Metric::Registered(name, label) => (name, label.as_deref()),
+        }
+    }
+
+    /// Current value in the metric's unit (percent for the short names, °C
+    /// for `temp`), or `None` if unavailable.
+    pub fn value(&self, sys: &SysCache) -> Option<f64> {
+        let (name, label) = self.series();
+        registry().value(name, label, sys)
+    }
+}
+
+impl fmt::Display for Metric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Metric::Cpu => write!(f, "cpu"),
+            Metric::Mem => write!(f, "mem"),
+            Metric::Temp => write!(f, "temp"),
+            Metric::Disk(mount) => write!(f, "disk:{}", mount),
+            Metric::Registered(name, None) => write!(f, "{}", name),
+            Metric::Registered(name, Some(label)) => write!(f, "{}:{}", name, label),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Comparator {
+    Greater,
+    GreaterEq,
+    Less,
+    LessEq,
+}
+
+impl Comparator {
+    /// Splits a leading comparator off `s`; `=` is shorthand for `>=`.
+    pub fn split(s: &str) -> Option<(Self, &str)> {
+        if let Some(r) = s.strip_prefix(">=") {
+            Some((Comparator::GreaterEq, r))
+        } else if let Some(r) = s.strip_prefix("<=") {
+            Some((Comparator::LessEq, r))
+        } else if let Some(r) = s.strip_prefix('>') {
+            Some((Comparator::Greater, r))
+        } else if let Some(r) = s.strip_prefix('<') {
+            Some((Comparator::Less, r))
+        } else {
+            s.strip_prefix('=').map(|r| (Comparator::GreaterEq, r))
+        }
+    }
+
+    pub fn matches(self, value: f64, threshold: f64) -> bool {
+        match self {
+            Comparator::Greater => value > threshold,
+            Comparator::GreaterEq => value >= threshold,
+            Comparator::Less => value < threshold,
+            Comparator::LessEq => value <= threshold,
+        }
+    }
+}
+
+/// A single threshold such as `cpu>90` or `disk:/=80:90`.
+///
+/// The right-hand side is either `CRIT` or `WARN:CRIT`; `=` is shorthand for `>=`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Rule {
+    pub metric: Metric,
+    pub cmp: Comparator,
+    pub warn: Option<f64>,
+    pub crit: f64,
+}
+
+impl Rule {
+    pub fn status(&self, value: f64) -> Status {
+        if self.cmp.matches(value, self.crit) {
+            Status::Critical
+        } else if self.warn.is_some_and(|w| self.cmp.matches(value, w)) {
+            Status::Warning
+        } else {
+            Status::Ok
+        }
+    }
+}
+
+pub fn parse_rules(spec: &str) -> Result<Vec<Rule>, String> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(parse_rule)
+        .collect()
+}
+
+fn parse_rule(s: &str) -> Result<Rule, String> {
+    let pos = s
+        .find(['>', '<', '='])
+        .ok_or_else(|| format!("missing comparator in '{}'", s))?;
+    let (lhs, rest) = s.split_at(pos);
+    let (cmp, rhs) =
+        Comparator::split(rest).ok_or_else(|| format!("invalid comparator in '{}'", s))?;
+    let metric = Metric::parse(lhs)?;
+
+    let parse_num = |v: &str| {
+        v.trim()
+            .parse::<f64>()
+            .map_err(|_| format!("invalid threshold '{}' in '{}'", v, s))
+    };
+    let (warn, crit) = match rhs.split_once(':') {
+        Some((w, c)) => (Some(parse_num(w)?), parse_num(c)?),
+        None => (None, parse_num(rhs)?),
+    };
+
+    Ok(Rule {
+        metric,
+        cmp,
+        warn,
+        crit,
+    })
+}
+
+/// Evaluates every rule against the current snapshot and returns the worst
+/// status together with a Nagios-style `STATUS - summary | perfdata` line.
+pub fn evaluate(rules: &[Rule], sys: &SysCache) -> (Status, String) {
+    let mut worst = Status::Ok;
+    let mut summary = Vec::new();
+    let mut perf = Vec::new();
+
+    for rule in rules {
+        let value = rule.metric.value(sys);
+        let status = value.map_or(Status::Unknown, |v| rule.status(v));
+        if status > worst {
+            worst = status;
+        }
+        match value {
+            Some(value) => {
+                if status != Status::Ok {
+                    summary.push(format!("{} {:.1} ({})", rule.metric, value, status.label()));
+                }
+                perf.push(format!(
+                    "'{}'={:.1};{};{}",
+                    rule.metric,
+                    value,
+                    rule.warn.map(|w| w.to_string()).unwrap_or_default(),
+                    rule.crit
+                ));
+            }
+            None => {
+                summary.push(format!("{} unavailable", rule.metric));
+            }
+        }
+    }
+
+    let text = if summary.is_empty() {
+        format!("{} checks passed", rules.len())
+    } else {
+        summary.join(", ")
+    };
+    (
+        worst,
+        format!("SYSDASH {} - {} | {}", worst.label(), text, perf.join(" ")),
+    )
+}
+
+pub fn run(spec: &str) -> i32 {
+    let rules = match parse_rules(spec) {
+        Ok(rules) if !rules.is_empty() => rules,
+        Ok(_) => {
+            println!("SYSDASH UNKNOWN - no checks given");
+            return Status::Unknown.exit_code();
+        }
+        Err(e) => {
+            println!("SYSDASH UNKNOWN - {}", e);
+            return Status::Unknown.exit_code();
+        }
+    };
+
+    let mut collector = Collector::new();
+    // CPU usage is a delta between two refreshes, so take a second sample.
+    std::thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+    let sys = collector.collect();
+
+    let (status, line) = evaluate(&rules, &sys);
+    println!("{}", line);
+    status.exit_code()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rules() {
+        let rules = parse_rules("cpu>90,mem>=80:95,disk:/=90").unwrap();
+        assert_eq!(rules.len(), 3);
+        assert_eq!(rules[0].metric, Metric::Cpu);
+        assert_eq!(rules[0].cmp, Comparator::Greater);
+        assert_eq!(rules[0].warn, None);
+        assert_eq!(rules[1].warn, Some(80.0));
+        assert_eq!(rules[1].crit, 95.0);
+        assert_eq!(rules[2].metric, Metric::Disk("/".into()));
+        assert_eq!(rules[2].cmp, Comparator::GreaterEq);
+
+        assert!(parse_rules("cpu90").is_err());
+        assert!(parse_rules("gpu>90").is_err());
+        assert!(parse_rules("cpu>lots").is_err());
+
+        let rules = parse_rules("swap.io.rate:in>100,process.count>500").unwrap();
+        assert_eq!(
+            rules[0].metric,
+            Metric::Registered("swap.io.rate".into(), Some("in".into()))
+        );
+        assert_eq!(rules[0].metric.to_string(), "swap.io.rate:in");
+        assert_eq!(rules[1].metric.series(), ("process.count", None));
+        assert!(parse_rules("swap.io.rate>100").is_err());
+        assert!(parse_rules("process.count:x>1").is_err());
+    }
+
+    #[test]
+    fn test_rule_status() {
+        let rule = parse_rule("cpu>80:90").unwrap();
+        assert_eq!(rule.status(50.0), Status::Ok);
+        assert_eq!(rule.status(85.0), Status::Warning);
+        assert_eq!(rule.status(95.0), Status::Critical);
+
+        let rule = parse_rule("temp<10").unwrap();
+        assert_eq!(rule.status(5.0), Status::Critical);
+        assert_eq!(rule.status(40.0), Status::Ok);
+    }
+
+    #[test]
+    fn test_disk_value() {
+        use crate::sys::DiskInfo;
+
+        // FUSE and network mounts may report more available than total.
+        let sys = SysCache::default().with_disks(vec![DiskInfo {
+            _name: "sshfs".into(),
+            mount_point: "/mnt/remote".into(),
+            total: 100,
+            available: 150,
+            temperature: None,
+        }]);
+        let metric = Metric::Disk("/mnt/remote".into());
+        assert_eq!(metric.value(&sys), Some(0.0));
+    }
+
+    #[test]
+    fn test_status_exit_codes() {
+        assert_eq!(Status::Ok.exit_code(), 0);
+        assert_eq!(Status::Warning.exit_code(), 1);
+        assert_eq!(Status::Critical.exit_code(), 2);
+        assert_eq!(Status::Unknown.exit_code(), 3);
+    }
+}
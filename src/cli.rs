@@ -0,0 +1,370 @@
+//! Command-line options. `--tick`, `--sort` and `--theme` override the same
+//! settings in `config.toml`.
+
+use crate::app::SortColumn;
+use crate::batch::Format;
+use crate::config::Config;
+use crate::focus::Target;
+use crate::statusline::Colors;
+use crate::theme;
+use clap::Parser;
+use std::path::PathBuf;
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Options {
+    pub check: Option<String>,
+    pub batch: bool,
+    /// How batch frames are printed; `--json` also turns batch mode on.
+    pub format: Format,
+    /// Answer in sentences for screen readers instead of drawing.
+    pub accessible: bool,
+    /// Print one compact line and exit.
+    pub statusline: Option<Colors>,
+    pub iterations: Option<u64>,
+    pub otlp: Option<String>,
+    /// Append a row of metrics to this CSV file on every update.
+    pub log_csv: Option<PathBuf>,
+    /// Serve the latest snapshot as JSON over HTTP on this address.
+    pub api: Option<String>,
+    /// Append every snapshot to this recording.
+    pub record: Option<PathBuf>,
+    /// Play this recording instead of watching the machine.
+    pub replay: Option<PathBuf>,
+    /// Serve snapshots on this address instead of drawing.
+    pub agent: Option<String>,
+    /// Render snapshots from the agents at these addresses, one tab each.
+    pub connect: Vec<String>,
+    /// The Docker daemon for the containers popup, e.g. ssh://user@nas.
+    pub docker_host: Option<String>,
+    /// Show one process instead of the whole machine.
+    pub focus: Option<Target>,
+    /// Milliseconds between updates.
+    pub tick: Option<u64>,
+    pub sort: Option<SortColumn>,
+    /// The initial process search.
+    pub filter: Option<String>,
+    pub theme: Option<String>,
+    /// Draw with the 16 ANSI colours.
+    pub no_truecolor: bool,
+}
+
+impl Options {
+    /// Overrides the settings of `config` given on the command line; an
+    /// unknown theme is an error.
+    pub fn apply(&self, config: &mut Config) -> Result<(), String> {
+        if let Some(tick) = self.tick {
+            config.tick = tick;
+        }
+        if let Some(sort) = self.sort {
+            config.sort = sort;
+        }
+        if let Some(theme) = &self.theme {
+            config.theme = Some(theme.clone());
+            theme::start(config, &theme::list(config)).map_err(|e| format!("--theme: {}", e))?;
+        }
+        if self.no_truecolor {
+            config.truecolor = Some(false);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Parser)]
+#[command(name = "sysdash", version, about = "A terminal system monitor")]
+struct Args {
+    /// Check thresholds once and exit with a Nagios status, e.g. 'cpu>90,mem>80:95'
+    #[arg(long, value_name = "SPEC")]
+    check: Option<String>,
+    /// Print plain-text frames instead of drawing, like top -b
+    #[arg(short, long)]
+    batch: bool,
+    /// Frames printed by --batch before exiting
+    #[arg(short = 'n', long, value_name = "N")]
+    iterations: Option<u64>,
+    /// Print a single frame and exit, like --batch -n 1
+    #[arg(long, conflicts_with = "iterations")]
+    once: bool,
+    /// Print frames as JSON, one snapshot per line, instead of drawing
+    #[arg(long)]
+    json: bool,
+    /// Describe the machine in sentences for screen readers
+    #[arg(long)]
+    accessible: bool,
+    /// Print one line for tmux or a prompt and exit: plain, tmux or ansi
+    #[arg(
+        long,
+        value_name = "COLORS",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "plain",
+        value_parser = parse_statusline
+    )]
+    statusline: Option<Colors>,
+    /// Export metrics over OTLP/HTTP to this endpoint
+    #[arg(long, value_name = "URL")]
+    otlp: Option<String>,
+    /// Append CPU, memory, swap, network and disk rates to this CSV file on
+    /// every update
+    #[arg(long, value_name = "PATH")]
+    log_csv: Option<PathBuf>,
+    /// Serve /api/v1/system and /api/v1/processes as JSON on this address
+    #[arg(long, value_name = "ADDR")]
+    api: Option<String>,
+    /// Record every snapshot to this file for --replay
+    #[arg(long, value_name = "PATH")]
+    record: Option<PathBuf>,
+    /// Play back a recording made with --record; Left and Right step through it
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["record", "connect", "remote"])]
+    replay: Option<PathBuf>,
+    /// Serve snapshots to remote dashboards on this address
+    #[arg(long, value_name = "ADDR")]
+    agent: Option<String>,
+    /// Show the agent at this address; repeat for more hosts
+    #[arg(long, value_name = "ADDR")]
+    connect: Vec<String>,
+    /// Show this machine through ssh, running sysdash --agent - on it;
+    /// repeat for more hosts. Same as --connect ssh://USER@HOST
+    #[arg(long, value_name = "USER@HOST")]
+    remote: Vec<String>,
+    /// The Docker daemon for the containers popup
+    #[arg(long, value_name = "HOST")]
+    docker_host: Option<String>,
+    /// Follow one process by PID
+    #[arg(long, conflicts_with = "name")]
+    pid: Option<u32>,
+    /// Follow the busiest process whose name contains this
+    #[arg(long)]
+    name: Option<String>,
+    /// Milliseconds between updates
+    #[arg(long, value_name = "MS")]
+    tick: Option<u64>,
+    /// Sort processes by this column
+    #[arg(long, value_enum)]
+    sort: Option<SortColumn>,
+    /// Show only processes whose name or PID contains this
+    #[arg(long, value_name = "TEXT")]
+    filter: Option<String>,
+    /// The colour theme: dark, light, tokyo-night, gruvbox, dracula,
+    /// monochrome or one from config.toml
+    #[arg(long, value_name = "NAME")]
+    theme: Option<String>,
+    /// Use the 16 ANSI colours and the terminal's own background
+    #[arg(long)]
+    no_truecolor: bool,
+}
+
+fn parse_statusline(s: &str) -> Result<Colors, String> {
+    Colors::parse(s).ok_or_else(|| "expected plain, tmux or ansi".into())
+}
+
+/// Parses `args`, the program name first. Help and version requests come
+/// back as errors too; `Error::exit` prints them.
+pub fn parse<I>(args: I) -> Result<Options, clap::Error>
+where
+    I: IntoIterator<Item = String>,
+{
+    let args = Args::try_parse_from(args)?;
+    let focus = match (args.pid, args.name) {
+        (Some(pid), _) => Some(Target::Pid(pid)),
+        (None, Some(name)) => Some(Target::Name(name)),
+        (None, None) => None,
+    };
+    let format = if args.json {
+        Format::Json
+    } else {
+        Format::Text
+    };
+    Ok(Options {
+        check: args.check,
+        batch: args.batch || args.once || args.json,
+        format,
+        accessible: args.accessible,
+        statusline: args.statusline,
+        iterations: if args.once { Some(1) } else { args.iterations },
+        otlp: args.otlp,
+        log_csv: args.log_csv,
+        api: args.api,
+        record: args.record,
+        replay: args.replay,
+        agent: args.agent,
+        connect: args
+            .connect
+            .into_iter()
+            .chain(args.remote.iter().map(|r| format!("ssh://{}", r)))
+            .collect(),
+        docker_host: args.docker_host,
+        focus,
+        tick: args.tick,
+        sort: args.sort,
+        filter: args.filter,
+        theme: args.theme,
+        no_truecolor: args.no_truecolor,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::error::ErrorKind;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        std::iter::once("sysdash")
+            .chain(list.iter().copied())
+            .map(|s| s.to_string())
+            .collect()
+    }
+
+    fn error(list: &[&str]) -> ErrorKind {
+        parse(args(list)).unwrap_err().kind()
+    }
+
+    #[test]
+    fn test_parse_empty() {
+        assert_eq!(parse(args(&[])).unwrap(), Options::default());
+    }
+
+    #[test]
+    fn test_parse_check() {
+        let opts = parse(args(&["--check", "cpu>90,mem>95"])).unwrap();
+        assert_eq!(opts.check.as_deref(), Some("cpu>90,mem>95"));
+
+        let opts = parse(args(&["--check=disk:/=90"])).unwrap();
+        assert_eq!(opts.check.as_deref(), Some("disk:/=90"));
+    }
+
+    #[test]
+    fn test_parse_batch() {
+        let opts = parse(args(&["--batch", "--iterations", "3"])).unwrap();
+        assert!(opts.batch);
+        assert!(parse(args(&["--accessible"])).unwrap().accessible);
+        assert_eq!(opts.iterations, Some(3));
+
+        let opts = parse(args(&["-b", "-n", "1"])).unwrap();
+        assert!(opts.batch);
+        assert_eq!(opts.iterations, Some(1));
+    }
+
+    #[test]
+    fn test_parse_once_json() {
+        let opts = parse(args(&["--once", "--json"])).unwrap();
+        assert!(opts.batch);
+        assert_eq!(opts.format, Format::Json);
+        assert_eq!(opts.iterations, Some(1));
+
+        let opts = parse(args(&["--json"])).unwrap();
+        assert!(opts.batch);
+        assert_eq!(opts.iterations, None);
+
+        let opts = parse(args(&["--once"])).unwrap();
+        assert_eq!(opts.format, Format::Text);
+        assert_eq!(error(&["--once", "-n", "3"]), ErrorKind::ArgumentConflict);
+    }
+
+    #[test]
+    fn test_parse_log_csv() {
+        let opts = parse(args(&["--log-csv", "/tmp/load.csv"])).unwrap();
+        assert_eq!(opts.log_csv, Some(PathBuf::from("/tmp/load.csv")));
+        assert_eq!(error(&["--log-csv"]), ErrorKind::InvalidValue);
+
+        let opts = parse(args(&["--api", "127.0.0.1:7880"])).unwrap();
+        assert_eq!(opts.api.as_deref(), Some("127.0.0.1:7880"));
+    }
+
+    #[test]
+    fn test_parse_record_replay() {
+        let opts = parse(args(&["--record", "session.bin"])).unwrap();
+        assert_eq!(opts.record, Some(PathBuf::from("session.bin")));
+
+        let opts = parse(args(&["--replay", "session.bin"])).unwrap();
+        assert_eq!(opts.replay, Some(PathBuf::from("session.bin")));
+        assert_eq!(
+            error(&["--replay", "a", "--record", "b"]),
+            ErrorKind::ArgumentConflict
+        );
+        assert_eq!(
+            error(&["--replay", "a", "--connect", "nas:7879"]),
+            ErrorKind::ArgumentConflict
+        );
+    }
+
+    #[test]
+    fn test_parse_statusline() {
+        let opts = parse(args(&["--statusline"])).unwrap();
+        assert_eq!(opts.statusline, Some(Colors::Plain));
+
+        let opts = parse(args(&["--statusline=tmux"])).unwrap();
+        assert_eq!(opts.statusline, Some(Colors::Tmux));
+
+        assert_eq!(error(&["--statusline=red"]), ErrorKind::ValueValidation);
+    }
+
+    #[test]
+    fn test_parse_focus() {
+        let opts = parse(args(&["--pid", "4242"])).unwrap();
+        assert_eq!(opts.focus, Some(Target::Pid(4242)));
+
+        let opts = parse(args(&["--name=postgres"])).unwrap();
+        assert_eq!(opts.focus, Some(Target::Name("postgres".into())));
+
+        assert!(parse(args(&["--pid", "self"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_remote() {
+        let opts = parse(args(&["--agent", "0.0.0.0:7879"])).unwrap();
+        assert_eq!(opts.agent.as_deref(), Some("0.0.0.0:7879"));
+
+        let opts = parse(args(&[
+            "--connect=unix:/run/sysdash.sock",
+            "--connect",
+            "nas:7879",
+        ]))
+        .unwrap();
+        assert_eq!(opts.connect, ["unix:/run/sysdash.sock", "nas:7879"]);
+
+        let opts = parse(args(&["--connect", "nas:7879", "--remote", "me@pi"])).unwrap();
+        assert_eq!(opts.connect, ["nas:7879", "ssh://me@pi"]);
+
+        let opts = parse(args(&["--docker-host=ssh://me@nas"])).unwrap();
+        assert_eq!(opts.docker_host.as_deref(), Some("ssh://me@nas"));
+    }
+
+    #[test]
+    fn test_overrides() {
+        let opts = parse(args(&[
+            "--tick", "500", "--sort", "mem", "--filter", "firefox", "--theme", "light",
+        ]))
+        .unwrap();
+        assert_eq!(opts.filter.as_deref(), Some("firefox"));
+
+        let mut config = Config {
+            tick: 2000,
+            descending: false,
+            ..Config::default()
+        };
+        opts.apply(&mut config).unwrap();
+        assert_eq!(config.tick, 500);
+        assert_eq!(config.sort, SortColumn::Mem);
+        assert_eq!(config.theme.as_deref(), Some("light"));
+        assert!(!config.descending);
+        assert_eq!(config.truecolor, None);
+        let opts = parse(args(&["--no-truecolor"])).unwrap();
+        opts.apply(&mut config).unwrap();
+        assert_eq!(config.truecolor, Some(false));
+
+        assert_eq!(error(&["--sort", "size"]), ErrorKind::InvalidValue);
+        let opts = parse(args(&["--theme", "neon"])).unwrap();
+        assert!(opts.apply(&mut config).is_err());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(error(&["--check"]), ErrorKind::InvalidValue);
+        assert_eq!(error(&["--bogus"]), ErrorKind::UnknownArgument);
+        assert_eq!(error(&["--iterations", "many"]), ErrorKind::ValueValidation);
+        assert_eq!(
+            error(&["--pid", "1", "--name", "x"]),
+            ErrorKind::ArgumentConflict
+        );
+    }
+}
@@ -0,0 +1,95 @@
+//! The top bar's clock, set in the `[clock]` table of `ui.toml`:
+//!
+//! ```toml
+//! [clock]
+//! hour12 = true    # 2:05:09 PM instead of 14:05:09
+//! date = true      # 2024-03-01 before the time
+//! zone = true      # the local UTC offset after it, e.g. +01:00
+//! utc = true       # UTC time alongside, for servers in other regions
+//! format = "%a %d %b %H:%M"   # any strftime string; replaces hour12/date/zone
+//! ```
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::Deserialize;
+use std::fmt::Display;
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct Clock {
+    pub format: Option<String>,
+    pub hour12: bool,
+    pub date: bool,
+    pub zone: bool,
+    pub utc: bool,
+}
+
+impl Clock {
+    /// Rejects a `format` chrono can't parse, which would otherwise panic on
+    /// the first frame.
+    pub fn validate(&self) -> Result<(), String> {
+        match &self.format {
+            Some(format) if StrftimeItems::new(format).any(|item| item == Item::Error) => {
+                Err(format!("bad clock format {:?}", format))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn render<Tz: TimeZone>(&self, local: &DateTime<Tz>) -> String
+    where
+        Tz::Offset: Display,
+    {
+        let time = if self.hour12 {
+            "%-I:%M:%S %p"
+        } else {
+            "%H:%M:%S"
+        };
+        let mut text = match &self.format {
+            Some(format) => local.format(format).to_string(),
+            None => {
+                let date = if self.date { "%Y-%m-%d " } else { "" };
+                let zone = if self.zone { " %:z" } else { "" };
+                local
+                    .format(&format!("{}{}{}", date, time, zone))
+                    .to_string()
+            }
+        };
+        if self.utc {
+            let utc = local.with_timezone(&Utc);
+            let time = if self.hour12 { "%-I:%M %p" } else { "%H:%M" };
+            text.push_str(&utc.format(&format!(" (UTC {})", time)).to_string());
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+
+    #[test]
+    fn test_render() {
+        let tz = FixedOffset::east_opt(2 * 3600).unwrap();
+        let at = tz.with_ymd_and_hms(2024, 3, 1, 14, 5, 9).unwrap();
+
+        assert_eq!(Clock::default().render(&at), "14:05:09");
+        let clock = Clock {
+            hour12: true,
+            date: true,
+            zone: true,
+            utc: true,
+            ..Clock::default()
+        };
+        assert_eq!(
+            clock.render(&at),
+            "2024-03-01 2:05:09 PM +02:00 (UTC 12:05 PM)"
+        );
+        let clock = Clock {
+            format: Some("%a %H:%M".into()),
+            ..Clock::default()
+        };
+        assert_eq!(clock.render(&at), "Fri 14:05");
+    }
+}
@@ -0,0 +1,263 @@
+//! Widgets that show the output of a shell command, defined in
+//! `~/.config/sysdash/widgets.toml`:
+//!
+//! ```toml
+//! [[widget]]
+//! title = "Print queue"
+//! command = "lpstat -o"
+//! interval = 30          # seconds, default 5
+//! slot = "sidebar"       # or "footer" (default)
+//!
+//! [[widget.highlight]]
+//! contains = "error"
+//! color = "red"
+//! ```
+
+use crate::plugin::Slot;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct Highlight {
+    pub contains: String,
+    pub color: String,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct CommandSpec {
+    pub title: String,
+    pub command: String,
+    #[serde(default = "default_interval")]
+    pub interval: u64,
+    #[serde(default)]
+    pub slot: Slot,
+    #[serde(default)]
+    pub highlight: Vec<Highlight>,
+}
+
+fn default_interval() -> u64 {
+    5
+}
+
+#[derive(Deserialize, Default)]
+struct WidgetsFile {
+    #[serde(default)]
+    widget: Vec<CommandSpec>,
+}
+
+pub struct CommandWidget {
+    pub spec: CommandSpec,
+    output: Arc<Mutex<Vec<String>>>,
+    /// Dropping it wakes the thread running the command and ends it, without
+    /// waiting for a command that is still running.
+    _stop: Option<Sender<()>>,
+}
+
+pub fn widgets_path() -> PathBuf {
+    crate::plugin::config_dir().join("widgets.toml")
+}
+
+pub fn parse_specs(text: &str) -> Result<Vec<CommandSpec>, String> {
+    toml::from_str::<WidgetsFile>(text)
+        .map(|f| f.widget)
+        .map_err(|e| e.to_string())
+}
+
+/// Loads and starts every widget in `path`. A missing file means no widgets;
+/// a malformed one is reported as a single widget showing the parse error.
+pub fn load(path: &Path) -> Vec<CommandWidget> {
+    let Ok(text) = std::fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    match parse_specs(&text) {
+        Ok(specs) => specs.into_iter().map(CommandWidget::spawn).collect(),
+        Err(e) => vec![CommandWidget {
+            spec: CommandSpec {
+                title: "widgets.toml".into(),
+                command: String::new(),
+                interval: default_interval(),
+                slot: Slot::Footer,
+                highlight: Vec::new(),
+            },
+            output: Arc::new(Mutex::new(e.lines().map(str::to_string).collect())),
+            _stop: None,
+        }],
+    }
+}
+
+/// `command` run by the platform's shell: `sh -c` or, on Windows, `cmd /C`.
+pub fn shell(command: &str) -> Command {
+    let (program, flag) = if cfg!(windows) {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    };
+    let mut cmd = Command::new(program);
+    cmd.arg(flag).arg(command);
+    cmd
+}
+
+/// Runs `command` through [`shell`] and returns stdout, followed by stderr
+/// when the command fails.
+pub fn run_command(command: &str) -> Vec<String> {
+    match shell(command).output() {
+        Ok(out) => {
+            let mut lines: Vec<String> = String::from_utf8_lossy(&out.stdout)
+                .lines()
+                .map(str::to_string)
+                .collect();
+            if !out.status.success() {
+                lines.extend(
+                    String::from_utf8_lossy(&out.stderr)
+                        .lines()
+                        .map(str::to_string),
+                );
+                lines.push(format!("({})", out.status));
+            }
+            lines
+        }
+        Err(e) => vec![format!("failed to run: {}", e)],
+    }
+}
+
+impl CommandWidget {
+    /// Starts a background thread that re-runs the command every `interval`
+    /// seconds, so a slow command never stalls drawing. It runs until the
+    /// widget is dropped.
+    pub fn spawn(spec: CommandSpec) -> Self {
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let shared = Arc::clone(&output);
+        let command = spec.command.clone();
+        let interval = Duration::from_secs(spec.interval.max(1));
+        let (stop, stopped) = mpsc::channel::<()>();
+        thread::spawn(move || loop {
+            let lines = run_command(&command);
+            if let Ok(mut out) = shared.lock() {
+                *out = lines;
+            }
+            if stopped.recv_timeout(interval) != Err(RecvTimeoutError::Timeout) {
+                break;
+            }
+        });
+        Self {
+            spec,
+            output,
+            _stop: Some(stop),
+        }
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        self.output.lock().map(|o| o.clone()).unwrap_or_default()
+    }
+
+    /// The color of the first highlight rule whose text appears in `line`.
+    pub fn color_for(&self, line: &str) -> Option<&str> {
+        self.spec
+            .highlight
+            .iter()
+            .find(|h| line.contains(&h.contains))
+            .map(|h| h.color.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_specs() {
+        let specs = parse_specs(
+            r#"
+            [[widget]]
+            title = "Queue"
+            command = "lpstat -o"
+            slot = "sidebar"
+
+            [[widget.highlight]]
+            contains = "error"
+            color = "red"
+
+            [[widget]]
+            title = "Date"
+            command = "date"
+            interval = 60
+            "#,
+        )
+        .unwrap();
+        assert_eq!(specs.len(), 2);
+        assert_eq!(specs[0].slot, Slot::Sidebar);
+        assert_eq!(specs[0].interval, 5);
+        assert_eq!(specs[0].highlight[0].color, "red");
+        assert_eq!(specs[1].slot, Slot::Footer);
+        assert_eq!(specs[1].interval, 60);
+
+        assert!(parse_specs("[[widget]]\ntitle = 1").is_err());
+    }
+
+    #[test]
+    fn test_run_command() {
+        assert_eq!(run_command("echo one; echo two"), vec!["one", "two"]);
+        let failed = run_command("echo oops >&2; exit 3");
+        assert_eq!(failed[0], "oops");
+        assert!(failed[1].contains('3'));
+    }
+
+    #[test]
+    fn test_stop_on_drop() {
+        let spec = CommandSpec {
+            title: "t".into(),
+            command: "echo hi".into(),
+            interval: 3600,
+            slot: Slot::Footer,
+            highlight: Vec::new(),
+        };
+        let widget = CommandWidget::spawn(spec);
+        let start = std::time::Instant::now();
+        while widget.lines().is_empty() {
+            assert!(start.elapsed() < Duration::from_secs(10));
+            thread::sleep(Duration::from_millis(10));
+        }
+        let output = Arc::clone(&widget.output);
+        // Ends the thread without waiting out the interval.
+        drop(widget);
+        while Arc::strong_count(&output) > 1 {
+            assert!(start.elapsed() < Duration::from_secs(10));
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn test_drop_does_not_wait_for_command() {
+        let spec = CommandSpec {
+            title: "t".into(),
+            command: "sleep 5".into(),
+            interval: 3600,
+            slot: Slot::Footer,
+            highlight: Vec::new(),
+        };
+        let widget = CommandWidget::spawn(spec);
+        let start = std::time::Instant::now();
+        drop(widget);
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_color_for() {
+        let widget = CommandWidget {
+            spec: parse_specs(
+                "[[widget]]\ntitle = \"t\"\ncommand = \"true\"\n[[widget.highlight]]\ncontains = \"warn\"\ncolor = \"yellow\"",
+            )
+            .unwrap()
+            .remove(0),
+            output: Arc::new(Mutex::new(Vec::new())),
+            _stop: None,
+        };
+        assert_eq!(widget.color_for("a warning"), Some("yellow"));
+        assert_eq!(widget.color_for("all good"), None);
+    }
+}
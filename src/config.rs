@@ -0,0 +1,309 @@
+//! Settings read once at startup from `~/.config/sysdash/`. A missing file
+//! gives the defaults.
+//!
+//! `config.toml` holds the basics:
+//!
+//! ```toml
+//! tick = 1000          # milliseconds between updates, 250 to 16000
+//! sort = "mem"         # pid, name, user, cpu (default) or mem
+//! descending = true    # largest first
+//! theme = "gruvbox"    # see theme
+//! truecolor = false    # 16 colours; detected from COLORTERM by default
+//!
+//! [colors]             # names ("cyan", "lightred") or "#rrggbb"
+//! background = "#1a1b26"
+//! accent = "#00ff7f"
+//!
+//! [panels]             # all shown by default
+//! cores = false
+//! network = false
+//!
+//! [keys]               # see keymap
+//! kill = "x"
+//!
+//! hosts = ["local", "nas:7879", "ssh://me@pi"]  # unless --connect/--remote
+//!
+//! [influx]             # see influx; needs the "influx" feature
+//! url = "http://tsdb:8086/api/v2/write?org=home&bucket=sysdash"
+//! token = "..."
+//! ```
+//!
+//! `ui.toml` holds the rest; each table belongs to the module that uses it:
+//!
+//! ```toml
+//! locale = "pt"        # see i18n
+//! process_limit = 200  # rows in the process table until `A` shows all
+//! [clock]              # see clock
+//! [screensaver]        # see screensaver
+//! ```
+
+use crate::app::{SortColumn, MAX_TICK_RATE, MIN_TICK_RATE};
+use crate::clock::Clock;
+use crate::keymap::Keymap;
+use crate::screensaver::Screensaver;
+use crate::theme;
+use ratatui::style::Color;
+use serde::{Deserialize, Deserializer};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct Config {
+    /// Milliseconds between updates.
+    pub tick: u64,
+    pub sort: SortColumn,
+    pub descending: bool,
+    /// The theme to start with, by name.
+    pub theme: Option<String>,
+    /// The `custom` theme.
+    pub colors: Option<Colors>,
+    /// Themes by name, besides the built-in ones.
+    pub themes: BTreeMap<String, Colors>,
+    /// 24-bit colour, or the 16 ANSI colours; unset is taken as 24-bit.
+    pub truecolor: Option<bool>,
+    pub panels: Panels,
+    pub keys: Keymap,
+    /// Where to push metrics in line protocol, if anywhere.
+    pub influx: Option<Influx>,
+    /// Hosts to watch, as `--connect` takes them; `local` is this machine.
+    pub hosts: Vec<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            tick: 1000,
+            sort: SortColumn::Cpu,
+            descending: true,
+            theme: None,
+            colors: None,
+            themes: BTreeMap::new(),
+            truecolor: None,
+            panels: Panels::default(),
+            keys: Keymap::default(),
+            influx: None,
+            hosts: Vec::new(),
+        }
+    }
+}
+
+pub fn config_path() -> PathBuf {
+    crate::plugin::config_dir().join("config.toml")
+}
+
+impl Config {
+    /// The hosts to show: those given on the command line, else `hosts`,
+    /// else this machine alone. This machine is the empty name.
+    pub fn host_names(&self, connect: &[String]) -> Vec<String> {
+        let names = if connect.is_empty() { &self.hosts } else { connect };
+        if names.is_empty() {
+            return vec![String::new()];
+        }
+        names
+            .iter()
+            .map(|name| if name == "local" { String::new() } else { name.clone() })
+            .collect()
+    }
+
+    /// Reads `path`; a missing file gives the defaults.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => {
+                let config: Self =
+                    toml::from_str(&text).map_err(|e| format!("config.toml: {}", e))?;
+                theme::start(&config, &theme::list(&config))
+                    .map_err(|e| format!("config.toml: {}", e))?;
+                Ok(config)
+            }
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    /// `tick` within the bounds `+` and `-` keep to.
+    pub fn tick_rate(&self) -> Duration {
+        Duration::from_millis(self.tick).clamp(MIN_TICK_RATE, MAX_TICK_RATE)
+    }
+}
+
+/// The dashboard's palette; see [`theme`] for the named ones.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct Colors {
+    #[serde(deserialize_with = "color")]
+    pub background: Color,
+    #[serde(deserialize_with = "color")]
+    pub border: Color,
+    /// Titles, gauges and the focused panel.
+    #[serde(deserialize_with = "color")]
+    pub accent: Color,
+    /// Critical values and firing alerts.
+    #[serde(deserialize_with = "color")]
+    pub high: Color,
+    #[serde(deserialize_with = "color")]
+    pub warn: Color,
+    #[serde(deserialize_with = "color")]
+    pub text: Color,
+    #[serde(deserialize_with = "color")]
+    pub header_bg: Color,
+    #[serde(deserialize_with = "color")]
+    pub header_fg: Color,
+}
+
+impl Default for Colors {
+    fn default() -> Self {
+        Self {
+            background: Color::Rgb(26, 27, 38),
+            border: Color::Rgb(160, 160, 160),
+            accent: Color::Rgb(0, 255, 127),
+            high: Color::Rgb(255, 85, 85),
+            warn: Color::Rgb(255, 184, 108),
+            text: Color::Rgb(192, 202, 245),
+            header_bg: Color::Rgb(65, 72, 104),
+            header_fg: Color::White,
+        }
+    }
+}
+
+fn color<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    Color::from_str(&name)
+        .map_err(|_| serde::de::Error::custom(format!("unknown color {:?}", name)))
+}
+
+/// Which panels are drawn; the others take the space of hidden ones.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct Panels {
+    /// The CPU history chart.
+    pub cpu: bool,
+    pub cores: bool,
+    pub memory: bool,
+    pub disks: bool,
+    pub network: bool,
+    pub processes: bool,
+    /// Shown only when the collector finds a GPU.
+    pub gpu: bool,
+    /// Battery and hardware sensors, shown only when there are readings.
+    pub sensors: bool,
+}
+
+impl Default for Panels {
+    fn default() -> Self {
+        Self {
+            cpu: true,
+            cores: true,
+            memory: true,
+            disks: true,
+            network: true,
+            processes: true,
+            gpu: true,
+            sensors: true,
+        }
+    }
+}
+
+/// An InfluxDB or VictoriaMetrics write endpoint.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct Influx {
+    /// The full write URL, with the org and bucket (or db) in the query.
+    pub url: String,
+    /// Sent as `Authorization: Token <token>` (InfluxDB 2).
+    pub token: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct UiConfig {
+    /// A language tag such as `pt_BR`; the environment's when absent.
+    pub locale: Option<String>,
+    /// At most this many processes, the first in sort order; 0 shows all.
+    pub process_limit: usize,
+    pub clock: Clock,
+    pub screensaver: Screensaver,
+}
+
+pub fn ui_path() -> PathBuf {
+    crate::plugin::config_dir().join("ui.toml")
+}
+
+impl UiConfig {
+    /// Reads `path`; a missing file gives the defaults. Settings that would
+    /// only fail on the first frame, like a bad clock format, fail here.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let config: Self = match std::fs::read_to_string(path) {
+            Ok(text) => toml::from_str(&text).map_err(|e| format!("ui.toml: {}", e))?,
+            Err(_) => Self::default(),
+        };
+        config
+            .clock
+            .validate()
+            .map_err(|e| format!("ui.toml: {}", e))?;
+        Ok(config)
+    }
+
+    pub fn process_limit(&self) -> Option<usize> {
+        (self.process_limit > 0).then_some(self.process_limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_config() {
+        let config: Config = toml::from_str(
+            "tick = 100\nsort = \"mem\"\n[colors]\naccent = \"#ff0000\"\nborder = \"cyan\"\n[panels]\ncores = false",
+        )
+        .unwrap();
+        assert_eq!(config.tick_rate(), MIN_TICK_RATE);
+        assert_eq!(config.sort, SortColumn::Mem);
+        let colors = config.colors.unwrap();
+        assert_eq!(colors.accent, Color::Rgb(255, 0, 0));
+        assert_eq!(colors.border, Color::Cyan);
+        assert!(!config.panels.cores && config.panels.cpu);
+        assert!(toml::from_str::<Config>("[colors]\ntext = \"nope\"").is_err());
+        assert_eq!(config.influx, None);
+
+        let config: Config =
+            toml::from_str("[influx]\nurl = \"http://vm:8428/write\"").unwrap();
+        assert_eq!(config.influx.unwrap().url, "http://vm:8428/write");
+        assert!(toml::from_str::<Config>("[influx]\ntoken = \"t\"").is_err());
+    }
+
+    #[test]
+    fn test_host_names() {
+        let mut config = Config::default();
+        assert_eq!(config.host_names(&[]), [""]);
+        config.hosts = vec!["local".into(), "nas:7879".into()];
+        assert_eq!(config.host_names(&[]), ["", "nas:7879"]);
+        assert_eq!(config.host_names(&["ssh://me@pi".into()]), ["ssh://me@pi"]);
+    }
+
+    #[test]
+    fn test_load() {
+        let dir = std::env::temp_dir().join(format!("sysdash-ui-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("ui.toml");
+        assert_eq!(UiConfig::load(&path), Ok(UiConfig::default()));
+
+        std::fs::write(
+            &path,
+            "locale = \"pt\"\nprocess_limit = 50\n[clock]\nutc = true\n[screensaver]\nafter = 60",
+        )
+        .unwrap();
+        let config = UiConfig::load(&path).unwrap();
+        assert_eq!(config.locale.as_deref(), Some("pt"));
+        assert_eq!(config.process_limit(), Some(50));
+        assert!(config.clock.utc);
+        assert_eq!(config.screensaver.after(), Some(Duration::from_secs(60)));
+
+        std::fs::write(&path, "[clock]\nformat = \"%Q\"").unwrap();
+        assert!(UiConfig::load(&path).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,435 @@
+//! Containers from a Docker daemon. With the "docker" feature a background
+//! task polls the daemon named by `--docker-host` or `DOCKER_HOST`: the local
+//! socket, `tcp://host:2375` or `ssh://user@host`, so the containers of a NAS
+//! or server can be watched without deploying an agent there. Podman serves
+//! the same API on its own socket, which is used when Docker's is missing,
+//! so rootless containers show up too. The same task
+//! stops or kills a container when the panel asks it to.
+
+use crate::history::History;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedSender;
+
+/// Polls kept per container for the sparklines in its row.
+pub const RATE_HISTORY: usize = 20;
+
+/// One running container, as of the last poll.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ContainerInfo {
+    pub id: String,
+    pub name: String,
+    pub image: String,
+    pub status: String,
+    pub cpu: f32,
+    /// Usage without the page cache, as `docker stats` shows it.
+    pub mem_bytes: u64,
+    pub mem_limit: u64,
+    /// Bytes per second since the previous poll, summed over interfaces.
+    pub rx_rate: u64,
+    pub tx_rate: u64,
+    /// Block I/O in bytes per second since the previous poll.
+    pub read_rate: u64,
+    pub write_rate: u64,
+}
+
+/// The result of one poll of a daemon.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Containers {
+    /// The daemon's address, for the panel title.
+    pub host: String,
+    pub list: Vec<ContainerInfo>,
+    /// Why the daemon could not be queried; `list` is then empty.
+    pub error: Option<String>,
+    /// The outcome of a [`ContainerRequest`] made since the previous poll.
+    pub notice: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContainerAction {
+    /// SIGTERM, then SIGKILL after the container's stop timeout.
+    Stop,
+    Kill,
+}
+
+impl ContainerAction {
+    pub fn verb(self) -> &'static str {
+        match self {
+            ContainerAction::Stop => "stop",
+            ContainerAction::Kill => "kill",
+        }
+    }
+
+    pub fn done(self) -> &'static str {
+        match self {
+            ContainerAction::Stop => "stopped",
+            ContainerAction::Kill => "killed",
+        }
+    }
+}
+
+/// An action on one container, confirmed in the panel and carried out by
+/// the polling task.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContainerRequest {
+    pub action: ContainerAction,
+    pub id: String,
+    /// For the confirmation and the outcome.
+    pub name: String,
+}
+
+/// Where the panel sends its requests.
+pub type Control = UnboundedSender<ContainerRequest>;
+
+/// Recent network and block I/O rates of each running container, by id.
+/// Container load comes in bursts that a single poll's figure misses.
+#[derive(Clone, Debug, Default)]
+pub struct RateHistory {
+    rates: HashMap<String, Rates>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Rates {
+    /// Received plus sent.
+    pub net: History,
+    /// Read plus written.
+    pub io: History,
+}
+
+impl RateHistory {
+    /// Adds a poll's rates; containers no longer listed are forgotten.
+    pub fn record(&mut self, list: &[ContainerInfo]) {
+        self.rates.retain(|id, _| list.iter().any(|c| &c.id == id));
+        for c in list {
+            let rates = self.rates.entry(c.id.clone()).or_insert_with(|| Rates {
+                net: History::new(RATE_HISTORY),
+                io: History::new(RATE_HISTORY),
+            });
+            rates.net.push(c.rx_rate + c.tx_rate);
+            rates.io.push(c.read_rate + c.write_rate);
+        }
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Rates> {
+        self.rates.get(id)
+    }
+}
+
+/// The daemon to poll: `flag` if given, else `DOCKER_HOST`, else the first
+/// local socket that exists.
+pub fn docker_host(flag: Option<&str>) -> String {
+    flag.map(str::to_string)
+        .or_else(|| std::env::var("DOCKER_HOST").ok().filter(|h| !h.is_empty()))
+        .unwrap_or_else(|| {
+            if cfg!(windows) {
+                return "npipe:////./pipe/docker_engine".into();
+            }
+            let sockets = local_sockets(std::env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from));
+            let socket = sockets.iter().find(|s| s.exists()).unwrap_or(&sockets[0]);
+            format!("unix://{}", socket.display())
+        })
+}
+
+/// Docker's socket, then rootless Podman's in the user's runtime directory,
+/// then the system Podman's.
+fn local_sockets(runtime_dir: Option<PathBuf>) -> Vec<PathBuf> {
+    let mut sockets = vec![PathBuf::from("/var/run/docker.sock")];
+    sockets.extend(runtime_dir.map(|dir| dir.join("podman/podman.sock")));
+    sockets.push(PathBuf::from("/run/podman/podman.sock"));
+    sockets
+}
+
+/// How often containers are polled: as often as processes, but no faster
+/// than the daemon can sample them.
+pub fn poll_interval(processes_secs: u64) -> Duration {
+    Duration::from_secs(processes_secs.max(2))
+}
+
+#[cfg(feature = "docker")]
+pub use self::docker::spawn;
+
+#[cfg(feature = "docker")]
+mod docker {
+    use super::{ContainerAction, ContainerInfo, ContainerRequest, Containers, Control};
+    use bollard::models::{ContainerStatsResponse, ContainerSummary};
+    use bollard::query_parameters::{
+        KillContainerOptions, ListContainersOptions, StatsOptions, StopContainerOptions,
+    };
+    use bollard::Docker;
+    use futures_util::future::join_all;
+    use futures_util::StreamExt;
+    use std::collections::HashMap;
+    use std::time::{Duration, Instant};
+    use tokio::sync::mpsc;
+
+    /// Polls `host` every `interval` in a tokio task and passes each result
+    /// to `deliver`, until it returns false. A failed connection is retried
+    /// on the next poll. Requests sent to the returned [`Control`] are
+    /// carried out between polls, and their outcome comes with the next one,
+    /// right after. Must be called from within a tokio runtime.
+    pub fn spawn<F>(host: String, interval: Duration, mut deliver: F) -> Control
+    where
+        F: FnMut(Containers) -> bool + Send + 'static,
+    {
+        let (control, mut requests) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let mut docker = None;
+            let mut previous = HashMap::new();
+            let mut notice = None;
+            let mut ticks = tokio::time::interval(interval);
+            ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                tokio::select! {
+                    _ = ticks.tick() => {}
+                    Some(request) = requests.recv() => {
+                        notice = Some(match &docker {
+                            Some(client) => act(client, &request).await,
+                            None => format!(
+                                "{} {}: not connected",
+                                request.action.verb(),
+                                request.name
+                            ),
+                        });
+                        ticks.reset_immediately();
+                        continue;
+                    }
+                }
+                let result = match &docker {
+                    Some(client) => poll(client, &mut previous).await,
+                    None => match Docker::connect_with_host(&host) {
+                        Ok(client) => poll(docker.insert(client), &mut previous).await,
+                        Err(e) => Err(e.to_string()),
+                    },
+                };
+                let containers = match result {
+                    Ok(list) => Containers {
+                        host: host.clone(),
+                        list,
+                        error: None,
+                        notice: notice.take(),
+                    },
+                    Err(error) => {
+                        docker = None;
+                        previous.clear();
+                        Containers {
+                            host: host.clone(),
+                            list: Vec::new(),
+                            error: Some(error),
+                            notice: notice.take(),
+                        }
+                    }
+                };
+                if !deliver(containers) {
+                    return;
+                }
+            }
+        });
+        control
+    }
+
+    async fn act(docker: &Docker, request: &ContainerRequest) -> String {
+        let result = match request.action {
+            ContainerAction::Stop => {
+                docker
+                    .stop_container(&request.id, None::<StopContainerOptions>)
+                    .await
+            }
+            ContainerAction::Kill => {
+                docker
+                    .kill_container(&request.id, None::<KillContainerOptions>)
+                    .await
+            }
+        };
+        match result {
+            Ok(()) => format!("{} {}", request.action.done(), request.name),
+            Err(e) => format!("{} {}: {}", request.action.verb(), request.name, e),
+        }
+    }
+
+    /// CPU percent between two samples, like `docker stats`: the container's share
+    /// of the whole machine's time, scaled by the number of CPUs.
+    pub(super) fn cpu_percent(
+        total: u64,
+        pre_total: u64,
+        system: u64,
+        pre_system: u64,
+        cpus: u32,
+    ) -> f32 {
+        let used = total.saturating_sub(pre_total) as f64;
+        let elapsed = system.saturating_sub(pre_system) as f64;
+        if elapsed <= 0.0 {
+            return 0.0;
+        }
+        (used / elapsed * cpus.max(1) as f64 * 100.0) as f32
+    }
+
+    /// Network and block I/O byte counters per container id from the
+    /// previous poll.
+    type Counters = HashMap<String, ([u64; 4], Instant)>;
+
+    async fn poll(docker: &Docker, previous: &mut Counters) -> Result<Vec<ContainerInfo>, String> {
+        let summaries = docker
+            .list_containers(None::<ListContainersOptions>)
+            .await
+            .map_err(|e| e.to_string())?;
+        // The daemon takes a second to sample each container's CPU, so all
+        // of them are asked at once.
+        let stats = join_all(summaries.iter().map(|summary| async move {
+            let id = summary.id.as_deref()?;
+            let options = StatsOptions {
+                stream: false,
+                one_shot: false,
+            };
+            docker.stats(id, Some(options)).next().await?.ok()
+        }))
+        .await;
+
+        let now = Instant::now();
+        let mut list = Vec::new();
+        let mut seen = Counters::new();
+        for (summary, stats) in summaries.iter().zip(stats) {
+            let mut info = info(summary, stats.as_ref());
+            let (rx, tx) = stats.as_ref().map_or((0, 0), network_totals);
+            let (read, write) = stats.as_ref().map_or((0, 0), io_totals);
+            let totals = [rx, tx, read, write];
+            if let Some((pre, at)) = previous.get(&info.id) {
+                let secs = now.duration_since(*at).as_secs_f64().max(0.001);
+                let rate = |i: usize| (totals[i].saturating_sub(pre[i]) as f64 / secs) as u64;
+                info.rx_rate = rate(0);
+                info.tx_rate = rate(1);
+                info.read_rate = rate(2);
+                info.write_rate = rate(3);
+            }
+            seen.insert(info.id.clone(), (totals, now));
+            list.push(info);
+        }
+        *previous = seen;
+        Ok(list)
+    }
+
+    fn info(summary: &ContainerSummary, stats: Option<&ContainerStatsResponse>) -> ContainerInfo {
+        let name = summary
+            .names
+            .as_ref()
+            .and_then(|names| names.first())
+            .map(|name| name.trim_start_matches('/').to_string())
+            .unwrap_or_default();
+        let mut info = ContainerInfo {
+            id: summary.id.clone().unwrap_or_default(),
+            name,
+            image: summary.image.clone().unwrap_or_default(),
+            status: summary.status.clone().unwrap_or_default(),
+            ..ContainerInfo::default()
+        };
+        let Some(stats) = stats else {
+            return info;
+        };
+        if let (Some(cpu), Some(pre)) = (&stats.cpu_stats, &stats.precpu_stats) {
+            let total = |s: &bollard::models::ContainerCpuStats| {
+                s.cpu_usage
+                    .as_ref()
+                    .and_then(|u| u.total_usage)
+                    .unwrap_or(0)
+            };
+            info.cpu = cpu_percent(
+                total(cpu),
+                total(pre),
+                cpu.system_cpu_usage.unwrap_or(0),
+                pre.system_cpu_usage.unwrap_or(0),
+                cpu.online_cpus.unwrap_or(1),
+            );
+        }
+        if let Some(memory) = &stats.memory_stats {
+            // cgroup v2 reports the page cache as inactive_file, v1 as cache.
+            let cache = memory
+                .stats
+                .as_ref()
+                .and_then(|s| s.get("inactive_file").or_else(|| s.get("cache")).copied())
+                .unwrap_or(0);
+            info.mem_bytes = memory.usage.unwrap_or(0).saturating_sub(cache);
+            info.mem_limit = memory.limit.unwrap_or(0);
+        }
+        info
+    }
+
+    fn network_totals(stats: &ContainerStatsResponse) -> (u64, u64) {
+        stats
+            .networks
+            .iter()
+            .flatten()
+            .fold((0, 0), |(rx, tx), (_, n)| {
+                (rx + n.rx_bytes.unwrap_or(0), tx + n.tx_bytes.unwrap_or(0))
+            })
+    }
+
+    /// Bytes read and written over all devices. cgroup v2 hosts report
+    /// these too, with the op in lower case.
+    fn io_totals(stats: &ContainerStatsResponse) -> (u64, u64) {
+        stats
+            .blkio_stats
+            .as_ref()
+            .and_then(|b| b.io_service_bytes_recursive.as_ref())
+            .into_iter()
+            .flatten()
+            .fold((0, 0), |(read, write), entry| {
+                let value = entry.value.unwrap_or(0);
+                match entry.op.as_deref().map(str::to_lowercase).as_deref() {
+                    Some("read") => (read + value, write),
+                    Some("write") => (read, write + value),
+                    _ => (read, write),
+                }
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "docker")]
+    #[test]
+    fn test_cpu_percent() {
+        use super::docker::cpu_percent;
+
+        // 0.5s of CPU out of 4s of machine time on 4 CPUs: half a core.
+        assert_eq!(cpu_percent(1_500, 1_000, 5_000, 1_000, 4), 50.0);
+        assert_eq!(cpu_percent(1_000, 1_000, 1_000, 1_000, 4), 0.0);
+    }
+
+    #[test]
+    fn test_rate_history() {
+        let container = |id: &str, rx: u64, write: u64| ContainerInfo {
+            id: id.into(),
+            rx_rate: rx,
+            write_rate: write,
+            ..ContainerInfo::default()
+        };
+        let mut history = RateHistory::default();
+        history.record(&[container("a", 10, 0), container("b", 0, 5)]);
+        history.record(&[container("a", 30, 7)]);
+
+        let a = history.get("a").unwrap();
+        assert_eq!(a.net.last(2), vec![10, 30]);
+        assert_eq!(a.io.last(2), vec![0, 7]);
+        assert_eq!(a.net.capacity(), RATE_HISTORY);
+        assert!(history.get("b").is_none());
+    }
+
+    #[test]
+    fn test_docker_host() {
+        assert_eq!(docker_host(Some("ssh://me@nas")), "ssh://me@nas");
+        if std::env::var_os("DOCKER_HOST").is_none() {
+            assert!(docker_host(None).ends_with(".sock"));
+        }
+        assert_eq!(
+            local_sockets(Some("/run/user/1000".into())),
+            [
+                PathBuf::from("/var/run/docker.sock"),
+                PathBuf::from("/run/user/1000/podman/podman.sock"),
+                PathBuf::from("/run/podman/podman.sock"),
+            ]
+        );
+        assert_eq!(local_sockets(None).len(), 2);
+    }
+}
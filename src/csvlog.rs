@@ -0,0 +1,154 @@
+//! `--log-csv <path>` appends a row of every metric of the registry for
+//! every snapshot of the local machine while the dashboard runs, for
+//! graphing later in a spreadsheet. The header is written when the file is
+//! new or empty.
+
+use crate::sys::metrics::{registry, Unit};
+use crate::sys::SysCache;
+use std::fmt::Write as _;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+
+/// A metric of the registry and the label of one of its series.
+type Column = (&'static str, Option<String>);
+
+pub struct CsvLog {
+    file: File,
+    /// The header still has to be written, before the first row.
+    header: bool,
+    /// Fixed by the first snapshot; empty until then.
+    columns: Vec<Column>,
+}
+
+impl CsvLog {
+    /// Opens `path` for appending, creating it if needed.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let header = file.metadata()?.len() == 0;
+        Ok(Self {
+            file,
+            header,
+            columns: Vec::new(),
+        })
+    }
+
+    /// Appends the row for `sys`, preceded by the header on a new file.
+    pub fn record(&mut self, sys: &SysCache) -> io::Result<()> {
+        if self.columns.is_empty() {
+            self.columns = columns(sys);
+        }
+        let mut out = String::new();
+        if std::mem::take(&mut self.header) {
+            out.push_str(&header(&self.columns));
+        }
+        out.push_str(&row(&self.columns, sys, &chrono::Local::now().to_rfc3339()));
+        self.file.write_all(out.as_bytes())
+    }
+}
+
+/// Every series of the registry that `sys` reports. A series that shows up
+/// later, such as a new mount point, gets no column.
+fn columns(sys: &SysCache) -> Vec<Column> {
+    registry()
+        .iter()
+        .flat_map(|def| (def.read)(sys).into_iter().map(|s| (def.name, s.label)))
+        .collect()
+}
+
+/// The metric name, and the label of the series in brackets, e.g.
+/// `filesystem.utilization[/home]`.
+fn header(columns: &[Column]) -> String {
+    let mut out = String::from("time");
+    for (name, label) in columns {
+        let column = match label {
+            Some(label) => format!("{}[{}]", name, label),
+            None => name.to_string(),
+        };
+        let _ = write!(out, ",{}", quote(&column));
+    }
+    out.push('\n');
+    out
+}
+
+/// Values in the metric's unit; empty where the series isn't reported.
+fn row(columns: &[Column], sys: &SysCache, time: &str) -> String {
+    let registry = registry();
+    let mut out = time.to_string();
+    for (name, label) in columns {
+        out.push(',');
+        let Some(def) = registry.get(name) else {
+            continue;
+        };
+        if let Some(value) = registry.value(name, label.as_deref(), sys) {
+            let _ = match def.unit {
+                Unit::Percent | Unit::Celsius | Unit::Watts => write!(out, "{:.1}", value),
+                _ => write!(out, "{}", value),
+            };
+        }
+    }
+    out.push('\n');
+    out
+}
+
+/// Quotes a cell with a comma, quote or line break, e.g. from a mount point.
+fn quote(cell: &str) -> String {
+    if cell.contains([',', '"', '\n']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_row() {
+        let mut sys = SysCache::default();
+        sys.cpu_cores = vec![10.0, 30.26];
+        sys.cpu_global = 20.14;
+        sys.used_mem = 2048;
+        sys.total_mem = 8192;
+        sys.rx_rate = 100;
+        sys.tx_rate = 50;
+        let columns = columns(&sys);
+        let head = header(&columns);
+        assert!(head.starts_with(
+            "time,cpu.utilization,cpu.core.utilization[0],cpu.core.utilization[1],"
+        ));
+        assert!(head.contains(",memory.usage[used],memory.usage[total],"));
+        assert!(head.contains(",network.io.rate[receive],network.io.rate[transmit],"));
+        // No swap or disk counters on this host, so no columns for them.
+        assert!(!head.contains("swap.usage"));
+        assert!(!head.contains("disk.io.rate"));
+        assert_eq!(head.matches(',').count(), columns.len());
+
+        let line = row(&columns, &sys, "2024-03-01T14:05:09+01:00");
+        assert!(line.starts_with("2024-03-01T14:05:09+01:00,20.1,10.0,30.3,"));
+        assert!(line.contains(",25.0,2048,8192,"));
+        assert!(line.contains(",100,50,"));
+        assert_eq!(line.matches(',').count(), columns.len());
+
+        // A core that goes away leaves its cell empty.
+        sys.cpu_cores.pop();
+        assert!(row(&columns, &sys, "t").starts_with("t,20.1,10.0,,"));
+        assert_eq!(quote("/mnt/a,b"), "\"/mnt/a,b\"");
+    }
+
+    #[test]
+    fn test_header_once() {
+        let path = std::env::temp_dir().join(format!("sysdash-csv-{}.csv", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        CsvLog::open(&path).unwrap().record(&SysCache::default()).unwrap();
+        CsvLog::open(&path).unwrap().record(&SysCache::default()).unwrap();
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("time,cpu.utilization,"));
+        assert!(!lines[2].starts_with("time"));
+    }
+}
@@ -0,0 +1,148 @@
+//! Compare mode: a baseline marked with `b`, and what changed since then.
+//! sysinfo has no cumulative CPU time, so each process' share is integrated
+//! from its usage over the snapshots that followed the baseline; memory
+//! growth is a plain difference and network bytes are summed per refresh.
+
+use crate::sys::{ProcessInfo, SysCache};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// How the delta popup orders processes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DeltaSort {
+    #[default]
+    CpuTime,
+    MemGrowth,
+}
+
+/// What one process did since the baseline.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProcessDelta {
+    pub pid: u32,
+    pub name: Arc<str>,
+    /// CPU seconds consumed.
+    pub cpu_secs: f64,
+    pub mem_now: u64,
+    /// Bytes gained (negative when it shrank).
+    pub mem_growth: i64,
+    /// Started after the baseline (or reused a PID under another name).
+    pub new: bool,
+}
+
+pub struct Baseline {
+    pub at: chrono::DateTime<chrono::Local>,
+    started: Instant,
+    last: Instant,
+    /// Name and memory of each process at the baseline.
+    procs: HashMap<u32, (Arc<str>, u64)>,
+    cpu_secs: HashMap<u32, f64>,
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+}
+
+impl Baseline {
+    pub fn new(sys: &SysCache, now: Instant) -> Self {
+        Self {
+            at: chrono::Local::now(),
+            started: now,
+            last: now,
+            procs: sys
+                .processes()
+                .iter()
+                .map(|p| (p.pid, (p.name.clone(), p.mem_bytes)))
+                .collect(),
+            cpu_secs: HashMap::new(),
+            rx_bytes: 0,
+            tx_bytes: 0,
+        }
+    }
+
+    /// Adds a snapshot taken at `now`: usage is assumed to have held since
+    /// the previous one.
+    pub fn record(&mut self, sys: &SysCache, now: Instant) {
+        let secs = now.duration_since(self.last).as_secs_f64();
+        self.last = now;
+        for p in sys.processes() {
+            *self.cpu_secs.entry(p.pid).or_default() += p.cpu as f64 / 100.0 * secs;
+        }
+        self.rx_bytes += sys.rx_rate;
+        self.tx_bytes += sys.tx_rate;
+    }
+
+    /// Seconds since the baseline was marked.
+    pub fn elapsed(&self) -> u64 {
+        self.last.duration_since(self.started).as_secs()
+    }
+
+    /// The running processes, biggest change first.
+    pub fn rows(&self, procs: &[ProcessInfo], sort: DeltaSort) -> Vec<ProcessDelta> {
+        let mut rows: Vec<ProcessDelta> = procs
+            .iter()
+            .map(|p| {
+                let before = self.procs.get(&p.pid).filter(|(name, _)| *name == p.name);
+                ProcessDelta {
+                    pid: p.pid,
+                    name: p.name.clone(),
+                    cpu_secs: self.cpu_secs.get(&p.pid).copied().unwrap_or(0.0),
+                    mem_now: p.mem_bytes,
+                    mem_growth: p.mem_bytes as i64 - before.map_or(0, |(_, mem)| *mem as i64),
+                    new: before.is_none(),
+                }
+            })
+            .collect();
+        match sort {
+            DeltaSort::CpuTime => rows.sort_by(|a, b| b.cpu_secs.total_cmp(&a.cpu_secs)),
+            DeltaSort::MemGrowth => rows.sort_by_key(|r| std::cmp::Reverse(r.mem_growth)),
+        }
+        rows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn proc(pid: u32, name: &str, cpu: f32, mem_bytes: u64) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            name: name.into(),
+            user: "me".into(),
+            cmd: name.into(),
+            cpu,
+            mem_bytes,
+            parent: None,
+        }
+    }
+
+    #[test]
+    fn test_deltas() {
+        let t0 = Instant::now();
+        let before = SysCache::default()
+            .with_processes(vec![proc(1, "db", 0.0, 100), proc(2, "web", 0.0, 500)]);
+        let mut baseline = Baseline::new(&before, t0);
+
+        let mut now = SysCache::default().with_processes(vec![
+            proc(1, "db", 50.0, 400),
+            proc(2, "web", 10.0, 300),
+            proc(3, "cron", 0.0, 50),
+        ]);
+        now.rx_rate = 1000;
+        for i in 1..=10 {
+            baseline.record(&now, t0 + Duration::from_secs(i));
+        }
+        assert_eq!(baseline.elapsed(), 10);
+        assert_eq!(baseline.rx_bytes, 10_000);
+
+        let rows = baseline.rows(now.processes(), DeltaSort::CpuTime);
+        assert_eq!(rows[0].pid, 1);
+        assert!((rows[0].cpu_secs - 5.0).abs() < 1e-9);
+        assert_eq!(rows[0].mem_growth, 300);
+
+        let rows = baseline.rows(now.processes(), DeltaSort::MemGrowth);
+        assert_eq!(rows.iter().map(|r| r.pid).collect::<Vec<_>>(), [1, 3, 2]);
+        assert!(rows[1].new);
+        assert_eq!(rows[2].mem_growth, -200);
+    }
+}
@@ -0,0 +1,78 @@
+//! Zero-configuration discovery of agents on the LAN over mDNS (cargo
+//! feature "mdns"). Agents announce `_sysdash._tcp`; clients browse for it
+//! and list what they find in the connect popup.
+
+use crate::remote::Agent;
+use mdns_sd::{Error, ServiceDaemon, ServiceEvent, ServiceInfo};
+use std::collections::HashMap;
+
+pub const SERVICE_TYPE: &str = "_sysdash._tcp.local.";
+
+/// Announces an agent listening on `port` for as long as the returned daemon
+/// is kept. A `tls=1` TXT property tells clients to connect over TLS.
+pub fn announce(port: u16, tls: bool) -> Result<ServiceDaemon, Error> {
+    let daemon = ServiceDaemon::new()?;
+    let host = sysinfo::System::host_name().unwrap_or_else(|| "sysdash".into());
+    let info = ServiceInfo::new(
+        SERVICE_TYPE,
+        &host,
+        &format!("{}.local.", host),
+        "",
+        port,
+        tls.then(|| HashMap::from([("tls".to_string(), "1".to_string())])),
+    )?
+    .enable_addr_auto();
+    daemon.register(info)?;
+    Ok(daemon)
+}
+
+/// Browses for agents in a background task and passes each resolved one to
+/// `found`, until it returns false. Must be called from within a tokio
+/// runtime; keep the returned daemon for as long as browsing should go on.
+pub fn browse<F>(mut found: F) -> Result<ServiceDaemon, Error>
+where
+    F: FnMut(Agent) -> bool + Send + 'static,
+{
+    let daemon = ServiceDaemon::new()?;
+    let events = daemon.browse(SERVICE_TYPE)?;
+    tokio::spawn(async move {
+        while let Ok(event) = events.recv_async().await {
+            if let ServiceEvent::ServiceResolved(info) = event {
+                if let Some(agent) = agent(&info) {
+                    if !found(agent) {
+                        return;
+                    }
+                }
+            }
+        }
+    });
+    Ok(daemon)
+}
+
+/// The instance name and an address to connect to, preferring IPv4.
+fn agent(info: &ServiceInfo) -> Option<Agent> {
+    let ip = info
+        .get_addresses_v4()
+        .into_iter()
+        .next()
+        .map(|ip| ip.to_string())
+        .or_else(|| {
+            info.get_addresses()
+                .iter()
+                .next()
+                .map(|ip| format!("[{}]", ip))
+        })?;
+    let name = info
+        .get_fullname()
+        .strip_suffix(SERVICE_TYPE)
+        .unwrap_or(info.get_fullname())
+        .trim_end_matches('.');
+    let scheme = match info.get_property_val_str("tls") {
+        Some("1") => "tls://",
+        _ => "",
+    };
+    Some(Agent {
+        name: name.to_string(),
+        addr: format!("{}{}:{}", scheme, ip, info.get_port()),
+    })
+}
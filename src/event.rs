@@ -1,25 +1,196 @@
-use crossterm::event::{self, Event as CEvent, KeyEvent, KeyEventKind};
-use std::sync::mpsc::{self, Receiver};
-use std::thread;
+//! The UI event queue. Input, ticks and signals each run as a task on the
+//! tokio runtime and feed one channel; other producers such as the collector
+//! hold a [`sender`](Events::sender).
+
+use crate::containers::Containers;
+use crate::remote::Agent;
+use crate::sys::SysCache;
+use crossterm::event::{
+    Event as CEvent, EventStream, KeyEvent, KeyEventKind, MouseEvent, MouseEventKind,
+};
+use futures_util::StreamExt;
 use std::time::Duration;
+use tokio::sync::{mpsc, watch};
+use tokio::time::{self, Instant, MissedTickBehavior};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub enum Event {
     Input(KeyEvent),
+    /// The mouse wheel turned; other mouse events are dropped.
+    Mouse(MouseEvent),
     Tick,
+    /// A fresh snapshot for the host at this index, from its
+    /// [`crate::worker::Worker`].
+    Refresh(usize, Box<SysCache>),
+    /// A fresh list from the Docker daemon.
+    Containers(Box<Containers>),
+    /// An agent was discovered on the network.
+    Discovered(Agent),
+    /// The terminal gained (true) or lost focus.
+    Focus(bool),
+    /// SIGTERM, SIGHUP, SIGINT or SIGQUIT arrived, or on Windows a console
+    /// control event (its `CTRL_*_EVENT` number); shut down cleanly.
+    Terminate(i32),
+}
+
+pub struct Events {
+    tx: mpsc::UnboundedSender<Event>,
+    rx: mpsc::UnboundedReceiver<Event>,
+    tick_rate: watch::Sender<Duration>,
+}
+
+impl Events {
+    /// Starts the input, tick and signal tasks; must be called from within a
+    /// tokio runtime.
+    pub fn spawn(tick_rate: Duration) -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(read_input(tx.clone()));
+
+        let (control, rates) = watch::channel(tick_rate);
+        tokio::spawn(run_timer(rates, tx.clone()));
+
+        #[cfg(any(unix, windows))]
+        tokio::spawn(forward_signals(tx.clone()));
+
+        Self {
+            tx,
+            rx,
+            tick_rate: control,
+        }
+    }
+
+    pub fn sender(&self) -> mpsc::UnboundedSender<Event> {
+        self.tx.clone()
+    }
+
+    pub async fn next(&mut self) -> Option<Event> {
+        self.rx.recv().await
+    }
+
+    /// Changes the tick interval; the next tick comes one new interval from now.
+    pub fn set_tick_rate(&self, rate: Duration) {
+        let _ = self.tick_rate.send(rate);
+    }
+}
+
+async fn read_input(tx: mpsc::UnboundedSender<Event>) {
+    let mut stream = EventStream::new();
+    while let Some(Ok(event)) = stream.next().await {
+        let event = match event {
+            CEvent::Key(key) if key.kind == KeyEventKind::Press => Event::Input(key),
+            CEvent::Mouse(mouse)
+                if matches!(
+                    mouse.kind,
+                    MouseEventKind::ScrollUp | MouseEventKind::ScrollDown
+                ) =>
+            {
+                Event::Mouse(mouse)
+            }
+            CEvent::FocusGained => Event::Focus(true),
+            CEvent::FocusLost => Event::Focus(false),
+            _ => continue,
+        };
+        if tx.send(event).is_err() {
+            return;
+        }
+    }
 }
 
-pub fn spawn_events(tick_rate: Duration) -> Receiver<Event> {
-    let (tx, rx) = mpsc::channel();
-    thread::spawn(move || loop {
-        if event::poll(tick_rate).unwrap_or(false) {
-            if let Ok(CEvent::Key(key)) = event::read() {
-                if key.kind == KeyEventKind::Press {
-                    let _ = tx.send(Event::Input(key));
+/// Sends a tick at fixed deadlines, so input never shifts the schedule. A
+/// late tick is not made up for; the schedule continues from when it fired.
+async fn run_timer(mut rates: watch::Receiver<Duration>, tx: mpsc::UnboundedSender<Event>) {
+    loop {
+        let rate = *rates.borrow_and_update();
+        let mut ticks = time::interval_at(Instant::now() + rate, rate);
+        ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                _ = ticks.tick() => {
+                    if tx.send(Event::Tick).is_err() {
+                        return;
+                    }
+                }
+                changed = rates.changed() => {
+                    if changed.is_err() {
+                        return;
+                    }
+                    break;
                 }
             }
         }
-        let _ = tx.send(Event::Tick);
-    });
-    rx
+    }
+}
+
+/// Forwards termination signals as [`Event::Terminate`], so the loop can
+/// restore the terminal instead of dying mid-frame when the window closes or
+/// a service manager stops us.
+#[cfg(unix)]
+async fn forward_signals(tx: mpsc::UnboundedSender<Event>) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let (Ok(mut term), Ok(mut hup), Ok(mut int), Ok(mut quit)) = (
+        signal(SignalKind::terminate()),
+        signal(SignalKind::hangup()),
+        signal(SignalKind::interrupt()),
+        signal(SignalKind::quit()),
+    ) else {
+        return;
+    };
+    loop {
+        let kind = tokio::select! {
+            _ = term.recv() => SignalKind::terminate(),
+            _ = hup.recv() => SignalKind::hangup(),
+            _ = int.recv() => SignalKind::interrupt(),
+            _ = quit.recv() => SignalKind::quit(),
+        };
+        if tx.send(Event::Terminate(kind.as_raw_value())).is_err() {
+            return;
+        }
+    }
+}
+
+/// The Windows counterpart: Ctrl-Break, closing the console window, logoff
+/// and shutdown. Ctrl-C arrives as a key press in raw mode.
+#[cfg(windows)]
+async fn forward_signals(tx: mpsc::UnboundedSender<Event>) {
+    use tokio::signal::windows::{ctrl_break, ctrl_close, ctrl_logoff, ctrl_shutdown};
+
+    let (Ok(mut brk), Ok(mut close), Ok(mut logoff), Ok(mut shutdown)) =
+        (ctrl_break(), ctrl_close(), ctrl_logoff(), ctrl_shutdown())
+    else {
+        return;
+    };
+    loop {
+        // CTRL_BREAK_EVENT, CTRL_CLOSE_EVENT, CTRL_LOGOFF_EVENT, CTRL_SHUTDOWN_EVENT
+        let code = tokio::select! {
+            _ = brk.recv() => 1,
+            _ = close.recv() => 2,
+            _ = logoff.recv() => 5,
+            _ = shutdown.recv() => 6,
+        };
+        if tx.send(Event::Terminate(code)).is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_timer_ticks_and_changes_rate() {
+        let (control, rates) = watch::channel(Duration::from_secs(3600));
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        tokio::spawn(run_timer(rates, tx));
+        assert!(time::timeout(Duration::from_millis(50), rx.recv())
+            .await
+            .is_err());
+
+        control.send(Duration::from_millis(5)).unwrap();
+        for _ in 0..3 {
+            let event = time::timeout(Duration::from_secs(5), rx.recv()).await;
+            assert!(matches!(event, Ok(Some(Event::Tick))));
+        }
+    }
 }
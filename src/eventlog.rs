@@ -0,0 +1,207 @@
+//! The event log panel, toggled with `e`: what changed on a host, newest
+//! first. Alerts firing and resolving, processes appearing and exiting,
+//! filesystems being mounted and unmounted and sudden jumps of the CPU
+//! temperature, each noted when the snapshot showing it arrives. Nothing is
+//! logged for the first snapshot, which only sets what later ones are
+//! compared with.
+
+use crate::alerts::{AlertEvent, AlertKind, Severity};
+use crate::sys::SysCache;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::sync::Arc;
+
+/// How many events are kept.
+pub const EVENT_LOG_LEN: usize = 200;
+
+/// A rise of the CPU temperature by at least this many degrees between two
+/// snapshots is logged as a spike.
+pub const TEMP_SPIKE: f32 = 10.0;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum EventKind {
+    Alert {
+        rule: String,
+        kind: AlertKind,
+        severity: Severity,
+    },
+    Started {
+        pid: u32,
+        name: Arc<str>,
+    },
+    Exited {
+        pid: u32,
+        name: Arc<str>,
+    },
+    Mounted(String),
+    Unmounted(String),
+    /// The CPU temperature jumped from the first value to the second.
+    TempSpike(f32, f32),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogEvent {
+    pub at: chrono::DateTime<chrono::Local>,
+    pub kind: EventKind,
+}
+
+/// What the last snapshot showed.
+struct Seen {
+    procs: HashMap<u32, Arc<str>>,
+    mounts: BTreeSet<String>,
+    temp: f32,
+}
+
+#[derive(Default)]
+pub struct EventLog {
+    /// Newest last.
+    pub entries: VecDeque<LogEvent>,
+    seen: Option<Seen>,
+}
+
+impl EventLog {
+    /// Logs what changed since the previous snapshot. A PID reused under
+    /// another name is an exit and a start.
+    pub fn record(&mut self, sys: &SysCache) {
+        let now = Seen {
+            procs: sys
+                .processes()
+                .iter()
+                .map(|p| (p.pid, p.name.clone()))
+                .collect(),
+            mounts: sys.disks().iter().map(|d| d.mount_point.clone()).collect(),
+            temp: sys.cpu_temp,
+        };
+        let Some(before) = self.seen.replace(now) else {
+            return;
+        };
+        let now = self.seen.as_ref().expect("just set");
+
+        let mut events = Vec::new();
+        for (pid, name) in &before.procs {
+            if now.procs.get(pid) != Some(name) {
+                events.push(EventKind::Exited {
+                    pid: *pid,
+                    name: name.clone(),
+                });
+            }
+        }
+        for (pid, name) in &now.procs {
+            if before.procs.get(pid) != Some(name) {
+                events.push(EventKind::Started {
+                    pid: *pid,
+                    name: name.clone(),
+                });
+            }
+        }
+        events.extend(
+            before
+                .mounts
+                .difference(&now.mounts)
+                .map(|m| EventKind::Unmounted(m.clone())),
+        );
+        events.extend(
+            now.mounts
+                .difference(&before.mounts)
+                .map(|m| EventKind::Mounted(m.clone())),
+        );
+        // 0 means no sensor, or none read yet.
+        if before.temp > 0.0 && now.temp - before.temp >= TEMP_SPIKE {
+            events.push(EventKind::TempSpike(before.temp, now.temp));
+        }
+        for kind in events {
+            self.push(kind, chrono::Local::now());
+        }
+    }
+
+    pub fn alert(&mut self, event: &AlertEvent) {
+        self.push(
+            EventKind::Alert {
+                rule: event.rule.clone(),
+                kind: event.kind,
+                severity: event.severity,
+            },
+            event.at,
+        );
+    }
+
+    fn push(&mut self, kind: EventKind, at: chrono::DateTime<chrono::Local>) {
+        if self.entries.len() == EVENT_LOG_LEN {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(LogEvent { at, kind });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sys::{DiskInfo, ProcessInfo};
+
+    fn sys(procs: &[(u32, &str)], mounts: &[&str], temp: f32) -> SysCache {
+        let procs = procs
+            .iter()
+            .map(|&(pid, name)| ProcessInfo {
+                pid,
+                name: name.into(),
+                user: "me".into(),
+                cmd: name.into(),
+                cpu: 0.0,
+                mem_bytes: 0,
+                parent: None,
+            })
+            .collect();
+        let disks = mounts
+            .iter()
+            .map(|m| DiskInfo {
+                _name: String::new(),
+                mount_point: m.to_string(),
+                total: 100,
+                available: 50,
+                temperature: None,
+            })
+            .collect();
+        let mut sys = SysCache::default().with_processes(procs).with_disks(disks);
+        sys.cpu_temp = temp;
+        sys
+    }
+
+    #[test]
+    fn test_record() {
+        let mut log = EventLog::default();
+        log.record(&sys(&[(1, "init"), (2, "old")], &["/"], 50.0));
+        assert!(log.entries.is_empty());
+
+        log.record(&sys(&[(1, "init"), (3, "new")], &["/", "/mnt/usb"], 55.0));
+        let kinds: Vec<_> = log.entries.iter().map(|e| e.kind.clone()).collect();
+        assert_eq!(
+            kinds,
+            [
+                EventKind::Exited {
+                    pid: 2,
+                    name: "old".into()
+                },
+                EventKind::Started {
+                    pid: 3,
+                    name: "new".into()
+                },
+                EventKind::Mounted("/mnt/usb".into()),
+            ]
+        );
+
+        log.entries.clear();
+        log.record(&sys(&[(1, "init"), (3, "cron")], &["/"], 70.0));
+        assert_eq!(log.entries.len(), 4);
+        assert_eq!(log.entries[2].kind, EventKind::Unmounted("/mnt/usb".into()));
+        assert_eq!(log.entries[3].kind, EventKind::TempSpike(55.0, 70.0));
+    }
+
+    #[test]
+    fn test_ring() {
+        let mut log = EventLog::default();
+        for i in 0..EVENT_LOG_LEN + 5 {
+            log.push(EventKind::Mounted(i.to_string()), chrono::Local::now());
+        }
+        assert_eq!(log.entries.len(), EVENT_LOG_LEN);
+        assert_eq!(log.entries[0].kind, EventKind::Mounted("5".into()));
+    }
+}
@@ -0,0 +1,149 @@
+//! The exit log: processes that disappeared between two snapshots, with
+//! what they last used and how long they were seen, so a crash-looping
+//! service that never stays in the table long enough to read still leaves
+//! a trace. Processes that start and exit between two process refreshes
+//! are never seen at all.
+
+use crate::sys::ProcessInfo;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How many exits are kept.
+pub const EXIT_LOG_LEN: usize = 500;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProcessExit {
+    pub at: chrono::DateTime<chrono::Local>,
+    pub pid: u32,
+    pub name: Arc<str>,
+    pub cpu: f32,
+    pub mem_bytes: u64,
+    /// From the first snapshot it was in to the last.
+    pub lifetime: Duration,
+    /// It was already running when sysdash started, so it lived longer.
+    pub at_least: bool,
+}
+
+struct Seen {
+    name: Arc<str>,
+    cpu: f32,
+    mem_bytes: u64,
+    first: Instant,
+    last: Instant,
+    at_least: bool,
+}
+
+#[derive(Default)]
+pub struct ExitLog {
+    seen: HashMap<u32, Seen>,
+    /// Newest last.
+    pub entries: VecDeque<ProcessExit>,
+    primed: bool,
+}
+
+impl ExitLog {
+    /// Compares a snapshot's processes with the previous ones. A PID reused
+    /// under another name counts as an exit too.
+    pub fn record(&mut self, procs: &[ProcessInfo], now: Instant) {
+        let mut seen = HashMap::with_capacity(procs.len());
+        for p in procs {
+            let entry = match self.seen.remove(&p.pid) {
+                Some(before) if before.name == p.name => Seen {
+                    cpu: p.cpu,
+                    mem_bytes: p.mem_bytes,
+                    last: now,
+                    ..before
+                },
+                other => {
+                    if let Some(before) = other {
+                        self.push(p.pid, before);
+                    }
+                    Seen {
+                        name: p.name.clone(),
+                        cpu: p.cpu,
+                        mem_bytes: p.mem_bytes,
+                        first: now,
+                        last: now,
+                        at_least: !self.primed,
+                    }
+                }
+            };
+            seen.insert(p.pid, entry);
+        }
+        for (pid, before) in std::mem::replace(&mut self.seen, seen) {
+            self.push(pid, before);
+        }
+        self.primed = true;
+    }
+
+    fn push(&mut self, pid: u32, seen: Seen) {
+        if self.entries.len() == EXIT_LOG_LEN {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(ProcessExit {
+            at: chrono::Local::now(),
+            pid,
+            name: seen.name,
+            cpu: seen.cpu,
+            mem_bytes: seen.mem_bytes,
+            lifetime: seen.last.duration_since(seen.first),
+            at_least: seen.at_least,
+        });
+    }
+
+    /// How often each name exited, most frequent first, for spotting a
+    /// process that keeps restarting.
+    pub fn counts(&self) -> Vec<(Arc<str>, usize)> {
+        let mut counts: HashMap<&Arc<str>, usize> = HashMap::new();
+        for exit in &self.entries {
+            *counts.entry(&exit.name).or_default() += 1;
+        }
+        let mut counts: Vec<(Arc<str>, usize)> =
+            counts.into_iter().map(|(n, c)| (n.clone(), c)).collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proc(pid: u32, name: &str, cpu: f32) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            name: name.into(),
+            user: "me".into(),
+            cmd: name.into(),
+            cpu,
+            mem_bytes: 1024,
+            parent: None,
+        }
+    }
+
+    #[test]
+    fn test_exits() {
+        let mut log = ExitLog::default();
+        let t0 = Instant::now();
+        let secs = |s| t0 + Duration::from_secs(s);
+        log.record(&[proc(1, "init", 0.0), proc(2, "old", 1.0)], t0);
+        log.record(&[proc(1, "init", 0.0), proc(3, "worker", 5.0)], secs(1));
+        log.record(&[proc(1, "init", 0.0), proc(3, "worker", 90.0)], secs(3));
+        // 3 crashed and its PID went to something else.
+        log.record(&[proc(1, "init", 0.0), proc(3, "cron", 0.0)], secs(4));
+
+        let exits: Vec<_> = log
+            .entries
+            .iter()
+            .map(|e| (e.pid, e.name.as_ref()))
+            .collect();
+        assert_eq!(exits, [(2, "old"), (3, "worker")]);
+        assert!(log.entries[0].at_least);
+        let worker = &log.entries[1];
+        assert!(!worker.at_least);
+        assert_eq!(worker.lifetime, Duration::from_secs(2));
+        assert_eq!(worker.cpu, 90.0);
+        assert_eq!(log.counts()[0].1, 1);
+    }
+}
@@ -0,0 +1,140 @@
+//! The single-process dashboard of `--pid` and `--name`: one process'
+//! CPU and memory history, threads, I/O, open files and children, for
+//! profiling one service rather than the whole machine.
+
+use crate::history::History;
+use crate::sys::{ProcessInfo, SysCache};
+use std::time::{Duration, Instant};
+
+/// What to follow.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Target {
+    Pid(u32),
+    /// The busiest process whose name contains this, case-insensitively;
+    /// picked again if it exits.
+    Name(String),
+}
+
+pub struct Focus {
+    pub target: Target,
+    /// The process followed, once found.
+    pub pid: Option<u32>,
+    /// Percent of one core, like the process table.
+    pub cpu_history: History,
+    pub mem_history: History,
+    /// Bytes per second between the last two snapshots.
+    pub read_rate: u64,
+    pub write_rate: u64,
+    last_io: Option<(u32, u64, u64, Instant)>,
+}
+
+impl Focus {
+    pub fn new(target: Target, window: Duration, tick_rate: Duration) -> Self {
+        let pid = match target {
+            Target::Pid(pid) => Some(pid),
+            Target::Name(_) => None,
+        };
+        Self {
+            target,
+            pid,
+            cpu_history: History::for_window(window, tick_rate),
+            mem_history: History::for_window(window, tick_rate),
+            read_rate: 0,
+            write_rate: 0,
+            last_io: None,
+        }
+    }
+
+    /// The followed process in `sys`, if it is running.
+    pub fn process<'a>(&self, sys: &'a SysCache) -> Option<&'a ProcessInfo> {
+        let pid = self.pid?;
+        sys.processes().iter().find(|p| p.pid == pid)
+    }
+
+    /// Extends the histories with a snapshot. Returns true when the process
+    /// followed changed, so the collector has to be told.
+    pub fn update(&mut self, sys: &SysCache, now: Instant) -> bool {
+        let before = self.pid;
+        if let Target::Name(pattern) = &self.target {
+            if self.process(sys).is_none() {
+                let pattern = pattern.to_lowercase();
+                // Processes come busiest first.
+                self.pid = sys
+                    .processes()
+                    .iter()
+                    .find(|p| p.name.to_lowercase().contains(&pattern))
+                    .map(|p| p.pid);
+            }
+        }
+
+        let (cpu, mem) = self
+            .process(sys)
+            .map_or((0, 0), |p| (p.cpu as u64, p.mem_bytes));
+        self.cpu_history.push(cpu);
+        self.mem_history.push(mem);
+
+        match sys.focus.as_ref().filter(|f| Some(f.pid) == self.pid) {
+            Some(info) => {
+                if let Some((pid, read, written, at)) = self.last_io {
+                    let secs = now.duration_since(at).as_secs_f64().max(0.001);
+                    if pid == info.pid {
+                        self.read_rate =
+                            (info.read_bytes.saturating_sub(read) as f64 / secs) as u64;
+                        self.write_rate =
+                            (info.written_bytes.saturating_sub(written) as f64 / secs) as u64;
+                    }
+                }
+                self.last_io = Some((info.pid, info.read_bytes, info.written_bytes, now));
+            }
+            None => {
+                self.read_rate = 0;
+                self.write_rate = 0;
+            }
+        }
+        self.pid != before
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sys::FocusInfo;
+
+    fn proc(pid: u32, name: &str, cpu: f32) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            name: name.into(),
+            user: "me".into(),
+            cmd: name.into(),
+            cpu,
+            mem_bytes: 1024,
+            parent: None,
+        }
+    }
+
+    #[test]
+    fn test_follow_by_name() {
+        let tick = Duration::from_secs(1);
+        let mut focus = Focus::new(Target::Name("PG".into()), Duration::from_secs(10), tick);
+        let t0 = Instant::now();
+
+        let sys = SysCache::default()
+            .with_processes(vec![proc(7, "postgres", 30.0), proc(8, "bash", 0.0)]);
+        assert!(!focus.update(&sys, t0));
+        assert_eq!(focus.pid, None);
+
+        let mut sys = SysCache::default().with_processes(vec![proc(9, "pgbouncer", 12.0)]);
+        sys.focus = Some(FocusInfo {
+            pid: 9,
+            read_bytes: 1000,
+            ..FocusInfo::default()
+        });
+        assert!(focus.update(&sys, t0));
+        assert_eq!(focus.pid, Some(9));
+        assert_eq!(focus.cpu_history.latest(), 12);
+
+        sys.focus.as_mut().unwrap().read_bytes = 5000;
+        assert!(!focus.update(&sys, t0 + tick));
+        assert_eq!(focus.read_rate, 4000);
+    }
+}
@@ -0,0 +1,88 @@
+//! Fixed-size sample history for the charts and sparklines.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Ring buffer of the most recent samples, oldest first. Starts zero-filled
+/// so charts keep a stable width from the first frame.
+#[derive(Clone, Debug)]
+pub struct History {
+    samples: VecDeque<u64>,
+    capacity: usize,
+}
+
+impl History {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            samples: VecDeque::from(vec![0; capacity]),
+            capacity,
+        }
+    }
+
+    /// Enough samples to cover `window` when one is pushed every `interval`.
+    pub fn for_window(window: Duration, interval: Duration) -> Self {
+        let interval = interval.max(Duration::from_millis(1));
+        Self::new((window.as_secs_f64() / interval.as_secs_f64()).ceil() as usize)
+    }
+
+    pub fn push(&mut self, value: u64) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(value);
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = u64> + '_ {
+        self.samples.iter().copied()
+    }
+
+    pub fn latest(&self) -> u64 {
+        self.samples.back().copied().unwrap_or(0)
+    }
+
+    /// `(index, value)` pairs for a chart dataset.
+    pub fn points(&self) -> Vec<(f64, f64)> {
+        self.iter()
+            .enumerate()
+            .map(|(i, v)| (i as f64, v as f64))
+            .collect()
+    }
+
+    /// The newest `n` samples, oldest first, for a sparkline `n` cells wide.
+    pub fn last(&self, n: usize) -> Vec<u64> {
+        self.iter()
+            .skip(self.samples.len().saturating_sub(n))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_drops_oldest() {
+        let mut history = History::new(3);
+        assert_eq!(history.iter().collect::<Vec<_>>(), vec![0, 0, 0]);
+        for v in 1..=4 {
+            history.push(v);
+        }
+        assert_eq!(history.iter().collect::<Vec<_>>(), vec![2, 3, 4]);
+        assert_eq!(history.latest(), 4);
+        assert_eq!(history.last(2), vec![3, 4]);
+        assert_eq!(history.last(10), vec![2, 3, 4]);
+        assert_eq!(history.points()[2], (2.0, 4.0));
+    }
+
+    #[test]
+    fn test_for_window() {
+        let history = History::for_window(Duration::from_secs(120), Duration::from_millis(500));
+        assert_eq!(history.capacity(), 240);
+        assert_eq!(History::for_window(Duration::ZERO, Duration::from_secs(1)).capacity(), 1);
+    }
+}
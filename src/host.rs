@@ -0,0 +1,160 @@
+//! Per-machine state. The TUI can watch several hosts (local or agents) and
+//! switch between them; each keeps its own snapshot and histories so
+//! switching doesn't lose the charts.
+
+use crate::app::{DISK_TREND_WINDOW, HISTORY_WINDOW, MEM_TREND_WINDOW};
+use crate::delta::Baseline;
+use crate::eventlog::EventLog;
+use crate::exits::ExitLog;
+use crate::history::History;
+use crate::stats::SessionStats;
+use crate::sys::SysCache;
+use crate::tree::ProcessTree;
+use crate::trend::TrendTracker;
+use crate::worker::Worker;
+use std::time::{Duration, Instant};
+
+/// The key memory samples are recorded under in [`Host::mem_trend`].
+pub const MEM_KEY: &str = "mem";
+
+pub struct Host {
+    /// The address it was reached at; empty for the local machine.
+    pub name: String,
+    pub sys: SysCache,
+    pub tree: ProcessTree,
+    pub cpu_history: History,
+    pub net_rx_history: History,
+    pub net_tx_history: History,
+    /// Memory used, in percent, for the projection on the history chart.
+    pub mem_history: History,
+    /// Pages swapped in plus out per second.
+    pub swap_history: History,
+    /// CPU package power, in watts.
+    pub cpu_power_history: History,
+    pub disk_trends: TrendTracker,
+    /// Bytes of memory used, under [`MEM_KEY`].
+    pub mem_trend: TrendTracker,
+    /// Min/avg/max/p95 since sysdash started, for the stats popup.
+    pub stats: SessionStats,
+    /// Marked with `b`; the delta popup compares against it.
+    pub baseline: Option<Baseline>,
+    /// Processes that disappeared, for the exit log popup.
+    pub exits: ExitLog,
+    /// What changed between snapshots, for the event log panel.
+    pub events: EventLog,
+    /// Collector task that feeds this host; kills and details go through it.
+    pub worker: Option<Worker>,
+}
+
+impl Host {
+    pub fn new(name: String, tick_rate: Duration) -> Self {
+        Self {
+            name,
+            sys: SysCache::default(),
+            tree: ProcessTree::new(),
+            cpu_history: History::for_window(HISTORY_WINDOW, tick_rate),
+            net_rx_history: History::for_window(HISTORY_WINDOW, tick_rate),
+            net_tx_history: History::for_window(HISTORY_WINDOW, tick_rate),
+            mem_history: History::for_window(HISTORY_WINDOW, tick_rate),
+            swap_history: History::for_window(HISTORY_WINDOW, tick_rate),
+            cpu_power_history: History::for_window(HISTORY_WINDOW, tick_rate),
+            disk_trends: TrendTracker::new(DISK_TREND_WINDOW),
+            mem_trend: TrendTracker::new(MEM_TREND_WINDOW),
+            stats: SessionStats::default(),
+            baseline: None,
+            exits: ExitLog::default(),
+            events: EventLog::default(),
+            worker: None,
+        }
+    }
+
+    pub fn is_local(&self) -> bool {
+        self.name.is_empty()
+    }
+
+    /// The reported host name once known, otherwise the address.
+    pub fn label(&self) -> &str {
+        if self.sys.host.is_empty() {
+            &self.name
+        } else {
+            &self.sys.host
+        }
+    }
+
+    fn mem_percent(sys: &SysCache) -> Option<f64> {
+        (sys.total_mem > 0).then(|| sys.used_mem as f64 / sys.total_mem as f64 * 100.0)
+    }
+
+    /// When memory runs out if its growth over [`MEM_TREND_WINDOW`] goes on.
+    pub fn mem_eta(&self) -> Option<Duration> {
+        let free = self.sys.total_mem.saturating_sub(self.sys.used_mem);
+        self.mem_trend.eta(MEM_KEY, free as f64)
+    }
+
+    /// Growth of memory use in percent per second, where it is sustained.
+    pub fn mem_slope_percent(&self) -> Option<f64> {
+        let slope = self.mem_trend.slope_per_sec(MEM_KEY)?;
+        (self.sys.total_mem > 0).then(|| slope / self.sys.total_mem as f64 * 100.0)
+    }
+
+    /// Stores a snapshot and extends the histories and trends with it.
+    pub fn apply(&mut self, sys: SysCache, now: Instant) {
+        self.record_history(&sys, now);
+        self.sys = sys;
+        self.tree.update(self.sys.processes());
+
+        // An offline snapshot repeats the last known values.
+        if self.sys.offline.is_none() {
+            self.stats.record(&self.sys);
+            self.exits.record(self.sys.processes(), now);
+            self.events.record(&self.sys);
+            if let Some(baseline) = &mut self.baseline {
+                baseline.record(&self.sys, now);
+            }
+        }
+
+        for disk in self.sys.disks() {
+            let used = disk.total.saturating_sub(disk.available);
+            self.disk_trends.record(&disk.mount_point, now, used as f64);
+        }
+    }
+
+    /// Extends the charts and the memory trend with `sys` without storing
+    /// it, e.g. with samples kept from an earlier run.
+    pub fn record_history(&mut self, sys: &SysCache, now: Instant) {
+        self.cpu_history.push(sys.cpu_global as u64);
+        self.net_rx_history.push(sys.rx_rate);
+        self.net_tx_history.push(sys.tx_rate);
+        if let Some((swap_in, swap_out)) = sys.swap_rates {
+            self.swap_history.push(swap_in + swap_out);
+        }
+        if let Some(watts) = sys.cpu_power {
+            self.cpu_power_history.push(watts.round() as u64);
+        }
+        if let Some(percent) = Self::mem_percent(sys) {
+            self.mem_history.push(percent as u64);
+            self.mem_trend.record(MEM_KEY, now, sys.used_mem as f64);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mem_projection() {
+        let mut host = Host::new(String::new(), Duration::from_secs(1));
+        let t0 = Instant::now();
+        for i in 0..=60 {
+            let mut sys = SysCache::default();
+            sys.total_mem = 10_000;
+            sys.used_mem = 4_000 + i * 10;
+            host.apply(sys, t0 + Duration::from_secs(i));
+        }
+        // 10 bytes/s with 5_400 left.
+        assert_eq!(host.mem_eta(), Some(Duration::from_secs(540)));
+        assert!((host.mem_slope_percent().unwrap() - 0.1).abs() < 1e-9);
+        assert_eq!(host.mem_history.latest(), 46);
+    }
+}
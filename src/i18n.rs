@@ -0,0 +1,361 @@
+//! Translations of the dashboard's text. The English strings in `ui.rs` are
+//! the message keys: [`tr`] returns the translation for the chosen locale
+//! and [`trf`] fills the `{}` placeholders of a translated template, so a
+//! missing translation falls back to English.
+//!
+//! The locale comes from `locale` in `ui.toml`, else from `LC_ALL`,
+//! `LC_MESSAGES` or `LANG`. Adding a language is a [`Locale`] variant and a
+//! catalog like [`PT`].
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::OnceLock;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Locale {
+    #[default]
+    En,
+    Pt,
+}
+
+impl Locale {
+    /// A language tag such as `pt`, `pt-BR` or `pt_BR.UTF-8`; only the
+    /// language counts.
+    pub fn parse(tag: &str) -> Option<Self> {
+        let lang = tag.split(['_', '-', '.', '@']).next()?.to_lowercase();
+        match lang.as_str() {
+            "en" | "c" | "posix" => Some(Locale::En),
+            "pt" => Some(Locale::Pt),
+            _ => None,
+        }
+    }
+
+    fn catalog(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Locale::En => &[],
+            Locale::Pt => PT,
+        }
+    }
+}
+
+/// The configured locale, else the environment's, else English. An unknown
+/// locale in `ui.toml` is an error; one in the environment is ignored.
+pub fn locale(configured: Option<&str>) -> Result<Locale, String> {
+    if let Some(tag) = configured {
+        return Locale::parse(tag).ok_or_else(|| format!("ui.toml: unknown locale {:?}", tag));
+    }
+    let env = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty());
+    Ok(env.and_then(|tag| Locale::parse(&tag)).unwrap_or_default())
+}
+
+static CATALOG: OnceLock<HashMap<&'static str, &'static str>> = OnceLock::new();
+
+/// Selects the language for the rest of the run; only the first call has
+/// an effect.
+pub fn set_locale(locale: Locale) {
+    let _ = CATALOG.set(locale.catalog().iter().copied().collect());
+}
+
+/// `text` in the selected language.
+pub fn tr(text: &'static str) -> &'static str {
+    CATALOG
+        .get()
+        .and_then(|catalog| catalog.get(text).copied())
+        .unwrap_or(text)
+}
+
+/// The translation of `template` with each `{}` replaced by the next of
+/// `args`.
+pub fn trf(template: &'static str, args: &[&dyn Display]) -> String {
+    fill(tr(template), args)
+}
+
+fn fill(template: &str, args: &[&dyn Display]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut args = args.iter();
+    let mut parts = template.split("{}");
+    if let Some(first) = parts.next() {
+        out.push_str(first);
+    }
+    for part in parts {
+        if let Some(arg) = args.next() {
+            out.push_str(&arg.to_string());
+        }
+        out.push_str(part);
+    }
+    out
+}
+
+/// Brazilian Portuguese.
+const PT: &[(&str, &str)] = &[
+    // Single-process dashboard
+    (" PID {} is not running ", " PID {} não está em execução "),
+    (
+        " Waiting for a process named \"{}\" ",
+        " Aguardando um processo chamado \"{}\" ",
+    ),
+    (" Memory ", " Memória "),
+    ("Memory", "Memória"),
+    ("Open files", "Arq. abertos"),
+    ("Read", "Lidos"),
+    ("Written", "Escritos"),
+    ("{}/s ({} total)", "{}/s ({} no total)"),
+    (" Details ", " Detalhes "),
+    (" Children ({}) ", " Filhos ({}) "),
+    ("Name", "Nome"),
+    // Overview
+    ("Overview", "Visão geral"),
+    ("Disk", "Disco"),
+    ("Net ↓ {}/s ↑ {}/s", "Rede ↓ {}/s ↑ {}/s"),
+    // Alerts
+    (" ACTIVE   {}{}", " ATIVO    {}{}"),
+    (" [ack]", " [ciente]"),
+    (" SNOOZED  {} ({}m left)", " ADIADO   {} (faltam {}m)"),
+    ("No alerts have fired yet.", "Nenhum alerta disparou ainda."),
+    ("RESOLVED", "RESOLVIDO"),
+    ("FIRED   ", "DISPARADO"),
+    (" {} (value {}, threshold {})", " {} (valor {}, limite {})"),
+    (
+        " Alerts (x acknowledge, s snooze, a/Esc close) ",
+        " Alertas (x reconhece, s adia, a/Esc fecha) ",
+    ),
+    // Process details
+    ("Cwd", "Diretório"),
+    ("Command", "Comando"),
+    ("Loading…", "Carregando…"),
+    ("Environment ({})", "Ambiente ({})"),
+    (" Process (Esc to close) ", " Processo (Esc para fechar) "),
+    // Perf
+    ("Interval", "Intervalo"),
+    ("Last refresh", "Última atualização"),
+    ("CPU/memory", "CPU/memória"),
+    ("Network", "Rede"),
+    ("Processes", "Processos"),
+    ("Disks", "Discos"),
+    ("Sensors", "Sensores"),
+    ("Frame", "Quadro"),
+    (
+        " Perf (P/Esc to close) ",
+        " Desempenho (P/Esc para fechar) ",
+    ),
+    // Connect
+    ("Searching for agents…", "Procurando agentes…"),
+    (
+        "Discovery needs a build with the \"mdns\" feature",
+        "A descoberta requer um build com o recurso \"mdns\"",
+    ),
+    (" connected", " conectado"),
+    (
+        " Connect (Enter connect, C/Esc close) ",
+        " Conectar (Enter conecta, C/Esc fecha) ",
+    ),
+    // Containers
+    (
+        "Asking the Docker daemon…",
+        "Consultando o daemon do Docker…",
+    ),
+    (
+        "Containers need a build with the \"docker\" feature",
+        "Contêineres requerem um build com o recurso \"docker\"",
+    ),
+    (" Containers ", " Contêineres "),
+    (
+        " Containers on {} (D/Esc to close) ",
+        " Contêineres em {} (D/Esc para fechar) ",
+    ),
+    ("No running containers", "Nenhum contêiner em execução"),
+    ("Image", "Imagem"),
+    ("Net rx/tx", "Rede rx/tx"),
+    ("Disk r/w", "Disco l/e"),
+    ("Status", "Estado"),
+    ("s stop, k kill", "s para, k mata"),
+    (" cgroups (G/Esc to close) ", " cgroups (G/Esc para fechar) "),
+    (" Pods (K/Esc to close) ", " Pods (K/Esc para fechar) "),
+    ("No Kubernetes pods on this host", "Nenhum pod Kubernetes neste host"),
+    ("Pod / container", "Pod / contêiner"),
+    ("QoS / processes", "QoS / processos"),
+    (
+        "Reading cgroups… (Linux with cgroup v2, local hosts only)",
+        "Lendo cgroups… (Linux com cgroup v2, só hosts locais)",
+    ),
+    ("Stop {}? y to confirm", "Parar {}? y para confirmar"),
+    ("Kill {}? y to confirm", "Matar {}? y para confirmar"),
+    // Key bindings
+    ("Quit", "Sair"),
+    ("Move down", "Descer"),
+    ("Move up", "Subir"),
+    ("Page down", "Página abaixo"),
+    ("Page up", "Página acima"),
+    ("First process", "Primeiro processo"),
+    ("Last process", "Último processo"),
+    ("Show all processes", "Mostrar todos os processos"),
+    ("Focus the next panel", "Focar o próximo painel"),
+    ("Kill the process", "Encerrar o processo"),
+    ("Lower its priority", "Baixar a prioridade"),
+    ("Raise its priority", "Subir a prioridade"),
+    ("Watch the process", "Vigiar o processo"),
+    ("Cap its CPU", "Limitar a CPU"),
+    ("Search processes", "Buscar processos"),
+    ("Next sort column", "Próxima coluna de ordenação"),
+    ("Reverse the sort", "Inverter a ordenação"),
+    ("Process tree", "Árvore de processos"),
+    ("Count children in", "Somar os filhos"),
+    ("Memory projection", "Projeção de memória"),
+    ("Alert history", "Histórico de alertas"),
+    ("Monitor overhead", "Custo do monitor"),
+    ("Connect to an agent", "Conectar a um agente"),
+    ("Containers", "Contêineres"),
+    ("cgroup tree", "Árvore de cgroups"),
+    ("Kubernetes pods", "Pods Kubernetes"),
+    ("Session statistics", "Estatísticas da sessão"),
+    ("Mark a baseline", "Marcar uma referência"),
+    ("Compare to the baseline", "Comparar com a referência"),
+    ("Exited processes", "Processos encerrados"),
+    ("Event log", "Registro de eventos"),
+    ("Save the screen", "Salvar a tela"),
+    ("Save the data as JSON", "Salvar os dados em JSON"),
+    ("All hosts", "Todos os hosts"),
+    ("Next host", "Próximo host"),
+    ("Previous host", "Host anterior"),
+    ("Process details", "Detalhes do processo"),
+    ("Update faster", "Atualizar mais rápido"),
+    ("Update slower", "Atualizar mais devagar"),
+    ("Next theme", "Próximo tema"),
+    ("Close the popup", "Fechar a janela"),
+    ("Pause updates", "Pausar atualizações"),
+    ("Key bindings", "Teclas"),
+    (" Key bindings ({} to close) ", " Teclas ({} para fechar) "),
+    // Session statistics
+    ("Temperature", "Temperatura"),
+    ("Net rx", "Rede rx"),
+    ("Net tx", "Rede tx"),
+    ("Swap in", "Swap entrada"),
+    ("Swap out", "Swap saída"),
+    ("Avg", "Méd"),
+    (
+        " Since {} ({}, {} samples), S/Esc to close ",
+        " Desde {} ({}, {} amostras), S/Esc para fechar ",
+    ),
+    // Delta
+    (
+        "Press b to mark a baseline, then B to compare against it",
+        "Pressione b para marcar uma referência e depois B para comparar",
+    ),
+    ("{} (new)", "{} (novo)"),
+    ("CPU time", "Tempo de CPU"),
+    ("CPU time ▼", "Tempo de CPU ▼"),
+    (
+        " Since {} ({}), net rx {} tx {} | m sort, b re-mark, B/Esc close ",
+        " Desde {} ({}), rede rx {} tx {} | m ordena, b remarca, B/Esc fecha ",
+    ),
+    // Exit log
+    (
+        " Exited processes ({}) | E/Esc close ",
+        " Processos encerrados ({}) | E/Esc fecha ",
+    ),
+    (
+        "No process exited more than once",
+        "Nenhum processo encerrou mais de uma vez",
+    ),
+    ("Most exits: {}", "Mais encerramentos: {}"),
+    ("Time", "Hora"),
+    ("Lifetime", "Duração"),
+    // Event log
+    (" Events (e to hide) ", " Eventos (e para ocultar) "),
+    ("Nothing has happened yet.", "Nada aconteceu ainda."),
+    ("Alert fired: {}", "Alerta disparado: {}"),
+    ("Alert resolved: {}", "Alerta resolvido: {}"),
+    ("Process started: {} ({})", "Processo iniciado: {} ({})"),
+    ("Process exited: {} ({})", "Processo encerrado: {} ({})"),
+    ("Mounted: {}", "Montado: {}"),
+    ("Unmounted: {}", "Desmontado: {}"),
+    (
+        "CPU temperature jumped from {}°C to {}°C",
+        "Temperatura da CPU subiu de {}°C para {}°C",
+    ),
+    // Top bar
+    ("charging", "carregando"),
+    ("discharging", "descarregando"),
+    ("Battery", "Bateria"),
+    ("{} to full", "{} até carregar"),
+    ("{} left", "{} restantes"),
+    (" Sensors{} ", " Sensores{} "),
+    ("Fans", "Ventoinhas"),
+    ("health {}%", "saúde {}%"),
+    ("{} cycles", "{} ciclos"),
+    ("BAT: N/A", "BAT: N/D"),
+    (" Uptime: {} ", " Ligado há: {} "),
+    (" OFFLINE: {} ", " DESCONECTADO: {} "),
+    (" PAUSED ", " PAUSADO "),
+    (" REPLAY {}/{} {} ", " REPRODUÇÃO {}/{} {} "),
+    (" idle, every {}s ", " ocioso, a cada {}s "),
+    (" ALERT: {} ", " ALERTA: {} "),
+    (" ALERT: {}{}{} ", " ALERTA: {}{}{} "),
+    (" plugin error: {} ", " erro de plugin: {} "),
+    // Panels
+    (" CPU History ", " Histórico de CPU "),
+    (
+        " CPU History (own processes) ",
+        " Histórico de CPU (processos próprios) ",
+    ),
+    (
+        " CPU / Memory History (projected) ",
+        " Histórico de CPU / Memória (projetado) ",
+    ),
+    (
+        " CPU (own processes) / Memory History (projected) ",
+        " Histórico de CPU (processos próprios) / Memória (projetado) ",
+    ),
+    (" Cores{} ", " Núcleos{} "),
+    (", exhausted in {}", ", esgota em {}"),
+    (
+        "Swap in {} out {} pages/s",
+        "Swap entrada {} saída {} páginas/s",
+    ),
+    (" Disks{} ", " Discos{} "),
+    ("{} full in {}", "{} cheio em {}"),
+    (" Network ", " Rede "),
+    (" Processes ", " Processos "),
+    (" Processes (tree) ", " Processos (árvore) "),
+    (" Own processes ", " Processos próprios "),
+    (" Own processes (tree) ", " Processos próprios (árvore) "),
+    ("(with children) ", "(com filhos) "),
+    ("(filter: {}) ", "(filtro: {}) "),
+    (
+        "(top {} of {}, A shows all) ",
+        "(primeiros {} de {}, A mostra todos) ",
+    ),
+    ("User", "Usuário"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_locale() {
+        assert_eq!(Locale::parse("pt_BR.UTF-8"), Some(Locale::Pt));
+        assert_eq!(Locale::parse("pt-PT"), Some(Locale::Pt));
+        assert_eq!(Locale::parse("C.UTF-8"), Some(Locale::En));
+        assert_eq!(Locale::parse("de_DE"), None);
+        assert_eq!(fill("{} of {} done", &[&3, &"4"]), "3 of 4 done");
+    }
+
+    #[test]
+    fn test_catalog_placeholders() {
+        let mut seen = std::collections::HashSet::new();
+        for (en, pt) in PT {
+            assert!(seen.insert(en), "{:?} is translated twice", en);
+            assert_eq!(
+                en.matches("{}").count(),
+                pt.matches("{}").count(),
+                "{:?} and {:?} differ in placeholders",
+                en,
+                pt
+            );
+        }
+    }
+}
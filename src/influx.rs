@@ -0,0 +1,125 @@
+//! Pushes every metric of the registry to an InfluxDB or VictoriaMetrics
+//! write endpoint in line protocol, on every update of the local machine
+//! (cargo feature "influx"). The endpoint is the `[influx]` table of
+//! `config.toml`.
+//!
+//! Each series is one line: the metric name as the measurement, the host
+//! and the metric's label as tags and the value as the `value` field, e.g.
+//! `filesystem.utilization,host=nas,mountpoint=/home value=61.2 1700000000000000000`.
+
+use crate::config::Influx;
+use crate::sys::metrics::registry;
+use crate::sys::SysCache;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc::{self, UnboundedSender};
+use tokio::task;
+
+pub struct Exporter {
+    tx: UnboundedSender<String>,
+    host: String,
+}
+
+impl Exporter {
+    /// Starts the sender task; pushes happen there so a slow or unreachable
+    /// database never blocks the caller. Lines that queue up meanwhile go
+    /// out together in the next push. Must be called from within a tokio
+    /// runtime.
+    pub fn new(config: Influx) -> Self {
+        let (tx, mut rx) = mpsc::unbounded_channel::<String>();
+        tokio::spawn(async move {
+            while let Some(mut body) = rx.recv().await {
+                while let Ok(more) = rx.try_recv() {
+                    body.push_str(&more);
+                }
+                let config = config.clone();
+                let _ = task::spawn_blocking(move || {
+                    let request = ureq::post(&config.url).content_type("text/plain; charset=utf-8");
+                    match &config.token {
+                        Some(token) => request
+                            .header("Authorization", &format!("Token {}", token))
+                            .send(body.as_str()),
+                        None => request.send(body.as_str()),
+                    }
+                })
+                .await;
+            }
+        });
+        Self {
+            tx,
+            host: sysinfo::System::host_name().unwrap_or_default(),
+        }
+    }
+
+    /// Queues the lines of `sys`, timestamped now.
+    pub fn record(&self, sys: &SysCache) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let _ = self.tx.send(lines(sys, &self.host, now));
+    }
+}
+
+/// Every series the host reports, one line each, newline-terminated.
+pub fn lines(sys: &SysCache, host: &str, now_nanos: u128) -> String {
+    let mut out = String::new();
+    for def in registry().iter() {
+        for sample in (def.read)(sys) {
+            out.push_str(&escape(def.name, false));
+            out.push_str(",host=");
+            out.push_str(&escape(host, true));
+            if let (Some(key), Some(value)) = (def.label, &sample.label) {
+                out.push(',');
+                out.push_str(&escape(key, true));
+                out.push('=');
+                out.push_str(&escape(value, true));
+            }
+            out.push_str(&format!(" value={} {}\n", sample.value, now_nanos));
+        }
+    }
+    out
+}
+
+/// Escapes commas and spaces, and in tags also equals signs, with a
+/// backslash.
+fn escape(s: &str, tag: bool) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        if c == ',' || c == ' ' || (tag && c == '=') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sys::DiskInfo;
+
+    #[test]
+    fn test_lines() {
+        let mut sys = SysCache::default().with_disks(vec![DiskInfo {
+            _name: "sdb1".into(),
+            mount_point: "/mnt/my disk".into(),
+            total: 100,
+            available: 25,
+            temperature: None,
+        }]);
+        sys.cpu_global = 12.5;
+        let text = lines(&sys, "nas", 42);
+
+        assert!(text.ends_with('\n'));
+        assert!(text.contains("cpu.utilization,host=nas value=12.5 42\n"));
+        assert!(text.contains("filesystem.utilization,host=nas,mountpoint=/mnt/my\\ disk value=75 42\n"));
+        // No swap counters: no line rather than a zero.
+        assert!(!text.contains("swap.io.rate"));
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("a b,c=d", true), "a\\ b\\,c\\=d");
+        assert_eq!(escape("a=b", false), "a=b");
+    }
+}
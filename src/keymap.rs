@@ -0,0 +1,485 @@
+//! Key bindings of the dashboard. [`App::on_key`](crate::app::App::on_key)
+//! looks the key up here and runs the [`Action`] it is bound to; `?` lists
+//! the bindings in effect. The `[keys]` table of `config.toml` rebinds
+//! actions, one key or a list:
+//!
+//! ```toml
+//! [keys]
+//! kill = "x"
+//! search = ["/", "Ctrl+f"]
+//! quit = ["q", "F10"]
+//! ```
+//!
+//! Keys are single characters, case-sensitive, or names: `Up`, `Down`,
+//! `Left`, `Right`, `PageUp`, `PageDown`, `Home`, `End`, `Enter`, `Esc`,
+//! `Tab`, `Backspace`, `Space` and `F1` to `F12`, optionally after `Ctrl+`.
+//! A rebound action loses its default keys, and a key taken by a rebound
+//! action leaves the action it had. Keys inside popups and the search field
+//! are fixed.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Quit,
+    Down,
+    Up,
+    PageDown,
+    PageUp,
+    Home,
+    End,
+    ShowAll,
+    NextPanel,
+    Kill,
+    NiceUp,
+    NiceDown,
+    Watch,
+    Limit,
+    Search,
+    Sort,
+    Reverse,
+    Tree,
+    Aggregate,
+    Projection,
+    Alerts,
+    Perf,
+    Connect,
+    Containers,
+    Cgroups,
+    Pods,
+    Stats,
+    Baseline,
+    Delta,
+    Exits,
+    Events,
+    Snapshot,
+    Export,
+    Overview,
+    NextHost,
+    PrevHost,
+    Details,
+    Faster,
+    Slower,
+    Theme,
+    Close,
+    Pause,
+    Help,
+}
+
+impl Action {
+    /// In the order the help popup lists them.
+    pub const ALL: [Action; 43] = [
+        Action::Quit,
+        Action::Pause,
+        Action::Help,
+        Action::Close,
+        Action::Down,
+        Action::Up,
+        Action::PageDown,
+        Action::PageUp,
+        Action::Home,
+        Action::End,
+        Action::NextPanel,
+        Action::Search,
+        Action::Sort,
+        Action::Reverse,
+        Action::Tree,
+        Action::Aggregate,
+        Action::ShowAll,
+        Action::Details,
+        Action::Kill,
+        Action::NiceUp,
+        Action::NiceDown,
+        Action::Limit,
+        Action::Watch,
+        Action::Projection,
+        Action::Alerts,
+        Action::Stats,
+        Action::Baseline,
+        Action::Delta,
+        Action::Exits,
+        Action::Events,
+        Action::Perf,
+        Action::Containers,
+        Action::Cgroups,
+        Action::Pods,
+        Action::Connect,
+        Action::Overview,
+        Action::NextHost,
+        Action::PrevHost,
+        Action::Faster,
+        Action::Slower,
+        Action::Theme,
+        Action::Snapshot,
+        Action::Export,
+    ];
+
+    /// What the help popup says the action does; the key of its translation.
+    pub fn help(self) -> &'static str {
+        match self {
+            Action::Quit => "Quit",
+            Action::Down => "Move down",
+            Action::Up => "Move up",
+            Action::PageDown => "Page down",
+            Action::PageUp => "Page up",
+            Action::Home => "First process",
+            Action::End => "Last process",
+            Action::ShowAll => "Show all processes",
+            Action::NextPanel => "Focus the next panel",
+            Action::Kill => "Kill the process",
+            Action::NiceUp => "Lower its priority",
+            Action::NiceDown => "Raise its priority",
+            Action::Watch => "Watch the process",
+            Action::Limit => "Cap its CPU",
+            Action::Search => "Search processes",
+            Action::Sort => "Next sort column",
+            Action::Reverse => "Reverse the sort",
+            Action::Tree => "Process tree",
+            Action::Aggregate => "Count children in",
+            Action::Projection => "Memory projection",
+            Action::Alerts => "Alert history",
+            Action::Perf => "Monitor overhead",
+            Action::Connect => "Connect to an agent",
+            Action::Containers => "Containers",
+            Action::Cgroups => "cgroup tree",
+            Action::Pods => "Kubernetes pods",
+            Action::Stats => "Session statistics",
+            Action::Baseline => "Mark a baseline",
+            Action::Delta => "Compare to the baseline",
+            Action::Exits => "Exited processes",
+            Action::Events => "Event log",
+            Action::Snapshot => "Save the screen",
+            Action::Export => "Save the data as JSON",
+            Action::Overview => "All hosts",
+            Action::NextHost => "Next host",
+            Action::PrevHost => "Previous host",
+            Action::Details => "Process details",
+            Action::Faster => "Update faster",
+            Action::Slower => "Update slower",
+            Action::Theme => "Next theme",
+            Action::Close => "Close the popup",
+            Action::Pause => "Pause updates",
+            Action::Help => "Key bindings",
+        }
+    }
+}
+
+/// A key with or without Ctrl. Shift is part of the character, so `P` is
+/// Shift+p.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(try_from = "String")]
+pub struct Key {
+    pub code: KeyCode,
+    pub ctrl: bool,
+}
+
+const NAMES: [(&str, KeyCode); 13] = [
+    ("Up", KeyCode::Up),
+    ("Down", KeyCode::Down),
+    ("Left", KeyCode::Left),
+    ("Right", KeyCode::Right),
+    ("PageUp", KeyCode::PageUp),
+    ("PageDown", KeyCode::PageDown),
+    ("Home", KeyCode::Home),
+    ("End", KeyCode::End),
+    ("Enter", KeyCode::Enter),
+    ("Esc", KeyCode::Esc),
+    ("Tab", KeyCode::Tab),
+    ("Backspace", KeyCode::Backspace),
+    ("Space", KeyCode::Char(' ')),
+];
+
+impl Key {
+    const fn plain(code: KeyCode) -> Self {
+        Self { code, ctrl: false }
+    }
+
+    const fn char(c: char) -> Self {
+        Self::plain(KeyCode::Char(c))
+    }
+
+    /// `q`, `F10`, `Ctrl+c`; names ignore case.
+    pub fn parse(s: &str) -> Option<Self> {
+        let (ctrl, name) = match s.get(..5) {
+            Some(prefix) if s.len() > 5 && prefix.eq_ignore_ascii_case("ctrl+") => (true, &s[5..]),
+            _ => (false, s),
+        };
+        let mut chars = name.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => NAMES
+                .iter()
+                .find(|(n, _)| n.eq_ignore_ascii_case(name))
+                .map(|&(_, code)| code)
+                .or_else(|| {
+                    let n = name.strip_prefix(['F', 'f'])?.parse().ok()?;
+                    (1..=12).contains(&n).then_some(KeyCode::F(n))
+                })?,
+        };
+        Some(Self { code, ctrl })
+    }
+
+    fn matches(&self, event: &KeyEvent) -> bool {
+        self.code == event.code && self.ctrl == event.modifiers.contains(KeyModifiers::CONTROL)
+    }
+}
+
+impl TryFrom<String> for Key {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, String> {
+        Key::parse(&s).ok_or_else(|| format!("unknown key {:?}", s))
+    }
+}
+
+/// The way [`Key::parse`] reads it.
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.ctrl {
+            f.write_str("Ctrl+")?;
+        }
+        match self.code {
+            KeyCode::F(n) => write!(f, "F{}", n),
+            code => match NAMES.iter().find(|&&(_, c)| c == code) {
+                Some((name, _)) => f.write_str(name),
+                None => match code {
+                    KeyCode::Char(c) => write!(f, "{}", c),
+                    _ => write!(f, "{:?}", code),
+                },
+            },
+        }
+    }
+}
+
+/// One key or several, as `[keys]` accepts them.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Keys {
+    One(Key),
+    Many(Vec<Key>),
+}
+
+impl Keys {
+    fn into_vec(self) -> Vec<Key> {
+        match self {
+            Keys::One(key) => vec![key],
+            Keys::Many(keys) => keys,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(try_from = "HashMap<Action, Keys>")]
+pub struct Keymap {
+    bindings: Vec<(Key, Action)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        use KeyCode::*;
+        let bindings = [
+            (Key::char('q'), Action::Quit),
+            (Key::char('Q'), Action::Quit),
+            (Key::plain(F(10)), Action::Quit),
+            (
+                Key {
+                    code: Char('c'),
+                    ctrl: true,
+                },
+                Action::Quit,
+            ),
+            (Key::plain(Down), Action::Down),
+            (Key::char('n'), Action::Down),
+            (Key::plain(Up), Action::Up),
+            (Key::plain(PageDown), Action::PageDown),
+            (Key::plain(PageUp), Action::PageUp),
+            (Key::plain(Home), Action::Home),
+            (Key::plain(End), Action::End),
+            (Key::char('A'), Action::ShowAll),
+            (Key::char('f'), Action::NextPanel),
+            (Key::char('k'), Action::Kill),
+            (Key::plain(F(9)), Action::Kill),
+            (Key::char('>'), Action::NiceUp),
+            (Key::plain(F(8)), Action::NiceUp),
+            (Key::char('<'), Action::NiceDown),
+            (Key::plain(F(7)), Action::NiceDown),
+            (Key::char('w'), Action::Watch),
+            (Key::char('L'), Action::Limit),
+            (Key::char('/'), Action::Search),
+            (Key::plain(F(3)), Action::Search),
+            (Key::plain(Tab), Action::Sort),
+            (Key::plain(F(6)), Action::Sort),
+            (Key::char('I'), Action::Reverse),
+            (Key::char('t'), Action::Tree),
+            (Key::plain(F(5)), Action::Tree),
+            (Key::char('c'), Action::Aggregate),
+            (Key::char('T'), Action::Projection),
+            (Key::char('a'), Action::Alerts),
+            (Key::char('P'), Action::Perf),
+            (Key::char('C'), Action::Connect),
+            (Key::char('D'), Action::Containers),
+            (Key::char('G'), Action::Cgroups),
+            (Key::char('K'), Action::Pods),
+            (Key::char('S'), Action::Stats),
+            (Key::char('b'), Action::Baseline),
+            (Key::char('B'), Action::Delta),
+            (Key::char('E'), Action::Exits),
+            (Key::char('e'), Action::Events),
+            (Key::char('X'), Action::Snapshot),
+            (Key::char('J'), Action::Export),
+            (Key::char('o'), Action::Overview),
+            (Key::char(']'), Action::NextHost),
+            (Key::char('['), Action::PrevHost),
+            (Key::plain(Enter), Action::Details),
+            (Key::char('+'), Action::Faster),
+            (Key::char('-'), Action::Slower),
+            (
+                Key {
+                    code: Char('t'),
+                    ctrl: true,
+                },
+                Action::Theme,
+            ),
+            (Key::plain(Esc), Action::Close),
+            (Key::char('p'), Action::Pause),
+            (Key::char('?'), Action::Help),
+            (Key::plain(F(1)), Action::Help),
+        ];
+        Self {
+            bindings: bindings.to_vec(),
+        }
+    }
+}
+
+impl TryFrom<HashMap<Action, Keys>> for Keymap {
+    type Error = String;
+
+    /// The defaults with the actions of `keys` rebound.
+    fn try_from(keys: HashMap<Action, Keys>) -> Result<Self, String> {
+        let mut keymap = Self::default();
+        let mut rebound: Vec<(Key, Action)> = Vec::new();
+        for (action, keys) in keys {
+            for key in keys.into_vec() {
+                if let Some((_, other)) = rebound.iter().find(|(k, a)| *k == key && *a != action) {
+                    return Err(format!(
+                        "{} is bound to both {:?} and {:?}",
+                        key, other, action
+                    ));
+                }
+                rebound.push((key, action));
+            }
+        }
+        keymap
+            .bindings
+            .retain(|(key, action)| !rebound.iter().any(|(k, a)| k == key || a == action));
+        keymap.bindings.extend(rebound);
+        Ok(keymap)
+    }
+}
+
+impl Keymap {
+    pub fn action(&self, event: &KeyEvent) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(key, _)| key.matches(event))
+            .map(|&(_, action)| action)
+    }
+
+    /// The keys bound to `action`, defaults first.
+    pub fn keys(&self, action: Action) -> impl Iterator<Item = Key> + '_ {
+        self.bindings
+            .iter()
+            .filter(move |&&(_, a)| a == action)
+            .map(|&(key, _)| key)
+    }
+
+    /// The keys of `action` for the help popup, e.g. `k, F9`.
+    pub fn describe(&self, action: Action) -> String {
+        self.keys(action)
+            .map(|key| key.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn test_parse_key() {
+        for name in ["q", "Q", "F10", "Ctrl+c", "PageDown", "Space", "+", "Esc"] {
+            assert_eq!(Key::parse(name).unwrap().to_string(), name);
+        }
+        assert_eq!(Key::parse("pagedown"), Key::parse("PageDown"));
+        assert_eq!(
+            Key::parse("ctrl+F"),
+            Some(Key {
+                code: KeyCode::Char('F'),
+                ctrl: true
+            })
+        );
+        assert_eq!(Key::parse("F13"), None);
+        assert_eq!(Key::parse("Ctrl+"), None);
+        assert_eq!(Key::parse("qq"), None);
+    }
+
+    #[test]
+    fn test_p_pauses() {
+        let keymap = Keymap::default();
+        let p = press(KeyCode::Char('p'), KeyModifiers::empty());
+        let actions: Vec<_> = keymap
+            .bindings
+            .iter()
+            .filter(|(key, _)| key.matches(&p))
+            .map(|&(_, action)| action)
+            .collect();
+        assert_eq!(actions, [Action::Pause]);
+    }
+
+    #[test]
+    fn test_rebind() {
+        let defaults = Keymap::default();
+        assert!(Action::ALL
+            .iter()
+            .all(|&a| defaults.keys(a).next().is_some()));
+
+        let keymap: Keymap =
+            toml::from_str("kill = \"x\"\nsearch = [\"/\", \"Ctrl+f\"]\nquit = \"c\"").unwrap();
+        let none = KeyModifiers::empty();
+        assert_eq!(
+            keymap.action(&press(KeyCode::Char('x'), none)),
+            Some(Action::Kill)
+        );
+        assert_eq!(keymap.action(&press(KeyCode::Char('k'), none)), None);
+        assert_eq!(keymap.describe(Action::Kill), "x");
+        assert_eq!(
+            keymap.action(&press(KeyCode::Char('f'), KeyModifiers::CONTROL)),
+            Some(Action::Search)
+        );
+        // `c` moved from aggregate to quit, and quit lost `q`.
+        assert_eq!(
+            keymap.action(&press(KeyCode::Char('c'), none)),
+            Some(Action::Quit)
+        );
+        assert_eq!(keymap.action(&press(KeyCode::Char('q'), none)), None);
+        assert_eq!(keymap.describe(Action::Aggregate), "");
+        // Shift only shows in the character.
+        assert_eq!(
+            keymap.action(&press(KeyCode::Char('S'), KeyModifiers::SHIFT)),
+            Some(Action::Stats)
+        );
+
+        assert!(toml::from_str::<Keymap>("kill = \"x\"\nquit = \"x\"").is_err());
+        assert!(toml::from_str::<Keymap>("explode = \"x\"").is_err());
+        assert!(toml::from_str::<Keymap>("kill = \"Hyper+x\"").is_err());
+    }
+}
@@ -0,0 +1,76 @@
+//! sysdash: a terminal system dashboard.
+//!
+//! The collection layer ([`sys`]) and the application state ([`app`]) are
+//! usable on their own, so other tools can embed [`Collector`] and [`SysCache`]
+//! without the TUI. The binary is one consumer of this crate; the modules
+//! behind the dashboard ([`ui`], [`event`], [`keymap`]) are public too but
+//! only make sense with a terminal.
+//!
+//! ```no_run
+//! use sysdash::{format_bytes, Collector};
+//!
+//! let mut collector = Collector::new();
+//! let sys = collector.collect();
+//! println!("cpu {:.1}%, mem {}", sys.cpu_global, format_bytes(sys.used_mem));
+//! for disk in sys.disks() {
+//!     println!("{} {} free", disk.mount_point, format_bytes(disk.available));
+//! }
+//! ```
+//!
+//! CPU usage is measured between two collections, so the first snapshot of
+//! a new [`Collector`] says little about it; keep the collector and call
+//! [`collect`](Collector::collect) once per interval, or run it in a
+//! [`worker::Worker`].
+
+pub mod accessible;
+pub mod alerts;
+pub mod api;
+pub mod app;
+pub mod batch;
+pub mod cgroups;
+pub mod check;
+pub mod cli;
+pub mod clock;
+pub mod command;
+pub mod config;
+pub mod containers;
+pub mod csvlog;
+pub mod delta;
+#[cfg(feature = "mdns")]
+pub mod discovery;
+pub mod event;
+pub mod eventlog;
+pub mod exits;
+pub mod focus;
+pub mod history;
+pub mod host;
+pub mod i18n;
+#[cfg(feature = "influx")]
+pub mod influx;
+pub mod keymap;
+#[cfg(feature = "otlp")]
+pub mod otlp;
+pub mod plugin;
+pub mod remote;
+pub mod replay;
+pub mod sbc;
+pub mod screensaver;
+pub mod sinks;
+pub mod snapshot;
+pub mod source;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod stats;
+pub mod statusline;
+pub mod sys;
+#[cfg(feature = "tls")]
+pub mod tls;
+pub mod theme;
+pub mod tree;
+pub mod trend;
+pub mod ui;
+pub mod watchdog;
+pub mod worker;
+
+pub use app::App;
+pub use sys::{format_bytes, format_duration_secs, Collector, DiskInfo, ProcessInfo, SysCache};
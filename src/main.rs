@@ -1,41 +1,324 @@
 use std::error::Error;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use std::path::Path;
 
-mod app;
-mod event;
-mod sys;
-mod ui;
-
-use app::App;
 use crossterm::{
-    event::{KeyCode, KeyEvent, KeyModifiers},
+    cursor::Show,
+    event::{
+        DisableFocusChange, DisableMouseCapture, EnableFocusChange, EnableMouseCapture, KeyCode,
+        KeyEvent, KeyModifiers,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use event::{spawn_events, Event as AppEvent};
 use ratatui::{backend::CrosstermBackend, Terminal};
-use std::io;
+use std::io::{self, Write};
+use tokio::sync::mpsc::UnboundedSender;
+use sysdash::app::HISTORY_WINDOW;
+use sysdash::config::{config_path, ui_path, Config, UiConfig};
+use sysdash::csvlog::CsvLog;
+use sysdash::event::{Event as AppEvent, Events};
+use sysdash::focus::Focus;
+use sysdash::remote::{self, remote_path, RemoteConfig, RemoteSource};
+use sysdash::replay::{self, Recorder, Replay};
+use sysdash::sys::{refresh_path, RefreshIntervals};
+use sysdash::worker::Worker;
+use sysdash::{accessible, api, batch, check, cli, i18n, statusline, theme, ui, App};
 
 fn main() -> Result<(), Box<dyn Error>> {
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen)?;
-    let backend = CrosstermBackend::new(stdout);
+    let opts = cli::parse(std::env::args()).unwrap_or_else(|e| e.exit());
+
+    if let Some(spec) = opts.check {
+        std::process::exit(check::run(&spec));
+    }
+
+    if let Some(colors) = opts.statusline {
+        statusline::run(colors, Duration::from_millis(500));
+        return Ok(());
+    }
+
+    if opts.accessible {
+        accessible::run(Duration::from_millis(500))?;
+        return Ok(());
+    }
+
+    let mut config = Config::load(&config_path())?;
+    opts.apply(&mut config)?;
+    config.truecolor.get_or_insert_with(theme::detect_truecolor);
+    let tick_rate = config.tick_rate();
+    if opts.batch {
+        batch::run(tick_rate, opts.iterations, opts.format)?;
+        return Ok(());
+    }
+
+    #[cfg(not(feature = "otlp"))]
+    if opts.otlp.is_some() {
+        return Err("--otlp requires building with the \"otlp\" feature".into());
+    }
+
+    #[cfg(not(feature = "influx"))]
+    if config.influx.is_some() {
+        return Err("[influx] in config.toml requires building with the \"influx\" feature".into());
+    }
+
+    #[cfg(not(feature = "docker"))]
+    if opts.docker_host.is_some() {
+        return Err("--docker-host requires building with the \"docker\" feature".into());
+    }
+
+    if let Some(addr) = &opts.agent {
+        let intervals = RefreshIntervals::load(&refresh_path())?;
+        let config = RemoteConfig::load(&remote_path())?;
+        let runtime = tokio::runtime::Runtime::new()?;
+        runtime.block_on(remote::serve(addr, intervals, config))?;
+        return Ok(());
+    }
+
+    run_tui(config, &opts)
+}
+
+/// Raw mode and the alternate screen for as long as it lives; dropping it
+/// restores the terminal on every exit path, including `?` returns.
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn enter() -> io::Result<Self> {
+        enable_raw_mode()?;
+        // Constructed before entering the alternate screen so a failure there
+        // still disables raw mode.
+        let guard = TerminalGuard;
+        execute!(
+            io::stdout(),
+            EnterAlternateScreen,
+            EnableFocusChange,
+            EnableMouseCapture
+        )?;
+        Ok(guard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore_terminal();
+    }
+}
+
+fn restore_terminal() {
+    let _ = disable_raw_mode();
+    let _ = execute!(
+        io::stdout(),
+        DisableMouseCapture,
+        DisableFocusChange,
+        LeaveAlternateScreen,
+        Show
+    );
+}
+
+/// Restores the terminal before the panic message is printed, so it lands on
+/// the normal screen. A panic in any task (collector, input, timer) leaves
+/// the dashboard half-dead, so the process exits rather than carrying on.
+fn install_panic_hook() {
+    let default = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        restore_terminal();
+        default(info);
+        std::process::exit(101);
+    }));
+}
+
+fn run_tui(config: Config, opts: &cli::Options) -> Result<(), Box<dyn Error>> {
+    let runtime = tokio::runtime::Runtime::new()?;
+    let result = runtime.block_on(run_app(config, opts));
+    // The input reader and an in-flight refresh may be blocked; don't wait.
+    runtime.shutdown_background();
+    result
+}
+
+/// The dashboard proper: input, ticks, signals, the collector and the
+/// exporter all run as tasks and reach this loop through one channel.
+async fn run_app(config: Config, opts: &cli::Options) -> Result<(), Box<dyn Error>> {
+    #[cfg(feature = "otlp")]
+    let mut exporter = sysdash::otlp::Exporter::from_env(opts.otlp.as_deref());
+    #[cfg(feature = "influx")]
+    let influx = config.influx.clone().map(sysdash::influx::Exporter::new);
+
+    let mut csv_log = match &opts.log_csv {
+        Some(path) => Some(
+            CsvLog::open(path).map_err(|e| format!("--log-csv {}: {}", path.display(), e))?,
+        ),
+        None => None,
+    };
+    let mut recorder = match &opts.record {
+        Some(path) => Some(
+            Recorder::open(path).map_err(|e| format!("--record {}: {}", path.display(), e))?,
+        ),
+        None => None,
+    };
+    let replay = match &opts.replay {
+        Some(path) => {
+            let frames =
+                replay::load(path).map_err(|e| format!("--replay {}: {}", path.display(), e))?;
+            let replay = Replay::new(frames)
+                .ok_or_else(|| format!("--replay {}: no snapshots", path.display()))?;
+            Some(replay)
+        }
+        None => None,
+    };
+    let api = match &opts.api {
+        Some(addr) => Some(
+            api::spawn(addr)
+                .await
+                .map_err(|e| format!("--api {}: {}", addr, e))?,
+        ),
+        None => None,
+    };
+
+    let intervals = RefreshIntervals::load(&refresh_path())?;
+    let remote_config = RemoteConfig::load(&remote_path())?;
+    let ui_config = UiConfig::load(&ui_path())?;
+    i18n::set_locale(i18n::locale(ui_config.locale.as_deref())?);
+
+    install_panic_hook();
+    let _guard = TerminalGuard::enter()?;
+    let backend = CrosstermBackend::new(io::stdout());
     let mut terminal = Terminal::new(backend)?;
     terminal.clear()?;
 
-    let tick_rate = Duration::from_millis(1000);
-    let mut app = App::new(tick_rate);
-    let rx = spawn_events(tick_rate);
+    // A replay is of one machine, whatever config.toml lists.
+    let hosts = if replay.is_some() {
+        vec![String::new()]
+    } else {
+        config.host_names(&opts.connect)
+    };
+    let tick_rate = config.tick_rate();
+    let mut app = App::with_hosts(config, hosts);
+    app.load_user_files();
+    app.search_query = opts.filter.clone().unwrap_or_default();
+    app.process_limit = ui_config.process_limit();
+    app.clock = ui_config.clock;
+    app.screensaver = ui_config.screensaver;
+    app.focus = opts
+        .focus
+        .clone()
+        .map(|target| Focus::new(target, HISTORY_WINDOW, tick_rate));
+    // A replay plays one frame per tick, so it keeps its pace while idle.
+    app.set_idle_rate(
+        intervals.idle_rate().filter(|_| replay.is_none()),
+        Duration::from_secs(intervals.idle_after),
+    );
+    if let Some(replay) = replay {
+        app.start_replay(replay);
+    }
+    #[cfg(feature = "sqlite")]
+    let history = match app.hosts.iter().position(|h| h.is_local()) {
+        Some(local) if app.replay().is_none() => {
+            use sysdash::sqlite;
+            match sqlite::History::open(&sqlite::history_path()) {
+                Ok((history, samples)) => {
+                    sqlite::seed(&mut app.hosts[local], &samples);
+                    Some(history)
+                }
+                Err(e) => {
+                    app.notice = Some(format!("History unavailable: {}", e));
+                    None
+                }
+            }
+        }
+        _ => None,
+    };
+    let mut events = Events::spawn(tick_rate);
+    let tx = events.sender();
+
+    #[cfg(feature = "mdns")]
+    let _browser = {
+        let tx = tx.clone();
+        sysdash::discovery::browse(move |agent| tx.send(AppEvent::Discovered(agent)).is_ok()).ok()
+    };
 
-    app.on_tick();
+    #[cfg(feature = "docker")]
+    {
+        let tx = tx.clone();
+        app.container_control = Some(sysdash::containers::spawn(
+            sysdash::containers::docker_host(opts.docker_host.as_deref()),
+            sysdash::containers::poll_interval(intervals.processes),
+            move |containers| tx.send(AppEvent::Containers(Box::new(containers))).is_ok(),
+        ));
+    }
 
     loop {
-        terminal.draw(|f| ui::draw(f, &mut app))?;
+        if app.replay().is_none() {
+            start_workers(&mut app, intervals, &remote_config, &tx);
+        }
+        app.poll_detail();
+        let started = Instant::now();
+        let frame = terminal.draw(|f| ui::draw(f, &mut app))?;
+        let snapshot = app
+            .take_snapshot()
+            .then(|| sysdash::snapshot::save(frame.buffer, Path::new(".")));
+        app.record_frame(started.elapsed());
+        match snapshot {
+            Some(Ok(path)) => app.notice = Some(format!("Saved {}", path.display())),
+            Some(Err(e)) => app.notice = Some(format!("Snapshot failed: {}", e)),
+            None => {}
+        }
+        if app.take_bell() {
+            let backend = terminal.backend_mut();
+            backend.write_all(b"\x07")?;
+            backend.flush()?;
+        }
 
-        match rx.recv() {
-            Ok(AppEvent::Tick) => app.on_tick(),
-            Ok(AppEvent::Input(key)) => {
+        if let Some(rate) = app.take_tick_rate_change() {
+            events.set_tick_rate(rate);
+            for worker in app.hosts.iter().filter_map(|h| h.worker.as_ref()) {
+                worker.set_interval(rate);
+            }
+        }
+
+        let Some(event) = events.next().await else {
+            break;
+        };
+        match event {
+            AppEvent::Tick => app.on_tick(Instant::now()),
+            AppEvent::Focus(focused) => app.on_focus(focused),
+            AppEvent::Mouse(mouse) => app.on_mouse(mouse),
+            AppEvent::Discovered(agent) => app.on_discovered(agent),
+            AppEvent::Containers(containers) => app.on_containers(*containers),
+            AppEvent::Terminate(_) => app.request_quit(),
+            AppEvent::Refresh(host, sys) => {
+                // Logged and served as it arrives, so pausing doesn't hold them up.
+                let local = app.hosts.get(host).is_some_and(|h| h.is_local());
+                if let (Some(log), true) = (csv_log.as_mut(), local) {
+                    if let Err(e) = log.record(&sys) {
+                        app.notice = Some(format!("CSV log stopped: {}", e));
+                        csv_log = None;
+                    }
+                }
+                if let (Some(session), true) = (recorder.as_mut(), local) {
+                    if let Err(e) = session.record(&sys) {
+                        app.notice = Some(format!("Recording stopped: {}", e));
+                        recorder = None;
+                    }
+                }
+                if let (Some(api), true) = (&api, local) {
+                    api.send_replace(sys.as_ref().clone());
+                }
+                #[cfg(feature = "sqlite")]
+                if let (Some(history), true) = (&history, local) {
+                    history.record(&sys);
+                }
+                #[cfg(feature = "influx")]
+                if let (Some(influx), true) = (&influx, local) {
+                    influx.record(&sys);
+                }
+                app.on_host_refresh(host, *sys);
+                #[cfg(feature = "otlp")]
+                if let (Some(exporter), Some(host)) = (exporter.as_mut(), app.hosts.get(host)) {
+                    if host.is_local() {
+                        exporter.record(&host.sys);
+                    }
+                }
+            }
+            AppEvent::Input(key) => {
                 if matches!(
                     key,
                     KeyEvent {
@@ -49,7 +332,6 @@ fn main() -> Result<(), Box<dyn Error>> {
                     app.on_key(key);
                 }
             }
-            Err(_) => break,
         }
 
         if app.should_quit() {
@@ -57,9 +339,34 @@ fn main() -> Result<(), Box<dyn Error>> {
         }
     }
 
-    disable_raw_mode()?;
-    let mut out = io::stdout();
-    execute!(out, LeaveAlternateScreen)?;
-    terminal.show_cursor()?;
     Ok(())
 }
+
+/// Starts a collector for every host that has none yet: the local machine
+/// or a remote agent. Hosts can be added at runtime from the connect popup.
+fn start_workers(
+    app: &mut App,
+    intervals: RefreshIntervals,
+    config: &RemoteConfig,
+    tx: &UnboundedSender<AppEvent>,
+) {
+    let tick_rate = app.effective_tick_rate();
+    for (i, host) in app.hosts.iter_mut().enumerate() {
+        if host.worker.is_some() {
+            continue;
+        }
+        let tx = tx.clone();
+        let deliver = move |sys| tx.send(AppEvent::Refresh(i, Box::new(sys))).is_ok();
+        host.worker = Some(if host.is_local() {
+            Worker::spawn(tick_rate, intervals, deliver)
+        } else {
+            let addr = host.name.clone();
+            let config = config.clone();
+            Worker::spawn_with(
+                move || RemoteSource::with_config(&addr, config),
+                tick_rate,
+                deliver,
+            )
+        });
+    }
+}
@@ -0,0 +1,205 @@
+//! OpenTelemetry metrics export over OTLP/HTTP with JSON encoding.
+//!
+//! The endpoint comes from `--otlp <url>` or the standard
+//! `OTEL_EXPORTER_OTLP_ENDPOINT` / `OTEL_EXPORTER_OTLP_METRICS_ENDPOINT`
+//! variables; `OTEL_METRIC_EXPORT_INTERVAL` (ms) sets the push interval.
+
+use crate::sys::metrics::{registry, Kind, MetricDef, Sample, Unit};
+use crate::sys::SysCache;
+use serde_json::{json, Value};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc::{self, UnboundedSender};
+use tokio::task;
+
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(60);
+
+pub struct Exporter {
+    tx: UnboundedSender<String>,
+    interval: Duration,
+    last: Option<Instant>,
+    host: String,
+}
+
+/// Resolves the metrics URL: a base endpoint gets `/v1/metrics` appended,
+/// while the metrics-specific variable is used verbatim.
+pub fn endpoint_from_env(flag: Option<&str>) -> Option<String> {
+    if let Some(base) = flag {
+        return Some(format!("{}/v1/metrics", base.trim_end_matches('/')));
+    }
+    if let Ok(url) = std::env::var("OTEL_EXPORTER_OTLP_METRICS_ENDPOINT") {
+        return Some(url);
+    }
+    std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT")
+        .ok()
+        .map(|base| format!("{}/v1/metrics", base.trim_end_matches('/')))
+}
+
+impl Exporter {
+    pub fn from_env(flag: Option<&str>) -> Option<Self> {
+        let url = endpoint_from_env(flag)?;
+        let interval = std::env::var("OTEL_METRIC_EXPORT_INTERVAL")
+            .ok()
+            .and_then(|ms| ms.parse().ok())
+            .map(Duration::from_millis)
+            .unwrap_or(DEFAULT_INTERVAL);
+        Some(Self::new(url, interval))
+    }
+
+    /// Starts the sender task; pushes happen there so a slow or unreachable
+    /// collector never blocks the caller. Must be called from within a tokio
+    /// runtime.
+    pub fn new(url: String, interval: Duration) -> Self {
+        let (tx, mut rx) = mpsc::unbounded_channel::<String>();
+        tokio::spawn(async move {
+            while let Some(body) = rx.recv().await {
+                let url = url.clone();
+                let _ = task::spawn_blocking(move || {
+                    ureq::post(&url)
+                        .content_type("application/json")
+                        .send(body.as_str())
+                })
+                .await;
+            }
+        });
+        Self {
+            tx,
+            interval,
+            last: None,
+            host: sysinfo::System::host_name().unwrap_or_default(),
+        }
+    }
+
+    /// Queues a push if the export interval has elapsed since the last one.
+    pub fn record(&mut self, sys: &SysCache) {
+        if self.last.is_some_and(|t| t.elapsed() < self.interval) {
+            return;
+        }
+        self.last = Some(Instant::now());
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let _ = self.tx.send(payload(sys, &self.host, now).to_string());
+    }
+}
+
+fn attr(key: &str, value: &str) -> Value {
+    json!({ "key": key, "value": { "stringValue": value } })
+}
+
+/// OTLP's unit for `unit`, and what values are divided by: OTLP expresses
+/// utilization as a ratio.
+fn otlp_unit(unit: Unit) -> (&'static str, f64) {
+    match unit {
+        Unit::Percent => ("1", 100.0),
+        Unit::Celsius => ("Cel", 1.0),
+        Unit::Bytes => ("By", 1.0),
+        Unit::BytesPerSec => ("By/s", 1.0),
+        Unit::PagesPerSec => ("{page}/s", 1.0),
+        Unit::Seconds => ("s", 1.0),
+        Unit::Count => ("{count}", 1.0),
+        Unit::Watts => ("W", 1.0),
+    }
+}
+
+/// `def` as an OTLP metric: a gauge, or a cumulative sum for counters.
+fn metric(def: &MetricDef, samples: Vec<Sample>, now: &str) -> Value {
+    let (unit, divisor) = otlp_unit(def.unit);
+    let points: Vec<Value> = samples
+        .into_iter()
+        .map(|sample| {
+            let attrs: Vec<Value> = def
+                .label
+                .zip(sample.label.as_deref())
+                .map(|(key, value)| attr(key, value))
+                .into_iter()
+                .collect();
+            json!({ "timeUnixNano": now, "asDouble": sample.value / divisor, "attributes": attrs })
+        })
+        .collect();
+    let name = format!("sysdash.{}", def.name);
+    match def.kind {
+        Kind::Gauge => json!({
+            "name": name,
+            "description": def.help,
+            "unit": unit,
+            "gauge": { "dataPoints": points },
+        }),
+        Kind::Counter => json!({
+            "name": name,
+            "description": def.help,
+            "unit": unit,
+            "sum": { "dataPoints": points, "aggregationTemporality": 2, "isMonotonic": true },
+        }),
+    }
+}
+
+/// Builds an `ExportMetricsServiceRequest` in OTLP JSON form from every
+/// registered metric the host reports.
+pub fn payload(sys: &SysCache, host: &str, now_nanos: u128) -> Value {
+    let now = now_nanos.to_string();
+    let metrics: Vec<Value> = registry()
+        .iter()
+        .filter_map(|def| {
+            let samples = (def.read)(sys);
+            (!samples.is_empty()).then(|| metric(def, samples, &now))
+        })
+        .collect();
+
+    json!({
+        "resourceMetrics": [{
+            "resource": { "attributes": [attr("service.name", "sysdash"), attr("host.name", host)] },
+            "scopeMetrics": [{
+                "scope": { "name": "sysdash", "version": env!("CARGO_PKG_VERSION") },
+                "metrics": metrics,
+            }],
+        }],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_endpoint_from_flag() {
+        assert_eq!(
+            endpoint_from_env(Some("http://collector:4318/")).as_deref(),
+            Some("http://collector:4318/v1/metrics")
+        );
+    }
+
+    #[test]
+    fn test_payload_shape() {
+        let sys = SysCache::new();
+        let body = payload(&sys, "box", 42);
+        let scope = &body["resourceMetrics"][0]["scopeMetrics"][0];
+        assert_eq!(scope["scope"]["name"], "sysdash");
+
+        let metrics = scope["metrics"].as_array().unwrap();
+        let mem = metrics
+            .iter()
+            .find(|m| m["name"] == "sysdash.memory.usage")
+            .unwrap();
+        let points = mem["gauge"]["dataPoints"].as_array().unwrap();
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[0]["timeUnixNano"], "42");
+        assert_eq!(points[1]["asDouble"], sys.total_mem as f64);
+        assert_eq!(points[1]["attributes"][0]["value"]["stringValue"], "total");
+
+        let cpu = metrics
+            .iter()
+            .find(|m| m["name"] == "sysdash.cpu.utilization")
+            .unwrap();
+        assert_eq!(cpu["unit"], "1");
+        assert_eq!(
+            cpu["gauge"]["dataPoints"][0]["asDouble"],
+            sys.cpu_global as f64 / 100.0
+        );
+        let uptime = metrics
+            .iter()
+            .find(|m| m["name"] == "sysdash.uptime")
+            .unwrap();
+        assert_eq!(uptime["sum"]["isMonotonic"], true);
+    }
+}
@@ -0,0 +1,332 @@
+//! Rhai plugins loaded from `~/.config/sysdash/plugins/*.rhai`.
+//!
+//! A plugin may define any of these functions; each receives a `data` map with
+//! the refreshed snapshot (`cpu`, `cores`, `mem_used`, `mem_total`,
+//! `mem_percent`, `temp`, `uptime`, `rx_rate`, `tx_rate`, `procs`, `disks`) plus
+//! the `metrics` computed by all plugins this tick:
+//!
+//! ```text
+//! fn slot() { "sidebar" }                     // or "footer" (default)
+//! fn metrics(data) { #{ mem_free: data.mem_total - data.mem_used } }
+//! fn widget(data) { #{ title: "Load", lines: [`cpu ${data.cpu}`] } }
+//! fn alerts(data) { if data.cpu > 90.0 { ["cpu above 90%"] } else { [] } }
+//! ```
+
+use crate::sys::SysCache;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Copy, PartialEq, Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Slot {
+    Sidebar,
+    #[default]
+    Footer,
+}
+
+#[derive(Clone, Debug)]
+pub struct PluginWidget {
+    pub slot: Slot,
+    pub title: String,
+    pub lines: Vec<String>,
+}
+
+#[cfg(feature = "plugins")]
+struct Plugin {
+    name: String,
+    ast: rhai::AST,
+    slot: Slot,
+}
+
+#[derive(Default)]
+pub struct PluginHost {
+    #[cfg(feature = "plugins")]
+    engine: rhai::Engine,
+    #[cfg(feature = "plugins")]
+    plugins: Vec<Plugin>,
+    pub metrics: BTreeMap<String, f64>,
+    pub widgets: Vec<PluginWidget>,
+    pub alerts: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+/// `$XDG_CONFIG_HOME/sysdash`, falling back to `~/.config/sysdash`, or to
+/// `%APPDATA%\sysdash` on Windows.
+pub fn config_dir() -> PathBuf {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| {
+            if cfg!(windows) {
+                std::env::var_os("APPDATA").map(PathBuf::from)
+            } else {
+                std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config"))
+            }
+        })
+        .unwrap_or_default();
+    base.join("sysdash")
+}
+
+pub fn plugins_dir() -> PathBuf {
+    config_dir().join("plugins")
+}
+
+impl PluginHost {
+    pub fn is_empty(&self) -> bool {
+        self.widgets.is_empty() && self.alerts.is_empty() && self.errors.is_empty()
+    }
+
+    pub fn widgets_in(&self, slot: Slot) -> Vec<&PluginWidget> {
+        self.widgets.iter().filter(|w| w.slot == slot).collect()
+    }
+}
+
+#[cfg(not(feature = "plugins"))]
+impl PluginHost {
+    pub fn load(_dir: &Path) -> Self {
+        Self::default()
+    }
+
+    pub fn run(&mut self, _sys: &SysCache) {}
+}
+
+#[cfg(feature = "plugins")]
+impl PluginHost {
+    /// Compiles every `*.rhai` file in `dir`, in file-name order. A missing
+    /// directory simply yields no plugins.
+    pub fn load(dir: &Path) -> Self {
+        let mut engine = rhai::Engine::new();
+        engine.set_max_operations(100_000);
+
+        let mut host = Self::default();
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .filter_map(|e| e.ok().map(|e| e.path()))
+                    .filter(|p| p.extension().is_some_and(|ext| ext == "rhai"))
+                    .collect()
+            })
+            .unwrap_or_default();
+        paths.sort();
+
+        for path in paths {
+            let name = path
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default();
+            match engine.compile_file(path) {
+                Ok(ast) => {
+                    let mut plugin = Plugin {
+                        name,
+                        ast,
+                        slot: Slot::Footer,
+                    };
+                    if has_fn(&plugin.ast, "slot") {
+                        let slot = engine
+                            .call_fn::<String>(&mut rhai::Scope::new(), &plugin.ast, "slot", ())
+                            .unwrap_or_default();
+                        if slot == "sidebar" {
+                            plugin.slot = Slot::Sidebar;
+                        }
+                    }
+                    host.plugins.push(plugin);
+                }
+                Err(e) => host.errors.push(format!("{}: {}", name, e)),
+            }
+        }
+
+        host.engine = engine;
+        host
+    }
+
+    /// Runs all plugins against the latest snapshot: computed metrics first,
+    /// so widgets and alerts of every plugin can read them.
+    pub fn run(&mut self, sys: &SysCache) {
+        if self.plugins.is_empty() {
+            return;
+        }
+        self.metrics.clear();
+        self.widgets.clear();
+        self.alerts.clear();
+        self.errors.retain(|e| !e.starts_with("runtime "));
+
+        let mut data = snapshot(sys);
+        for plugin in &self.plugins {
+            if !has_fn(&plugin.ast, "metrics") {
+                continue;
+            }
+            match self.call(plugin, "metrics", &data) {
+                Ok(value) => {
+                    if let Some(map) = value.try_cast::<rhai::Map>() {
+                        for (k, v) in map {
+                            if let Some(n) = as_f64(&v) {
+                                self.metrics.insert(k.to_string(), n);
+                            }
+                        }
+                    }
+                }
+                Err(e) => self.errors.push(e),
+            }
+        }
+
+        let metrics: rhai::Map = self
+            .metrics
+            .iter()
+            .map(|(k, v)| (k.as_str().into(), (*v).into()))
+            .collect();
+        data.insert("metrics".into(), metrics.into());
+
+        for plugin in &self.plugins {
+            if has_fn(&plugin.ast, "widget") {
+                match self.call(plugin, "widget", &data) {
+                    Ok(value) => self.widgets.push(to_widget(plugin, value)),
+                    Err(e) => self.errors.push(e),
+                }
+            }
+            if has_fn(&plugin.ast, "alerts") {
+                match self.call(plugin, "alerts", &data) {
+                    Ok(value) => {
+                        if let Some(list) = value.try_cast::<rhai::Array>() {
+                            self.alerts.extend(list.iter().map(|a| a.to_string()));
+                        }
+                    }
+                    Err(e) => self.errors.push(e),
+                }
+            }
+        }
+    }
+
+    fn call(&self, plugin: &Plugin, func: &str, data: &rhai::Map) -> Result<rhai::Dynamic, String> {
+        self.engine
+            .call_fn::<rhai::Dynamic>(
+                &mut rhai::Scope::new(),
+                &plugin.ast,
+                func,
+                (rhai::Dynamic::from_map(data.clone()),),
+            )
+            .map_err(|e| format!("runtime {}::{}: {}", plugin.name, func, e))
+    }
+}
+
+#[cfg(feature = "plugins")]
+fn has_fn(ast: &rhai::AST, name: &str) -> bool {
+    ast.iter_functions().any(|f| f.name == name)
+}
+
+#[cfg(feature = "plugins")]
+fn as_f64(v: &rhai::Dynamic) -> Option<f64> {
+    v.as_float()
+        .ok()
+        .or_else(|| v.as_int().ok().map(|i| i as f64))
+}
+
+#[cfg(feature = "plugins")]
+fn to_widget(plugin: &Plugin, value: rhai::Dynamic) -> PluginWidget {
+    let mut title = plugin.name.clone();
+    let lines = if value.is_map() {
+        let map = value.cast::<rhai::Map>();
+        if let Some(t) = map.get("title") {
+            title = t.to_string();
+        }
+        map.get("lines")
+            .and_then(|l| l.clone().try_cast::<rhai::Array>())
+            .map(|l| l.iter().map(|line| line.to_string()).collect())
+            .unwrap_or_default()
+    } else {
+        value.to_string().lines().map(str::to_string).collect()
+    };
+    PluginWidget {
+        slot: plugin.slot,
+        title,
+        lines,
+    }
+}
+
+#[cfg(feature = "plugins")]
+fn snapshot(sys: &SysCache) -> rhai::Map {
+    let mut m = rhai::Map::new();
+    let mem_percent = if sys.total_mem > 0 {
+        sys.used_mem as f64 / sys.total_mem as f64 * 100.0
+    } else {
+        0.0
+    };
+    let cores: rhai::Array = sys.cpu_cores.iter().map(|&c| (c as f64).into()).collect();
+    let disks: rhai::Array = sys
+        .disks()
+        .iter()
+        .map(|d| {
+            let mut disk = rhai::Map::new();
+            disk.insert("mount".into(), d.mount_point.clone().into());
+            disk.insert("total".into(), (d.total as i64).into());
+            disk.insert("available".into(), (d.available as i64).into());
+            disk.into()
+        })
+        .collect();
+
+    m.insert("cpu".into(), (sys.cpu_global as f64).into());
+    m.insert("cores".into(), cores.into());
+    m.insert("mem_used".into(), (sys.used_mem as i64).into());
+    m.insert("mem_total".into(), (sys.total_mem as i64).into());
+    m.insert("mem_percent".into(), mem_percent.into());
+    m.insert("temp".into(), (sys.cpu_temp as f64).into());
+    m.insert("uptime".into(), (sys.uptime as i64).into());
+    m.insert("rx_rate".into(), (sys.rx_rate as i64).into());
+    m.insert("tx_rate".into(), (sys.tx_rate as i64).into());
+    m.insert("procs".into(), (sys.processes().len() as i64).into());
+    m.insert("disks".into(), disks.into());
+    m
+}
+
+#[cfg(all(test, feature = "plugins"))]
+mod tests {
+    use super::*;
+
+    fn plugin_dir(name: &str, script: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("sysdash-plugins-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(format!("{}.rhai", name)), script).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_missing_dir_is_empty() {
+        let host = PluginHost::load(Path::new("/nonexistent/sysdash/plugins"));
+        assert!(host.is_empty());
+        assert!(host.plugins.is_empty());
+    }
+
+    #[test]
+    fn test_run_plugin() {
+        let dir = plugin_dir(
+            "demo",
+            r#"
+            fn slot() { "sidebar" }
+            fn metrics(data) { #{ doubled: data.mem_percent * 2.0, procs: data.procs } }
+            fn widget(data) { #{ title: "Demo", lines: ["a", `${data.metrics.procs}`] } }
+            fn alerts(data) { if data.mem_total > 0 { ["memory present"] } else { [] } }
+            "#,
+        );
+        let mut host = PluginHost::load(&dir);
+        let sys = SysCache::new();
+        host.run(&sys);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(host.errors.is_empty(), "{:?}", host.errors);
+        assert!(host.metrics.contains_key("doubled"));
+        assert_eq!(host.metrics["procs"], sys.processes().len() as f64);
+        let widgets = host.widgets_in(Slot::Sidebar);
+        assert_eq!(widgets.len(), 1);
+        assert_eq!(widgets[0].title, "Demo");
+        assert_eq!(widgets[0].lines[0], "a");
+        assert_eq!(host.alerts, vec!["memory present".to_string()]);
+    }
+
+    #[test]
+    fn test_compile_error_is_reported() {
+        let dir = plugin_dir("broken", "fn widget(data) { ");
+        let host = PluginHost::load(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(host.errors.len(), 1);
+        assert!(host.errors[0].starts_with("broken: "));
+    }
+}
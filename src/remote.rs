@@ -0,0 +1,1034 @@
+//! Agent and client modes. `--agent <addr>` serves snapshots to remote
+//! clients; `--connect <addr>` renders them in the usual TUI.
+//!
+//! `--connect ssh://user@host` (or `--remote user@host`) needs no listening
+//! agent: it runs `sysdash --agent -` on the host through `ssh`, which
+//! serves the one client on its stdin and stdout.
+//!
+//! The protocol is newline-delimited JSON over TCP or a Unix socket. Each
+//! line from the client is a [`Request`]; `collect` and `detail` get one
+//! [`Response`] line back. The requests mirror [`SystemSource`], so the
+//! client side is just another source for the worker.
+//!
+//! Security is configured in `remote.toml`. With a `token`, the agent answers
+//! only clients whose first line is a matching `hello`. With a `[tls]`
+//! section and the "tls" feature, the agent serves TLS and `tls://host:port`
+//! clients verify it against the CA; see [`crate::tls`].
+//!
+//! Process actions from clients are refused unless listed in `allow`, and
+//! every attempt is written to the audit log, allowed or not. The same goes
+//! for the environment in process details. A TCP agent without a token
+//! only listens on loopback addresses.
+
+use crate::plugin::config_dir;
+use crate::source::SystemSource;
+use crate::sys::{Collector, ProcessDetail, RefreshIntervals, SysCache};
+use crate::worker::blocking;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// How long the client waits to connect or for an answer before it reports
+/// the agent as offline.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The longest request line the agent reads, before or after the `hello`.
+/// Requests are a few dozen bytes; tokens have room to spare.
+const MAX_REQUEST: u64 = 4096;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Request {
+    /// The client's first line, carrying the token when one is configured.
+    Hello {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        token: Option<String>,
+    },
+    Collect,
+    Kill {
+        pid: u32,
+    },
+    /// Changes the nice value by `delta`.
+    Renice {
+        pid: u32,
+        delta: i32,
+    },
+    /// Caps CPU at `percent` of one core; no percent lifts the cap.
+    Limit {
+        pid: u32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        percent: Option<u32>,
+    },
+    Detail {
+        pid: u32,
+    },
+    /// The client's collection interval, so the agent can schedule the slower
+    /// subsystems.
+    Tick {
+        ms: u64,
+    },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Response {
+    Snapshot(Box<SysCache>),
+    Detail { detail: Option<ProcessDetail> },
+    /// The answer to a process action; `error` if the agent refused it.
+    Done { error: Option<String> },
+    /// Sent before the agent closes the connection, e.g. on a wrong token.
+    Error { message: String },
+}
+
+/// `remote.toml`, shared by agent and client.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RemoteConfig {
+    /// Pre-shared token; the agent rejects clients that don't send it.
+    pub token: Option<String>,
+    pub tls: Option<TlsConfig>,
+    /// Process actions the agent carries out for clients; none by default.
+    pub allow: Vec<Action>,
+    /// Where the agent appends its audit log; stderr if unset.
+    pub audit: Option<PathBuf>,
+    /// What `ssh://` clients run on the host; [`SSH_COMMAND`] if unset.
+    pub ssh_command: Option<String>,
+}
+
+/// The agent address that serves a single client on stdin and stdout.
+pub const STDIO: &str = "-";
+
+/// Runs the agent on an `ssh://` host, found through the remote `PATH`.
+pub const SSH_COMMAND: &str = "sysdash --agent -";
+
+/// A process action a client can ask an agent for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Kill,
+    Renice,
+    Limit,
+    /// Sending the environment with a process detail; it may hold secrets.
+    Environ,
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Action::Kill => "kill",
+            Action::Renice => "renice",
+            Action::Limit => "limit",
+            Action::Environ => "environ",
+        })
+    }
+}
+
+/// PEM files for TLS. The agent needs `cert` and `key`, the client `ca`.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TlsConfig {
+    pub cert: Option<PathBuf>,
+    pub key: Option<PathBuf>,
+    pub ca: Option<PathBuf>,
+}
+
+pub fn remote_path() -> PathBuf {
+    config_dir().join("remote.toml")
+}
+
+impl RemoteConfig {
+    /// Reads `path`; a missing file means no token and no TLS.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => toml::from_str(&text).map_err(|e| format!("remote.toml: {}", e)),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+}
+
+/// Compares without stopping at the first difference, so the time taken
+/// doesn't reveal how much of a guessed token was right.
+fn same_token(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a
+            .bytes()
+            .zip(b.bytes())
+            .fold(0, |diff, (x, y)| diff | (x ^ y))
+            == 0
+}
+
+/// An agent found on the network, e.g. through mDNS.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Agent {
+    pub name: String,
+    pub addr: String,
+}
+
+/// Where an agent listens: `unix:<path>` (or anything with a `/`) is a Unix
+/// socket, `tls://host:port` TCP with TLS, `ssh://destination` an agent
+/// started through ssh, everything else `host:port`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Endpoint {
+    Tcp(String),
+    Tls(String),
+    Unix(PathBuf),
+    Ssh(String),
+}
+
+impl Endpoint {
+    pub fn parse(addr: &str) -> Self {
+        if let Some(addr) = addr.strip_prefix("tls://") {
+            return Endpoint::Tls(addr.to_string());
+        }
+        if let Some(destination) = addr.strip_prefix("ssh://") {
+            return Endpoint::Ssh(destination.to_string());
+        }
+        match addr.strip_prefix("unix:") {
+            Some(path) => Endpoint::Unix(path.into()),
+            None if addr.contains('/') => Endpoint::Unix(addr.into()),
+            None => Endpoint::Tcp(addr.to_string()),
+        }
+    }
+}
+
+#[cfg(not(feature = "tls"))]
+fn no_tls() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "TLS needs sysdash built with --features tls",
+    )
+}
+
+/// Serves every connection with its own [`Collector`] until the listener
+/// fails. With the "mdns" feature a TCP agent also announces itself. TCP is
+/// served over TLS when `config` has a `[tls]` section, which `tls://` needs,
+/// and refused on a non-loopback address when it has no token. [`STDIO`]
+/// serves one client on stdin and stdout and returns when it disconnects.
+pub async fn serve(addr: &str, intervals: RefreshIntervals, config: RemoteConfig) -> io::Result<()> {
+    let make = move || Collector::with_intervals(intervals, Duration::from_secs(1));
+    let config = Arc::new(config);
+    if addr == STDIO {
+        // Started by sshd, which says where the client is.
+        let peer = std::env::var("SSH_CLIENT")
+            .ok()
+            .and_then(|client| client.split_whitespace().next().map(String::from))
+            .unwrap_or_else(|| "stdio".into());
+        let stream = tokio::io::join(tokio::io::stdin(), tokio::io::stdout());
+        handle_new(stream, make, config, peer).await;
+        return Ok(());
+    }
+    let endpoint = Endpoint::parse(addr);
+    if matches!(endpoint, Endpoint::Tls(_)) && config.tls.is_none() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "tls:// needs a [tls] section with cert and key in remote.toml",
+        ));
+    }
+    match endpoint {
+        Endpoint::Tcp(addr) | Endpoint::Tls(addr) => {
+            #[cfg(feature = "tls")]
+            let acceptor = config.tls.as_ref().map(crate::tls::acceptor).transpose()?;
+            #[cfg(not(feature = "tls"))]
+            if config.tls.is_some() {
+                return Err(no_tls());
+            }
+            let listener = tokio::net::TcpListener::bind(&addr).await?;
+            if config.token.is_none() && !listener.local_addr()?.ip().is_loopback() {
+                return Err(io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    format!(
+                        "refusing to serve {} without a token; set one in remote.toml or bind to 127.0.0.1",
+                        addr
+                    ),
+                ));
+            }
+            #[cfg(feature = "mdns")]
+            let _announcement = match crate::discovery::announce(
+                listener.local_addr()?.port(),
+                config.tls.is_some(),
+            ) {
+                Ok(daemon) => Some(daemon),
+                Err(e) => {
+                    eprintln!("sysdash: mDNS announcement failed: {}", e);
+                    None
+                }
+            };
+            loop {
+                let (stream, peer) = listener.accept().await?;
+                let (config, peer) = (config.clone(), peer.to_string());
+                #[cfg(feature = "tls")]
+                if let Some(acceptor) = &acceptor {
+                    let accept = acceptor.accept(stream);
+                    tokio::spawn(async move {
+                        if let Ok(stream) = accept.await {
+                            handle_new(stream, make, config, peer).await;
+                        }
+                    });
+                    continue;
+                }
+                tokio::spawn(handle_new(stream, make, config, peer));
+            }
+        }
+        #[cfg(unix)]
+        Endpoint::Unix(path) => {
+            // A socket left behind by an earlier run would fail the bind.
+            let _ = std::fs::remove_file(&path);
+            let listener = tokio::net::UnixListener::bind(&path)?;
+            loop {
+                let (stream, _) = listener.accept().await?;
+                let peer = format!("unix:{}", path.display());
+                tokio::spawn(handle_new(stream, make, config.clone(), peer));
+            }
+        }
+        #[cfg(not(unix))]
+        Endpoint::Unix(_) => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Unix sockets are not supported on this platform",
+        )),
+        Endpoint::Ssh(_) => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "an agent can't listen on ssh://; run --agent - through ssh instead",
+        )),
+    }
+}
+
+async fn handle_new<T, S, M>(stream: T, make: M, config: Arc<RemoteConfig>, peer: String)
+where
+    T: AsyncRead + AsyncWrite + Send + 'static,
+    S: SystemSource + Send + 'static,
+    M: FnOnce() -> S + Send + 'static,
+{
+    if let Ok(source) = tokio::task::spawn_blocking(make).await {
+        let _ = handle(stream, source, &config, &peer).await;
+    }
+}
+
+/// Answers one client's requests from `source` until it disconnects. With a
+/// token in `config`, the first line must be a `hello` carrying it; otherwise
+/// the client gets an [`Response::Error`] and the connection is closed.
+/// `peer` names the client in the audit log.
+pub async fn handle<T, S>(
+    stream: T,
+    mut source: S,
+    config: &RemoteConfig,
+    peer: &str,
+) -> io::Result<()>
+where
+    T: AsyncRead + AsyncWrite,
+    S: SystemSource + Send + 'static,
+{
+    let (read, mut write) = tokio::io::split(stream);
+    let mut read = tokio::io::BufReader::new(read);
+    let token = config.token.as_deref();
+    let mut authorized = token.is_none();
+    while let Some(request) = next_request(&mut read).await? {
+        if !authorized {
+            let Request::Hello { token: Some(given) } = &request else {
+                return refuse(&mut write, "authentication required").await;
+            };
+            if !same_token(given, token.unwrap_or_default()) {
+                return refuse(&mut write, "invalid token").await;
+            }
+            authorized = true;
+            continue;
+        }
+        let response = match request {
+            Request::Hello { .. } => continue,
+            Request::Collect => {
+                let (back, snapshot) = blocking(source, |s| s.collect())
+                    .await
+                    .ok_or_else(|| io::Error::other("collector panicked"))?;
+                source = back;
+                Response::Snapshot(Box::new(snapshot))
+            }
+            Request::Detail { pid } => {
+                let (back, mut detail) = blocking(source, move |s| s.process_detail(pid))
+                    .await
+                    .ok_or_else(|| io::Error::other("collector panicked"))?;
+                source = back;
+                if let Some(detail) = detail.as_mut().filter(|d| !d.environ.is_empty()) {
+                    if permit(config, peer, Action::Environ, pid, "").is_some() {
+                        detail.environ.clear();
+                    }
+                }
+                Response::Detail { detail }
+            }
+            Request::Kill { pid } => {
+                let error = permit(config, peer, Action::Kill, pid, "");
+                if error.is_none() {
+                    let (back, ()) = blocking(source, move |s| s.kill_process(pid))
+                        .await
+                        .ok_or_else(|| io::Error::other("collector panicked"))?;
+                    source = back;
+                }
+                Response::Done { error }
+            }
+            Request::Renice { pid, delta } => {
+                let error = permit(config, peer, Action::Renice, pid, &format!(" {:+}", delta));
+                if error.is_none() {
+                    let (back, ()) = blocking(source, move |s| s.renice_process(pid, delta))
+                        .await
+                        .ok_or_else(|| io::Error::other("collector panicked"))?;
+                    source = back;
+                }
+                Response::Done { error }
+            }
+            Request::Limit { pid, percent } => {
+                let args = percent.map_or(" off".to_string(), |p| format!(" {}%", p));
+                let error = permit(config, peer, Action::Limit, pid, &args);
+                if error.is_none() {
+                    let (back, ()) = blocking(source, move |s| s.limit_process(pid, percent))
+                        .await
+                        .ok_or_else(|| io::Error::other("collector panicked"))?;
+                    source = back;
+                }
+                Response::Done { error }
+            }
+            Request::Tick { ms } => {
+                source.set_tick(Duration::from_millis(ms));
+                continue;
+            }
+        };
+        let mut buf = serde_json::to_vec(&response)?;
+        buf.push(b'\n');
+        write.write_all(&buf).await?;
+    }
+    Ok(())
+}
+
+/// Reads one request line of at most [`MAX_REQUEST`] bytes, so a client can't
+/// make the agent buffer without end; `None` when the client has gone.
+async fn next_request<R: AsyncBufRead + Unpin>(read: &mut R) -> io::Result<Option<Request>> {
+    let mut line = Vec::new();
+    read.take(MAX_REQUEST).read_until(b'\n', &mut line).await?;
+    if line.is_empty() {
+        return Ok(None);
+    }
+    if line.last() != Some(&b'\n') && line.len() as u64 == MAX_REQUEST {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "request too long"));
+    }
+    Ok(Some(serde_json::from_slice(&line)?))
+}
+
+/// Checks `action` against the allowlist and records the attempt; returns
+/// why it was refused, if it was.
+fn permit(config: &RemoteConfig, peer: &str, action: Action, pid: u32, args: &str) -> Option<String> {
+    let allowed = config.allow.contains(&action);
+    let line = format!(
+        "{} {} {} {}{} {}\n",
+        chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+        peer,
+        action,
+        pid,
+        args,
+        if allowed { "allowed" } else { "denied" },
+    );
+    let written = match &config.audit {
+        Some(path) => OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| file.write_all(line.as_bytes())),
+        None => io::stderr().write_all(line.as_bytes()),
+    };
+    if let Err(e) = written {
+        eprintln!("sysdash: audit log: {}", e);
+    }
+    (!allowed).then(|| format!("{} is not allowed on this agent", action))
+}
+
+async fn refuse<W: AsyncWrite + Unpin>(write: &mut W, message: &str) -> io::Result<()> {
+    let mut buf = serde_json::to_vec(&Response::Error {
+        message: message.into(),
+    })?;
+    buf.push(b'\n');
+    write.write_all(&buf).await?;
+    write.shutdown().await
+}
+
+trait Stream: Read + Write + Send {}
+
+impl<T: Read + Write + Send> Stream for T {}
+
+/// The stdin and stdout of a child process, e.g. ssh running an agent. At
+/// the end of its output, the last line it wrote to stderr becomes the
+/// error, so a refused login says why. The child is killed with the stream.
+struct ChildStream {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: ChildStdout,
+    stderr: Option<thread::JoinHandle<String>>,
+}
+
+impl ChildStream {
+    fn spawn(mut command: Command) -> io::Result<Self> {
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let pipes = (child.stdin.take(), child.stdout.take(), child.stderr.take());
+        let (Some(stdin), Some(stdout), Some(stderr)) = pipes else {
+            return Err(io::Error::other("child without pipes"));
+        };
+        let stderr = thread::spawn(move || {
+            BufReader::new(stderr)
+                .lines()
+                .map_while(Result::ok)
+                .filter(|line| !line.trim().is_empty())
+                .last()
+                .unwrap_or_default()
+        });
+        Ok(Self {
+            child,
+            stdin,
+            stdout,
+            stderr: Some(stderr),
+        })
+    }
+}
+
+impl Read for ChildStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.stdout.read(buf)?;
+        if n == 0 && !buf.is_empty() {
+            let _ = self.child.wait();
+            let last = self
+                .stderr
+                .take()
+                .and_then(|stderr| stderr.join().ok())
+                .unwrap_or_default();
+            if !last.is_empty() {
+                return Err(io::Error::other(last.trim().to_string()));
+            }
+        }
+        Ok(n)
+    }
+}
+
+impl Write for ChildStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stdin.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stdin.flush()
+    }
+}
+
+impl Drop for ChildStream {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// `ssh` without prompts, which would garble the TUI, and giving up on a
+/// host that stops answering; login must work with a key or agent.
+fn ssh(destination: &str, config: &RemoteConfig) -> Command {
+    let mut command = Command::new("ssh");
+    command
+        .args(["-T", "-o", "BatchMode=yes", "-o"])
+        .arg(format!("ConnectTimeout={}", TIMEOUT.as_secs()))
+        .args(["-o", "ServerAliveInterval=5", "-o", "ServerAliveCountMax=2", "--"])
+        .arg(destination)
+        .arg(config.ssh_command.as_deref().unwrap_or(SSH_COMMAND));
+    command
+}
+
+/// One connection to an agent. Writes go through the `BufReader` to the
+/// stream underneath, since a TLS stream can't be split into halves.
+struct Connection {
+    stream: BufReader<Box<dyn Stream>>,
+}
+
+fn tcp(addr: &str) -> io::Result<TcpStream> {
+    let addr = addr
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no address"))?;
+    let stream = TcpStream::connect_timeout(&addr, TIMEOUT)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    Ok(stream)
+}
+
+impl Connection {
+    fn open(endpoint: &Endpoint, config: &RemoteConfig) -> io::Result<Self> {
+        let stream: Box<dyn Stream> = match endpoint {
+            Endpoint::Tcp(addr) => Box::new(tcp(addr)?),
+            #[cfg(feature = "tls")]
+            Endpoint::Tls(addr) => {
+                let tls = config.tls.clone().unwrap_or_default();
+                Box::new(crate::tls::connect(addr, tcp(addr)?, &tls)?)
+            }
+            #[cfg(not(feature = "tls"))]
+            Endpoint::Tls(_) => return Err(no_tls()),
+            #[cfg(unix)]
+            Endpoint::Unix(path) => {
+                let stream = std::os::unix::net::UnixStream::connect(path)?;
+                stream.set_read_timeout(Some(TIMEOUT))?;
+                Box::new(stream)
+            }
+            #[cfg(not(unix))]
+            Endpoint::Unix(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "Unix sockets are not supported on this platform",
+                ))
+            }
+            Endpoint::Ssh(destination) => Box::new(ChildStream::spawn(ssh(destination, config))?),
+        };
+        let mut conn = Self {
+            stream: BufReader::new(stream),
+        };
+        conn.send(&Request::Hello {
+            token: config.token.clone(),
+        })?;
+        Ok(conn)
+    }
+
+    fn send(&mut self, request: &Request) -> io::Result<()> {
+        let mut buf = serde_json::to_vec(request)?;
+        buf.push(b'\n');
+        let stream = self.stream.get_mut();
+        stream.write_all(&buf)?;
+        stream.flush()
+    }
+
+    fn receive(&mut self) -> io::Result<Response> {
+        let mut line = String::new();
+        if self.stream.read_line(&mut line)? == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "agent closed the connection",
+            ));
+        }
+        match serde_json::from_str(&line)? {
+            Response::Error { message } => Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("agent: {}", message),
+            )),
+            response => Ok(response),
+        }
+    }
+}
+
+/// A [`SystemSource`] backed by a remote agent. It connects on first use and
+/// reconnects after a failure; while the agent is unreachable it repeats the
+/// last snapshot with [`SysCache::offline`] set.
+pub struct RemoteSource {
+    endpoint: Endpoint,
+    config: RemoteConfig,
+    conn: Option<Connection>,
+    tick: Option<Duration>,
+    last: SysCache,
+    /// Why the last action failed, for the next snapshot.
+    notice: Option<String>,
+}
+
+impl RemoteSource {
+    pub fn new(addr: &str) -> Self {
+        Self::with_config(addr, RemoteConfig::default())
+    }
+
+    /// Connects with the token and TLS settings from `remote.toml`.
+    pub fn with_config(addr: &str, config: RemoteConfig) -> Self {
+        Self {
+            endpoint: Endpoint::parse(addr),
+            config,
+            conn: None,
+            tick: None,
+            last: SysCache::default(),
+            notice: None,
+        }
+    }
+
+    fn connection(&mut self) -> io::Result<&mut Connection> {
+        if self.conn.is_none() {
+            let mut conn = Connection::open(&self.endpoint, &self.config)?;
+            if let Some(tick) = self.tick {
+                conn.send(&Request::Tick {
+                    ms: tick.as_millis() as u64,
+                })?;
+            }
+            self.conn = Some(conn);
+        }
+        Ok(self.conn.as_mut().expect("connected above"))
+    }
+
+    fn send(&mut self, request: &Request) -> io::Result<()> {
+        let result = self.connection().and_then(|conn| conn.send(request));
+        if result.is_err() {
+            self.conn = None;
+        }
+        result
+    }
+
+    fn call(&mut self, request: &Request) -> io::Result<Response> {
+        let result = self.connection().and_then(|conn| {
+            conn.send(request)?;
+            conn.receive()
+        });
+        if result.is_err() {
+            self.conn = None;
+        }
+        result
+    }
+
+    fn act(&mut self, request: &Request) {
+        self.notice = match self.call(request) {
+            Ok(Response::Done { error }) => error,
+            Ok(_) => {
+                self.conn = None;
+                Some("unexpected response from agent".into())
+            }
+            Err(e) => Some(e.to_string()),
+        };
+    }
+}
+
+impl SystemSource for RemoteSource {
+    fn collect(&mut self) -> SysCache {
+        match self.call(&Request::Collect) {
+            Ok(Response::Snapshot(mut snapshot)) => {
+                let notice = snapshot.notice.take().or(self.notice.take());
+                self.last = *snapshot;
+                let mut sys = self.last.clone();
+                sys.notice = notice;
+                sys
+            }
+            Ok(_) => {
+                self.conn = None;
+                let mut last = self.last.clone();
+                last.offline = Some("unexpected response from agent".into());
+                last
+            }
+            Err(e) => {
+                let mut last = self.last.clone();
+                last.offline = Some(e.to_string());
+                last
+            }
+        }
+    }
+
+    fn kill_process(&mut self, pid: u32) {
+        self.act(&Request::Kill { pid });
+    }
+
+    fn renice_process(&mut self, pid: u32, delta: i32) {
+        self.act(&Request::Renice { pid, delta });
+    }
+
+    fn limit_process(&mut self, pid: u32, percent: Option<u32>) {
+        self.act(&Request::Limit { pid, percent });
+    }
+
+    fn process_detail(&mut self, pid: u32) -> Option<ProcessDetail> {
+        match self.call(&Request::Detail { pid }) {
+            Ok(Response::Detail { detail }) => detail,
+            _ => None,
+        }
+    }
+
+    fn set_tick(&mut self, tick: Duration) {
+        self.tick = Some(tick);
+        if self.conn.is_some() {
+            let _ = self.send(&Request::Tick {
+                ms: tick.as_millis() as u64,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::MockSource;
+    use crate::sys::ProcessInfo;
+
+    #[test]
+    fn test_endpoint_parse() {
+        assert_eq!(
+            Endpoint::parse("10.0.0.2:7879"),
+            Endpoint::Tcp("10.0.0.2:7879".into())
+        );
+        assert_eq!(
+            Endpoint::parse("unix:agent.sock"),
+            Endpoint::Unix("agent.sock".into())
+        );
+        assert_eq!(
+            Endpoint::parse("/run/sysdash.sock"),
+            Endpoint::Unix("/run/sysdash.sock".into())
+        );
+        assert_eq!(
+            Endpoint::parse("tls://nas:7879"),
+            Endpoint::Tls("nas:7879".into())
+        );
+        assert_eq!(
+            Endpoint::parse("ssh://me@nas"),
+            Endpoint::Ssh("me@nas".into())
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_child_stream() {
+        let mut snapshot = SysCache::default();
+        snapshot.host = "far".into();
+        let line = serde_json::to_string(&Response::Snapshot(Box::new(snapshot))).unwrap();
+        // Reads the hello and the collect request, answers, then fails.
+        let script = format!(
+            "read hello; read collect; printf '%s\\n' '{}'; echo 'Connection closed' >&2",
+            line
+        );
+        let mut command = Command::new("sh");
+        command.args(["-c", &script]);
+        let mut conn = Connection {
+            stream: BufReader::new(Box::new(ChildStream::spawn(command).unwrap())),
+        };
+        conn.send(&Request::Hello { token: None }).unwrap();
+        conn.send(&Request::Collect).unwrap();
+        match conn.receive().unwrap() {
+            Response::Snapshot(sys) => assert_eq!(sys.host, "far"),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(conn.receive().unwrap_err().to_string(), "Connection closed");
+    }
+
+    #[test]
+    fn test_config_load() {
+        let dir = std::env::temp_dir().join(format!("sysdash-remote-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("remote.toml");
+        std::fs::write(
+            &path,
+            "token = \"s3cret\"\nallow = [\"kill\"]\n[tls]\ncert = \"agent.pem\"\nkey = \"agent.key\"\n",
+        )
+        .unwrap();
+        let config = RemoteConfig::load(&path).unwrap();
+        assert_eq!(config.token.as_deref(), Some("s3cret"));
+        assert_eq!(config.allow, [Action::Kill]);
+        let tls = config.tls.unwrap();
+        assert_eq!(tls.cert, Some("agent.pem".into()));
+        assert_eq!(tls.ca, None);
+
+        std::fs::write(&path, "tokn = \"typo\"\n").unwrap();
+        assert!(RemoteConfig::load(&path).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(RemoteConfig::load(&path), Ok(RemoteConfig::default()));
+    }
+
+    #[test]
+    fn test_token_is_checked() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let listener = runtime
+            .block_on(tokio::net::TcpListener::bind("127.0.0.1:0"))
+            .unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        runtime.spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let source = MockSource::new([SysCache::default()]);
+                let config = RemoteConfig {
+                    token: Some("s3cret".into()),
+                    ..RemoteConfig::default()
+                };
+                tokio::spawn(async move { handle(stream, source, &config, "test").await });
+            }
+        });
+
+        let with_token = |token: Option<&str>| RemoteConfig {
+            token: token.map(Into::into),
+            ..RemoteConfig::default()
+        };
+        let sys = RemoteSource::with_config(&addr, with_token(Some("s3cret"))).collect();
+        assert_eq!(sys.offline, None);
+
+        let sys = RemoteSource::with_config(&addr, with_token(Some("guess"))).collect();
+        assert_eq!(sys.offline.as_deref(), Some("agent: invalid token"));
+        let sys = RemoteSource::new(&addr).collect();
+        assert_eq!(sys.offline.as_deref(), Some("agent: authentication required"));
+    }
+
+    #[test]
+    fn test_tls_needs_config() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let config = RemoteConfig {
+            token: Some("s3cret".into()),
+            ..RemoteConfig::default()
+        };
+        let error = runtime
+            .block_on(serve("tls://127.0.0.1:0", RefreshIntervals::default(), config))
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_request_length_is_capped() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let config = RemoteConfig {
+            token: Some("s3cret".into()),
+            ..RemoteConfig::default()
+        };
+        let error = runtime
+            .block_on(async {
+                let (agent, mut client) = tokio::io::duplex(1024);
+                // Never sends a newline.
+                tokio::spawn(async move {
+                    while client.write_all(&[b'a'; 1024]).await.is_ok() {}
+                });
+                handle(agent, MockSource::default(), &config, "test").await
+            })
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        let mut short = &b"{\"type\":\"collect\"}\n"[..];
+        let request = runtime.block_on(next_request(&mut short)).unwrap();
+        assert_eq!(request, Some(Request::Collect));
+        assert_eq!(runtime.block_on(next_request(&mut short)).unwrap(), None);
+    }
+
+    #[test]
+    fn test_open_agent_needs_token() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let serve = |addr, config| {
+            runtime.block_on(async {
+                let serving = serve(addr, RefreshIntervals::default(), config);
+                tokio::time::timeout(Duration::from_millis(200), serving).await
+            })
+        };
+        let refused = serve("0.0.0.0:0", RemoteConfig::default()).unwrap().unwrap_err();
+        assert_eq!(refused.kind(), io::ErrorKind::PermissionDenied);
+        // Still serving when the timeout ends it.
+        assert!(serve("127.0.0.1:0", RemoteConfig::default()).is_err());
+        let with_token = RemoteConfig {
+            token: Some("s3cret".into()),
+            ..RemoteConfig::default()
+        };
+        assert!(serve("0.0.0.0:0", with_token).is_err());
+    }
+
+    #[test]
+    fn test_environ_needs_allow() {
+        struct Detailed;
+        impl SystemSource for Detailed {
+            fn collect(&mut self) -> SysCache {
+                SysCache::default()
+            }
+            fn kill_process(&mut self, _pid: u32) {}
+            fn process_detail(&mut self, pid: u32) -> Option<ProcessDetail> {
+                Some(ProcessDetail {
+                    pid,
+                    name: "job".into(),
+                    exe: "/bin/job".into(),
+                    cwd: "/".into(),
+                    cmdline: vec!["job".into()],
+                    environ: vec!["SECRET=1".into()],
+                })
+            }
+        }
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let audit = std::env::temp_dir().join(format!("sysdash-environ-{}", std::process::id()));
+        let detail = |allow: Vec<Action>| {
+            let listener = runtime
+                .block_on(tokio::net::TcpListener::bind("127.0.0.1:0"))
+                .unwrap();
+            let addr = listener.local_addr().unwrap().to_string();
+            let config = RemoteConfig {
+                allow,
+                audit: Some(audit.clone()),
+                ..RemoteConfig::default()
+            };
+            runtime.spawn(async move {
+                let (stream, _) = listener.accept().await.unwrap();
+                let _ = handle(stream, Detailed, &config, "peer").await;
+            });
+            RemoteSource::new(&addr).process_detail(7).unwrap()
+        };
+
+        let hidden = detail(Vec::new());
+        assert_eq!(hidden.cmdline, ["job"]);
+        assert!(hidden.environ.is_empty());
+        assert_eq!(detail(vec![Action::Environ]).environ, ["SECRET=1"]);
+
+        let log = std::fs::read_to_string(&audit).unwrap();
+        let lines: Vec<&str> = log.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with(" peer environ 7 denied"));
+        assert!(lines[1].ends_with(" peer environ 7 allowed"));
+        std::fs::remove_file(&audit).unwrap();
+    }
+
+    #[test]
+    fn test_client_and_agent_round_trip() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let listener = runtime
+            .block_on(tokio::net::TcpListener::bind("127.0.0.1:0"))
+            .unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+
+        let proc = ProcessInfo {
+            pid: 7,
+            name: "job".into(),
+            user: "u".into(),
+            cmd: "".into(),
+            cpu: 1.5,
+            mem_bytes: 10,
+            parent: None,
+        };
+        let mut snapshot = SysCache::default().with_processes(vec![proc]);
+        snapshot.host = "far".into();
+        let audit = std::env::temp_dir().join(format!("sysdash-audit-{}", std::process::id()));
+        let log = audit.clone();
+        runtime.spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let config = RemoteConfig {
+                allow: vec![Action::Kill],
+                audit: Some(audit.clone()),
+                ..RemoteConfig::default()
+            };
+            let _ = handle(stream, MockSource::new([snapshot]), &config, "peer").await;
+        });
+
+        let mut remote = RemoteSource::new(&addr);
+        remote.set_tick(Duration::from_secs(2));
+        let sys = remote.collect();
+        assert_eq!(sys.offline, None);
+        assert_eq!(sys.host, "far");
+        assert_eq!(sys.processes()[0].name.as_ref(), "job");
+
+        remote.renice_process(7, 5);
+        let sys = remote.collect();
+        assert_eq!(
+            sys.notice.as_deref(),
+            Some("renice is not allowed on this agent")
+        );
+        assert_eq!(sys.processes().len(), 1);
+        remote.kill_process(7);
+        let sys = remote.collect();
+        assert_eq!(sys.notice, None);
+        assert!(sys.processes().is_empty());
+        assert_eq!(remote.process_detail(7), None);
+
+        let audit = std::fs::read_to_string(&log).unwrap();
+        let lines: Vec<&str> = audit.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with(" peer renice 7 +5 denied"));
+        assert!(lines[1].ends_with(" peer kill 7 allowed"));
+        std::fs::remove_file(&log).unwrap();
+
+        drop(runtime);
+        let sys = remote.collect();
+        assert!(sys.offline.is_some());
+        assert_eq!(sys.host, "far");
+    }
+}
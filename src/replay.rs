@@ -0,0 +1,192 @@
+//! Session recording and playback. `--record <path>` appends every snapshot
+//! of the local machine while the dashboard runs; `--replay <path>` opens
+//! the dashboard on the recording instead of the live machine. It plays one
+//! frame per tick, `p` pauses it and Left and Right step through it.
+//!
+//! The file is newline-delimited JSON, one [`Frame`] per line, the same
+//! snapshots `--json` prints with the time they arrived.
+
+use crate::sys::SysCache;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Frame {
+    /// When the snapshot arrived, in milliseconds since the Unix epoch.
+    pub at: i64,
+    pub sys: SysCache,
+}
+
+/// Appends frames to a recording.
+pub struct Recorder {
+    file: File,
+}
+
+impl Recorder {
+    /// Opens `path` for appending, creating it if needed.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self { file })
+    }
+
+    pub fn record(&mut self, sys: &SysCache) -> io::Result<()> {
+        let frame = Frame {
+            at: chrono::Utc::now().timestamp_millis(),
+            sys: sys.clone(),
+        };
+        let mut line = serde_json::to_string(&frame)?;
+        line.push('\n');
+        self.file.write_all(line.as_bytes())
+    }
+}
+
+/// Reads every frame of a recording; a line that doesn't parse is an error
+/// naming it.
+pub fn load(path: &Path) -> io::Result<Vec<Frame>> {
+    let mut frames = Vec::new();
+    for (i, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let frame = serde_json::from_str(&line).map_err(|e| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", i + 1, e))
+        })?;
+        frames.push(frame);
+    }
+    Ok(frames)
+}
+
+/// A recording being played back, and where in it the dashboard is.
+pub struct Replay {
+    frames: Vec<Frame>,
+    position: usize,
+    /// Stands in for the time of the first frame, so the others get
+    /// instants as far apart as they were recorded.
+    start: Instant,
+}
+
+impl Replay {
+    /// `None` for an empty recording.
+    pub fn new(frames: Vec<Frame>) -> Option<Self> {
+        (!frames.is_empty()).then(|| Self {
+            frames,
+            position: 0,
+            start: Instant::now(),
+        })
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn current(&self) -> &Frame {
+        &self.frames[self.position]
+    }
+
+    pub fn at_end(&self) -> bool {
+        self.position + 1 == self.frames.len()
+    }
+
+    /// Moves one frame forward or back; false at either end.
+    pub fn step(&mut self, forward: bool) -> bool {
+        let next = if forward {
+            (self.position + 1 < self.frames.len()).then_some(self.position + 1)
+        } else {
+            self.position.checked_sub(1)
+        };
+        match next {
+            Some(next) => {
+                self.position = next;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The instant standing in for when frame `index` was recorded.
+    pub fn instant(&self, index: usize) -> Instant {
+        let first = self.frames[0].at;
+        let offset = self.frames[index].at.saturating_sub(first).max(0);
+        self.start + Duration::from_millis(offset as u64)
+    }
+
+    /// Indices of the frames recorded within `window` before the current
+    /// one, oldest first, the current one last.
+    pub fn window(&self, window: Duration) -> std::ops::RangeInclusive<usize> {
+        let now = self.instant(self.position);
+        let first = (0..self.position)
+            .find(|&i| now.duration_since(self.instant(i)) <= window)
+            .unwrap_or(self.position);
+        first..=self.position
+    }
+
+    pub fn frame(&self, index: usize) -> &Frame {
+        &self.frames[index]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frames(times: &[i64]) -> Vec<Frame> {
+        times
+            .iter()
+            .map(|&at| {
+                let mut sys = SysCache::default();
+                sys.cpu_global = at as f32;
+                Frame { at, sys }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_step_and_window() {
+        assert!(Replay::new(Vec::new()).is_none());
+
+        let mut replay = Replay::new(frames(&[0, 1000, 2000, 3000, 4000])).unwrap();
+        assert!(!replay.step(false));
+        assert!(replay.step(true));
+        assert_eq!(replay.current().sys.cpu_global, 1000.0);
+        while replay.step(true) {}
+        assert!(replay.at_end());
+        assert_eq!(replay.position(), 4);
+        assert_eq!(
+            replay.instant(4).duration_since(replay.instant(1)),
+            Duration::from_secs(3)
+        );
+        assert_eq!(replay.window(Duration::from_secs(2)), 2..=4);
+        assert_eq!(replay.window(Duration::from_secs(60)), 0..=4);
+    }
+
+    #[test]
+    fn test_record_and_load() {
+        let path = std::env::temp_dir().join(format!("sysdash-replay-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut recorder = Recorder::open(&path).unwrap();
+        for frame in frames(&[1, 2]) {
+            recorder.record(&frame.sys).unwrap();
+        }
+        let loaded = load(&path).unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded[1].sys.cpu_global, 2.0);
+        assert!(loaded[0].at <= loaded[1].at);
+
+        std::fs::write(&path, "{\"at\": 1}\n").unwrap();
+        let err = load(&path).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        assert!(err.to_string().starts_with("line 1: "));
+    }
+}
@@ -0,0 +1,153 @@
+//! Raspberry Pi and other single-board computers. Boards are recognised by
+//! their device-tree model; on a Pi the firmware's throttled register and the
+//! GPU memory split are read too, through sysfs or `vcgencmd`. Throttling
+//! silently slows the board down, so [`crate::alerts`] raises it as an alert.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::Command;
+
+const MODEL: &str = "/proc/device-tree/model";
+const THROTTLED: &str = "/sys/devices/platform/soc/soc:firmware/get_throttled";
+const SOC_TEMP: &str = "/sys/class/thermal/thermal_zone0/temp";
+
+/// Bits of the firmware's throttled register that describe the current state;
+/// the same bits shifted by 16 say the condition occurred since boot.
+pub const UNDER_VOLTAGE: u32 = 1 << 0;
+pub const FREQ_CAPPED: u32 = 1 << 1;
+pub const THROTTLED_NOW: u32 = 1 << 2;
+pub const SOFT_TEMP_LIMIT: u32 = 1 << 3;
+const NOW_MASK: u32 = 0xf;
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SbcInfo {
+    pub model: String,
+    /// The firmware's throttled register, on boards that have one.
+    pub throttled: Option<u32>,
+    /// Memory reserved for the GPU, in bytes.
+    pub gpu_mem: Option<u64>,
+    /// SoC temperature in °C, from the first thermal zone.
+    pub soc_temp: Option<f32>,
+}
+
+impl SbcInfo {
+    /// Whether the board is under-volted, capped or throttled right now.
+    pub fn is_throttled(&self) -> bool {
+        self.throttled.is_some_and(|flags| flags & NOW_MASK != 0)
+    }
+}
+
+/// Names of the conditions set in the current-state bits of `flags`.
+pub fn throttle_reasons(flags: u32) -> Vec<&'static str> {
+    [
+        (UNDER_VOLTAGE, "under-voltage"),
+        (FREQ_CAPPED, "frequency capped"),
+        (THROTTLED_NOW, "throttled"),
+        (SOFT_TEMP_LIMIT, "soft temperature limit"),
+    ]
+    .into_iter()
+    .filter(|(bit, _)| flags & bit != 0)
+    .map(|(_, name)| name)
+    .collect()
+}
+
+/// Reads the board's state on every sensor refresh. The model and GPU split
+/// don't change, so they are read once by [`detect`](Self::detect).
+pub struct SbcReader {
+    info: SbcInfo,
+    vcgencmd: bool,
+}
+
+impl SbcReader {
+    /// `None` unless the machine has a device tree, which PCs don't.
+    pub fn detect() -> Option<Self> {
+        let model = std::fs::read_to_string(MODEL).ok()?;
+        let has_vcgencmd = vcgencmd(&["version"]).is_some();
+        let gpu_mem = if has_vcgencmd {
+            vcgencmd(&["get_mem", "gpu"]).and_then(|out| parse_mem(&out))
+        } else {
+            None
+        };
+        Some(Self {
+            info: SbcInfo {
+                model: model.trim_end_matches('\0').trim().to_string(),
+                gpu_mem,
+                ..SbcInfo::default()
+            },
+            vcgencmd: has_vcgencmd,
+        })
+    }
+
+    pub fn read(&mut self) -> SbcInfo {
+        self.info.throttled = std::fs::read_to_string(THROTTLED)
+            .ok()
+            .and_then(|text| parse_hex(text.trim()))
+            .or_else(|| {
+                self.vcgencmd
+                    .then(|| vcgencmd(&["get_throttled"]))
+                    .flatten()
+                    .and_then(|out| parse_hex(out.trim().strip_prefix("throttled=")?))
+            });
+        self.info.soc_temp = read_millidegrees(Path::new(SOC_TEMP));
+        self.info.clone()
+    }
+}
+
+fn vcgencmd(args: &[&str]) -> Option<String> {
+    let out = Command::new("vcgencmd").args(args).output().ok()?;
+    out.status
+        .success()
+        .then(|| String::from_utf8_lossy(&out.stdout).into_owned())
+}
+
+fn read_millidegrees(path: &Path) -> Option<f32> {
+    let text = std::fs::read_to_string(path).ok()?;
+    Some(text.trim().parse::<f32>().ok()? / 1000.0)
+}
+
+/// `0x50005` or `50005`; the sysfs file has no prefix.
+fn parse_hex(text: &str) -> Option<u32> {
+    u32::from_str_radix(text.trim_start_matches("0x"), 16).ok()
+}
+
+/// `gpu=76M` from `vcgencmd get_mem gpu`.
+fn parse_mem(text: &str) -> Option<u64> {
+    let value = text.trim().strip_prefix("gpu=")?;
+    let (digits, unit) = value.split_at(value.find(|c: char| !c.is_ascii_digit())?);
+    let factor = match unit {
+        "K" => 1 << 10,
+        "M" => 1 << 20,
+        "G" => 1 << 30,
+        _ => return None,
+    };
+    Some(digits.parse::<u64>().ok()? * factor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_throttled_flags() {
+        let flags = parse_hex("0x50005").unwrap();
+        assert_eq!(throttle_reasons(flags), ["under-voltage", "throttled"]);
+        let info = SbcInfo {
+            throttled: Some(flags),
+            ..SbcInfo::default()
+        };
+        assert!(info.is_throttled());
+
+        // Only "has occurred" bits: not throttled any more.
+        let info = SbcInfo {
+            throttled: parse_hex("50000"),
+            ..SbcInfo::default()
+        };
+        assert!(!info.is_throttled());
+    }
+
+    #[test]
+    fn test_parse_mem() {
+        assert_eq!(parse_mem("gpu=76M\n"), Some(76 << 20));
+        assert_eq!(parse_mem("error=1"), None);
+    }
+}
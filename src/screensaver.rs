@@ -0,0 +1,84 @@
+//! The screensaver: after a while without key presses the dashboard dims,
+//! refreshes less often and can hide the process table, which is easier on
+//! OLED panels and on a wall display nobody is reading. Any key wakes it,
+//! and that key does nothing else. Set in the `[screensaver]` table of
+//! `ui.toml`:
+//!
+//! ```toml
+//! [screensaver]
+//! after = 300            # seconds without input; 0 (the default) is off
+//! refresh = 10           # seconds between updates while dimmed
+//! blank_processes = true
+//! ```
+
+use ratatui::buffer::Buffer;
+use ratatui::style::{Color, Modifier};
+use serde::Deserialize;
+use std::time::Duration;
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct Screensaver {
+    pub after: u64,
+    pub refresh: u64,
+    pub blank_processes: bool,
+}
+
+impl Default for Screensaver {
+    fn default() -> Self {
+        Self {
+            after: 0,
+            refresh: 10,
+            blank_processes: false,
+        }
+    }
+}
+
+impl Screensaver {
+    /// How long without input before dimming, if enabled.
+    pub fn after(&self) -> Option<Duration> {
+        (self.after > 0).then(|| Duration::from_secs(self.after))
+    }
+
+    pub fn refresh(&self) -> Duration {
+        Duration::from_secs(self.refresh.max(1))
+    }
+}
+
+/// Darkens a drawn frame: RGB colours at half brightness, named ones grey
+/// on the default background, and the dim attribute for terminals that
+/// honour it.
+pub fn dim(buf: &mut Buffer) {
+    for cell in &mut buf.content {
+        cell.fg = match cell.fg {
+            Color::Reset => Color::Reset,
+            Color::Rgb(r, g, b) => Color::Rgb(r / 2, g / 2, b / 2),
+            _ => Color::DarkGray,
+        };
+        cell.bg = match cell.bg {
+            Color::Rgb(r, g, b) => Color::Rgb(r / 2, g / 2, b / 2),
+            _ => Color::Reset,
+        };
+        cell.modifier.insert(Modifier::DIM);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::layout::Rect;
+    use ratatui::style::Style;
+
+    #[test]
+    fn test_dim() {
+        let mut buf = Buffer::empty(Rect::new(0, 0, 2, 1));
+        buf.set_string(0, 0, "a", Style::default().fg(Color::Rgb(200, 100, 0)));
+        buf.set_string(1, 0, "b", Style::default().fg(Color::Black).bg(Color::Cyan));
+        dim(&mut buf);
+
+        assert_eq!(buf.get(0, 0).fg, Color::Rgb(100, 50, 0));
+        assert_eq!(buf.get(1, 0).fg, Color::DarkGray);
+        assert_eq!(buf.get(1, 0).bg, Color::Reset);
+        assert!(buf.get(0, 0).modifier.contains(Modifier::DIM));
+    }
+}
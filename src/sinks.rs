@@ -0,0 +1,264 @@
+//! Alert sinks: back ends that receive fired/resolved alert events.
+
+use crate::alerts::{AlertEvent, AlertSink};
+use crate::command::shell;
+use std::process::Stdio;
+#[cfg(feature = "webhook")]
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+#[cfg(feature = "notify")]
+use {
+    crate::alerts::{AlertKind, Severity},
+    std::collections::HashMap,
+    std::time::{Duration, Instant},
+};
+
+/// Runs the `on_alert` command through the shell for every event, passing the
+/// details in `SYSDASH_ALERT_*` environment variables and `SYSDASH_HOST`.
+pub struct CommandSink {
+    command: String,
+    host: String,
+}
+
+impl CommandSink {
+    pub fn new(command: String) -> Self {
+        Self {
+            command,
+            host: sysinfo::System::host_name().unwrap_or_default(),
+        }
+    }
+
+    pub fn env(&self, event: &AlertEvent) -> Vec<(&'static str, String)> {
+        vec![
+            ("SYSDASH_ALERT_RULE", event.rule.clone()),
+            ("SYSDASH_ALERT_METRIC", event.metric.clone()),
+            ("SYSDASH_ALERT_VALUE", format!("{:.1}", event.value)),
+            ("SYSDASH_ALERT_THRESHOLD", event.threshold.to_string()),
+            (
+                "SYSDASH_ALERT_SEVERITY",
+                event.severity.as_str().to_string(),
+            ),
+            ("SYSDASH_ALERT_STATE", event.kind.as_str().to_string()),
+            ("SYSDASH_ALERT_TIME", event.at.to_rfc3339()),
+            ("SYSDASH_HOST", self.host.clone()),
+        ]
+    }
+}
+
+impl AlertSink for CommandSink {
+    fn send(&mut self, event: &AlertEvent) {
+        let child = shell(&self.command)
+            .envs(self.env(event))
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        // Reap in the background so a slow hook never blocks the UI thread.
+        if let Ok(mut child) = child {
+            thread::spawn(move || child.wait());
+        }
+    }
+}
+
+/// Shows a desktop notification when an alert fires (cargo feature
+/// "notify"), at most once per `interval` for the same rule so a value
+/// hovering around its threshold doesn't keep popping up.
+#[cfg(feature = "notify")]
+pub struct NotifySink {
+    interval: Duration,
+    last: HashMap<String, Instant>,
+}
+
+#[cfg(feature = "notify")]
+impl NotifySink {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last: HashMap::new(),
+        }
+    }
+
+    /// Whether `event` is shown: only firings, and only the first of a rule
+    /// within `interval`, which is then counted from it.
+    fn allow(&mut self, event: &AlertEvent, now: Instant) -> bool {
+        if event.kind != AlertKind::Fired {
+            return false;
+        }
+        if let Some(last) = self.last.get(&event.rule) {
+            if now.duration_since(*last) < self.interval {
+                return false;
+            }
+        }
+        self.last.insert(event.rule.clone(), now);
+        true
+    }
+}
+
+#[cfg(feature = "notify")]
+impl AlertSink for NotifySink {
+    fn send(&mut self, event: &AlertEvent) {
+        if !self.allow(event, Instant::now()) {
+            return;
+        }
+        let mut notification = notify_rust::Notification::new();
+        notification
+            .appname("sysdash")
+            .summary(&event.rule)
+            .body(&format!(
+                "{} at {:.1}, threshold {}",
+                event.metric, event.value, event.threshold
+            ));
+        #[cfg(all(unix, not(target_os = "macos")))]
+        if event.severity == Severity::Critical {
+            notification.urgency(notify_rust::Urgency::Critical);
+        }
+        // Showing talks to the notification daemon; keep it off the UI thread.
+        thread::spawn(move || notification.show().map(|_| ()));
+    }
+}
+
+/// POSTs every event as JSON to a webhook (cargo feature "webhook"), from
+/// a thread of its own so a slow endpoint never blocks the UI.
+#[cfg(feature = "webhook")]
+pub struct WebhookSink {
+    tx: Sender<String>,
+    host: String,
+}
+
+#[cfg(feature = "webhook")]
+impl WebhookSink {
+    pub fn new(url: String) -> Self {
+        let (tx, rx) = mpsc::channel::<String>();
+        thread::spawn(move || {
+            for body in rx {
+                // A failed delivery is dropped; alerts aren't worth retrying late.
+                let _ = ureq::post(&url)
+                    .content_type("application/json")
+                    .send(body.as_str());
+            }
+        });
+        Self {
+            tx,
+            host: sysinfo::System::host_name().unwrap_or_default(),
+        }
+    }
+}
+
+/// The fields of [`CommandSink::env`] plus a one-line summary as `title`,
+/// `message` and `text`, which is what ntfy, Gotify and Slack-compatible
+/// endpoints display.
+#[cfg_attr(not(feature = "webhook"), allow(dead_code))]
+fn payload(event: &AlertEvent, host: &str) -> serde_json::Value {
+    let message = format!(
+        "{} {} on {}: {} at {:.1}, threshold {}",
+        event.rule,
+        event.kind.as_str(),
+        host,
+        event.metric,
+        event.value,
+        event.threshold
+    );
+    serde_json::json!({
+        "rule": event.rule,
+        "metric": event.metric,
+        "value": event.value,
+        "threshold": event.threshold,
+        "severity": event.severity.as_str(),
+        "state": event.kind.as_str(),
+        "time": event.at.to_rfc3339(),
+        "host": host,
+        "title": format!("sysdash: {}", event.rule),
+        "message": message,
+        "text": message,
+    })
+}
+
+#[cfg(feature = "webhook")]
+impl AlertSink for WebhookSink {
+    fn send(&mut self, event: &AlertEvent) {
+        let _ = self.tx.send(payload(event, &self.host).to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alerts::{AlertKind, Severity};
+    use std::time::{Duration, Instant};
+
+    fn event() -> AlertEvent {
+        AlertEvent {
+            rule: "cpu > 90".into(),
+            metric: "cpu".into(),
+            value: 97.25,
+            threshold: 90.0,
+            severity: Severity::Critical,
+            kind: AlertKind::Fired,
+            at: chrono::Local::now(),
+        }
+    }
+
+    #[test]
+    fn test_command_sink_env() {
+        let sink = CommandSink::new("true".into());
+        let env = sink.env(&event());
+        let get = |k: &str| env.iter().find(|(key, _)| *key == k).unwrap().1.clone();
+        assert_eq!(get("SYSDASH_ALERT_METRIC"), "cpu");
+        assert_eq!(get("SYSDASH_ALERT_VALUE"), "97.2");
+        assert_eq!(get("SYSDASH_ALERT_SEVERITY"), "critical");
+        assert_eq!(get("SYSDASH_ALERT_STATE"), "fired");
+    }
+
+    #[test]
+    fn test_command_sink_runs_hook() {
+        let out = std::env::temp_dir().join(format!("sysdash-hook-{}", std::process::id()));
+        let mut sink = CommandSink::new(format!(
+            "echo \"$SYSDASH_ALERT_RULE/$SYSDASH_ALERT_STATE\" > {}",
+            out.display()
+        ));
+        sink.send(&event());
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut text = String::new();
+        while Instant::now() < deadline {
+            text = std::fs::read_to_string(&out).unwrap_or_default();
+            if !text.is_empty() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+        let _ = std::fs::remove_file(&out);
+        assert_eq!(text.trim(), "cpu > 90/fired");
+    }
+
+    #[test]
+    fn test_webhook_payload() {
+        let value = payload(&event(), "nas");
+        assert_eq!(value["rule"], "cpu > 90");
+        assert_eq!(value["value"], 97.25);
+        assert_eq!(value["severity"], "critical");
+        assert_eq!(value["state"], "fired");
+        assert_eq!(value["host"], "nas");
+        assert_eq!(
+            value["text"],
+            "cpu > 90 fired on nas: cpu at 97.2, threshold 90"
+        );
+    }
+
+    #[cfg(feature = "notify")]
+    #[test]
+    fn test_notify_rate_limit() {
+        let mut sink = NotifySink::new(Duration::from_secs(300));
+        let t0 = Instant::now();
+        let mut resolved = event();
+        resolved.kind = AlertKind::Resolved;
+        let mut other = event();
+        other.rule = "mem > 95".into();
+
+        assert!(sink.allow(&event(), t0));
+        assert!(!sink.allow(&resolved, t0));
+        assert!(!sink.allow(&event(), t0 + Duration::from_secs(60)));
+        assert!(sink.allow(&other, t0 + Duration::from_secs(60)));
+        assert!(sink.allow(&event(), t0 + Duration::from_secs(300)));
+    }
+}
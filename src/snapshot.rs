@@ -0,0 +1,157 @@
+//! `X` saves the frame on screen, for pasting system state into a ticket
+//! or a chat: a plain-text copy, and an ANSI one that keeps the colours
+//! when printed with `cat` or `less -R`. `J` saves the data behind it as
+//! JSON instead, for scripts.
+
+use crate::batch;
+use crate::sys::SysCache;
+use ratatui::buffer::Buffer;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Span;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The frame as text, one line per row without trailing blanks.
+pub fn to_text(buf: &Buffer) -> String {
+    let mut out = String::new();
+    for y in 0..buf.area.height {
+        let mut line = String::new();
+        for (symbol, _) in row(buf, y) {
+            line.push_str(symbol);
+        }
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+/// The frame with SGR escapes wherever the style changes.
+pub fn to_ansi(buf: &Buffer) -> String {
+    let mut out = String::new();
+    for y in 0..buf.area.height {
+        let mut current = Style::default().fg(Color::Reset).bg(Color::Reset);
+        for (symbol, style) in row(buf, y) {
+            if style != current {
+                out.push_str(&sgr(style));
+                current = style;
+            }
+            out.push_str(symbol);
+        }
+        out.push_str("\x1b[0m\n");
+    }
+    out
+}
+
+/// The symbols of row `y` and their styles, leaving out the blank cells
+/// ratatui puts after wide characters.
+fn row(buf: &Buffer, y: u16) -> Vec<(&str, Style)> {
+    let area = buf.area;
+    let mut cells = Vec::with_capacity(area.width as usize);
+    let mut skip = 0;
+    for x in area.left()..area.right() {
+        if skip > 0 {
+            skip -= 1;
+            continue;
+        }
+        let cell = buf.get(x, area.top() + y);
+        skip = Span::raw(cell.symbol()).width().saturating_sub(1);
+        let style = Style::default()
+            .fg(cell.fg)
+            .bg(cell.bg)
+            .add_modifier(cell.modifier);
+        cells.push((cell.symbol(), style));
+    }
+    cells
+}
+
+/// A reset followed by the attributes of `style`.
+fn sgr(style: Style) -> String {
+    let mut codes = vec!["0".to_string()];
+    for (modifier, code) in [
+        (Modifier::BOLD, "1"),
+        (Modifier::DIM, "2"),
+        (Modifier::ITALIC, "3"),
+        (Modifier::UNDERLINED, "4"),
+        (Modifier::REVERSED, "7"),
+        (Modifier::CROSSED_OUT, "9"),
+    ] {
+        if style.add_modifier.contains(modifier) {
+            codes.push(code.into());
+        }
+    }
+    if let Some(code) = style.fg.and_then(|c| color(c, 30)) {
+        codes.push(code);
+    }
+    if let Some(code) = style.bg.and_then(|c| color(c, 40)) {
+        codes.push(code);
+    }
+    format!("\x1b[{}m", codes.join(";"))
+}
+
+/// The SGR code of a colour; `base` is 30 for foreground, 40 for background.
+fn color(color: Color, base: u8) -> Option<String> {
+    let named = |n: u8| Some((base + n).to_string());
+    let bright = |n: u8| Some((base + 60 + n).to_string());
+    match color {
+        Color::Reset => None,
+        Color::Black => named(0),
+        Color::Red => named(1),
+        Color::Green => named(2),
+        Color::Yellow => named(3),
+        Color::Blue => named(4),
+        Color::Magenta => named(5),
+        Color::Cyan => named(6),
+        Color::Gray => named(7),
+        Color::DarkGray => bright(0),
+        Color::LightRed => bright(1),
+        Color::LightGreen => bright(2),
+        Color::LightYellow => bright(3),
+        Color::LightBlue => bright(4),
+        Color::LightMagenta => bright(5),
+        Color::LightCyan => bright(6),
+        Color::White => bright(7),
+        Color::Indexed(i) => Some(format!("{};5;{}", base + 8, i)),
+        Color::Rgb(r, g, b) => Some(format!("{};2;{};{};{}", base + 8, r, g, b)),
+    }
+}
+
+/// Writes `sysdash-<timestamp>.txt` and `.ans` into `dir`, returning the
+/// text file's path.
+pub fn save(buf: &Buffer, dir: &Path) -> io::Result<PathBuf> {
+    let stem = format!("sysdash-{}", chrono::Local::now().format("%Y%m%d-%H%M%S"));
+    let text = dir.join(format!("{}.txt", stem));
+    std::fs::write(&text, to_text(buf))?;
+    std::fs::write(dir.join(format!("{}.ans", stem)), to_ansi(buf))?;
+    Ok(text)
+}
+
+/// Writes `sysdash-<timestamp>.json` into `dir`, in the format of
+/// `--json`, and returns its path.
+pub fn save_json(sys: &SysCache, dir: &Path) -> io::Result<PathBuf> {
+    let path = dir.join(format!(
+        "sysdash-{}.json",
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    ));
+    std::fs::write(&path, batch::to_json(sys)?)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::layout::Rect;
+
+    #[test]
+    fn test_render() {
+        let mut buf = Buffer::empty(Rect::new(0, 0, 8, 2));
+        buf.set_string(0, 0, "CPU 5%", Style::default());
+        buf.set_string(4, 0, "5%", Style::default().fg(Color::Red));
+        buf.set_string(0, 1, "°C 温度", Style::default());
+
+        assert_eq!(to_text(&buf), "CPU 5%\n°C 温度\n");
+        assert_eq!(
+            to_ansi(&buf),
+            "CPU \x1b[0;31m5%\x1b[0m  \x1b[0m\n°C 温度 \x1b[0m\n"
+        );
+    }
+}
@@ -0,0 +1,142 @@
+//! Where snapshots come from. The TUI's collector thread works with any
+//! [`SystemSource`], so tests can drive the app with [`MockSource`] instead
+//! of the host machine.
+
+use crate::sys::{Collector, ProcessDetail, SysCache};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+pub trait SystemSource {
+    /// Produces the next snapshot.
+    fn collect(&mut self) -> SysCache;
+
+    fn kill_process(&mut self, pid: u32);
+
+    /// Changes the nice value of `pid` by `delta`. Failures are reported as
+    /// [`SysCache::notice`] on the next snapshot.
+    fn renice_process(&mut self, _pid: u32, _delta: i32) {}
+
+    /// Caps `pid` at `percent` of one core, or lifts its cap. The outcome is
+    /// the next snapshot's [`SysCache::notice`].
+    fn limit_process(&mut self, _pid: u32, _percent: Option<u32>) {}
+
+    /// On-demand details for one process; `None` if unavailable.
+    fn process_detail(&mut self, _pid: u32) -> Option<ProcessDetail> {
+        None
+    }
+
+    /// Called when the collection interval changes.
+    fn set_tick(&mut self, _tick: Duration) {}
+
+    /// Follows one process closely; see [`Collector::set_focus`].
+    fn set_focus(&mut self, _pid: Option<u32>) {}
+
+    /// Reads the cgroup hierarchy; see [`Collector::set_cgroups`].
+    fn set_cgroups(&mut self, _on: bool) {}
+}
+
+impl SystemSource for Collector {
+    fn collect(&mut self) -> SysCache {
+        Collector::collect(self)
+    }
+
+    fn kill_process(&mut self, pid: u32) {
+        Collector::kill_process(self, pid)
+    }
+
+    fn renice_process(&mut self, pid: u32, delta: i32) {
+        Collector::renice_process(self, pid, delta)
+    }
+
+    fn limit_process(&mut self, pid: u32, percent: Option<u32>) {
+        Collector::limit_process(self, pid, percent)
+    }
+
+    fn process_detail(&mut self, pid: u32) -> Option<ProcessDetail> {
+        Collector::process_detail(self, pid)
+    }
+
+    fn set_tick(&mut self, tick: Duration) {
+        Collector::set_tick(self, tick)
+    }
+
+    fn set_focus(&mut self, pid: Option<u32>) {
+        Collector::set_focus(self, pid)
+    }
+
+    fn set_cgroups(&mut self, on: bool) {
+        Collector::set_cgroups(self, on)
+    }
+}
+
+/// Replays fixed snapshots in order, then repeats the last one. Killed
+/// processes are recorded and left out of every later snapshot.
+#[derive(Default)]
+pub struct MockSource {
+    queue: VecDeque<SysCache>,
+    last: SysCache,
+    pub killed: Vec<u32>,
+}
+
+impl MockSource {
+    pub fn new(snapshots: impl IntoIterator<Item = SysCache>) -> Self {
+        Self {
+            queue: snapshots.into_iter().collect(),
+            ..Self::default()
+        }
+    }
+}
+
+impl SystemSource for MockSource {
+    fn collect(&mut self) -> SysCache {
+        if let Some(next) = self.queue.pop_front() {
+            self.last = next;
+        }
+        let procs = self
+            .last
+            .processes()
+            .iter()
+            .filter(|p| !self.killed.contains(&p.pid))
+            .cloned()
+            .collect();
+        self.last.clone().with_processes(procs)
+    }
+
+    fn kill_process(&mut self, pid: u32) {
+        self.killed.push(pid);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sys::ProcessInfo;
+
+    fn proc(pid: u32) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            name: "p".into(),
+            user: "u".into(),
+            cmd: "".into(),
+            cpu: 0.0,
+            mem_bytes: 0,
+            parent: None,
+        }
+    }
+
+    #[test]
+    fn test_mock_replays_and_kills() {
+        let mut first = SysCache::default().with_processes(vec![proc(1), proc(2)]);
+        first.cpu_global = 10.0;
+        let mut second = first.clone();
+        second.cpu_global = 20.0;
+        let mut source = MockSource::new([first, second]);
+
+        assert_eq!(source.collect().cpu_global, 10.0);
+        source.kill_process(1);
+        let next = source.collect();
+        assert_eq!(next.cpu_global, 20.0);
+        assert_eq!(next.processes().len(), 1);
+        assert_eq!(source.collect().cpu_global, 20.0);
+    }
+}
@@ -0,0 +1,226 @@
+//! Metric history that outlives the dashboard (cargo feature "sqlite").
+//! Every snapshot of the local machine is stored in `history.db` next to
+//! the configuration, and a restarted dashboard draws its charts from the
+//! last hour of samples instead of starting empty. Older samples are
+//! deleted as new ones arrive.
+
+use crate::host::Host;
+use crate::plugin::config_dir;
+use crate::sys::SysCache;
+use rusqlite::{params, Connection};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How far back samples are kept and loaded.
+pub const RETENTION: Duration = Duration::from_secs(60 * 60);
+
+pub fn history_path() -> PathBuf {
+    config_dir().join("history.db")
+}
+
+/// One stored sample: the values the charts and the memory trend need.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Sample {
+    /// Milliseconds since the Unix epoch.
+    pub at: i64,
+    pub cpu: f32,
+    pub mem_used: u64,
+    pub mem_total: u64,
+    /// Pages swapped in and out per second, where the platform counts them.
+    pub swap_rates: Option<(u64, u64)>,
+    pub rx_rate: u64,
+    pub tx_rate: u64,
+}
+
+impl Sample {
+    pub fn new(sys: &SysCache, at: i64) -> Self {
+        Self {
+            at,
+            cpu: sys.cpu_global,
+            mem_used: sys.used_mem,
+            mem_total: sys.total_mem,
+            swap_rates: sys.swap_rates,
+            rx_rate: sys.rx_rate,
+            tx_rate: sys.tx_rate,
+        }
+    }
+
+    /// A snapshot carrying only the sampled values, for
+    /// [`Host::record_history`](crate::host::Host::record_history).
+    pub fn to_sys(&self) -> SysCache {
+        let mut sys = SysCache::default();
+        sys.cpu_global = self.cpu;
+        sys.used_mem = self.mem_used;
+        sys.total_mem = self.mem_total;
+        sys.swap_rates = self.swap_rates;
+        sys.rx_rate = self.rx_rate;
+        sys.tx_rate = self.tx_rate;
+        sys
+    }
+
+    /// The instant standing in for when it was taken, given the time
+    /// `now_ms` of `now`.
+    pub fn instant(&self, now: Instant, now_ms: i64) -> Instant {
+        let age = Duration::from_millis(now_ms.saturating_sub(self.at).max(0) as u64);
+        now.checked_sub(age).unwrap_or(now)
+    }
+}
+
+/// Writes samples from a thread of its own, so a slow disk never holds up
+/// the dashboard.
+pub struct History {
+    tx: Sender<Sample>,
+}
+
+impl History {
+    /// Opens (or creates) the database at `path` and returns it with the
+    /// samples of the last [`RETENTION`], oldest first.
+    pub fn open(path: &Path) -> rusqlite::Result<(Self, Vec<Sample>)> {
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        let conn = Connection::open(path)?;
+        init(&conn)?;
+        let now = chrono::Utc::now().timestamp_millis();
+        let samples = load(&conn, now - RETENTION.as_millis() as i64)?;
+
+        let (tx, rx) = mpsc::channel::<Sample>();
+        thread::spawn(move || {
+            for sample in rx {
+                // A failed write loses one sample; the next may well succeed.
+                let _ = insert(&conn, &sample);
+            }
+        });
+        Ok((Self { tx }, samples))
+    }
+
+    /// Queues the sample of `sys`, taken now.
+    pub fn record(&self, sys: &SysCache) {
+        let now = chrono::Utc::now().timestamp_millis();
+        let _ = self.tx.send(Sample::new(sys, now));
+    }
+}
+
+/// Draws `samples` into the charts of `host`, as long ago as they were taken.
+pub fn seed(host: &mut Host, samples: &[Sample]) {
+    let (now, now_ms) = (Instant::now(), chrono::Utc::now().timestamp_millis());
+    for sample in samples {
+        host.record_history(&sample.to_sys(), sample.instant(now, now_ms));
+    }
+}
+
+fn init(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "PRAGMA journal_mode = WAL;
+         CREATE TABLE IF NOT EXISTS samples (
+             at INTEGER NOT NULL,
+             cpu REAL NOT NULL,
+             mem_used INTEGER NOT NULL,
+             mem_total INTEGER NOT NULL,
+             swap_in INTEGER,
+             swap_out INTEGER,
+             rx_rate INTEGER NOT NULL,
+             tx_rate INTEGER NOT NULL
+         );
+         CREATE INDEX IF NOT EXISTS samples_at ON samples (at);",
+    )
+}
+
+/// Stores `sample` and drops those older than [`RETENTION`] before it.
+fn insert(conn: &Connection, sample: &Sample) -> rusqlite::Result<()> {
+    let (swap_in, swap_out) = sample.swap_rates.unzip();
+    conn.execute(
+        "INSERT INTO samples (at, cpu, mem_used, mem_total, swap_in, swap_out, rx_rate, tx_rate)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            sample.at,
+            sample.cpu,
+            sample.mem_used as i64,
+            sample.mem_total as i64,
+            swap_in.map(|n| n as i64),
+            swap_out.map(|n| n as i64),
+            sample.rx_rate as i64,
+            sample.tx_rate as i64,
+        ],
+    )?;
+    conn.execute(
+        "DELETE FROM samples WHERE at < ?1",
+        params![sample.at - RETENTION.as_millis() as i64],
+    )?;
+    Ok(())
+}
+
+fn load(conn: &Connection, since: i64) -> rusqlite::Result<Vec<Sample>> {
+    let mut stmt = conn.prepare(
+        "SELECT at, cpu, mem_used, mem_total, swap_in, swap_out, rx_rate, tx_rate
+         FROM samples WHERE at >= ?1 ORDER BY at",
+    )?;
+    let rows = stmt.query_map(params![since], |row| {
+        let swap_in: Option<i64> = row.get(4)?;
+        let swap_out: Option<i64> = row.get(5)?;
+        Ok(Sample {
+            at: row.get(0)?,
+            cpu: row.get(1)?,
+            mem_used: row.get::<_, i64>(2)? as u64,
+            mem_total: row.get::<_, i64>(3)? as u64,
+            swap_rates: swap_in.zip(swap_out).map(|(i, o)| (i as u64, o as u64)),
+            rx_rate: row.get::<_, i64>(6)? as u64,
+            tx_rate: row.get::<_, i64>(7)? as u64,
+        })
+    })?;
+    rows.collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_and_load() {
+        let conn = Connection::open_in_memory().unwrap();
+        init(&conn).unwrap();
+        let hour = RETENTION.as_millis() as i64;
+        let sample = |at, cpu| Sample {
+            at,
+            cpu,
+            mem_used: 4 << 30,
+            mem_total: 16 << 30,
+            swap_rates: Some((3, 4)),
+            rx_rate: 100,
+            tx_rate: 50,
+        };
+        insert(&conn, &sample(1_000, 5.0)).unwrap();
+        insert(&conn, &sample(2_000, 10.0)).unwrap();
+        insert(
+            &conn,
+            &Sample {
+                swap_rates: None,
+                ..sample(2_000 + hour, 20.0)
+            },
+        )
+        .unwrap();
+
+        // The first sample is more than an hour older than the last.
+        let samples = load(&conn, 0).unwrap();
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[0], sample(2_000, 10.0));
+        assert_eq!(samples[1].swap_rates, None);
+        assert_eq!(load(&conn, 3_000).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_instant() {
+        let now = Instant::now();
+        let sample = Sample {
+            at: 10_000,
+            ..Sample::default()
+        };
+        assert_eq!(
+            now.duration_since(sample.instant(now, 70_000)),
+            Duration::from_secs(60)
+        );
+        assert_eq!(sample.to_sys().cpu_global, 0.0);
+    }
+}
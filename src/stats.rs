@@ -0,0 +1,176 @@
+//! Session statistics: min, average, max and 95th percentile of the main
+//! metrics since sysdash started, for reading off the peaks after a load
+//! test. Percentiles come from a log-scale histogram, so memory stays
+//! bounded however long the session runs.
+
+use crate::sys::metrics::registry;
+use crate::sys::SysCache;
+use std::collections::BTreeMap;
+use std::time::Instant;
+
+/// Each histogram bucket spans 2% of its value, which bounds the
+/// percentile error.
+const BUCKET_GROWTH: f64 = 1.02;
+
+/// Running summary of one metric.
+#[derive(Clone, Debug, Default)]
+pub struct Stat {
+    min: f64,
+    max: f64,
+    sum: f64,
+    count: u64,
+    buckets: BTreeMap<u32, u64>,
+}
+
+impl Stat {
+    pub fn record(&mut self, value: f64) {
+        let value = value.max(0.0);
+        if self.count == 0 {
+            self.min = value;
+            self.max = value;
+        } else {
+            self.min = self.min.min(value);
+            self.max = self.max.max(value);
+        }
+        self.sum += value;
+        self.count += 1;
+        *self.buckets.entry(bucket(value)).or_default() += 1;
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn min(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.min)
+    }
+
+    pub fn max(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.max)
+    }
+
+    pub fn avg(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.sum / self.count as f64)
+    }
+
+    /// The value below which `p` percent of the samples fall, to within a
+    /// bucket's width.
+    pub fn percentile(&self, p: f64) -> Option<f64> {
+        if self.count == 0 {
+            return None;
+        }
+        let rank = ((p / 100.0) * self.count as f64).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (&index, &n) in &self.buckets {
+            seen += n;
+            if seen >= rank {
+                return Some(upper_bound(index).clamp(self.min, self.max));
+            }
+        }
+        Some(self.max)
+    }
+}
+
+fn bucket(value: f64) -> u32 {
+    (value + 1.0).log(BUCKET_GROWTH).floor() as u32
+}
+
+fn upper_bound(index: u32) -> f64 {
+    BUCKET_GROWTH.powi(index as i32 + 1) - 1.0
+}
+
+/// The series the stats popup summarises: a title, then a metric of the
+/// [registry](crate::sys::metrics) and the label of one of its series.
+pub const TRACKED: [(&str, &str, Option<&str>); 7] = [
+    ("CPU", "cpu.utilization", None),
+    ("Memory", "memory.utilization", None),
+    ("Temperature", "cpu.temperature", None),
+    ("Net rx", "network.io.rate", Some("receive")),
+    ("Net tx", "network.io.rate", Some("transmit")),
+    ("Swap in", "swap.io.rate", Some("in")),
+    ("Swap out", "swap.io.rate", Some("out")),
+];
+
+/// The [`TRACKED`] series of one host. One the host doesn't report, like
+/// the temperature without a sensor, records nothing.
+#[derive(Clone, Debug)]
+pub struct SessionStats {
+    pub since: chrono::DateTime<chrono::Local>,
+    started: Instant,
+    /// In the order of [`TRACKED`].
+    pub stats: [Stat; TRACKED.len()],
+    /// Snapshots recorded.
+    pub samples: u64,
+}
+
+impl Default for SessionStats {
+    fn default() -> Self {
+        Self {
+            since: chrono::Local::now(),
+            started: Instant::now(),
+            stats: Default::default(),
+            samples: 0,
+        }
+    }
+}
+
+impl SessionStats {
+    pub fn record(&mut self, sys: &SysCache) {
+        let registry = registry();
+        for ((_, name, label), stat) in TRACKED.iter().zip(&mut self.stats) {
+            if let Some(value) = registry.value(name, *label, sys) {
+                stat.record(value);
+            }
+        }
+        self.samples += 1;
+    }
+
+    /// Seconds since tracking started.
+    pub fn elapsed(&self) -> u64 {
+        self.started.elapsed().as_secs()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stat() {
+        let mut stat = Stat::default();
+        assert_eq!(stat.avg(), None);
+        for v in 1..=100 {
+            stat.record(v as f64);
+        }
+        assert_eq!(stat.min(), Some(1.0));
+        assert_eq!(stat.max(), Some(100.0));
+        assert_eq!(stat.avg(), Some(50.5));
+        let p95 = stat.percentile(95.0).unwrap();
+        assert!((95.0..=97.0).contains(&p95), "p95 = {}", p95);
+
+        // Rates span orders of magnitude; the error stays relative.
+        let mut rates = Stat::default();
+        for _ in 0..99 {
+            rates.record(1_000.0);
+        }
+        rates.record(50_000_000.0);
+        let p95 = rates.percentile(95.0).unwrap();
+        assert!((1_000.0..=1_020.0).contains(&p95), "p95 = {}", p95);
+        assert_eq!(rates.percentile(100.0), Some(50_000_000.0));
+    }
+
+    #[test]
+    fn test_session_stats() {
+        let mut sys = SysCache::default();
+        let mut stats = SessionStats::default();
+        for cpu in [10.0, 30.0] {
+            sys.cpu_global = cpu;
+            stats.record(&sys);
+        }
+        assert_eq!(stats.samples, 2);
+        assert_eq!(stats.stats[0].avg(), Some(20.0));
+        // No sensor and no swap counters.
+        assert_eq!(stats.stats[2].count(), 0);
+        assert_eq!(stats.stats[5].count(), 0);
+    }
+}
@@ -0,0 +1,114 @@
+//! `--statusline`: one compact line for tmux's status-right, i3status or a
+//! shell prompt, printed once from a fresh [`Collector`] without the TUI.
+//!
+//! ```text
+//! set -g status-right '#(sysdash --statusline=tmux)'
+//! ```
+
+use crate::sys::{format_bytes, Collector, SysCache};
+use std::time::{Duration, Instant};
+
+/// How values above the warning level are highlighted.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Colors {
+    #[default]
+    Plain,
+    /// `#[fg=red]` style codes for tmux.
+    Tmux,
+    /// ANSI escapes, for prompts and i3status' `output_format = "term"`.
+    Ansi,
+}
+
+impl Colors {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "plain" => Some(Colors::Plain),
+            "tmux" => Some(Colors::Tmux),
+            "ansi" => Some(Colors::Ansi),
+            _ => None,
+        }
+    }
+
+    fn high(self, text: String) -> String {
+        match self {
+            Colors::Plain => text,
+            Colors::Tmux => format!("#[fg=red]{}#[default]", text),
+            Colors::Ansi => format!("\x1b[31m{}\x1b[0m", text),
+        }
+    }
+}
+
+/// Percentages and temperatures above this are highlighted, like the
+/// dashboard's gauges.
+const HIGH: f32 = 80.0;
+
+/// The line for one snapshot. `sys.rx_rate` and `tx_rate` must already be
+/// per second; `load` is the 1-minute load average, left out when zero
+/// (Windows has none).
+pub fn render(sys: &SysCache, load: f64, colors: Colors) -> String {
+    let level = |text: String, value: f32| {
+        if value > HIGH {
+            colors.high(text)
+        } else {
+            text
+        }
+    };
+    let mut parts = vec![level(format!("CPU {:.0}%", sys.cpu_global), sys.cpu_global)];
+    if sys.total_mem > 0 {
+        let mem = sys.used_mem as f32 / sys.total_mem as f32 * 100.0;
+        parts.push(level(format!("MEM {:.0}%", mem), mem));
+    }
+    parts.push(format!(
+        "NET {}/{}",
+        format_bytes(sys.rx_rate),
+        format_bytes(sys.tx_rate)
+    ));
+    if load > 0.0 {
+        parts.push(format!("LOAD {:.2}", load));
+    }
+    if sys.cpu_temp > 0.0 {
+        parts.push(level(format!("{:.0}°C", sys.cpu_temp), sys.cpu_temp));
+    }
+    parts.join(" ")
+}
+
+/// Samples twice, `window` apart, since CPU usage and network rates are
+/// deltas, and prints the line.
+pub fn run(colors: Colors, window: Duration) {
+    let mut collector = Collector::new();
+    collector.collect();
+    let started = Instant::now();
+    std::thread::sleep(window);
+    let mut sys = collector.collect();
+    let secs = started.elapsed().as_secs_f64().max(0.001);
+    sys.rx_rate = (sys.rx_rate as f64 / secs) as u64;
+    sys.tx_rate = (sys.tx_rate as f64 / secs) as u64;
+    println!(
+        "{}",
+        render(&sys, sysinfo::System::load_average().one, colors)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let mut sys = SysCache::default();
+        sys.cpu_global = 12.4;
+        sys.total_mem = 1000;
+        sys.used_mem = 900;
+        sys.rx_rate = 2048;
+        assert_eq!(
+            render(&sys, 0.0, Colors::Plain),
+            "CPU 12% MEM 90% NET 2.0K/0B"
+        );
+
+        sys.cpu_temp = 48.0;
+        assert_eq!(
+            render(&sys, 0.5, Colors::Tmux),
+            "CPU 12% #[fg=red]MEM 90%#[default] NET 2.0K/0B LOAD 0.50 48°C"
+        );
+    }
+}
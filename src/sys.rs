@@ -1,60 +1,540 @@
+use self::gpu::{GpuInfo, Gpus};
+use crate::cgroups::CgroupInfo;
+use crate::sbc::SbcInfo;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use sysinfo::{
-    Components, CpuRefreshKind, Disks, MemoryRefreshKind, Networks, Pid, ProcessRefreshKind,
-    RefreshKind, System, Users,
+    Components, CpuRefreshKind, Disks, Networks, Pid, ProcessRefreshKind, RefreshKind, System, Uid,
+    UpdateKind, Users,
 };
 
-#[derive(Clone, Debug)]
+pub mod bsd;
+pub mod freebsd;
+pub mod gpu;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod hwmon;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod linux;
+pub mod macos;
+pub mod metrics;
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "netbsd"
+))]
+mod unix;
+#[cfg(windows)]
+mod windows;
+
+#[cfg(any(target_os = "openbsd", target_os = "netbsd"))]
+use self::bsd::Bsd as Native;
+#[cfg(target_os = "freebsd")]
+use self::freebsd::FreeBsd as Native;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use self::linux::Linux as Native;
+#[cfg(target_os = "macos")]
+use self::macos::MacOs as Native;
+#[cfg(windows)]
+use self::windows::Windows as Native;
+
+/// What a [`Collector`] reads beyond sysinfo, which differs per operating
+/// system. Each platform lives in its own module under `sys/`; a new one
+/// implements what it can and leaves the rest to the defaults.
+pub trait Platform: Send {
+    /// CPU, memory, uptime and network where sysinfo has no backend.
+    /// Called on every collection.
+    fn basics(&mut self) -> Option<Basics> {
+        None
+    }
+
+    /// The process list, busiest first, where sysinfo has no backend.
+    fn processes(&mut self) -> Option<Vec<ProcessInfo>> {
+        None
+    }
+
+    /// The battery with its health details, on the sensor schedule.
+    fn battery(&mut self) -> Option<BatteryInfo> {
+        None
+    }
+
+    /// A CPU temperature to use instead of sysinfo's sensors.
+    fn cpu_temperature(&mut self) -> Option<f32> {
+        None
+    }
+
+    /// Board details on single-board computers.
+    fn board(&mut self) -> Option<SbcInfo> {
+        None
+    }
+
+    /// Fan speeds, on the sensor schedule.
+    fn fans(&mut self) -> Vec<FanInfo> {
+        Vec::new()
+    }
+
+    /// The temperature of each of `cpus` logical CPUs, on the sensor
+    /// schedule; empty where cores have no sensors of their own.
+    fn core_temperatures(&mut self, _cpus: usize) -> Vec<Option<f32>> {
+        Vec::new()
+    }
+
+    /// Drive temperatures by device name without `/dev/`, e.g. `sda`, on
+    /// the sensor schedule.
+    fn drive_temperatures(&mut self) -> HashMap<String, f32> {
+        HashMap::new()
+    }
+
+    /// Whether this is a restricted environment such as Android or Termux,
+    /// where only sysdash's own user's processes are visible and the
+    /// system-wide CPU counters and sensors can't be read.
+    fn restricted(&self) -> bool {
+        false
+    }
+
+    /// Fills in the CPU usage of the visible processes where sysinfo can't
+    /// compute it.
+    fn process_cpu(&mut self, _procs: &mut [ProcessInfo]) {}
+
+    /// Number of open file descriptors of `pid`.
+    fn open_files(&self, _pid: u32) -> Option<usize> {
+        None
+    }
+
+    /// Changes the nice value of `pid` by `delta` and returns the new one.
+    fn renice(&self, _pid: u32, _delta: i32) -> std::io::Result<i32> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "not supported on this platform",
+        ))
+    }
+
+    /// Network interfaces and their link state, on every network refresh.
+    fn interfaces(&mut self) -> Option<Vec<Interface>> {
+        None
+    }
+
+    /// Pages swapped in and out since boot, on every collection.
+    fn swap_pages(&mut self) -> Option<(u64, u64)> {
+        None
+    }
+
+    /// Bytes read from and written to the disks since boot, on every
+    /// collection.
+    fn disk_bytes(&mut self) -> Option<(u64, u64)> {
+        None
+    }
+
+    /// Power drawn by the CPU packages since the previous call, in watts,
+    /// on every collection.
+    fn cpu_power(&mut self) -> Option<f32> {
+        None
+    }
+
+    /// The cgroup hierarchy with the usage of each group, on the process
+    /// schedule while the cgroup view is open.
+    fn cgroups(&mut self) -> Option<Vec<CgroupInfo>> {
+        None
+    }
+
+    /// Caps `pid` at `percent` of one core, or lifts its cap.
+    fn limit_cpu(&mut self, _pid: u32, _percent: Option<u32>) -> std::io::Result<()> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "not supported on this platform",
+        ))
+    }
+}
+
+/// Anything sysinfo supports but sysdash has no module for.
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "netbsd",
+    windows
+)))]
+#[derive(Default)]
+struct Native;
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "netbsd",
+    windows
+)))]
+impl Platform for Native {}
+
+/// The platform sysdash is running on.
+pub fn platform() -> Box<dyn Platform> {
+    Box::<Native>::default()
+}
+
+/// The metrics a [`Platform`] reads itself when sysinfo can't.
+#[derive(Clone, Debug, Default)]
+pub struct Basics {
+    pub cpu_cores: Vec<f32>,
+    pub cpu_global: f32,
+    pub total_mem: u64,
+    pub used_mem: u64,
+    pub uptime: u64,
+    /// Bytes since the previous call, like sysinfo's network counters.
+    pub rx: u64,
+    pub tx: u64,
+}
+
+/// One process. The strings are shared, so snapshots are cheap to clone and
+/// unchanged processes keep their allocations between refreshes.
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ProcessInfo {
     pub pid: u32,
-    pub name: String,
-    pub user: String,
-    pub cmd: String,
+    pub name: Arc<str>,
+    pub user: Arc<str>,
+    pub cmd: Arc<str>,
     pub cpu: f32,
     pub mem_bytes: u64,
+    pub parent: Option<u32>,
+}
+
+/// What `--pid` and `--name` show beyond [`ProcessInfo`]; collected every
+/// tick for the one process in focus.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct FocusInfo {
+    pub pid: u32,
+    /// Linux only.
+    pub threads: Option<usize>,
+    /// Totals since the process started.
+    pub read_bytes: u64,
+    pub written_bytes: u64,
+    pub open_files: Option<usize>,
 }
 
-#[derive(Clone, Debug)]
+/// Expensive per-process data, fetched on demand for the detail popup.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProcessDetail {
+    pub pid: u32,
+    pub name: String,
+    pub exe: String,
+    pub cwd: String,
+    pub cmdline: Vec<String>,
+    pub environ: Vec<String>,
+}
+
+/// A mounted filesystem; sizes in bytes.
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct DiskInfo {
     pub _name: String,
     pub mount_point: String,
     pub total: u64,
     pub available: u64,
+    /// °C of the drive it is on, where the platform reads drive sensors.
+    #[serde(default)]
+    pub temperature: Option<f32>,
+}
+
+/// A network interface and whether its link is up.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Interface {
+    pub name: String,
+    pub up: bool,
+}
+
+/// The laptop battery, where the platform reports one.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct BatteryInfo {
+    pub percent: f32,
+    pub charging: bool,
+    pub cycle_count: Option<u32>,
+    /// Full-charge capacity as a percentage of the design capacity.
+    pub health: Option<f32>,
+    /// °C.
+    pub temperature: Option<f32>,
+    /// Watts going into the battery while charging, out of it otherwise.
+    #[serde(default)]
+    pub power: Option<f32>,
+    /// Seconds until empty, or until full while charging.
+    #[serde(default)]
+    pub time_left: Option<u64>,
+}
+
+/// A fan as reported by the hardware monitoring chip driving it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FanInfo {
+    pub label: String,
+    /// 0 for a stopped fan.
+    pub rpm: u32,
+}
+
+/// Time spent refreshing each subsystem, as last measured. Subsystems that
+/// were not due keep the duration of their most recent refresh.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct CollectTimings {
+    pub cpu_memory: Duration,
+    pub network: Duration,
+    pub processes: Duration,
+    pub disks: Duration,
+    pub sensors: Duration,
+    /// The whole collection, including building the snapshot.
+    pub total: Duration,
 }
 
+/// One refresh worth of system data. Plain values, so it can be built on a
+/// worker thread and handed to the UI.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct SysCache {
-    sys: System,
-    users: Users,
-    networks: Networks,
-    disks: Disks,
-    components: Components,
     pub _cpu_model: String,
     pub cpu_cores: Vec<f32>,
     pub cpu_global: f32,
     pub cpu_temp: f32,
+    /// °C by logical CPU, where each core has a sensor.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub core_temps: Vec<Option<f32>>,
     pub total_mem: u64,
     pub used_mem: u64,
+    #[serde(default)]
+    pub total_swap: u64,
+    #[serde(default)]
+    pub used_swap: u64,
+    /// Pages swapped in and out per second, where the platform counts them.
+    /// Heavy swapping slows everything down while `used_swap` stays flat.
+    #[serde(default)]
+    pub swap_rates: Option<(u64, u64)>,
+    /// Bytes read from and written to all disks per second, where the
+    /// platform counts them.
+    #[serde(default)]
+    pub disk_rates: Option<(u64, u64)>,
+    /// Watts drawn by the CPU packages, where the platform measures them.
+    #[serde(default)]
+    pub cpu_power: Option<f32>,
     pub uptime: u64,
     pub rx_rate: u64,
     pub tx_rate: u64,
+    /// How long the collector spent on each subsystem.
+    pub timings: CollectTimings,
+    pub host: String,
+    #[serde(default)]
+    pub battery: Option<BatteryInfo>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fans: Vec<FanInfo>,
+    /// Board details on a Raspberry Pi or other single-board computer.
+    #[serde(default)]
+    pub sbc: Option<SbcInfo>,
+    /// Why this snapshot could not be refreshed, e.g. a lost connection to a
+    /// remote agent; the other values are then the last known ones.
+    #[serde(skip)]
+    pub offline: Option<String>,
+    /// A one-off message for the user, e.g. why a renice failed or that a
+    /// remote agent refused an action.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notice: Option<String>,
+    /// CPU caps by PID, in percent of one core.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub limits: BTreeMap<u32, u32>,
+    /// Collected in a restricted environment such as Termux: the process
+    /// list holds only the user's own processes, `cpu_global` is their
+    /// share of the machine and `cpu_cores` is empty.
+    #[serde(default)]
+    pub restricted: bool,
+    /// The process set with [`Collector::set_focus`], while it runs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub focus: Option<FocusInfo>,
+    /// Network interfaces by name, where the platform lists them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub interfaces: Vec<Interface>,
+    /// Graphics cards, where a GPU library is built in and finds any.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub gpus: Vec<GpuInfo>,
+    /// Set with [`Collector::set_cgroups`], where the platform has cgroup
+    /// v2.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cgroups: Vec<CgroupInfo>,
 
     procs: Vec<ProcessInfo>,
+    disks: Vec<DiskInfo>,
 }
 
 impl SysCache {
+    /// Collects a single snapshot; use a [`Collector`] to refresh repeatedly.
     pub fn new() -> Self {
-        let refresh = RefreshKind::new()
-            .with_cpu(CpuRefreshKind::everything())
-            .with_memory(MemoryRefreshKind::everything())
-            .with_processes(ProcessRefreshKind::everything());
+        Collector::new().collect()
+    }
 
-        let mut sys = System::new_with_specifics(refresh);
+    pub fn processes(&self) -> &[ProcessInfo] {
+        &self.procs
+    }
+
+    /// Replaces the process list, for synthetic snapshots.
+    pub fn with_processes(mut self, procs: Vec<ProcessInfo>) -> Self {
+        self.procs = procs;
+        self
+    }
+
+    /// Replaces the disk list, for synthetic snapshots.
+    pub fn with_disks(mut self, disks: Vec<DiskInfo>) -> Self {
+        self.disks = disks;
+        self
+    }
 
-        let users = Users::new_with_refreshed_list();
-        let networks = Networks::new_with_refreshed_list();
-        let disks = Disks::new_with_refreshed_list();
-        let components = Components::new_with_refreshed_list();
+    pub fn disks(&self) -> &[DiskInfo] {
+        &self.disks
+    }
+
+    pub fn battery_percentage(&self) -> Option<f32> {
+        self.battery.as_ref().map(|b| b.percent)
+    }
+}
+
+/// Seconds between refreshes of the slower subsystems, from
+/// `~/.config/sysdash/refresh.toml`. CPU, memory and network follow the tick.
+/// While the terminal is unfocused or has seen no input for `idle_after`
+/// seconds, the tick slows to `idle` seconds (0 keeps it unchanged).
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct RefreshIntervals {
+    pub processes: u64,
+    pub disks: u64,
+    pub sensors: u64,
+    pub idle: u64,
+    pub idle_after: u64,
+}
 
-        sys.refresh_all();
+impl Default for RefreshIntervals {
+    fn default() -> Self {
+        Self {
+            processes: 2,
+            disks: 10,
+            sensors: 10,
+            idle: 5,
+            idle_after: 120,
+        }
+    }
+}
+
+pub fn refresh_path() -> PathBuf {
+    crate::plugin::config_dir().join("refresh.toml")
+}
+
+impl RefreshIntervals {
+    /// Reads `path`; a missing file gives the defaults.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => toml::from_str(&text).map_err(|e| format!("refresh.toml: {}", e)),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    /// The slowed-down tick, if idle slowdown is enabled.
+    pub fn idle_rate(&self) -> Option<Duration> {
+        (self.idle > 0).then(|| Duration::from_secs(self.idle))
+    }
+}
+
+/// Refresh every `n`th collection, with `n` derived from seconds and the tick.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Every {
+    processes: u64,
+    disks: u64,
+    sensors: u64,
+}
+
+impl Every {
+    const ALWAYS: Every = Every {
+        processes: 1,
+        disks: 1,
+        sensors: 1,
+    };
+
+    fn new(intervals: RefreshIntervals, tick: Duration) -> Self {
+        let ticks = |secs: u64| {
+            let tick = tick.as_secs_f64().max(0.001);
+            ((secs as f64 / tick).round() as u64).max(1)
+        };
+        Self {
+            processes: ticks(intervals.processes),
+            disks: ticks(intervals.disks),
+            sensors: ticks(intervals.sensors),
+        }
+    }
+}
+
+/// Owns the sysinfo handles and turns each refresh into a [`SysCache`].
+pub struct Collector {
+    sys: System,
+    users: Users,
+    networks: Networks,
+    disks: Disks,
+    components: Components,
+    cpu_model: String,
+    host: String,
+    intervals: RefreshIntervals,
+    every: Every,
+    ticks: u64,
+    table: ProcessTable,
+    procs: Vec<ProcessInfo>,
+    disk_info: Vec<DiskInfo>,
+    interfaces: Vec<Interface>,
+    cpu_temp: f32,
+    battery: Option<BatteryInfo>,
+    fans: Vec<FanInfo>,
+    core_temps: Vec<Option<f32>>,
+    drive_temps: HashMap<String, f32>,
+    sbc: Option<SbcInfo>,
+    gpus: Gpus,
+    gpu_info: Vec<GpuInfo>,
+    platform: Box<dyn Platform>,
+    basics: Option<Basics>,
+    focus: Option<u32>,
+    /// Read the cgroup hierarchy along with the processes.
+    cgroups: bool,
+    cgroup_info: Vec<CgroupInfo>,
+    timings: CollectTimings,
+    /// Skip everything but CPU and memory on the next collection.
+    defer: bool,
+    /// Passed on with the next snapshot.
+    notice: Option<String>,
+    /// CPU caps applied with [`Collector::limit_process`], in percent of
+    /// one core.
+    limits: BTreeMap<u32, u32>,
+    /// The platform's swap counters at the previous collection.
+    swap_pages: Option<((u64, u64), Instant)>,
+    swap_rates: Option<(u64, u64)>,
+    /// The platform's disk counters at the previous collection.
+    disk_bytes: Option<((u64, u64), Instant)>,
+    disk_rates: Option<(u64, u64)>,
+    cpu_power: Option<f32>,
+}
+
+/// What the regular refresh fetches per process: usage every time, the exe
+/// path and owner once, and I/O counters while a process is in focus.
+/// Command lines and environments are left to [`Collector::process_detail`].
+fn process_kind(focus: bool) -> ProcessRefreshKind {
+    let kind = ProcessRefreshKind::new()
+        .with_cpu()
+        .with_memory()
+        .with_user(UpdateKind::OnlyIfNotSet)
+        .with_exe(UpdateKind::OnlyIfNotSet);
+    if focus {
+        kind.with_disk_usage()
+    } else {
+        kind
+    }
+}
+
+impl Collector {
+    /// Only the CPU list is read up front; everything else is enumerated by
+    /// the first [`collect`](Self::collect), so nothing is refreshed twice.
+    pub fn new() -> Self {
+        let sys = System::new_with_specifics(RefreshKind::new().with_cpu(CpuRefreshKind::new()));
 
         let cpu_model = sys
             .cpus()
@@ -62,82 +542,286 @@ impl SysCache {
             .map(|c| c.brand().to_string())
             .unwrap_or_default();
 
-        let mut temp_sum = 0.0;
-        let mut temp_count = 0;
-        for component in &components {
-            let label = component.label().to_lowercase();
-            if label.contains("cpu") || label.contains("core") || label.contains("package") {
-                temp_sum += component.temperature();
-                temp_count += 1;
-            }
+        Self {
+            sys,
+            users: Users::new(),
+            networks: Networks::new(),
+            disks: Disks::new(),
+            components: Components::new(),
+            cpu_model,
+            host: System::host_name().unwrap_or_default(),
+            intervals: RefreshIntervals::default(),
+            every: Every::ALWAYS,
+            ticks: 0,
+            table: ProcessTable::default(),
+            procs: Vec::new(),
+            disk_info: Vec::new(),
+            interfaces: Vec::new(),
+            cpu_temp: 0.0,
+            battery: None,
+            fans: Vec::new(),
+            core_temps: Vec::new(),
+            drive_temps: HashMap::new(),
+            sbc: None,
+            gpus: Gpus::default(),
+            gpu_info: Vec::new(),
+            platform: platform(),
+            basics: None,
+            focus: None,
+            cgroups: false,
+            cgroup_info: Vec::new(),
+            timings: CollectTimings::default(),
+            defer: false,
+            notice: None,
+            limits: BTreeMap::new(),
+            swap_pages: None,
+            swap_rates: None,
+            disk_bytes: None,
+            disk_rates: None,
+            cpu_power: None,
         }
-        let cpu_temp = if temp_count > 0 {
-            temp_sum / temp_count as f32
-        } else {
-            0.0
-        };
+    }
 
-        let mut s = Self {
-            sys,
-            users,
-            networks,
-            disks,
-            components,
-            _cpu_model: cpu_model,
-            cpu_cores: Vec::new(),
-            cpu_global: 0.0,
-            cpu_temp,
-            total_mem: 0,
-            used_mem: 0,
-            uptime: 0,
-            rx_rate: 0,
-            tx_rate: 0,
+    /// A collector that refreshes processes, disks and sensors only as often
+    /// as `intervals` asks, given one [`collect`](Self::collect) per `tick`.
+    /// Its first collection covers only CPU and memory, so the first frame
+    /// shows up before processes, disks and sensors have been enumerated.
+    pub fn with_intervals(intervals: RefreshIntervals, tick: Duration) -> Self {
+        let mut collector = Self::new();
+        collector.intervals = intervals;
+        collector.defer = true;
+        collector.set_tick(tick);
+        collector
+    }
 
-            procs: Vec::new(),
-        };
-        s.refresh();
-        s
+    /// Recomputes the per-subsystem schedule for a new collection interval.
+    pub fn set_tick(&mut self, tick: Duration) {
+        self.every = Every::new(self.intervals, tick);
     }
 
-    pub fn refresh(&mut self) {
+    /// Refreshes the sources that are due and returns the new values; the
+    /// first full call enumerates and refreshes everything.
+    pub fn collect(&mut self) -> SysCache {
+        let started = Instant::now();
         self.sys.refresh_cpu();
         self.sys.refresh_memory();
-        self.sys
-            .refresh_processes_specifics(ProcessRefreshKind::everything());
+        self.basics = self.platform.basics();
+        self.update_rates(started);
+        self.timings.cpu_memory = started.elapsed();
+        if std::mem::take(&mut self.defer) {
+            return self.snapshot(started);
+        }
+
+        let due = |every: u64| self.ticks % every == 0;
+        // A process in focus is followed every tick.
+        let (procs_due, disks_due, sensors_due) = (
+            due(self.every.processes) || self.focus.is_some(),
+            due(self.every.disks),
+            due(self.every.sensors),
+        );
+        let first = self.ticks == 0;
+        self.ticks += 1;
+
+        let start = Instant::now();
+        if first {
+            self.networks.refresh_list();
+        }
         self.networks.refresh();
-        self.disks.refresh();
-        self.components.refresh();
-
-        self.cpu_global = self.sys.global_cpu_info().cpu_usage();
-        self.cpu_cores = self.sys.cpus().iter().map(|c| c.cpu_usage()).collect();
-
-        let mut temp_sum = 0.0;
-        let mut temp_count = 0;
-        for component in &self.components {
-            let label = component.label().to_lowercase();
-            if label.contains("cpu") || label.contains("core") || label.contains("package") {
-                temp_sum += component.temperature();
-                temp_count += 1;
+        self.interfaces = self.platform.interfaces().unwrap_or_default();
+        self.timings.network = start.elapsed();
+        if procs_due {
+            let start = Instant::now();
+            if first {
+                self.users.refresh_list();
             }
+            if let Some(procs) = self.platform.processes() {
+                self.procs = procs;
+            } else {
+                self.sys
+                    .refresh_processes_specifics(process_kind(self.focus.is_some()));
+                self.table.update(&self.sys, &self.users, &mut self.procs);
+                if self.platform.restricted() {
+                    self.platform.process_cpu(&mut self.procs);
+                    self.procs.sort_unstable_by(|a, b| {
+                        b.cpu
+                            .partial_cmp(&a.cpu)
+                            .unwrap_or(std::cmp::Ordering::Equal)
+                    });
+                }
+            }
+            if self.cgroups {
+                self.cgroup_info = self.platform.cgroups().unwrap_or_default();
+            }
+            self.timings.processes = start.elapsed();
         }
-        self.cpu_temp = if temp_count > 0 {
-            temp_sum / temp_count as f32
-        } else {
-            0.0
-        };
+        if disks_due {
+            let start = Instant::now();
+            if first {
+                self.disks.refresh_list();
+            }
+            self.disks.refresh();
+            // Mounts an app may not stat report zero sizes in restricted mode.
+            let restricted = self.platform.restricted();
+            self.disk_info = self
+                .disks
+                .iter()
+                .filter(|d| !restricted || d.total_space() > 0)
+                .map(|d| DiskInfo {
+                    _name: d.name().to_string_lossy().to_string(),
+                    mount_point: d.mount_point().to_string_lossy().to_string(),
+                    total: d.total_space(),
+                    available: d.available_space(),
+                    temperature: None,
+                })
+                .collect();
+            self.timings.disks = start.elapsed();
+        }
+        // Thermal zones and hwmon are off limits in restricted mode.
+        if sensors_due && !self.platform.restricted() {
+            let start = Instant::now();
+            if first {
+                self.components.refresh_list();
+            }
+            self.components.refresh();
+            self.cpu_temp = self
+                .platform
+                .cpu_temperature()
+                .unwrap_or_else(|| cpu_temperature(&self.components));
+            self.sbc = self.platform.board();
+            if let Some(temp) = self.sbc.as_ref().and_then(|s| s.soc_temp) {
+                if self.cpu_temp == 0.0 {
+                    self.cpu_temp = temp;
+                }
+            }
+            self.battery = self.platform.battery();
+            self.fans = self.platform.fans();
+            self.core_temps = self.platform.core_temperatures(self.sys.cpus().len());
+            self.drive_temps = self.platform.drive_temperatures();
+            self.timings.sensors = start.elapsed();
+        }
+        // GPU load changes as fast as CPU load.
+        self.gpu_info = self.gpus.read();
+        self.snapshot(started)
+    }
 
-        self.total_mem = self.sys.total_memory();
-        self.used_mem = self.total_mem.saturating_sub(self.sys.available_memory());
-        self.uptime = System::uptime();
+    fn update_rates(&mut self, now: Instant) {
+        if let Some(pages) = self.platform.swap_pages() {
+            self.swap_rates = Some(per_second(pages, &mut self.swap_pages, now));
+        }
+        if let Some(bytes) = self.platform.disk_bytes() {
+            self.disk_rates = Some(per_second(bytes, &mut self.disk_bytes, now));
+        }
+        self.cpu_power = self.platform.cpu_power();
+    }
 
+    fn sysinfo_basics(&self) -> Basics {
+        let total_mem = self.sys.total_memory();
         let (rx, tx) = self.networks.iter().fold((0, 0), |acc, (_, n)| {
             (acc.0 + n.received(), acc.1 + n.transmitted())
         });
-        
-        self.rx_rate = rx;
-        self.tx_rate = tx;
+        Basics {
+            cpu_cores: self.sys.cpus().iter().map(|c| c.cpu_usage()).collect(),
+            cpu_global: self.sys.global_cpu_info().cpu_usage(),
+            total_mem,
+            used_mem: total_mem.saturating_sub(self.sys.available_memory()),
+            uptime: System::uptime(),
+            rx,
+            tx,
+        }
+    }
 
-        self.procs = top_processes(&self.sys, &self.users);
+    fn snapshot(&mut self, started: Instant) -> SysCache {
+        let mut basics = match self.basics.take() {
+            Some(basics) => basics,
+            None => self.sysinfo_basics(),
+        };
+        let restricted = self.platform.restricted();
+        if restricted {
+            let cpus = std::thread::available_parallelism().map_or(1, |n| n.get());
+            let used: f32 = self.procs.iter().map(|p| p.cpu).sum();
+            basics.cpu_global = (used / cpus as f32).min(100.0);
+            basics.cpu_cores.clear();
+        }
+        let gone: Vec<u32> = self
+            .limits
+            .keys()
+            .copied()
+            .filter(|&pid| self.sys.process(Pid::from_u32(pid)).is_none())
+            .collect();
+        for pid in gone {
+            // Lets the platform clean up, e.g. remove an empty cgroup.
+            let _ = self.platform.limit_cpu(pid, None);
+            self.limits.remove(&pid);
+        }
+        let procs = self.procs.clone();
+        self.timings.total = started.elapsed();
+        SysCache {
+            _cpu_model: self.cpu_model.clone(),
+            cpu_cores: basics.cpu_cores,
+            cpu_global: basics.cpu_global,
+            cpu_temp: self.cpu_temp,
+            core_temps: self.core_temps.clone(),
+            total_mem: basics.total_mem,
+            used_mem: basics.used_mem,
+            total_swap: self.sys.total_swap(),
+            used_swap: self.sys.used_swap(),
+            swap_rates: self.swap_rates,
+            disk_rates: self.disk_rates,
+            cpu_power: self.cpu_power,
+            uptime: basics.uptime,
+            rx_rate: basics.rx,
+            tx_rate: basics.tx,
+            timings: self.timings,
+            host: self.host.clone(),
+            battery: self.battery.clone(),
+            fans: self.fans.clone(),
+            sbc: self.sbc.clone(),
+            offline: None,
+            notice: self.notice.take(),
+            limits: self.limits.clone(),
+            restricted,
+            focus: self.focus.and_then(|pid| self.focus_info(pid)),
+            interfaces: self.interfaces.clone(),
+            gpus: self.gpu_info.clone(),
+            cgroups: self.cgroup_info.clone(),
+            procs,
+            disks: self
+                .disk_info
+                .iter()
+                .map(|disk| DiskInfo {
+                    temperature: drive_temperature(&self.drive_temps, &disk._name),
+                    ..disk.clone()
+                })
+                .collect(),
+        }
+    }
+
+    /// Follows `pid` closely: processes refresh every tick, with I/O
+    /// counters, and snapshots carry its [`FocusInfo`].
+    pub fn set_focus(&mut self, pid: Option<u32>) {
+        self.focus = pid;
+    }
+
+    /// Reads the cgroup hierarchy on every process refresh while `on`, and
+    /// snapshots carry it as [`SysCache::cgroups`].
+    pub fn set_cgroups(&mut self, on: bool) {
+        self.cgroups = on;
+        if !on {
+            self.cgroup_info.clear();
+        }
+    }
+
+    fn focus_info(&self, pid: u32) -> Option<FocusInfo> {
+        let process = self.sys.process(Pid::from_u32(pid))?;
+        let io = process.disk_usage();
+        Some(FocusInfo {
+            pid,
+            // sysinfo leaves the main thread out of the tasks.
+            threads: process.tasks().map(|tasks| tasks.len() + 1),
+            read_bytes: io.total_read_bytes,
+            written_bytes: io.total_written_bytes,
+            open_files: self.platform.open_files(pid),
+        })
     }
 
     pub fn kill_process(&self, pid: u32) {
@@ -146,59 +830,176 @@ impl SysCache {
         }
     }
 
-    pub fn processes(&self) -> &[ProcessInfo] {
-        &self.procs
+    /// Caps `pid` at `percent` of one core, or lifts its cap with `None`.
+    /// Either way the outcome is the next snapshot's [`SysCache::notice`].
+    pub fn limit_process(&mut self, pid: u32, percent: Option<u32>) {
+        self.notice = Some(match self.platform.limit_cpu(pid, percent) {
+            Ok(()) => match percent {
+                Some(percent) => {
+                    self.limits.insert(pid, percent);
+                    format!("CPU of {} capped at {}%", pid, percent)
+                }
+                None => {
+                    self.limits.remove(&pid);
+                    format!("CPU cap of {} lifted", pid)
+                }
+            },
+            Err(e) => format!("cap {}: {}", pid, e),
+        });
     }
 
-    pub fn disks(&self) -> Vec<DiskInfo> {
-        self.disks
-            .iter()
-            .map(|d| DiskInfo {
-                _name: d.name().to_string_lossy().to_string(),
-                mount_point: d.mount_point().to_string_lossy().to_string(),
-                total: d.total_space(),
-                available: d.available_space(),
-            })
-            .collect()
+    /// Changes the nice value of `pid` by `delta`, clamped to -20..=19.
+    /// Lowering it usually needs root; a failure is reported as the next
+    /// snapshot's [`SysCache::notice`].
+    pub fn renice_process(&mut self, pid: u32, delta: i32) {
+        if let Err(e) = self.platform.renice(pid, delta) {
+            self.notice = Some(format!("renice {}: {}", pid, e));
+        }
     }
 
-    pub fn battery_percentage(&self) -> Option<f32> {
-        None
+    /// Fetches the command line, working directory and environment of one
+    /// process, or `None` if it has exited.
+    pub fn process_detail(&mut self, pid: u32) -> Option<ProcessDetail> {
+        let pid = Pid::from_u32(pid);
+        let kind = ProcessRefreshKind::new()
+            .with_exe(UpdateKind::Always)
+            .with_cmd(UpdateKind::Always)
+            .with_cwd(UpdateKind::Always)
+            .with_environ(UpdateKind::Always);
+        if !self.sys.refresh_process_specifics(pid, kind) {
+            return None;
+        }
+        let p = self.sys.process(pid)?;
+        let path = |p: Option<&Path>| p.map(|p| p.display().to_string()).unwrap_or_default();
+        Some(ProcessDetail {
+            pid: pid.as_u32(),
+            name: p.name().to_string(),
+            exe: path(p.exe()),
+            cwd: path(p.cwd()),
+            cmdline: p.cmd().to_vec(),
+            environ: p.environ().to_vec(),
+        })
     }
 }
 
-fn top_processes(sys: &System, users: &Users) -> Vec<ProcessInfo> {
-    let mut v: Vec<ProcessInfo> = sys
-        .processes()
-        .values()
-        .map(|p| {
-            let user = p
-                .user_id()
-                .and_then(|uid| users.get_user_by_id(uid))
-                .map(|u| u.name().to_string())
-                .unwrap_or_else(|| "root".to_string());
-
-            ProcessInfo {
-                pid: p.pid().as_u32(),
-                name: p.name().to_string(),
-                user,
-                cmd: p
-                    .exe()
-                    .map(|p| p.to_string_lossy().to_string())
-                    .unwrap_or_default(),
-                cpu: p.cpu_usage(),
-                mem_bytes: p.memory(),
-            }
-        })
-        .collect();
-    v.sort_by(|a, b| {
-        b.cpu
-            .partial_cmp(&a.cpu)
-            .unwrap_or(std::cmp::Ordering::Equal)
-    });
-    v
+impl Default for Collector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The temperature of the drive `device` is on, from `temps` by drive
+/// name. Partitions are named after their drive, with a `p` before the
+/// number when the drive's name ends in a digit: `sda2`, `nvme0n1p2`.
+fn drive_temperature(temps: &HashMap<String, f32>, device: &str) -> Option<f32> {
+    let name = device.strip_prefix("/dev/").unwrap_or(device);
+    temps.iter().find_map(|(drive, &temp)| {
+        let rest = name.strip_prefix(drive.as_str())?;
+        let partition = match rest {
+            "" => "",
+            _ if drive.ends_with(|c: char| c.is_ascii_digit()) => rest.strip_prefix('p')?,
+            _ => rest,
+        };
+        partition.bytes().all(|b| b.is_ascii_digit()).then_some(temp)
+    })
+}
+
+/// Average of the CPU package/core sensors, or 0 when there are none. Apple
+/// Silicon names its die sensors "PMU tdie…".
+fn cpu_temperature(components: &Components) -> f32 {
+    let mut temp_sum = 0.0;
+    let mut temp_count = 0;
+    for component in components {
+        let label = component.label().to_lowercase();
+        if label.contains("cpu")
+            || label.contains("core")
+            || label.contains("package")
+            || label.contains("tdie")
+        {
+            temp_sum += component.temperature();
+            temp_count += 1;
+        }
+    }
+    if temp_count > 0 {
+        temp_sum / temp_count as f32
+    } else {
+        0.0
+    }
+}
+
+/// Builds the process list, busiest first, reusing what it can from the
+/// previous refresh: entries whose pid and name are unchanged keep their
+/// strings, and user names are resolved once per uid.
+#[derive(Default)]
+struct ProcessTable {
+    users: HashMap<Uid, Arc<str>>,
+    previous: HashMap<u32, ProcessInfo>,
+}
+
+impl ProcessTable {
+    fn update(&mut self, sys: &System, users: &Users, procs: &mut Vec<ProcessInfo>) {
+        self.previous.clear();
+        self.previous.extend(procs.drain(..).map(|p| (p.pid, p)));
+        procs.reserve(sys.processes().len());
+
+        for p in sys.processes().values() {
+            let pid = p.pid().as_u32();
+            let parent = p.parent().map(|pid| pid.as_u32());
+            let user = match p.user_id() {
+                Some(uid) => Arc::clone(self.users.entry(uid.clone()).or_insert_with(|| {
+                    users
+                        .get_user_by_id(uid)
+                        .map(|u| Arc::from(u.name()))
+                        .unwrap_or_else(|| Arc::from("root"))
+                })),
+                None => Arc::from("root"),
+            };
+            let info = match self.previous.remove(&pid) {
+                Some(mut prev) if *prev.name == *p.name() => {
+                    prev.user = user;
+                    prev.cpu = p.cpu_usage();
+                    prev.mem_bytes = p.memory();
+                    prev.parent = parent;
+                    prev
+                }
+                _ => ProcessInfo {
+                    pid,
+                    name: Arc::from(p.name()),
+                    user,
+                    cmd: p
+                        .exe()
+                        .map(|p| Arc::from(p.to_string_lossy().as_ref()))
+                        .unwrap_or_else(|| Arc::from("")),
+                    cpu: p.cpu_usage(),
+                    mem_bytes: p.memory(),
+                    parent,
+                },
+            };
+            procs.push(info);
+        }
+
+        procs.sort_unstable_by(|a, b| {
+            b.cpu
+                .partial_cmp(&a.cpu)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+    }
 }
 
+/// Turns a pair of counters into rates since the reading in `last`, which
+/// it replaces; the first reading gives zeros.
+fn per_second(
+    counters: (u64, u64),
+    last: &mut Option<((u64, u64), Instant)>,
+    now: Instant,
+) -> (u64, u64) {
+    let (pre, at) = last.replace((counters, now)).unwrap_or((counters, now));
+    let secs = now.duration_since(at).as_secs_f64().max(0.001);
+    let rate = |n: u64, pre: u64| (n.saturating_sub(pre) as f64 / secs).round() as u64;
+    (rate(counters.0, pre.0), rate(counters.1, pre.1))
+}
+
+/// `1536` as `1.5K`: binary units, one decimal.
 pub fn format_bytes(bytes: u64) -> String {
     const UNITS: [&str; 5] = ["B", "K", "M", "G", "T"];
     if bytes == 0 {
@@ -213,6 +1014,7 @@ pub fn format_bytes(bytes: u64) -> String {
     format!("{:.1}{}", size, UNITS[unit])
 }
 
+/// Seconds as `HH:MM:SS`; hours go past 99 rather than rolling over.
 pub fn format_duration_secs(total_secs: u64) -> String {
     let hours = total_secs / 3600;
     let mins = (total_secs % 3600) / 60;
@@ -224,6 +1026,18 @@ pub fn format_duration_secs(total_secs: u64) -> String {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_drive_temperature() {
+        let temps = HashMap::from([("sda".to_string(), 35.0), ("nvme0n1".to_string(), 41.0)]);
+        let temp = |device| drive_temperature(&temps, device);
+        assert_eq!(temp("/dev/sda2"), Some(35.0));
+        assert_eq!(temp("/dev/sda"), Some(35.0));
+        assert_eq!(temp("/dev/nvme0n1p3"), Some(41.0));
+        assert_eq!(temp("/dev/nvme0n10"), None);
+        assert_eq!(temp("/dev/sdaa1"), None);
+        assert_eq!(temp("/dev/mapper/root"), None);
+    }
+
     #[test]
     fn test_format_bytes() {
         assert_eq!(format_bytes(0), "0B");
@@ -241,9 +1055,75 @@ mod tests {
         assert_eq!(format_duration_secs(3661), "01:01:01");
     }
 
+    #[test]
+    fn test_refresh_intervals() {
+        let every = Every::new(RefreshIntervals::default(), Duration::from_millis(500));
+        assert_eq!(every.processes, 4);
+        assert_eq!(every.disks, 20);
+        let every = Every::new(RefreshIntervals::default(), Duration::from_secs(30));
+        assert_eq!(every, Every::ALWAYS);
+
+        let intervals: RefreshIntervals = toml::from_str("processes = 5").unwrap();
+        assert_eq!(intervals.processes, 5);
+        assert_eq!(intervals.sensors, 10);
+        assert_eq!(intervals.idle_rate(), Some(Duration::from_secs(5)));
+        let intervals: RefreshIntervals = toml::from_str("idle = 0").unwrap();
+        assert_eq!(intervals.idle_rate(), None);
+        assert!(RefreshIntervals::load(Path::new("/nonexistent/refresh.toml")).is_ok());
+    }
+
+    #[test]
+    fn test_unchanged_processes_keep_their_strings() {
+        let mut collector = Collector::new();
+        let own = |sys: &SysCache| {
+            sys.processes()
+                .iter()
+                .find(|p| p.pid == std::process::id())
+                .cloned()
+                .unwrap()
+        };
+        let first = own(&collector.collect());
+        let second = own(&collector.collect());
+        assert!(Arc::ptr_eq(&first.name, &second.name));
+        assert!(Arc::ptr_eq(&first.user, &second.user));
+    }
+
+    #[test]
+    fn test_process_detail() {
+        let mut collector = Collector::new();
+        let detail = collector.process_detail(std::process::id()).unwrap();
+        assert!(!detail.cmdline.is_empty());
+        assert!(!detail.exe.is_empty());
+        assert_eq!(collector.process_detail(u32::MAX), None);
+    }
+
     #[test]
     fn test_sys_cache_new() {
         let sys = SysCache::new();
         assert!(sys.total_mem > 0);
+        assert!(!sys.processes().is_empty());
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "freebsd"))]
+    #[test]
+    fn test_renice_reports_failure() {
+        let own = platform().renice(std::process::id(), 0).unwrap();
+        assert!((-20..=19).contains(&own));
+
+        let mut collector = Collector::new();
+        collector.renice_process(u32::MAX >> 1, 1);
+        let notice = collector.collect().notice.unwrap();
+        assert!(notice.starts_with(&format!("renice {}: ", u32::MAX >> 1)));
+        assert_eq!(collector.collect().notice, None);
+    }
+
+    #[test]
+    fn test_deferred_first_collection() {
+        let mut collector =
+            Collector::with_intervals(RefreshIntervals::default(), Duration::from_secs(1));
+        let first = collector.collect();
+        assert!(first.total_mem > 0);
+        assert!(first.processes().is_empty());
+        assert!(!collector.collect().processes().is_empty());
     }
 }
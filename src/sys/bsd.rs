@@ -0,0 +1,276 @@
+//! OpenBSD and NetBSD. sysinfo has no backend for either and would report
+//! zeros, so CPU, memory, uptime, network and processes are read from
+//! `sysctl`, `vmstat -s`, `netstat -ibn` and `ps` instead. The parsers are
+//! plain text, so they are tested on every platform.
+
+use super::ProcessInfo;
+use chrono::TimeZone;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// CPU tick counters as `kern.cp_time` lists them, idle last.
+pub type Ticks = Vec<u64>;
+
+#[cfg(any(target_os = "openbsd", target_os = "netbsd"))]
+pub use self::native::Bsd;
+
+#[cfg(any(target_os = "openbsd", target_os = "netbsd"))]
+mod native {
+    use super::*;
+    use crate::sys::unix::run;
+    use crate::sys::{Basics, Interface, Platform};
+
+    #[derive(Default)]
+    pub struct Bsd {
+        cpu: Ticks,
+        cores: Vec<Ticks>,
+        ncpu: Option<usize>,
+        net: Option<(u64, u64)>,
+        throttles: crate::sys::unix::Throttles,
+    }
+
+    impl Bsd {
+        /// `kern.cp_time` and `kern.boottime`, plus OpenBSD's per-CPU
+        /// `kern.cp_time2.N`. NetBSD only has the total, which then stands
+        /// in for a single core.
+        fn sysctl(&mut self) -> Option<String> {
+            let mut names = vec!["kern.cp_time".to_string(), "kern.boottime".to_string()];
+            if cfg!(target_os = "openbsd") {
+                let ncpu = *self.ncpu.get_or_insert_with(|| {
+                    run("sysctl", &["-n", "hw.ncpuonline"])
+                        .and_then(|n| n.trim().parse().ok())
+                        .unwrap_or(1)
+                });
+                names.extend((0..ncpu).map(|i| format!("kern.cp_time2.{}", i)));
+            }
+            let args: Vec<&str> = names.iter().map(String::as_str).collect();
+            run("sysctl", &args)
+        }
+    }
+
+    impl Platform for Bsd {
+        fn basics(&mut self) -> Option<Basics> {
+            let text = self.sysctl()?;
+            let values = parse_sysctl(&text);
+            let mut basics = Basics::default();
+
+            let cpu = parse_ticks(values.get("kern.cp_time")?);
+            basics.cpu_global = cpu_usage(&self.cpu, &cpu);
+            self.cpu = cpu;
+            let cores: Vec<Ticks> = (0..)
+                .map_while(|i| values.get(format!("kern.cp_time2.{}", i).as_str()))
+                .map(|v| parse_ticks(v))
+                .collect();
+            basics.cpu_cores = if cores.is_empty() {
+                vec![basics.cpu_global]
+            } else {
+                let empty = Ticks::new();
+                cores
+                    .iter()
+                    .enumerate()
+                    .map(|(i, now)| cpu_usage(self.cores.get(i).unwrap_or(&empty), now))
+                    .collect()
+            };
+            self.cores = cores;
+
+            let now = chrono::Local::now().timestamp();
+            if let Some(boot) = values.get("kern.boottime").and_then(|v| parse_boottime(v)) {
+                basics.uptime = now.saturating_sub(boot).max(0) as u64;
+            }
+            if let Some((total, used)) = run("vmstat", &["-s"]).and_then(|t| parse_vmstat(&t)) {
+                basics.total_mem = total;
+                basics.used_mem = used;
+            }
+            if let Some((rx, tx)) = run("netstat", &["-ibn"]).map(|t| parse_netstat(&t)) {
+                if let Some((pre_rx, pre_tx)) = self.net {
+                    basics.rx = rx.saturating_sub(pre_rx);
+                    basics.tx = tx.saturating_sub(pre_tx);
+                }
+                self.net = Some((rx, tx));
+            }
+            Some(basics)
+        }
+
+        fn processes(&mut self) -> Option<Vec<ProcessInfo>> {
+            run("ps", &["-axo", "pid=,ppid=,user=,pcpu=,rss=,comm="]).map(|t| parse_ps(&t))
+        }
+
+        fn interfaces(&mut self) -> Option<Vec<Interface>> {
+            crate::sys::unix::interfaces()
+        }
+
+        fn renice(&self, pid: u32, delta: i32) -> std::io::Result<i32> {
+            crate::sys::unix::renice(pid, delta)
+        }
+
+        fn limit_cpu(&mut self, pid: u32, percent: Option<u32>) -> std::io::Result<()> {
+            self.throttles.limit(pid, percent)
+        }
+    }
+}
+
+/// `name=value` (OpenBSD) or `name = value` / `name: value` (NetBSD) lines.
+pub fn parse_sysctl(text: &str) -> HashMap<&str, &str> {
+    text.lines()
+        .filter_map(|line| {
+            let (name, value) = line.split_once([':', '='])?;
+            Some((name.trim(), value.trim()))
+        })
+        .collect()
+}
+
+/// The counters of a `kern.cp_time` value: `1,2,3,4,5,6` on OpenBSD,
+/// `user = 1, nice = 2, ...` on NetBSD.
+pub fn parse_ticks(value: &str) -> Ticks {
+    value
+        .split(|c: char| !c.is_ascii_digit())
+        .filter_map(|n| n.parse().ok())
+        .collect()
+}
+
+/// Percent of the ticks between two readings not spent idle.
+pub fn cpu_usage(previous: &[u64], now: &[u64]) -> f32 {
+    if previous.len() != now.len() || now.is_empty() {
+        return 0.0;
+    }
+    let delta: Vec<u64> = now
+        .iter()
+        .zip(previous)
+        .map(|(n, p)| n.saturating_sub(*p))
+        .collect();
+    let total: u64 = delta.iter().sum();
+    if total == 0 {
+        return 0.0;
+    }
+    let idle = delta[delta.len() - 1];
+    (total - idle) as f32 / total as f32 * 100.0
+}
+
+/// Boot time as a Unix timestamp. Newer systems print the timestamp, older
+/// ones a local `ctime` date such as `Mon Oct  6 09:12:01 2026`.
+pub fn parse_boottime(value: &str) -> Option<i64> {
+    if let Ok(secs) = value.parse() {
+        return Some(secs);
+    }
+    let date = value.split_whitespace().collect::<Vec<_>>().join(" ");
+    let naive = chrono::NaiveDateTime::parse_from_str(&date, "%a %b %d %H:%M:%S %Y").ok()?;
+    let local = chrono::Local.from_local_datetime(&naive).earliest()?;
+    Some(local.timestamp())
+}
+
+/// Total and used memory in bytes from `vmstat -s`: the managed pages, less
+/// the free ones.
+pub fn parse_vmstat(text: &str) -> Option<(u64, u64)> {
+    let value = |label: &str| {
+        text.lines().find_map(|line| {
+            let (n, rest) = line.trim().split_once(' ')?;
+            (rest.trim() == label).then(|| n.parse::<u64>().ok())?
+        })
+    };
+    let page = value("bytes per page")?;
+    let managed = value("pages managed")?;
+    let free = value("pages free").unwrap_or(0);
+    Some((managed * page, managed.saturating_sub(free) * page))
+}
+
+/// Bytes received and sent by all interfaces but loopback, from the link
+/// rows of `netstat -ibn` (the last two columns, since the address may be
+/// empty).
+pub fn parse_netstat(text: &str) -> (u64, u64) {
+    text.lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 5 || !fields[2].starts_with("<Link") || fields[0].starts_with("lo") {
+                return None;
+            }
+            let rx = fields[fields.len() - 2].parse::<u64>().ok()?;
+            let tx = fields[fields.len() - 1].parse::<u64>().ok()?;
+            Some((rx, tx))
+        })
+        .fold((0, 0), |(rx, tx), (r, t)| (rx + r, tx + t))
+}
+
+/// `ps -axo pid=,ppid=,user=,pcpu=,rss=,comm=`, busiest first. The command
+/// name is last as it may contain spaces.
+pub fn parse_ps(text: &str) -> Vec<ProcessInfo> {
+    let mut procs: Vec<ProcessInfo> = text
+        .lines()
+        .filter_map(|line| {
+            let mut rest = line.trim_start();
+            let mut fields = [""; 5];
+            for field in &mut fields {
+                let end = rest.find(char::is_whitespace)?;
+                *field = &rest[..end];
+                rest = rest[end..].trim_start();
+            }
+            let name: Arc<str> = Arc::from(rest.trim_end());
+            let parent = fields[1].parse().ok().filter(|&ppid| ppid != 0);
+            Some(ProcessInfo {
+                pid: fields[0].parse().ok()?,
+                cmd: name.clone(),
+                name,
+                user: Arc::from(fields[2]),
+                cpu: fields[3].parse().unwrap_or(0.0),
+                mem_bytes: fields[4].parse::<u64>().unwrap_or(0) * 1024,
+                parent,
+            })
+        })
+        .collect();
+    procs.sort_unstable_by(|a, b| {
+        b.cpu
+            .partial_cmp(&a.cpu)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    procs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cpu_and_boottime() {
+        let openbsd = "kern.cp_time=100,0,50,0,10,840\nkern.boottime=1760000000\n";
+        let values = parse_sysctl(openbsd);
+        let before = parse_ticks(values["kern.cp_time"]);
+        assert_eq!(before, vec![100, 0, 50, 0, 10, 840]);
+        assert_eq!(parse_boottime(values["kern.boottime"]), Some(1_760_000_000));
+
+        let after = parse_ticks("200,0,100,0,10,890");
+        assert_eq!(cpu_usage(&before, &after), 75.0);
+        assert_eq!(cpu_usage(&[], &after), 0.0);
+
+        let netbsd = "kern.cp_time: user = 1, nice = 2, sys = 3, intr = 4, idle = 5\n\
+                      kern.boottime = Mon Oct  6 09:12:01 2025\n";
+        let values = parse_sysctl(netbsd);
+        assert_eq!(parse_ticks(values["kern.cp_time"]), vec![1, 2, 3, 4, 5]);
+        assert!(parse_boottime(values["kern.boottime"]).is_some());
+    }
+
+    #[test]
+    fn test_parse_vmstat_netstat_ps() {
+        let vmstat = "     4096 bytes per page\n    \
+                      1000 pages managed\n      \
+                      250 pages free\n      \
+                      300 pages active\n";
+        assert_eq!(parse_vmstat(vmstat), Some((4_096_000, 3_072_000)));
+
+        let netstat = "\
+Name    Mtu   Network     Address              Ibytes    Obytes
+lo0     32768 <Link>                             5000      5000
+lo0     32768 127/8       127.0.0.1              5000      5000
+em0     1500  <Link>      52:54:00:12:34:56    123456     65432
+em0     1500  10.0.2/24   10.0.2.15            123456     65432
+";
+        assert_eq!(parse_netstat(netstat), (123_456, 65_432));
+
+        let ps = "    1     0 root      0.0   512 init\n\
+                  4242     1 _sndio    12.5  2048 sndiod worker\n";
+        let procs = parse_ps(ps);
+        assert_eq!(procs[0].pid, 4242);
+        assert_eq!(&*procs[0].name, "sndiod worker");
+        assert_eq!(procs[0].mem_bytes, 2048 * 1024);
+        assert_eq!(procs[0].parent, Some(1));
+        assert_eq!(procs[1].parent, None);
+    }
+}
@@ -0,0 +1,138 @@
+//! FreeBSD extras that sysinfo doesn't cover: the battery from
+//! `acpiconf -i 0` and per-core temperatures from `dev.cpu.N.temperature`
+//! (with the coretemp or amdtemp module loaded). Both are read from command
+//! output, so the parsers are tested on every platform.
+
+#[cfg(target_os = "freebsd")]
+use super::unix::run;
+use super::BatteryInfo;
+
+#[cfg(target_os = "freebsd")]
+#[derive(Default)]
+pub struct FreeBsd {
+    throttles: super::unix::Throttles,
+}
+
+#[cfg(target_os = "freebsd")]
+impl super::Platform for FreeBsd {
+    /// Reads the first ACPI battery, or `None` on machines without one.
+    fn battery(&mut self) -> Option<BatteryInfo> {
+        parse_acpiconf(&run("acpiconf", &["-i", "0"])?)
+    }
+
+    /// Average of the per-core temperatures, if the sensors module is loaded.
+    fn cpu_temperature(&mut self) -> Option<f32> {
+        parse_cpu_temperatures(&run("sysctl", &["-i", "dev.cpu"])?)
+    }
+
+    fn interfaces(&mut self) -> Option<Vec<super::Interface>> {
+        super::unix::interfaces()
+    }
+
+    fn renice(&self, pid: u32, delta: i32) -> std::io::Result<i32> {
+        super::unix::renice(pid, delta)
+    }
+
+    fn limit_cpu(&mut self, pid: u32, percent: Option<u32>) -> std::io::Result<()> {
+        self.throttles.limit(pid, percent)
+    }
+}
+
+/// The `Label: value` lines of `acpiconf -i`.
+pub fn parse_acpiconf(text: &str) -> Option<BatteryInfo> {
+    let value = |key: &str| {
+        text.lines().find_map(|line| {
+            let (k, v) = line.split_once(':')?;
+            (k.trim() == key).then(|| v.trim())
+        })
+    };
+    // "4400 mAh", "85%": the leading number.
+    let number = |key: &str| {
+        value(key)?
+            .split(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .next()?
+            .parse::<f32>()
+            .ok()
+    };
+
+    let percent = number("Remaining capacity")?;
+    let health = match (number("Last full capacity"), number("Design capacity")) {
+        (Some(full), Some(design)) if design > 0.0 => Some((full / design * 100.0).min(100.0)),
+        _ => None,
+    };
+    // mW, or mA on batteries that report charge rather than energy.
+    let power = value("Present rate").and_then(|unit| {
+        let rate = number("Present rate")?;
+        if unit.ends_with("mA") {
+            Some(rate * number("Present voltage")? / 1e6)
+        } else {
+            Some(rate / 1000.0)
+        }
+    });
+    // "2:30", or "unknown" while charging.
+    let time_left = value("Remaining time")
+        .and_then(|time| time.split_once(':'))
+        .and_then(|(h, m)| Some(h.parse::<u64>().ok()? * 3600 + m.parse::<u64>().ok()? * 60));
+    Some(BatteryInfo {
+        percent,
+        charging: value("State") == Some("charging"),
+        cycle_count: number("Cycle Count").map(|c| c as u32),
+        health,
+        temperature: None,
+        power: power.filter(|&power| power > 0.0),
+        time_left,
+    })
+}
+
+/// Averages the `dev.cpu.N.temperature: 45.0C` lines of `sysctl dev.cpu`.
+pub fn parse_cpu_temperatures(text: &str) -> Option<f32> {
+    let temps: Vec<f32> = text
+        .lines()
+        .filter_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            if !name.trim().ends_with(".temperature") {
+                return None;
+            }
+            value.trim().trim_end_matches('C').parse().ok()
+        })
+        .collect();
+    (!temps.is_empty()).then(|| temps.iter().sum::<f32>() / temps.len() as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_acpiconf() {
+        let text = "Design capacity:\t4400 mAh\n\
+                    Last full capacity:\t3960 mAh\n\
+                    Technology:\t\tsecondary (rechargeable)\n\
+                    State:\t\t\tcharging\n\
+                    Remaining capacity:\t85%\n\
+                    Remaining time:\t\tunknown\n\
+                    Present rate:\t\t1200 mA\n\
+                    Present voltage:\t12500 mV\n";
+        let battery = parse_acpiconf(text).unwrap();
+        assert_eq!(battery.percent, 85.0);
+        assert!(battery.charging);
+        assert_eq!(battery.health, Some(90.0));
+        assert_eq!(battery.cycle_count, None);
+        assert_eq!(battery.power, Some(15.0));
+        assert_eq!(battery.time_left, None);
+        let discharging = "Remaining capacity:\t60%\nRemaining time:\t2:30\nPresent rate:\t9500 mW\n";
+        let battery = parse_acpiconf(discharging).unwrap();
+        assert_eq!(battery.power, Some(9.5));
+        assert_eq!(battery.time_left, Some(2 * 3600 + 30 * 60));
+        assert_eq!(parse_acpiconf("acpiconf: no such battery"), None);
+    }
+
+    #[test]
+    fn test_parse_cpu_temperatures() {
+        let text = "dev.cpu.1.temperature: 47.0C\n\
+                    dev.cpu.1.freq: 2400\n\
+                    dev.cpu.0.temperature: 45.0C\n";
+        assert_eq!(parse_cpu_temperatures(text), Some(46.0));
+        assert_eq!(parse_cpu_temperatures("dev.cpu.0.freq: 2400\n"), None);
+    }
+}
@@ -0,0 +1,234 @@
+//! Graphics cards. NVIDIA GPUs are read through NVML, the management
+//! library that ships with the driver, in builds with `--features nvidia`.
+//! AMD GPUs need no library: the amdgpu driver on Linux exposes load, VRAM
+//! and its hwmon sensors under `/sys/class/drm`. Where neither finds a
+//! card, no GPUs are reported and the GPU panel stays hidden.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const DRM: &str = "/sys/class/drm";
+const AMD_VENDOR: &str = "0x1002";
+
+/// One GPU as last read. Values the card or driver doesn't report are
+/// `None`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct GpuInfo {
+    pub name: String,
+    /// Busy time, in percent.
+    pub utilization: Option<f32>,
+    /// VRAM in use and installed, in bytes.
+    pub mem_used: u64,
+    pub mem_total: u64,
+    /// °C.
+    pub temperature: Option<f32>,
+    /// Power draw in watts.
+    pub power: Option<f32>,
+    /// Processes using the card, most VRAM first. Only NVML lists them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub processes: Vec<GpuProcess>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GpuProcess {
+    pub pid: u32,
+    /// VRAM in bytes, where the driver accounts it per process (not under
+    /// Windows' WDDM).
+    pub mem: Option<u64>,
+}
+
+impl GpuInfo {
+    /// VRAM in use, in percent, or `None` when the total is unknown.
+    pub fn mem_percent(&self) -> Option<f32> {
+        (self.mem_total > 0).then(|| (self.mem_used as f64 / self.mem_total as f64 * 100.0) as f32)
+    }
+}
+
+/// Reads every GPU found, on each collection. Cards are looked for on the
+/// first read: NVML is loaded and the DRM devices listed once.
+#[derive(Default)]
+pub struct Gpus {
+    /// The `device` directories of AMD cards.
+    amd: Option<Vec<PathBuf>>,
+    #[cfg(feature = "nvidia")]
+    nvml: Option<nvml_wrapper::Nvml>,
+    #[cfg(feature = "nvidia")]
+    tried: bool,
+}
+
+impl Gpus {
+    pub fn read(&mut self) -> Vec<GpuInfo> {
+        let mut gpus = Vec::new();
+        #[cfg(feature = "nvidia")]
+        gpus.extend(self.nvidia());
+        let amd = self.amd.get_or_insert_with(|| amd_devices(Path::new(DRM)));
+        gpus.extend(amd.iter().map(|device| amd_gpu(device)));
+        gpus
+    }
+
+    #[cfg(feature = "nvidia")]
+    fn nvidia(&mut self) -> Vec<GpuInfo> {
+        use nvml_wrapper::enum_wrappers::device::TemperatureSensor;
+
+        if !std::mem::replace(&mut self.tried, true) {
+            self.nvml = nvml_wrapper::Nvml::init().ok();
+        }
+        let Some(nvml) = &self.nvml else {
+            return Vec::new();
+        };
+        use nvml_wrapper::enums::device::UsedGpuMemory;
+
+        let count = nvml.device_count().unwrap_or(0);
+        (0..count)
+            .filter_map(|i| nvml.device_by_index(i).ok())
+            .map(|device| {
+                let memory = device.memory_info().ok();
+                let compute = device.running_compute_processes().unwrap_or_default();
+                let graphics = device.running_graphics_processes().unwrap_or_default();
+                let processes = compute.into_iter().chain(graphics).map(|p| {
+                    let mem = match p.used_gpu_memory {
+                        UsedGpuMemory::Used(bytes) => Some(bytes),
+                        UsedGpuMemory::Unavailable => None,
+                    };
+                    (p.pid, mem)
+                });
+                GpuInfo {
+                    name: device.name().unwrap_or_else(|_| "NVIDIA GPU".into()),
+                    utilization: device.utilization_rates().ok().map(|u| u.gpu as f32),
+                    mem_used: memory.as_ref().map_or(0, |m| m.used),
+                    mem_total: memory.as_ref().map_or(0, |m| m.total),
+                    temperature: device
+                        .temperature(TemperatureSensor::Gpu)
+                        .ok()
+                        .map(|t| t as f32),
+                    // Milliwatts.
+                    power: device.power_usage().ok().map(|p| p as f32 / 1000.0),
+                    processes: gpu_processes(processes),
+                }
+            })
+            .collect()
+    }
+}
+
+/// One entry per PID, most VRAM first. A process doing both compute and
+/// graphics work is listed by NVML twice, with the same memory.
+#[cfg_attr(not(feature = "nvidia"), allow(dead_code))]
+fn gpu_processes(list: impl IntoIterator<Item = (u32, Option<u64>)>) -> Vec<GpuProcess> {
+    let mut processes: Vec<GpuProcess> = Vec::new();
+    for (pid, mem) in list {
+        match processes.iter_mut().find(|p| p.pid == pid) {
+            Some(p) => p.mem = p.mem.max(mem),
+            None => processes.push(GpuProcess { pid, mem }),
+        }
+    }
+    processes.sort_by(|a, b| b.mem.cmp(&a.mem).then(a.pid.cmp(&b.pid)));
+    processes
+}
+
+/// The `device` directories of the AMD cards under `drm`, in card order.
+/// Connectors such as `card0-DP-1` are skipped.
+fn amd_devices(drm: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(drm) else {
+        return Vec::new();
+    };
+    let mut cards: Vec<(u32, PathBuf)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            let index = name.to_str()?.strip_prefix("card")?.parse().ok()?;
+            let device = entry.path().join("device");
+            let vendor = fs::read_to_string(device.join("vendor")).ok()?;
+            // Only amdgpu has the VRAM counters; radeon cards show no load.
+            (vendor.trim() == AMD_VENDOR && device.join("mem_info_vram_total").exists())
+                .then_some((index, device))
+        })
+        .collect();
+    cards.sort();
+    cards.into_iter().map(|(_, device)| device).collect()
+}
+
+fn amd_gpu(device: &Path) -> GpuInfo {
+    let read = |path: &Path| -> Option<u64> { fs::read_to_string(path).ok()?.trim().parse().ok() };
+    let card = device
+        .parent()
+        .and_then(|p| p.file_name())
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let name = fs::read_to_string(device.join("product_name"))
+        .ok()
+        .map(|n| n.trim().to_string())
+        .filter(|n| !n.is_empty())
+        .unwrap_or_else(|| format!("AMD GPU ({})", card));
+    // The first hwmon entry is the card's own.
+    let hwmon = fs::read_dir(device.join("hwmon"))
+        .ok()
+        .and_then(|mut entries| entries.next()?.ok())
+        .map(|entry| entry.path());
+    let hwmon = |file: &str| read(&hwmon.as_ref()?.join(file));
+    GpuInfo {
+        name,
+        utilization: read(&device.join("gpu_busy_percent")).map(|p| p as f32),
+        mem_used: read(&device.join("mem_info_vram_used")).unwrap_or(0),
+        mem_total: read(&device.join("mem_info_vram_total")).unwrap_or(0),
+        // Millidegrees, the edge sensor.
+        temperature: hwmon("temp1_input").map(|t| t as f32 / 1000.0),
+        // Microwatts; newer kernels only have the instantaneous reading.
+        power: hwmon("power1_average")
+            .or_else(|| hwmon("power1_input"))
+            .map(|p| p as f32 / 1_000_000.0),
+        processes: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(path: &Path, contents: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn test_amd_sysfs() {
+        let drm = std::env::temp_dir().join(format!("sysdash-drm-{}", std::process::id()));
+        let card1 = drm.join("card1/device");
+        write(&card1.join("vendor"), "0x1002\n");
+        write(&card1.join("gpu_busy_percent"), "37\n");
+        write(&card1.join("mem_info_vram_used"), "2147483648\n");
+        write(&card1.join("mem_info_vram_total"), "8589934592\n");
+        write(&card1.join("hwmon/hwmon3/temp1_input"), "54000\n");
+        write(&card1.join("hwmon/hwmon3/power1_average"), "35000000\n");
+        // An Intel iGPU and a connector.
+        write(&drm.join("card0/device/vendor"), "0x8086\n");
+        write(&drm.join("card1-DP-1/device/vendor"), "0x1002\n");
+
+        let devices = amd_devices(&drm);
+        assert_eq!(devices, std::slice::from_ref(&card1));
+        let gpu = amd_gpu(&card1);
+        fs::remove_dir_all(&drm).unwrap();
+        assert_eq!(
+            gpu,
+            GpuInfo {
+                name: "AMD GPU (card1)".into(),
+                utilization: Some(37.0),
+                mem_used: 2 << 30,
+                mem_total: 8 << 30,
+                temperature: Some(54.0),
+                power: Some(35.0),
+                processes: Vec::new(),
+            }
+        );
+        assert_eq!(gpu.mem_percent(), Some(25.0));
+        assert!(amd_devices(&drm).is_empty());
+    }
+
+    #[test]
+    fn test_gpu_processes() {
+        let processes = gpu_processes([(7, Some(100)), (9, None), (3, Some(900)), (7, Some(100))]);
+        let pids: Vec<_> = processes.iter().map(|p| p.pid).collect();
+        assert_eq!(pids, [3, 7, 9]);
+        assert_eq!(processes[1].mem, Some(100));
+    }
+}
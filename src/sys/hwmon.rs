@@ -0,0 +1,278 @@
+//! Hardware monitoring chips under `/sys/class/hwmon`, for what sysinfo's
+//! components leave out: fan speeds, the temperature of each core and that
+//! of each drive. Each chip is a `hwmonN` directory with
+//! its driver in `name` and one `<kind><index>_input` file per reading,
+//! labelled by an optional `<kind><index>_label`.
+
+use super::FanInfo;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const HWMON: &str = "/sys/class/hwmon";
+pub const CPUS: &str = "/sys/devices/system/cpu";
+/// `nvme0c0n1`: a namespace seen through controller 0, on kernels with NVMe
+/// multipathing.
+const NVME_PATH: char = 'c';
+
+/// The chips under `root` in `hwmonN` order, with their driver names.
+fn chips(root: &Path) -> Vec<(PathBuf, String)> {
+    let Ok(entries) = fs::read_dir(root) else {
+        return Vec::new();
+    };
+    let mut chips: Vec<(u32, PathBuf, String)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let index = entry
+                .file_name()
+                .to_str()?
+                .strip_prefix("hwmon")?
+                .parse()
+                .ok()?;
+            let dir = entry.path();
+            let name = read(&dir.join("name")).unwrap_or_default();
+            Some((index, dir, name))
+        })
+        .collect();
+    chips.sort();
+    chips
+        .into_iter()
+        .map(|(_, dir, name)| (dir, name))
+        .collect()
+}
+
+/// The indices of a chip's `<kind>N_input` files, in order.
+fn inputs(chip: &Path, kind: &str) -> Vec<u32> {
+    let Ok(entries) = fs::read_dir(chip) else {
+        return Vec::new();
+    };
+    let mut indices: Vec<u32> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            name.to_str()?
+                .strip_prefix(kind)?
+                .strip_suffix("_input")?
+                .parse()
+                .ok()
+        })
+        .collect();
+    indices.sort_unstable();
+    indices
+}
+
+fn read(path: &Path) -> Option<String> {
+    Some(fs::read_to_string(path).ok()?.trim().to_string())
+}
+
+/// Every fan the chips under `root` report, stopped ones included. Fans
+/// without a label are named after their chip, e.g. `nct6798 fan2`.
+pub fn fans(root: &Path) -> Vec<FanInfo> {
+    let mut fans = Vec::new();
+    for (chip, name) in chips(root) {
+        for index in inputs(&chip, "fan") {
+            let Some(rpm) =
+                read(&chip.join(format!("fan{}_input", index))).and_then(|rpm| rpm.parse().ok())
+            else {
+                continue;
+            };
+            let label = read(&chip.join(format!("fan{}_label", index)))
+                .filter(|label| !label.is_empty())
+                .unwrap_or_else(|| format!("{} fan{}", name, index));
+            fans.push(FanInfo { label, rpm });
+        }
+    }
+    fans
+}
+
+/// The temperature of each of `cpus` logical CPUs, from Intel's coretemp
+/// chips, one per package. Their `Core N` labels give the core's id within
+/// the package, which `cpus_dir/cpuI/topology` maps logical CPUs to, so
+/// hyper-threads show their core's reading. Empty without coretemp.
+pub fn core_temps(root: &Path, cpus_dir: &Path, cpus: usize) -> Vec<Option<f32>> {
+    let mut by_core: HashMap<(u32, u32), f32> = HashMap::new();
+    for (package, (chip, _)) in chips(root)
+        .into_iter()
+        .filter(|(_, name)| name == "coretemp")
+        .enumerate()
+    {
+        let mut package = package as u32;
+        let mut cores = Vec::new();
+        for index in inputs(&chip, "temp") {
+            let (Some(label), Some(millis)) = (
+                read(&chip.join(format!("temp{}_label", index))),
+                read(&chip.join(format!("temp{}_input", index)))
+                    .and_then(|t| t.parse::<i32>().ok()),
+            ) else {
+                continue;
+            };
+            if let Some(id) = label.strip_prefix("Package id ") {
+                package = id.parse().unwrap_or(package);
+            } else if let Some(Ok(core)) = label.strip_prefix("Core ").map(str::parse) {
+                cores.push((core, millis as f32 / 1000.0));
+            }
+        }
+        by_core.extend(
+            cores
+                .into_iter()
+                .map(|(core, temp)| ((package, core), temp)),
+        );
+    }
+    if by_core.is_empty() {
+        return Vec::new();
+    }
+    (0..cpus)
+        .map(|cpu| {
+            let topology = cpus_dir.join(format!("cpu{}/topology", cpu));
+            let id = |file: &str| read(&topology.join(file))?.parse().ok();
+            by_core
+                .get(&(id("physical_package_id")?, id("core_id")?))
+                .copied()
+        })
+        .collect()
+}
+
+/// Drive temperatures by block device name (`nvme0n1`, `sda`). The `nvme`
+/// driver's chips sit on the controller, with its namespaces below it;
+/// `drivetemp`'s (SATA drives, once the module is loaded) on the SCSI disk,
+/// with the block device in its `block` directory. Either reports the drive
+/// as a whole in `temp1`.
+pub fn drive_temps(root: &Path) -> HashMap<String, f32> {
+    let mut temps = HashMap::new();
+    for (chip, name) in chips(root) {
+        let device = chip.join("device");
+        let (blocks, nvme) = match name.as_str() {
+            "nvme" => (device, true),
+            "drivetemp" => (device.join("block"), false),
+            _ => continue,
+        };
+        let (Some(millis), Ok(entries)) = (
+            read(&chip.join("temp1_input")).and_then(|t| t.parse::<i32>().ok()),
+            fs::read_dir(&blocks),
+        ) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let Some(block) = entry.file_name().to_str().map(str::to_string) else {
+                continue;
+            };
+            // The controller directory holds more than namespaces.
+            if nvme && !entry.path().join("size").exists() {
+                continue;
+            }
+            temps.insert(namespace(&block), millis as f32 / 1000.0);
+        }
+    }
+    temps
+}
+
+/// `nvme0c0n1` as `nvme0n1`, the name of the block device in `/dev`.
+fn namespace(block: &str) -> String {
+    let Some(rest) = block.strip_prefix("nvme") else {
+        return block.to_string();
+    };
+    match rest
+        .split_once(NVME_PATH)
+        .and_then(|(ctrl, path)| Some((ctrl, path.split_once('n')?.1)))
+    {
+        Some((ctrl, ns)) => format!("nvme{}n{}", ctrl, ns),
+        None => block.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(path: &Path, contents: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn test_fans() {
+        let root = std::env::temp_dir().join(format!("sysdash-hwmon-{}", std::process::id()));
+        write(&root.join("hwmon0/name"), "acpitz\n");
+        write(&root.join("hwmon0/temp1_input"), "45000\n");
+        write(&root.join("hwmon10/name"), "thinkpad\n");
+        write(&root.join("hwmon10/fan1_input"), "2950\n");
+        write(&root.join("hwmon2/name"), "nct6798\n");
+        write(&root.join("hwmon2/fan10_input"), "0\n");
+        write(&root.join("hwmon2/fan2_input"), "1210\n");
+        write(&root.join("hwmon2/fan2_label"), "CPU Fan\n");
+        write(&root.join("hwmon2/fan2_min"), "300\n");
+
+        let fans = fans(&root);
+        fs::remove_dir_all(&root).unwrap();
+        let fan = |label: &str, rpm| FanInfo {
+            label: label.into(),
+            rpm,
+        };
+        assert_eq!(
+            fans,
+            [
+                fan("CPU Fan", 1210),
+                fan("nct6798 fan10", 0),
+                fan("thinkpad fan1", 2950),
+            ]
+        );
+        assert!(super::fans(&root).is_empty());
+    }
+
+    #[test]
+    fn test_drive_temps() {
+        let root = std::env::temp_dir().join(format!("sysdash-drivetemp-{}", std::process::id()));
+        write(&root.join("hwmon1/name"), "nvme\n");
+        write(&root.join("hwmon1/temp1_input"), "41850\n");
+        write(&root.join("hwmon1/device/nvme0c0n1/size"), "1000215216\n");
+        write(&root.join("hwmon1/device/power/control"), "on\n");
+        write(&root.join("hwmon2/name"), "drivetemp\n");
+        write(&root.join("hwmon2/temp1_input"), "35000\n");
+        write(&root.join("hwmon2/device/block/sda/size"), "3907029168\n");
+        write(&root.join("hwmon3/name"), "k10temp\n");
+        write(&root.join("hwmon3/temp1_input"), "60000\n");
+
+        let temps = drive_temps(&root);
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(
+            temps,
+            HashMap::from([("nvme0n1".to_string(), 41.85), ("sda".to_string(), 35.0)])
+        );
+        assert_eq!(namespace("nvme1n2"), "nvme1n2");
+    }
+
+    #[test]
+    fn test_core_temps() {
+        let root = std::env::temp_dir().join(format!("sysdash-coretemp-{}", std::process::id()));
+        let (hwmon, cpus) = (root.join("hwmon"), root.join("cpu"));
+        let temp = |index: u32, label: &str, millis: u32| {
+            let chip = hwmon.join("hwmon4");
+            write(
+                &chip.join(format!("temp{}_label", index)),
+                &format!("{}\n", label),
+            );
+            write(
+                &chip.join(format!("temp{}_input", index)),
+                &format!("{}\n", millis),
+            );
+        };
+        write(&hwmon.join("hwmon4/name"), "coretemp\n");
+        temp(1, "Package id 0", 61000);
+        temp(2, "Core 0", 55000);
+        temp(3, "Core 4", 72000);
+        // Two cores with a hyper-thread each, listed core by core.
+        for (cpu, core) in [(0, 0), (1, 4), (2, 0), (3, 4)] {
+            let topology = cpus.join(format!("cpu{}/topology", cpu));
+            write(&topology.join("physical_package_id"), "0\n");
+            write(&topology.join("core_id"), &format!("{}\n", core));
+        }
+
+        let temps = core_temps(&hwmon, &cpus, 5);
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(
+            temps,
+            [Some(55.0), Some(72.0), Some(55.0), Some(72.0), None]
+        );
+        assert!(core_temps(&hwmon, &cpus, 4).is_empty());
+    }
+}
@@ -0,0 +1,569 @@
+//! Linux and Android extras that sysinfo doesn't cover: board details on
+//! single-board computers (see [`crate::sbc`]), a restricted mode for
+//! Android and Termux, where apps can't read `/proc/stat` or see other
+//! users' processes, CPU caps through cgroup v2, swap activity from
+//! `/proc/vmstat`, disk activity from `/proc/diskstats`, the battery
+//! from `/sys/class/power_supply`, CPU package power from the RAPL
+//! counters under `/sys/class/powercap` and fans, per-core and drive
+//! temperatures from [`super::hwmon`].
+
+use super::unix::Throttles;
+use super::{hwmon, BatteryInfo, FanInfo, Interface, Platform, ProcessInfo};
+use crate::cgroups::{CgroupInfo, CgroupReader};
+use crate::sbc::{SbcInfo, SbcReader};
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+const POWER_SUPPLY: &str = "/sys/class/power_supply";
+const POWERCAP: &str = "/sys/class/powercap";
+/// Block devices that aren't disks of their own: loop and RAM disks, and
+/// device mapper and md RAID volumes, whose I/O the disks below them count.
+const VIRTUAL_DISKS: [&str; 5] = ["loop", "ram", "zram", "dm-", "md"];
+/// `cpu.max` quotas are per this many microseconds.
+const CGROUP_PERIOD: u32 = 100_000;
+
+pub struct Linux {
+    /// Looked for on the first sensor refresh rather than at startup.
+    board: Option<Option<SbcReader>>,
+    restricted: bool,
+    /// utime + stime of each process at the previous refresh, in clock
+    /// ticks; only kept in restricted mode.
+    times: HashMap<u32, (u64, Instant)>,
+    /// Processes moved into a cgroup of their own to cap them, with the
+    /// cgroup they came from.
+    cgroups: HashMap<u32, PathBuf>,
+    /// Caps for processes that couldn't be moved, e.g. without root or on
+    /// cgroup v1.
+    throttles: Throttles,
+    /// For the cgroup view.
+    usage: CgroupReader,
+    rapl: Rapl,
+}
+
+impl Default for Linux {
+    fn default() -> Self {
+        Self {
+            board: None,
+            restricted: restricted(),
+            times: HashMap::new(),
+            cgroups: HashMap::new(),
+            throttles: Throttles::default(),
+            usage: CgroupReader::new(CGROUP_ROOT),
+            rapl: Rapl::default(),
+        }
+    }
+}
+
+/// Android since 8 (and so Termux) denies `/proc/stat`, which sysinfo needs
+/// for CPU usage, and hides other users' processes.
+fn restricted() -> bool {
+    cfg!(target_os = "android")
+        || std::env::var_os("TERMUX_VERSION").is_some()
+        || std::fs::File::open("/proc/stat").is_err()
+}
+
+impl Platform for Linux {
+    fn board(&mut self) -> Option<SbcInfo> {
+        // Phones have a device tree too, but no board sensors an app may read.
+        if self.restricted {
+            return None;
+        }
+        self.board
+            .get_or_insert_with(SbcReader::detect)
+            .as_mut()
+            .map(SbcReader::read)
+    }
+
+    fn restricted(&self) -> bool {
+        self.restricted
+    }
+
+    fn battery(&mut self) -> Option<BatteryInfo> {
+        battery(Path::new(POWER_SUPPLY))
+    }
+
+    fn fans(&mut self) -> Vec<FanInfo> {
+        hwmon::fans(Path::new(hwmon::HWMON))
+    }
+
+    fn core_temperatures(&mut self, cpus: usize) -> Vec<Option<f32>> {
+        hwmon::core_temps(Path::new(hwmon::HWMON), Path::new(hwmon::CPUS), cpus)
+    }
+
+    fn drive_temperatures(&mut self) -> HashMap<String, f32> {
+        hwmon::drive_temps(Path::new(hwmon::HWMON))
+    }
+
+    /// sysinfo scales process times by the `/proc/stat` totals, so without
+    /// them every process shows 0%. Their own tick counters are readable,
+    /// and are compared against wall-clock time instead.
+    fn process_cpu(&mut self, procs: &mut [ProcessInfo]) {
+        if !self.restricted {
+            return;
+        }
+        let hz = unsafe { libc::sysconf(libc::_SC_CLK_TCK) }.max(1) as f32;
+        let now = Instant::now();
+        let mut times = HashMap::with_capacity(procs.len());
+        for p in procs.iter_mut() {
+            let Some(ticks) = std::fs::read_to_string(format!("/proc/{}/stat", p.pid))
+                .ok()
+                .and_then(|stat| cpu_ticks(&stat))
+            else {
+                continue;
+            };
+            if let Some(&(before, at)) = self.times.get(&p.pid) {
+                let secs = now.duration_since(at).as_secs_f32().max(0.001);
+                p.cpu = ticks.saturating_sub(before) as f32 / hz / secs * 100.0;
+            }
+            times.insert(p.pid, (ticks, now));
+        }
+        self.times = times;
+    }
+
+    fn open_files(&self, pid: u32) -> Option<usize> {
+        Some(std::fs::read_dir(format!("/proc/{}/fd", pid)).ok()?.count())
+    }
+
+    fn interfaces(&mut self) -> Option<Vec<Interface>> {
+        super::unix::interfaces()
+    }
+
+    fn swap_pages(&mut self) -> Option<(u64, u64)> {
+        swap_pages(&std::fs::read_to_string("/proc/vmstat").ok()?)
+    }
+
+    fn disk_bytes(&mut self) -> Option<(u64, u64)> {
+        disk_bytes(&std::fs::read_to_string("/proc/diskstats").ok()?, |name| {
+            Path::new("/sys/block").join(name).exists()
+        })
+    }
+
+    fn renice(&self, pid: u32, delta: i32) -> io::Result<i32> {
+        super::unix::renice(pid, delta)
+    }
+
+    fn cpu_power(&mut self) -> Option<f32> {
+        if self.restricted {
+            return None;
+        }
+        self.rapl.read(Path::new(POWERCAP), Instant::now())
+    }
+
+    fn cgroups(&mut self) -> Option<Vec<CgroupInfo>> {
+        if self.restricted {
+            return None;
+        }
+        self.usage.read(Instant::now())
+    }
+
+    /// Prefers `cpu.max` in a cgroup made for the process, which the kernel
+    /// enforces across all its threads, and falls back to stopping and
+    /// continuing it.
+    fn limit_cpu(&mut self, pid: u32, percent: Option<u32>) -> io::Result<()> {
+        let root = Path::new(CGROUP_ROOT);
+        let dir = root.join(format!("sysdash-{}", pid));
+        let Some(percent) = percent else {
+            if let Some(original) = self.cgroups.remove(&pid) {
+                // Fails once the process has exited, which is fine.
+                let _ = std::fs::write(original.join("cgroup.procs"), pid.to_string());
+                std::fs::remove_dir(&dir)?;
+            }
+            return self.throttles.limit(pid, None);
+        };
+        let moved = self.cgroups.contains_key(&pid)
+            || std::fs::read_to_string(format!("/proc/{}/cgroup", pid))
+                .ok()
+                .and_then(|text| cgroup_path(&text).map(|p| root.join(p)))
+                .is_some_and(|original| {
+                    let moved = move_to_cgroup(root, &dir, pid).is_ok();
+                    if moved {
+                        self.cgroups.insert(pid, original);
+                    } else {
+                        let _ = std::fs::remove_dir(&dir);
+                    }
+                    moved
+                });
+        if moved {
+            let quota = percent * CGROUP_PERIOD / 100;
+            return std::fs::write(dir.join("cpu.max"), format!("{} {}", quota, CGROUP_PERIOD));
+        }
+        self.throttles.limit(pid, Some(percent))
+    }
+}
+
+/// Creates `dir` below the root cgroup with the cpu controller and moves
+/// `pid` into it. Needs root, and the cgroup v2 hierarchy.
+fn move_to_cgroup(root: &Path, dir: &Path, pid: u32) -> io::Result<()> {
+    // Already enabled under systemd; harmless if it is.
+    let _ = std::fs::write(root.join("cgroup.subtree_control"), "+cpu");
+    std::fs::create_dir_all(dir)?;
+    if !dir.join("cpu.max").exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "cpu controller not available",
+        ));
+    }
+    std::fs::write(dir.join("cgroup.procs"), pid.to_string())
+}
+
+/// The cgroup v2 path in a `/proc/<pid>/cgroup` file, relative to the root:
+/// the `0::` line. Hybrid setups list v1 hierarchies as well.
+fn cgroup_path(text: &str) -> Option<&str> {
+    text.lines()
+        .find_map(|line| line.strip_prefix("0::"))
+        .map(|path| path.trim_start_matches('/'))
+}
+
+/// The laptop batteries under `power_supply`, combined: laptops with a
+/// second battery report the charge of both together. Batteries of
+/// peripherals such as wireless mice have the `Device` scope and are
+/// skipped, as are chargers.
+fn battery(power_supply: &Path) -> Option<BatteryInfo> {
+    let read = |dir: &Path, file: &str| {
+        std::fs::read_to_string(dir.join(file))
+            .ok()
+            .map(|text| text.trim().to_string())
+    };
+    let num = |dir: &Path, file: &str| read(dir, file)?.parse::<u64>().ok();
+    // µWh, or µAh from drivers that only count charge.
+    let level = |dir: &Path, what: &str| {
+        num(dir, &format!("energy_{}", what)).or_else(|| num(dir, &format!("charge_{}", what)))
+    };
+
+    let mut batteries: Vec<PathBuf> = std::fs::read_dir(power_supply)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|dir| {
+            read(dir, "type").as_deref() == Some("Battery")
+                && read(dir, "scope").as_deref() != Some("Device")
+        })
+        .collect();
+    batteries.sort();
+    let first = batteries.first()?;
+
+    let sum = |what: &str| {
+        batteries
+            .iter()
+            .map(|dir| level(dir, what))
+            .sum::<Option<u64>>()
+    };
+    let (now, full, design) = (sum("now"), sum("full"), sum("full_design"));
+    let percent = match (now, full) {
+        (Some(now), Some(full)) if full > 0 => now as f32 / full as f32 * 100.0,
+        _ => num(first, "capacity")? as f32,
+    };
+    let charging = batteries
+        .iter()
+        .any(|dir| read(dir, "status").as_deref() == Some("Charging"));
+    // The rate in the unit of the levels: µW next to energy, µA next to
+    // charge. Watts need the voltage for the latter.
+    let rate = batteries
+        .iter()
+        .map(|dir| num(dir, "power_now").or_else(|| num(dir, "current_now")))
+        .sum::<Option<u64>>()
+        .filter(|&rate| rate > 0);
+    let power = batteries
+        .iter()
+        .map(|dir| match num(dir, "power_now") {
+            Some(power) => Some(power as f32 / 1e6),
+            None => Some(num(dir, "current_now")? as f32 * num(dir, "voltage_now")? as f32 / 1e12),
+        })
+        .sum::<Option<f32>>()
+        .filter(|&power| power > 0.0);
+    let left = if charging {
+        full.zip(now).map(|(full, now)| full.saturating_sub(now))
+    } else {
+        now
+    };
+    Some(BatteryInfo {
+        percent: percent.min(100.0),
+        charging,
+        // Drivers that don't count cycles report 0.
+        cycle_count: batteries
+            .iter()
+            .filter_map(|dir| num(dir, "cycle_count"))
+            .find(|&cycles| cycles > 0)
+            .map(|cycles| cycles as u32),
+        health: match (full, design) {
+            (Some(full), Some(design)) if design > 0 => Some(full as f32 / design as f32 * 100.0),
+            _ => None,
+        },
+        // Tenths of a degree.
+        temperature: read(first, "temp")
+            .and_then(|t| t.parse::<i32>().ok())
+            .map(|t| t as f32 / 10.0),
+        power,
+        // Levels over the rate are hours.
+        time_left: left.zip(rate).map(|(left, rate)| left * 3600 / rate),
+    })
+}
+
+/// The package domains of the RAPL energy counters, one per CPU socket,
+/// with the value their counter wraps at. Found on the first read; since
+/// Linux 5.10 the counters are readable by root only, and without them
+/// nothing is reported.
+#[derive(Default)]
+struct Rapl {
+    domains: Option<Vec<(PathBuf, u64)>>,
+    /// Each domain's `energy_uj` at the previous read.
+    last: Option<(Vec<u64>, Instant)>,
+}
+
+impl Rapl {
+    fn read(&mut self, powercap: &Path, now: Instant) -> Option<f32> {
+        let domains = self.domains.get_or_insert_with(|| rapl_domains(powercap));
+        if domains.is_empty() {
+            return None;
+        }
+        let energy = domains
+            .iter()
+            .map(|(dir, _)| {
+                let energy = std::fs::read_to_string(dir.join("energy_uj")).ok()?;
+                energy.trim().parse().ok()
+            })
+            .collect::<Option<Vec<u64>>>()?;
+        let (before, at) = self.last.replace((energy.clone(), now))?;
+        let secs = now.duration_since(at).as_secs_f64();
+        if secs <= 0.0 {
+            return None;
+        }
+        let joules: u64 = energy
+            .iter()
+            .zip(&before)
+            .zip(domains.iter())
+            .map(|((&now, &before), (_, range))| {
+                if now >= before {
+                    now - before
+                } else {
+                    range.saturating_sub(before) + now
+                }
+            })
+            .sum();
+        Some((joules as f64 / 1e6 / secs) as f32)
+    }
+}
+
+/// `intel-rapl:N` entries named `package-N`, which AMD's Zen driver uses
+/// too. Their `intel-rapl:N:M` subdomains (cores, uncore, DRAM) are parts
+/// of the package, `psys` covers the whole platform and `intel-rapl-mmio`
+/// repeats the package counter, so those are left out.
+fn rapl_domains(powercap: &Path) -> Vec<(PathBuf, u64)> {
+    let Ok(entries) = std::fs::read_dir(powercap) else {
+        return Vec::new();
+    };
+    let mut domains: Vec<(PathBuf, u64)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            name.to_str()?.strip_prefix("intel-rapl:")?.parse::<u32>().ok()?;
+            let dir = entry.path();
+            let read = |file: &str| std::fs::read_to_string(dir.join(file)).ok();
+            if !read("name")?.starts_with("package") {
+                return None;
+            }
+            let range = read("max_energy_range_uj")?.trim().parse().ok()?;
+            Some((dir, range))
+        })
+        .collect();
+    domains.sort();
+    domains
+}
+
+/// `pswpin` and `pswpout` from `/proc/vmstat`: pages swapped in and out
+/// since boot.
+fn swap_pages(vmstat: &str) -> Option<(u64, u64)> {
+    let value = |key: &str| {
+        vmstat.lines().find_map(|line| {
+            let (name, value) = line.split_once(' ')?;
+            (name == key).then(|| value.trim().parse().ok())?
+        })
+    };
+    Some((value("pswpin")?, value("pswpout")?))
+}
+
+/// Bytes read and written since boot by the disks of `/proc/diskstats`,
+/// which counts in 512-byte sectors. Partitions would count their disk's
+/// I/O twice, so only names `is_disk` accepts (those in `/sys/block`) are
+/// summed.
+fn disk_bytes(diskstats: &str, is_disk: impl Fn(&str) -> bool) -> Option<(u64, u64)> {
+    let mut total = None;
+    for line in diskstats.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let (Some(name), Some(read), Some(written)) = (fields.get(2), fields.get(5), fields.get(9))
+        else {
+            continue;
+        };
+        if VIRTUAL_DISKS.iter().any(|v| name.starts_with(v)) || !is_disk(name) {
+            continue;
+        }
+        let (Ok(read), Ok(written)) = (read.parse::<u64>(), written.parse::<u64>()) else {
+            continue;
+        };
+        let (r, w) = total.get_or_insert((0, 0));
+        *r += read * 512;
+        *w += written * 512;
+    }
+    total
+}
+
+/// utime + stime from a `/proc/<pid>/stat` line. The command name may
+/// contain spaces and parentheses, so fields are counted from the last `)`.
+fn cpu_ticks(stat: &str) -> Option<u64> {
+    let (_, rest) = stat.rsplit_once(')')?;
+    let mut fields = rest.split_whitespace().skip(11);
+    let utime: u64 = fields.next()?.parse().ok()?;
+    let stime: u64 = fields.next()?.parse().ok()?;
+    Some(utime + stime)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cpu_ticks() {
+        let stat = "4242 (tmux: server (1)) S 1 4242 4242 0 -1 4194560 1503 0 0 0 \
+                    120 35 0 0 20 0 1 0 12345 9000000 800 18446744073709551615";
+        assert_eq!(cpu_ticks(stat), Some(155));
+        assert_eq!(cpu_ticks("garbage"), None);
+    }
+
+    #[test]
+    fn test_swap_pages() {
+        let vmstat = "nr_free_pages 812345\npswpin 1520\npswpout 30411\npgpgin 99\n";
+        assert_eq!(swap_pages(vmstat), Some((1520, 30411)));
+        assert_eq!(swap_pages("pswpin 3\n"), None);
+    }
+
+    #[test]
+    fn test_disk_bytes() {
+        let diskstats = "   7       0 loop0 52 0 2110 8 0 0 0 0 0 20 8 0 0 0 0 0 0\n \
+             259       0 nvme0n1 9000 100 400000 5000 2000 300 100000 900 0 4000 6000\n \
+             259       1 nvme0n1p1 8000 100 390000 4800 1900 300 99000 880 0 3900 5700\n \
+               8       0 sda 10 0 8 1 4 0 16 2 0 3 3\n \
+             253       0 dm-0 8900 0 399000 5100 2100 0 100000 950 0 4100 6050\n";
+        let is_disk = |name: &str| !name.contains('p') || name == "nvme0n1";
+        assert_eq!(
+            disk_bytes(diskstats, is_disk),
+            Some(((400_000 + 8) * 512, (100_000 + 16) * 512))
+        );
+        assert_eq!(disk_bytes("", is_disk), None);
+    }
+
+    #[test]
+    fn test_battery() {
+        let dir = std::env::temp_dir().join(format!("sysdash-power-{}", std::process::id()));
+        let supply = |name: &str, files: &[(&str, &str)]| {
+            std::fs::create_dir_all(dir.join(name)).unwrap();
+            for (file, value) in files {
+                std::fs::write(dir.join(name).join(file), format!("{}\n", value)).unwrap();
+            }
+        };
+        supply("AC", &[("type", "Mains"), ("online", "1")]);
+        supply(
+            "BAT0",
+            &[
+                ("type", "Battery"),
+                ("status", "Charging"),
+                ("capacity", "50"),
+                ("energy_now", "20000000"),
+                ("energy_full", "40000000"),
+                ("energy_full_design", "50000000"),
+                ("cycle_count", "0"),
+            ],
+        );
+        supply(
+            "BAT1",
+            &[
+                ("type", "Battery"),
+                ("status", "Full"),
+                ("capacity", "100"),
+                ("energy_now", "20000000"),
+                ("energy_full", "20000000"),
+                ("energy_full_design", "25000000"),
+                ("cycle_count", "212"),
+            ],
+        );
+        supply(
+            "hidpp_battery_0",
+            &[("type", "Battery"), ("scope", "Device"), ("capacity", "5")],
+        );
+
+        let charging = battery(&dir);
+        supply("BAT0", &[("status", "Discharging"), ("power_now", "15000000")]);
+        supply("BAT1", &[("status", "Discharging"), ("power_now", "5000000")]);
+        let discharging = battery(&dir);
+        supply(
+            "BAT0",
+            &[
+                ("energy_now", "0"),
+                ("current_now", "1000000"),
+                ("voltage_now", "12000000"),
+            ],
+        );
+        std::fs::remove_file(dir.join("BAT0/power_now")).unwrap();
+        let by_current = battery(&dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            charging,
+            Some(BatteryInfo {
+                percent: 66.666_67,
+                charging: true,
+                cycle_count: Some(212),
+                health: Some(80.0),
+                temperature: None,
+                power: None,
+                time_left: None,
+            })
+        );
+        // 40 Wh at 20 W.
+        let discharging = discharging.unwrap();
+        assert_eq!(discharging.power, Some(20.0));
+        assert_eq!(discharging.time_left, Some(2 * 3600));
+        assert!(!discharging.charging);
+        // 1 A at 12 V, and 5 W.
+        assert_eq!(by_current.power, Some(17.0));
+        assert_eq!(super::battery(&dir), None);
+    }
+
+    #[test]
+    fn test_rapl() {
+        let dir = std::env::temp_dir().join(format!("sysdash-powercap-{}", std::process::id()));
+        let domain = |name: &str, label: &str, energy: u64| {
+            std::fs::create_dir_all(dir.join(name)).unwrap();
+            std::fs::write(dir.join(name).join("name"), format!("{}\n", label)).unwrap();
+            std::fs::write(dir.join(name).join("energy_uj"), format!("{}\n", energy)).unwrap();
+            std::fs::write(dir.join(name).join("max_energy_range_uj"), "1000000000\n").unwrap();
+        };
+        domain("intel-rapl:0", "package-0", 999_000_000);
+        domain("intel-rapl:0:0", "core", 5);
+        domain("intel-rapl:1", "psys", 7);
+        domain("intel-rapl-mmio:0", "package-0", 9);
+
+        let mut rapl = Rapl::default();
+        let start = Instant::now();
+        assert_eq!(rapl.read(&dir, start), None);
+        assert_eq!(rapl.domains.as_ref().map(Vec::len), Some(1));
+        // 30 J over 2 s, across the wrap.
+        domain("intel-rapl:0", "package-0", 29_000_000);
+        let power = rapl.read(&dir, start + std::time::Duration::from_secs(2));
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(power, Some(15.0));
+        assert_eq!(rapl.read(&dir, start), None);
+    }
+
+    #[test]
+    fn test_cgroup_path() {
+        let text = "12:cpuset:/\n1:name=systemd:/user.slice\n0::/user.slice/user-1000.slice/session-2.scope\n";
+        assert_eq!(
+            cgroup_path(text),
+            Some("user.slice/user-1000.slice/session-2.scope")
+        );
+        assert_eq!(cgroup_path("0::/\n"), Some(""));
+        assert_eq!(cgroup_path("4:memory:/docker\n"), None);
+    }
+}
@@ -0,0 +1,111 @@
+//! macOS extras that sysinfo doesn't cover. Battery details come from the
+//! `AppleSmartBattery` entry of the IOKit registry, read with `ioreg` so no
+//! IOKit bindings are needed. The parser is plain text, so it is tested on
+//! every platform.
+
+use super::BatteryInfo;
+
+#[cfg(target_os = "macos")]
+#[derive(Default)]
+pub struct MacOs {
+    throttles: super::unix::Throttles,
+}
+
+#[cfg(target_os = "macos")]
+impl super::Platform for MacOs {
+    /// Reads the battery, or `None` on machines without one.
+    fn battery(&mut self) -> Option<BatteryInfo> {
+        let out = std::process::Command::new("ioreg")
+            .args(["-rn", "AppleSmartBattery"])
+            .output()
+            .ok()?;
+        parse_battery(&String::from_utf8_lossy(&out.stdout))
+    }
+
+    fn interfaces(&mut self) -> Option<Vec<super::Interface>> {
+        super::unix::interfaces()
+    }
+
+    fn renice(&self, pid: u32, delta: i32) -> std::io::Result<i32> {
+        super::unix::renice(pid, delta)
+    }
+
+    fn limit_cpu(&mut self, pid: u32, percent: Option<u32>) -> std::io::Result<()> {
+        self.throttles.limit(pid, percent)
+    }
+}
+
+/// Parses the `"Key" = value` lines of `ioreg -rn AppleSmartBattery`.
+pub fn parse_battery(text: &str) -> Option<BatteryInfo> {
+    let value = |key: &str| {
+        let prefix = format!("\"{}\" = ", key);
+        text.lines()
+            .find_map(|line| line.trim().strip_prefix(&prefix).map(str::trim))
+    };
+    let number = |key: &str| value(key).and_then(|v| v.parse::<f32>().ok());
+
+    let current = number("CurrentCapacity")?;
+    let max = number("MaxCapacity").filter(|&max| max > 0.0)?;
+    // Apple Silicon reports MaxCapacity as a percentage and the real charge
+    // capacity in mAh as AppleRawMaxCapacity; Intel Macs report mAh in both.
+    let full = number("AppleRawMaxCapacity").unwrap_or(max);
+    let health = number("DesignCapacity")
+        .filter(|&design| design > 0.0 && full > 100.0)
+        .map(|design| (full / design * 100.0).min(100.0));
+    // mA, negative while discharging, though older releases print it as
+    // an unsigned 64-bit number.
+    let amperage = value("InstantAmperage")
+        .or_else(|| value("Amperage"))
+        .and_then(|a| a.parse::<i64>().ok().or_else(|| Some(a.parse::<u64>().ok()? as i64)));
+    let power = amperage
+        .zip(number("Voltage"))
+        .map(|(amps, volts)| amps.unsigned_abs() as f32 * volts / 1e6)
+        .filter(|&power| power > 0.0);
+    Some(BatteryInfo {
+        percent: (current / max * 100.0).min(100.0),
+        charging: value("IsCharging") == Some("Yes"),
+        cycle_count: number("CycleCount").map(|c| c as u32),
+        health,
+        // Hundredths of a degree.
+        temperature: number("Temperature").map(|t| t / 100.0),
+        power,
+        // Minutes; 65535 while still estimating.
+        time_left: number("TimeRemaining")
+            .filter(|&minutes| minutes > 0.0 && minutes < 65535.0)
+            .map(|minutes| minutes as u64 * 60),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_battery() {
+        let text = r#"+-o AppleSmartBattery  <class AppleSmartBattery, id 0x1000002a1>
+    {
+      "AppleRawCurrentCapacity" = 4700
+      "AppleRawMaxCapacity" = 5531
+      "CurrentCapacity" = 85
+      "MaxCapacity" = 100
+      "DesignCapacity" = 6075
+      "CycleCount" = 312
+      "IsCharging" = Yes
+      "Temperature" = 3012
+      "InstantAmperage" = 18446744073709550616
+      "Voltage" = 12500
+      "TimeRemaining" = 95
+      "BatteryData" = {"CycleCount"=312,"DesignCapacity"=6075}
+    }"#;
+        let battery = parse_battery(text).unwrap();
+        assert_eq!(battery.percent, 85.0);
+        assert!(battery.charging);
+        assert_eq!(battery.cycle_count, Some(312));
+        assert_eq!(battery.health.map(f32::round), Some(91.0));
+        assert_eq!(battery.temperature, Some(30.12));
+        assert_eq!(battery.power, Some(12.5));
+        assert_eq!(battery.time_left, Some(95 * 60));
+
+        assert_eq!(parse_battery(""), None);
+    }
+}
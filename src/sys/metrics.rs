@@ -0,0 +1,453 @@
+//! The metric registry: every value a [`SysCache`] carries, by name, with
+//! its unit and kind. Exporters, alert rules and the stats popup read
+//! metrics from here instead of from `SysCache` fields, so a new metric is
+//! one [`Registry::register`] call next to the others of its subsystem.
+//!
+//! Metrics with several series, such as one per core or mount point, name
+//! the [`MetricDef::label`] that tells them apart.
+
+use super::gpu::GpuInfo;
+use super::{format_bytes, format_duration_secs, SysCache};
+use std::sync::OnceLock;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Unit {
+    Percent,
+    Celsius,
+    Bytes,
+    BytesPerSec,
+    PagesPerSec,
+    Seconds,
+    Count,
+    Watts,
+}
+
+impl Unit {
+    /// `value` the way the dashboard shows this unit.
+    pub fn format(self, value: f64) -> String {
+        match self {
+            Unit::Percent => format!("{:.1}%", value),
+            Unit::Celsius => format!("{:.1}°C", value),
+            Unit::Bytes => format_bytes(value as u64),
+            Unit::BytesPerSec => format!("{}/s", format_bytes(value as u64)),
+            Unit::PagesPerSec => format!("{:.0}/s", value),
+            Unit::Seconds => format_duration_secs(value as u64),
+            Unit::Count => format!("{:.0}", value),
+            Unit::Watts => format!("{:.1} W", value),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    /// A value that goes up and down.
+    Gauge,
+    /// A total that only grows, until a restart.
+    Counter,
+}
+
+/// One series of a metric: its label value, if the metric has a label.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sample {
+    pub label: Option<String>,
+    pub value: f64,
+}
+
+impl Sample {
+    pub fn new(value: f64) -> Self {
+        Self { label: None, value }
+    }
+
+    pub fn labelled(label: impl Into<String>, value: f64) -> Self {
+        Self {
+            label: Some(label.into()),
+            value,
+        }
+    }
+}
+
+pub struct MetricDef {
+    /// Dotted and lower case, e.g. `memory.utilization`.
+    pub name: &'static str,
+    pub help: &'static str,
+    pub unit: Unit,
+    pub kind: Kind,
+    /// The attribute that tells the series apart, e.g. `mountpoint`.
+    pub label: Option<&'static str>,
+    /// The current series; none where the host doesn't report the metric.
+    pub read: fn(&SysCache) -> Vec<Sample>,
+}
+
+#[derive(Default)]
+pub struct Registry {
+    metrics: Vec<MetricDef>,
+}
+
+impl Registry {
+    /// Adds `def`; a second one with the same name replaces the first.
+    pub fn register(&mut self, def: MetricDef) {
+        self.metrics.retain(|m| m.name != def.name);
+        self.metrics.push(def);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&MetricDef> {
+        self.metrics.iter().find(|m| m.name == name)
+    }
+
+    /// In registration order.
+    pub fn iter(&self) -> impl Iterator<Item = &MetricDef> {
+        self.metrics.iter()
+    }
+
+    /// The value of the series of `name` with `label`, or of its only
+    /// series when `label` is `None`.
+    pub fn value(&self, name: &str, label: Option<&str>, sys: &SysCache) -> Option<f64> {
+        (self.get(name)?.read)(sys)
+            .into_iter()
+            .find(|s| s.label.as_deref() == label)
+            .map(|s| s.value)
+    }
+}
+
+/// Everything the [`Collector`](super::Collector) reads.
+pub fn registry() -> &'static Registry {
+    static REGISTRY: OnceLock<Registry> = OnceLock::new();
+    REGISTRY.get_or_init(|| {
+        let mut registry = Registry::default();
+        register_cpu(&mut registry);
+        register_memory(&mut registry);
+        register_network(&mut registry);
+        register_disks(&mut registry);
+        register_gpu(&mut registry);
+        register_system(&mut registry);
+        registry
+    })
+}
+
+fn gauge(
+    name: &'static str,
+    help: &'static str,
+    unit: Unit,
+    label: Option<&'static str>,
+    read: fn(&SysCache) -> Vec<Sample>,
+) -> MetricDef {
+    MetricDef {
+        name,
+        help,
+        unit,
+        kind: Kind::Gauge,
+        label,
+        read,
+    }
+}
+
+fn percent(part: u64, total: u64) -> Option<f64> {
+    (total > 0).then(|| part as f64 / total as f64 * 100.0)
+}
+
+fn register_cpu(registry: &mut Registry) {
+    registry.register(gauge(
+        "cpu.utilization",
+        "CPU busy time across all cores",
+        Unit::Percent,
+        None,
+        |sys| vec![Sample::new(sys.cpu_global as f64)],
+    ));
+    registry.register(gauge(
+        "cpu.core.utilization",
+        "CPU busy time per core",
+        Unit::Percent,
+        Some("cpu"),
+        |sys| {
+            sys.cpu_cores
+                .iter()
+                .enumerate()
+                .map(|(i, &c)| Sample::labelled(i.to_string(), c as f64))
+                .collect()
+        },
+    ));
+    registry.register(gauge(
+        "cpu.temperature",
+        "CPU (or SoC) temperature",
+        Unit::Celsius,
+        None,
+        |sys| {
+            (sys.cpu_temp > 0.0)
+                .then(|| Sample::new(sys.cpu_temp as f64))
+                .into_iter()
+                .collect()
+        },
+    ));
+}
+
+fn register_memory(registry: &mut Registry) {
+    registry.register(gauge(
+        "memory.utilization",
+        "Memory in use, without caches",
+        Unit::Percent,
+        None,
+        |sys| {
+            percent(sys.used_mem, sys.total_mem)
+                .map(Sample::new)
+                .into_iter()
+                .collect()
+        },
+    ));
+    registry.register(gauge(
+        "memory.usage",
+        "Memory used and installed",
+        Unit::Bytes,
+        Some("state"),
+        |sys| {
+            vec![
+                Sample::labelled("used", sys.used_mem as f64),
+                Sample::labelled("total", sys.total_mem as f64),
+            ]
+        },
+    ));
+    registry.register(gauge(
+        "swap.usage",
+        "Swap used and configured",
+        Unit::Bytes,
+        Some("state"),
+        |sys| {
+            if sys.total_swap == 0 {
+                return Vec::new();
+            }
+            vec![
+                Sample::labelled("used", sys.used_swap as f64),
+                Sample::labelled("total", sys.total_swap as f64),
+            ]
+        },
+    ));
+    registry.register(gauge(
+        "swap.io.rate",
+        "Pages swapped in and out",
+        Unit::PagesPerSec,
+        Some("direction"),
+        |sys| {
+            sys.swap_rates
+                .map(|(swap_in, swap_out)| {
+                    vec![
+                        Sample::labelled("in", swap_in as f64),
+                        Sample::labelled("out", swap_out as f64),
+                    ]
+                })
+                .unwrap_or_default()
+        },
+    ));
+}
+
+fn register_network(registry: &mut Registry) {
+    registry.register(gauge(
+        "network.io.rate",
+        "Bytes received and sent over all interfaces",
+        Unit::BytesPerSec,
+        Some("direction"),
+        |sys| {
+            vec![
+                Sample::labelled("receive", sys.rx_rate as f64),
+                Sample::labelled("transmit", sys.tx_rate as f64),
+            ]
+        },
+    ));
+}
+
+fn register_disks(registry: &mut Registry) {
+    registry.register(gauge(
+        "filesystem.usage",
+        "Bytes used per mounted filesystem",
+        Unit::Bytes,
+        Some("mountpoint"),
+        |sys| {
+            sys.disks()
+                .iter()
+                .map(|d| {
+                    let used = d.total.saturating_sub(d.available);
+                    Sample::labelled(d.mount_point.clone(), used as f64)
+                })
+                .collect()
+        },
+    ));
+    registry.register(gauge(
+        "filesystem.utilization",
+        "Share of each mounted filesystem in use",
+        Unit::Percent,
+        Some("mountpoint"),
+        |sys| {
+            sys.disks()
+                .iter()
+                .filter_map(|d| {
+                    let used = percent(d.total.saturating_sub(d.available), d.total)?;
+                    Some(Sample::labelled(d.mount_point.clone(), used))
+                })
+                .collect()
+        },
+    ));
+    registry.register(gauge(
+        "disk.io.rate",
+        "Bytes read from and written to all disks",
+        Unit::BytesPerSec,
+        Some("direction"),
+        |sys| {
+            sys.disk_rates
+                .map(|(read, write)| {
+                    vec![
+                        Sample::labelled("read", read as f64),
+                        Sample::labelled("write", write as f64),
+                    ]
+                })
+                .unwrap_or_default()
+        },
+    ));
+}
+
+fn register_gpu(registry: &mut Registry) {
+    registry.register(gauge(
+        "gpu.utilization",
+        "GPU busy time per card",
+        Unit::Percent,
+        Some("gpu"),
+        |sys| gpu_samples(sys, |g| g.utilization.map(f64::from)),
+    ));
+    registry.register(gauge(
+        "gpu.memory.utilization",
+        "Share of each card's VRAM in use",
+        Unit::Percent,
+        Some("gpu"),
+        |sys| gpu_samples(sys, |g| g.mem_percent().map(f64::from)),
+    ));
+    registry.register(gauge(
+        "gpu.temperature",
+        "GPU temperature per card",
+        Unit::Celsius,
+        Some("gpu"),
+        |sys| gpu_samples(sys, |g| g.temperature.map(f64::from)),
+    ));
+    registry.register(gauge(
+        "gpu.power",
+        "Power drawn by each card",
+        Unit::Watts,
+        Some("gpu"),
+        |sys| gpu_samples(sys, |g| g.power.map(f64::from)),
+    ));
+}
+
+/// One series per GPU that reports the value, labelled with its index.
+fn gpu_samples(sys: &SysCache, read: fn(&GpuInfo) -> Option<f64>) -> Vec<Sample> {
+    sys.gpus
+        .iter()
+        .enumerate()
+        .filter_map(|(i, g)| Some(Sample::labelled(i.to_string(), read(g)?)))
+        .collect()
+}
+
+fn register_system(registry: &mut Registry) {
+    registry.register(MetricDef {
+        name: "uptime",
+        help: "Time since boot",
+        unit: Unit::Seconds,
+        kind: Kind::Counter,
+        label: None,
+        read: |sys| vec![Sample::new(sys.uptime as f64)],
+    });
+    registry.register(gauge(
+        "process.count",
+        "Processes listed",
+        Unit::Count,
+        None,
+        |sys| vec![Sample::new(sys.processes().len() as f64)],
+    ));
+    registry.register(gauge(
+        "cpu.power",
+        "CPU package power",
+        Unit::Watts,
+        None,
+        |sys| sys.cpu_power.map(|w| Sample::new(w as f64)).into_iter().collect(),
+    ));
+    registry.register(gauge(
+        "battery.charge",
+        "Battery charge",
+        Unit::Percent,
+        None,
+        |sys| {
+            sys.battery_percentage()
+                .map(|p| Sample::new(p as f64))
+                .into_iter()
+                .collect()
+        },
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sys::DiskInfo;
+
+    #[test]
+    fn test_registry() {
+        let mut sys = SysCache::default().with_disks(vec![DiskInfo {
+            _name: "sda1".into(),
+            mount_point: "/".into(),
+            total: 100,
+            available: 25,
+            temperature: None,
+        }]);
+        sys.cpu_global = 42.0;
+        sys.rx_rate = 2048;
+
+        let registry = registry();
+        assert_eq!(registry.value("cpu.utilization", None, &sys), Some(42.0));
+        assert_eq!(
+            registry.value("filesystem.utilization", Some("/"), &sys),
+            Some(75.0)
+        );
+        assert_eq!(
+            registry.value("network.io.rate", Some("receive"), &sys),
+            Some(2048.0)
+        );
+        // No sensor, no swap counters: no series rather than zeros.
+        assert_eq!(registry.value("cpu.temperature", None, &sys), None);
+        assert_eq!(registry.value("swap.io.rate", Some("in"), &sys), None);
+        assert!(registry.get("nope").is_none());
+
+        let mut names: Vec<_> = registry.iter().map(|m| m.name).collect();
+        let count = names.len();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), count);
+        assert_eq!(Unit::BytesPerSec.format(2048.0), "2.0K/s");
+    }
+
+    #[test]
+    fn test_gpu_metrics() {
+        let mut sys = SysCache::default();
+        assert_eq!(registry().value("gpu.utilization", Some("0"), &sys), None);
+
+        sys.gpus = vec![
+            GpuInfo {
+                name: "A".into(),
+                utilization: Some(30.0),
+                mem_used: 2,
+                mem_total: 8,
+                temperature: Some(60.0),
+                power: Some(120.5),
+                processes: Vec::new(),
+            },
+            GpuInfo {
+                name: "B".into(),
+                ..GpuInfo::default()
+            },
+        ];
+        let registry = registry();
+        assert_eq!(registry.value("gpu.utilization", Some("0"), &sys), Some(30.0));
+        assert_eq!(
+            registry.value("gpu.memory.utilization", Some("0"), &sys),
+            Some(25.0)
+        );
+        assert_eq!(registry.value("gpu.power", Some("0"), &sys), Some(120.5));
+        // The second card reports nothing.
+        assert_eq!(registry.value("gpu.temperature", Some("1"), &sys), None);
+        assert_eq!(Unit::Watts.format(120.5), "120.5 W");
+    }
+}
@@ -0,0 +1,156 @@
+//! What every Unix platform shares: process priority through
+//! `getpriority`/`setpriority`, CPU caps by stopping and continuing a
+//! process, link states from `getifaddrs`, and running the BSD tools whose
+//! output the platform modules parse.
+
+use super::Interface;
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::CStr;
+use std::io;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// How often a throttled process is stopped and continued.
+const THROTTLE_PERIOD: Duration = Duration::from_millis(100);
+
+/// Changes the nice value of `pid` by `delta`, clamped to -20..=19, and
+/// returns the new value.
+pub fn renice(pid: u32, delta: i32) -> io::Result<i32> {
+    // getpriority may legitimately return -1, so errno has to be cleared
+    // first to tell that apart from an error.
+    clear_errno();
+    let current = unsafe { libc::getpriority(libc::PRIO_PROCESS, pid as libc::id_t) };
+    if current == -1 {
+        let err = io::Error::last_os_error();
+        if err.raw_os_error().unwrap_or(0) != 0 {
+            return Err(err);
+        }
+    }
+    let nice = (current + delta).clamp(-20, 19);
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS, pid as libc::id_t, nice) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(nice)
+}
+
+/// Every network interface, sorted by name, and whether it is up and
+/// running (has a carrier or an association). `getifaddrs` lists an
+/// interface once per address, including its link-level one.
+pub fn interfaces() -> Option<Vec<Interface>> {
+    let mut addrs: *mut libc::ifaddrs = std::ptr::null_mut();
+    if unsafe { libc::getifaddrs(&mut addrs) } != 0 {
+        return None;
+    }
+    let mut links = BTreeMap::new();
+    let mut next = addrs;
+    while let Some(ifa) = unsafe { next.as_ref() } {
+        let name = unsafe { CStr::from_ptr(ifa.ifa_name) }
+            .to_string_lossy()
+            .into_owned();
+        let flags = ifa.ifa_flags as libc::c_int;
+        let up = flags & libc::IFF_UP != 0 && flags & libc::IFF_RUNNING != 0;
+        links.insert(name, up);
+        next = ifa.ifa_next;
+    }
+    unsafe { libc::freeifaddrs(addrs) };
+    Some(
+        links
+            .into_iter()
+            .map(|(name, up)| Interface { name, up })
+            .collect(),
+    )
+}
+
+/// CPU caps applied the way cpulimit does: a thread per process lets it run
+/// for its share of each [`THROTTLE_PERIOD`] and stops it for the rest.
+/// This caps the share of time it runs, not of a core, so a process with
+/// several busy threads still uses more than its percentage of one core.
+#[derive(Default)]
+pub struct Throttles(HashMap<u32, Throttle>);
+
+struct Throttle {
+    percent: Arc<AtomicU32>,
+    stop: Arc<AtomicBool>,
+}
+
+impl Drop for Throttle {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Throttles {
+    /// Caps `pid` at `percent` of the time, or lifts its cap.
+    pub fn limit(&mut self, pid: u32, percent: Option<u32>) -> io::Result<()> {
+        self.0.retain(|_, t| !t.stop.load(Ordering::Relaxed));
+        let Some(percent) = percent else {
+            self.0.remove(&pid);
+            return Ok(());
+        };
+        if let Some(throttle) = self.0.get(&pid) {
+            throttle.percent.store(percent, Ordering::Relaxed);
+            return Ok(());
+        }
+        // Checks that the process exists and may be signalled.
+        if unsafe { libc::kill(pid as libc::pid_t, 0) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        let throttle = Throttle {
+            percent: Arc::new(AtomicU32::new(percent)),
+            stop: Arc::new(AtomicBool::new(false)),
+        };
+        let (shared, stop) = (throttle.percent.clone(), throttle.stop.clone());
+        thread::Builder::new()
+            .name(format!("throttle-{}", pid))
+            .spawn(move || throttle_loop(pid as libc::pid_t, &shared, &stop))?;
+        self.0.insert(pid, throttle);
+        Ok(())
+    }
+}
+
+fn throttle_loop(pid: libc::pid_t, percent: &AtomicU32, stop: &AtomicBool) {
+    while !stop.load(Ordering::Relaxed) {
+        let share = percent.load(Ordering::Relaxed).clamp(1, 100);
+        let run = THROTTLE_PERIOD * share / 100;
+        if unsafe { libc::kill(pid, libc::SIGCONT) } == -1 {
+            // Gone; the next call to limit drops it.
+            stop.store(true, Ordering::Relaxed);
+            return;
+        }
+        thread::sleep(run);
+        if share < 100 && !stop.load(Ordering::Relaxed) {
+            unsafe { libc::kill(pid, libc::SIGSTOP) };
+            thread::sleep(THROTTLE_PERIOD - run);
+        }
+    }
+    unsafe { libc::kill(pid, libc::SIGCONT) };
+}
+
+fn clear_errno() {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    unsafe {
+        *libc::__errno_location() = 0
+    };
+    #[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd"))]
+    unsafe {
+        *libc::__error() = 0
+    };
+    #[cfg(any(target_os = "openbsd", target_os = "netbsd"))]
+    unsafe {
+        *libc::__errno() = 0
+    };
+}
+
+/// The stdout of `program`, or `None` if it couldn't be run or failed.
+#[cfg(any(target_os = "freebsd", target_os = "openbsd", target_os = "netbsd"))]
+pub fn run(program: &str, args: &[&str]) -> Option<String> {
+    let out = std::process::Command::new(program)
+        .args(args)
+        .output()
+        .ok()?;
+    out.status
+        .success()
+        .then(|| String::from_utf8_lossy(&out.stdout).into_owned())
+}
@@ -0,0 +1,10 @@
+//! Windows. sysinfo covers CPU, memory, processes, disks and network, and
+//! reads temperatures through WMI; nothing is added on top yet, and
+//! processes can't be reniced.
+
+use super::Platform;
+
+#[derive(Default)]
+pub struct Windows;
+
+impl Platform for Windows {}
@@ -0,0 +1,259 @@
+//! Colour themes: the built-in palettes and those defined in `config.toml`,
+//! cycled at runtime with Ctrl+t.
+//!
+//! ```toml
+//! theme = "gruvbox"    # the theme to start with
+//!
+//! [themes.solarized]   # like [colors]; missing colours are dark's
+//! background = "#002b36"
+//! accent = "#b58900"
+//! ```
+//!
+//! A `[colors]` table is the theme `custom`, started with unless `theme`
+//! names another. A theme of `config.toml` with a built-in name replaces it.
+//!
+//! Terminals without 24-bit colour get every theme in the 16 ANSI colours
+//! instead, on the terminal's own background and text colour, which also
+//! keeps the dashboard readable on a light terminal.
+
+use crate::config::{Colors, Config};
+use ratatui::buffer::Buffer;
+use ratatui::style::Color;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    pub name: String,
+    pub colors: Colors,
+}
+
+/// In the order Ctrl+t cycles through them.
+pub const BUILT_IN: [&str; 6] = [
+    "dark",
+    "light",
+    "tokyo-night",
+    "gruvbox",
+    "dracula",
+    "monochrome",
+];
+
+pub fn built_in(name: &str) -> Option<Colors> {
+    let colors = match name {
+        "dark" => Colors::default(),
+        "light" => Colors {
+            background: Color::Rgb(250, 250, 250),
+            border: Color::Rgb(120, 120, 120),
+            accent: Color::Rgb(0, 135, 90),
+            high: Color::Rgb(200, 30, 30),
+            warn: Color::Rgb(180, 95, 0),
+            text: Color::Rgb(40, 40, 50),
+            header_bg: Color::Rgb(210, 215, 230),
+            header_fg: Color::Black,
+        },
+        "tokyo-night" => Colors {
+            background: Color::Rgb(26, 27, 38),
+            border: Color::Rgb(86, 95, 137),
+            accent: Color::Rgb(122, 162, 247),
+            high: Color::Rgb(247, 118, 142),
+            warn: Color::Rgb(224, 175, 104),
+            text: Color::Rgb(192, 202, 245),
+            header_bg: Color::Rgb(65, 72, 104),
+            header_fg: Color::Rgb(192, 202, 245),
+        },
+        "gruvbox" => Colors {
+            background: Color::Rgb(40, 40, 40),
+            border: Color::Rgb(168, 153, 132),
+            accent: Color::Rgb(184, 187, 38),
+            high: Color::Rgb(251, 73, 52),
+            warn: Color::Rgb(250, 189, 47),
+            text: Color::Rgb(235, 219, 178),
+            header_bg: Color::Rgb(80, 73, 69),
+            header_fg: Color::Rgb(251, 241, 199),
+        },
+        "dracula" => Colors {
+            background: Color::Rgb(40, 42, 54),
+            border: Color::Rgb(98, 114, 164),
+            accent: Color::Rgb(189, 147, 249),
+            high: Color::Rgb(255, 85, 85),
+            warn: Color::Rgb(255, 184, 108),
+            text: Color::Rgb(248, 248, 242),
+            header_bg: Color::Rgb(68, 71, 90),
+            header_fg: Color::Rgb(248, 248, 242),
+        },
+        // The terminal's own background and no hues, for e-ink and
+        // recordings.
+        "monochrome" => Colors {
+            background: Color::Reset,
+            border: Color::Gray,
+            accent: Color::White,
+            high: Color::White,
+            warn: Color::Gray,
+            text: Color::Gray,
+            header_bg: Color::Gray,
+            header_fg: Color::Black,
+        },
+        _ => return None,
+    };
+    Some(colors)
+}
+
+/// The built-in themes, then those of `config` by name, then `custom`.
+pub fn list(config: &Config) -> Vec<Theme> {
+    let mut themes: Vec<Theme> = BUILT_IN
+        .iter()
+        .filter_map(|&name| {
+            Some(Theme {
+                name: name.into(),
+                colors: built_in(name)?,
+            })
+        })
+        .collect();
+    let custom = config.colors.map(|colors| ("custom".to_string(), colors));
+    for (name, colors) in config.themes.clone().into_iter().chain(custom) {
+        match themes.iter_mut().find(|t| t.name == name) {
+            Some(theme) => theme.colors = colors,
+            None => themes.push(Theme { name, colors }),
+        }
+    }
+    themes
+}
+
+/// The index in `themes` of the one to start with.
+pub fn start(config: &Config, themes: &[Theme]) -> Result<usize, String> {
+    let name = match (&config.theme, config.colors) {
+        (Some(name), _) => name.as_str(),
+        (None, Some(_)) => "custom",
+        (None, None) => return Ok(0),
+    };
+    themes.iter().position(|t| t.name == name).ok_or_else(|| {
+        let names: Vec<&str> = themes.iter().map(|t| t.name.as_str()).collect();
+        format!(
+            "unknown theme {:?}, expected one of {}",
+            name,
+            names.join(", ")
+        )
+    })
+}
+
+/// Whether the terminal says it shows 24-bit colour, through `COLORTERM`
+/// as most do.
+pub fn detect_truecolor() -> bool {
+    matches!(
+        std::env::var("COLORTERM").as_deref(),
+        Ok("truecolor") | Ok("24bit")
+    )
+}
+
+/// The ANSI colours with xterm's values, to find the nearest.
+const ANSI: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+/// The closest of the 16 ANSI colours to an RGB one; others as they are.
+pub fn nearest_ansi(color: Color) -> Color {
+    let Color::Rgb(r, g, b) = color else {
+        return color;
+    };
+    let distance = |&(_, (ar, ag, ab)): &(Color, (u8, u8, u8))| {
+        let d = |x: u8, y: u8| (x as i32 - y as i32).pow(2);
+        d(r, ar) + d(g, ag) + d(b, ab)
+    };
+    ANSI.iter()
+        .min_by_key(|c| distance(c))
+        .map_or(color, |c| c.0)
+}
+
+/// `colors` for a 16-colour terminal: its own background and text colour,
+/// the nearest ANSI colour for the rest.
+pub fn ansi16(colors: Colors) -> Colors {
+    Colors {
+        background: Color::Reset,
+        border: nearest_ansi(colors.border),
+        accent: nearest_ansi(colors.accent),
+        high: nearest_ansi(colors.high),
+        warn: nearest_ansi(colors.warn),
+        text: Color::Reset,
+        header_bg: nearest_ansi(colors.header_bg),
+        header_fg: nearest_ansi(colors.header_fg),
+    }
+}
+
+/// Replaces the RGB colours left in a drawn frame, such as those of plugins
+/// and command widgets, with the nearest ANSI ones.
+pub fn downsample(buf: &mut Buffer) {
+    for cell in &mut buf.content {
+        cell.fg = nearest_ansi(cell.fg);
+        cell.bg = nearest_ansi(cell.bg);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_themes() {
+        let config: Config = toml::from_str(
+            "[colors]\naccent = \"red\"\n[themes.gruvbox]\ntext = \"white\"\n[themes.paper]\nbackground = \"white\"",
+        )
+        .unwrap();
+        let themes = list(&config);
+        let names: Vec<&str> = themes.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "dark",
+                "light",
+                "tokyo-night",
+                "gruvbox",
+                "dracula",
+                "monochrome",
+                "paper",
+                "custom"
+            ]
+        );
+        assert_eq!(themes[3].colors.text, Color::White);
+        assert_eq!(themes[6].colors.accent, Colors::default().accent);
+        assert_eq!(start(&config, &themes), Ok(7));
+
+        let config = Config {
+            theme: Some("dracula".into()),
+            ..config
+        };
+        assert_eq!(start(&config, &themes), Ok(4));
+        let config = Config {
+            theme: Some("neon".into()),
+            ..config
+        };
+        assert!(start(&config, &themes)
+            .unwrap_err()
+            .contains("paper, custom"));
+        assert_eq!(start(&Config::default(), &list(&Config::default())), Ok(0));
+    }
+
+    #[test]
+    fn test_ansi16() {
+        assert_eq!(nearest_ansi(Color::Rgb(20, 240, 10)), Color::LightGreen);
+        assert_eq!(nearest_ansi(Color::Rgb(40, 40, 50)), Color::Black);
+        assert_eq!(nearest_ansi(Color::Cyan), Color::Cyan);
+
+        let colors = ansi16(Colors::default());
+        assert_eq!(colors.background, Color::Reset);
+        assert_eq!(colors.text, Color::Reset);
+        assert_eq!(colors.high, Color::LightRed);
+    }
+}
@@ -0,0 +1,85 @@
+//! TLS for the agent protocol (cargo feature "tls"), with rustls. The agent
+//! presents the certificate from remote.toml; clients trust only the CA named
+//! there. A self-signed agent certificate can serve as that CA as long as it
+//! isn't marked as a CA itself (`basicConstraints=CA:FALSE`).
+
+use crate::remote::TlsConfig;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName};
+use rustls::{ClientConfig, ClientConnection, RootCertStore, ServerConfig, StreamOwned};
+use std::io;
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio_rustls::TlsAcceptor;
+
+fn certificates(path: &Path) -> io::Result<Vec<CertificateDer<'static>>> {
+    CertificateDer::pem_file_iter(path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| io::Error::other(format!("{}: {}", path.display(), e)))
+}
+
+fn required<'a>(path: &'a Option<PathBuf>, key: &str) -> io::Result<&'a Path> {
+    path.as_deref().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("remote.toml: [tls] needs {}", key),
+        )
+    })
+}
+
+/// The agent's side: its certificate chain and private key.
+pub fn acceptor(config: &TlsConfig) -> io::Result<TlsAcceptor> {
+    let certs = certificates(required(&config.cert, "cert")?)?;
+    let key_path = required(&config.key, "key")?;
+    let key = PrivateKeyDer::from_pem_file(key_path)
+        .map_err(|e| io::Error::other(format!("{}: {}", key_path.display(), e)))?;
+    let server = ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(io::Error::other)?;
+    Ok(TlsAcceptor::from(Arc::new(server)))
+}
+
+/// The client's side: wraps `stream` and verifies the agent against the CA
+/// and the host name in `addr`.
+pub fn connect(
+    addr: &str,
+    stream: TcpStream,
+    config: &TlsConfig,
+) -> io::Result<StreamOwned<ClientConnection, TcpStream>> {
+    let mut roots = RootCertStore::empty();
+    for cert in certificates(required(&config.ca, "ca")?)? {
+        roots.add(cert).map_err(io::Error::other)?;
+    }
+    let client = ClientConfig::builder()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    let host = host(addr);
+    let name = ServerName::try_from(host.to_string()).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid server name: {}", host),
+        )
+    })?;
+    let conn = ClientConnection::new(Arc::new(client), name).map_err(io::Error::other)?;
+    Ok(StreamOwned::new(conn, stream))
+}
+
+/// The host part of `host:port` or `[v6]:port`.
+fn host(addr: &str) -> &str {
+    let host = addr.rsplit_once(':').map_or(addr, |(host, _)| host);
+    host.trim_start_matches('[').trim_end_matches(']')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_host() {
+        assert_eq!(host("nas.lan:7879"), "nas.lan");
+        assert_eq!(host("[::1]:7879"), "::1");
+        assert_eq!(host("localhost"), "localhost");
+    }
+}
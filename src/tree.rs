@@ -0,0 +1,234 @@
+//! Parent/child links between processes, kept across refreshes. Each update
+//! only touches processes that appeared, exited or were reparented, so the
+//! tree view stays cheap on hosts with thousands of processes.
+
+use crate::sys::ProcessInfo;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+struct Node {
+    parent: Option<u32>,
+    children: Vec<u32>,
+    /// Generation of the last update that listed this process.
+    seen: u64,
+}
+
+#[derive(Default)]
+pub struct ProcessTree {
+    nodes: HashMap<u32, Node>,
+    generation: u64,
+    /// Processes added or reparented during the current update.
+    relink: Vec<(u32, Option<u32>, Option<u32>)>,
+    gone: Vec<u32>,
+}
+
+impl ProcessTree {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Brings the tree in line with a fresh process list.
+    pub fn update(&mut self, procs: &[ProcessInfo]) {
+        self.generation += 1;
+        let generation = self.generation;
+
+        for p in procs {
+            let parent = p.parent.filter(|&parent| parent != p.pid);
+            match self.nodes.get_mut(&p.pid) {
+                Some(node) => {
+                    node.seen = generation;
+                    if node.parent != parent {
+                        self.relink.push((p.pid, node.parent, parent));
+                        node.parent = parent;
+                    }
+                }
+                None => {
+                    self.nodes.insert(
+                        p.pid,
+                        Node {
+                            parent,
+                            children: Vec::new(),
+                            seen: generation,
+                        },
+                    );
+                    self.relink.push((p.pid, None, parent));
+                }
+            }
+        }
+
+        self.gone.extend(
+            self.nodes
+                .iter()
+                .filter(|(_, node)| node.seen != generation)
+                .map(|(&pid, _)| pid),
+        );
+        for pid in std::mem::take(&mut self.gone) {
+            if let Some(parent) = self.nodes.remove(&pid).and_then(|node| node.parent) {
+                self.unlink(parent, pid);
+            }
+        }
+
+        for (pid, old, new) in std::mem::take(&mut self.relink) {
+            if let Some(old) = old {
+                self.unlink(old, pid);
+            }
+            if let Some(node) = new.and_then(|new| self.nodes.get_mut(&new)) {
+                node.children.push(pid);
+            }
+        }
+    }
+
+    fn unlink(&mut self, parent: u32, pid: u32) {
+        if let Some(node) = self.nodes.get_mut(&parent) {
+            node.children.retain(|&child| child != pid);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn parent(&self, pid: u32) -> Option<u32> {
+        self.nodes
+            .get(&pid)?
+            .parent
+            .filter(|parent| self.nodes.contains_key(parent))
+    }
+
+    pub fn children(&self, pid: u32) -> &[u32] {
+        self.nodes
+            .get(&pid)
+            .map(|node| node.children.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Every process below `pid`, depth-first, with its depth (1 for
+    /// direct children).
+    pub fn descendants(&self, pid: u32) -> Vec<(u32, usize)> {
+        let mut out = Vec::new();
+        let mut stack: Vec<(u32, usize)> =
+            self.children(pid).iter().rev().map(|&c| (c, 1)).collect();
+        while let Some((pid, depth)) = stack.pop() {
+            out.push((pid, depth));
+            stack.extend(self.children(pid).iter().rev().map(|&c| (c, depth + 1)));
+        }
+        out
+    }
+
+    /// CPU and memory of each process plus all of its descendants, the way
+    /// `systemd-cgtop` counts a cgroup.
+    pub fn subtree_totals(&self, procs: &[ProcessInfo]) -> HashMap<u32, (f32, u64)> {
+        let mut totals: HashMap<u32, (f32, u64)> = procs
+            .iter()
+            .map(|p| (p.pid, (p.cpu, p.mem_bytes)))
+            .collect();
+        let mut order = Vec::with_capacity(self.nodes.len());
+        self.walk(|a, b| a.cmp(&b), |pid, _| order.push(pid));
+        // Children come after their parent, so backwards they are summed first.
+        for &pid in order.iter().rev() {
+            let (Some(parent), Some(&(cpu, mem))) = (self.parent(pid), totals.get(&pid)) else {
+                continue;
+            };
+            if let Some(total) = totals.get_mut(&parent) {
+                total.0 += cpu;
+                total.1 += mem;
+            }
+        }
+        totals
+    }
+
+    /// Visits every process depth-first with its depth, roots and siblings
+    /// ordered by `cmp`.
+    pub fn walk(&self, cmp: impl Fn(u32, u32) -> Ordering, mut visit: impl FnMut(u32, usize)) {
+        let mut roots: Vec<u32> = self
+            .nodes
+            .iter()
+            .filter(|(_, node)| !matches!(node.parent, Some(p) if self.nodes.contains_key(&p)))
+            .map(|(&pid, _)| pid)
+            .collect();
+        roots.sort_by(|&a, &b| cmp(b, a));
+        let mut stack: Vec<(u32, usize)> = roots.into_iter().map(|pid| (pid, 0)).collect();
+        let mut siblings = Vec::new();
+        while let Some((pid, depth)) = stack.pop() {
+            visit(pid, depth);
+            siblings.clear();
+            siblings.extend_from_slice(self.children(pid));
+            siblings.sort_by(|&a, &b| cmp(b, a));
+            stack.extend(siblings.iter().map(|&child| (child, depth + 1)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proc(pid: u32, parent: Option<u32>) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            name: "p".into(),
+            user: "u".into(),
+            cmd: "".into(),
+            cpu: 0.0,
+            mem_bytes: 0,
+            parent,
+        }
+    }
+
+    fn rows(tree: &ProcessTree) -> Vec<(u32, usize)> {
+        let mut rows = Vec::new();
+        tree.walk(|a, b| a.cmp(&b), |pid, depth| rows.push((pid, depth)));
+        rows
+    }
+
+    #[test]
+    fn test_incremental_updates() {
+        let mut tree = ProcessTree::new();
+        // Children listed before their parent still get linked.
+        tree.update(&[
+            proc(3, Some(2)),
+            proc(2, Some(1)),
+            proc(1, None),
+            proc(4, Some(1)),
+        ]);
+        assert_eq!(rows(&tree), vec![(1, 0), (2, 1), (3, 2), (4, 1)]);
+
+        // 2 exits and 3 is reparented to 1; 5 appears under 4.
+        tree.update(&[
+            proc(1, None),
+            proc(3, Some(1)),
+            proc(4, Some(1)),
+            proc(5, Some(4)),
+        ]);
+        assert_eq!(rows(&tree), vec![(1, 0), (3, 1), (4, 1), (5, 2)]);
+        assert_eq!(tree.children(1), &[4, 3]);
+        assert_eq!(tree.parent(5), Some(4));
+
+        // An orphan whose parent is gone shows up as a root.
+        tree.update(&[proc(1, None), proc(5, Some(4))]);
+        assert_eq!(rows(&tree), vec![(1, 0), (5, 0)]);
+        assert_eq!(tree.len(), 2);
+    }
+
+    #[test]
+    fn test_subtree_totals() {
+        let procs: Vec<ProcessInfo> = [(1, None), (2, Some(1)), (3, Some(2)), (4, Some(1))]
+            .into_iter()
+            .map(|(pid, parent)| ProcessInfo {
+                cpu: pid as f32,
+                mem_bytes: 10,
+                ..proc(pid, parent)
+            })
+            .collect();
+        let mut tree = ProcessTree::new();
+        tree.update(&procs);
+        let totals = tree.subtree_totals(&procs);
+        assert_eq!(totals[&1], (10.0, 40));
+        assert_eq!(totals[&2], (5.0, 20));
+        assert_eq!(totals[&4], (4.0, 10));
+    }
+}
@@ -0,0 +1,156 @@
+//! Linear trends over a sliding time window, used to project when a growing
+//! resource (disk, memory) will run out.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Samples must span at least this long before a projection is made, so a
+/// single burst doesn't produce a wild estimate.
+const MIN_SPAN: Duration = Duration::from_secs(60);
+
+/// The line must explain at least this share of the variance (R²) for the
+/// growth to count as sustained rather than noise around a flat level.
+const MIN_FIT: f64 = 0.5;
+
+/// Least-squares slope of `y` over `x`, or `None` for fewer than two distinct `x`.
+pub fn linear_slope(points: &[(f64, f64)]) -> Option<f64> {
+    linear_fit(points).map(|(slope, _)| slope)
+}
+
+/// Least-squares slope and R² (1 for a constant `y`), or `None` for fewer
+/// than two distinct `x`.
+pub fn linear_fit(points: &[(f64, f64)]) -> Option<(f64, f64)> {
+    if points.len() < 2 {
+        return None;
+    }
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|p| p.0).sum::<f64>() / n;
+    let mean_y = points.iter().map(|p| p.1).sum::<f64>() / n;
+    let (num, den, var_y) = points
+        .iter()
+        .fold((0.0, 0.0, 0.0), |(num, den, var_y), &(x, y)| {
+            let (dx, dy) = (x - mean_x, y - mean_y);
+            (num + dx * dy, den + dx * dx, var_y + dy * dy)
+        });
+    if den <= 0.0 {
+        return None;
+    }
+    let r2 = if var_y > 0.0 {
+        num * num / (den * var_y)
+    } else {
+        1.0
+    };
+    Some((num / den, r2))
+}
+
+pub struct TrendTracker {
+    window: Duration,
+    samples: HashMap<String, VecDeque<(Instant, f64)>>,
+}
+
+impl TrendTracker {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            samples: HashMap::new(),
+        }
+    }
+
+    pub fn record(&mut self, key: &str, now: Instant, value: f64) {
+        let samples = self.samples.entry(key.to_string()).or_default();
+        samples.push_back((now, value));
+        while samples
+            .front()
+            .is_some_and(|(t, _)| now.duration_since(*t) > self.window)
+        {
+            samples.pop_front();
+        }
+    }
+
+    /// Growth per second over the window, once enough time has been covered
+    /// and the trend is steady enough to extrapolate.
+    pub fn slope_per_sec(&self, key: &str) -> Option<f64> {
+        let samples = self.samples.get(key)?;
+        let (first, _) = *samples.front()?;
+        let (last, _) = *samples.back()?;
+        if last.duration_since(first) < MIN_SPAN {
+            return None;
+        }
+        let points: Vec<(f64, f64)> = samples
+            .iter()
+            .map(|(t, v)| (t.duration_since(first).as_secs_f64(), *v))
+            .collect();
+        let (slope, r2) = linear_fit(&points)?;
+        (r2 >= MIN_FIT).then_some(slope)
+    }
+
+    /// Time until `remaining` is used up at the current growth rate, or `None`
+    /// if the value is flat or shrinking.
+    pub fn eta(&self, key: &str, remaining: f64) -> Option<Duration> {
+        let slope = self.slope_per_sec(key)?;
+        (slope > 0.0).then(|| Duration::from_secs_f64(remaining.max(0.0) / slope))
+    }
+}
+
+/// Compact "~45m" / "~6h" / "~3d" rendering of an estimate.
+pub fn format_eta(eta: Duration) -> String {
+    let secs = eta.as_secs();
+    if secs < 3600 {
+        format!("~{}m", (secs / 60).max(1))
+    } else if secs < 48 * 3600 {
+        format!("~{}h", secs / 3600)
+    } else {
+        format!("~{}d", secs / 86400)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_linear_slope() {
+        assert_eq!(
+            linear_slope(&[(0.0, 1.0), (1.0, 3.0), (2.0, 5.0)]),
+            Some(2.0)
+        );
+        assert_eq!(linear_slope(&[(0.0, 1.0)]), None);
+        assert_eq!(linear_slope(&[(1.0, 1.0), (1.0, 2.0)]), None);
+
+        let (_, r2) = linear_fit(&[(0.0, 0.0), (1.0, 10.0), (2.0, 0.0), (3.0, 10.0)]).unwrap();
+        assert!(r2 < MIN_FIT);
+    }
+
+    #[test]
+    fn test_tracker_eta() {
+        let mut trends = TrendTracker::new(Duration::from_secs(600));
+        let t0 = Instant::now();
+        trends.record("/", t0, 100.0);
+        trends.record("/", t0 + Duration::from_secs(30), 130.0);
+        assert_eq!(trends.eta("/", 1000.0), None);
+
+        trends.record("/", t0 + Duration::from_secs(60), 160.0);
+        let eta = trends.eta("/", 600.0).unwrap();
+        assert_eq!(eta.as_secs(), 600);
+
+        trends.record("/", t0 + Duration::from_secs(120), 100.0);
+        assert_eq!(trends.eta("/", 600.0), None);
+    }
+
+    #[test]
+    fn test_window_drops_old_samples() {
+        let mut trends = TrendTracker::new(Duration::from_secs(100));
+        let t0 = Instant::now();
+        trends.record("m", t0, 0.0);
+        trends.record("m", t0 + Duration::from_secs(200), 10.0);
+        assert_eq!(trends.samples["m"].len(), 1);
+    }
+
+    #[test]
+    fn test_format_eta() {
+        assert_eq!(format_eta(Duration::from_secs(30)), "~1m");
+        assert_eq!(format_eta(Duration::from_secs(45 * 60)), "~45m");
+        assert_eq!(format_eta(Duration::from_secs(6 * 3600)), "~6h");
+        assert_eq!(format_eta(Duration::from_secs(3 * 86400)), "~3d");
+    }
+}
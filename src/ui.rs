@@ -1,74 +1,1311 @@
-use crate::app::{App, SortColumn};
+use crate::alerts::{AlertKind, Severity};
+use crate::app::{sensor_rows, App, Panel, Popup, HISTORY_WINDOW};
+use crate::check::Metric;
+use crate::config::Colors;
+use crate::cgroups;
+use crate::containers::{ContainerAction, RATE_HISTORY};
+use crate::delta::DeltaSort;
+use crate::eventlog::EventKind;
+use crate::focus::Target;
+use crate::history::History;
+use crate::i18n::{tr, trf};
+use crate::keymap::Action;
+use crate::plugin::Slot;
+use crate::sbc::throttle_reasons;
+use crate::screensaver;
+use crate::theme;
+use crate::stats::TRACKED;
+use crate::sys::metrics::registry;
 use crate::sys::{format_bytes, format_duration_secs, ProcessInfo};
+use crate::trend::format_eta;
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     symbols,
     text::{Line, Span},
     widgets::{
-        Axis, Block, Borders, Cell, Chart, Dataset, Gauge, GraphType, Paragraph, Row, Sparkline,
-        Table,
+        Axis, Block, Borders, Cell, Chart, Clear, Dataset, Gauge, GraphType, Paragraph, Row,
+        Sparkline, Table, TableState, Tabs,
     },
     Frame,
 };
+use std::collections::HashMap;
+use std::time::Duration;
 
-const COLOR_BG: Color = Color::Rgb(26, 27, 38);
+thread_local! {
+    /// The palette of the frame being drawn, set by [`draw`] from the
+    /// config so that helpers without the `App` can use it too.
+    static COLORS: std::cell::Cell<Colors> = std::cell::Cell::new(Colors::default());
+}
+
+fn colors() -> Colors {
+    COLORS.get()
+}
+
+pub fn draw(f: &mut Frame, app: &mut App) {
+    COLORS.set(app.colors());
+    let size = f.size();
+    app.panel_areas.clear();
+
+    if app.truecolor() {
+        let bg_block = Block::default().style(Style::default().bg(colors().background));
+        f.render_widget(bg_block, size);
+    }
+
+    let footer = extra_panels(app, Slot::Footer);
+    let footer_height = footer
+        .iter()
+        .map(|(_, lines)| lines.len() as u16 + 2)
+        .max()
+        .unwrap_or(0)
+        .min(8);
+
+    let tabs_height = if app.hosts.len() > 1 { 1 } else { 0 };
+    let panels = app.config.panels;
+    let cores = panels.cores && !app.sys().cpu_cores.is_empty();
+    let cpu_height = if panels.cpu || cores { 30 } else { 0 };
+    let events_height = if app.show_events && !app.overview { EVENTS_HEIGHT } else { 0 };
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),
+            Constraint::Length(tabs_height),
+            Constraint::Percentage(cpu_height),
+            Constraint::Min(0),
+            Constraint::Length(events_height),
+            Constraint::Length(footer_height),
+        ])
+        .spacing(0)
+        .split(size);
 
-const COLOR_BORDER: Color = Color::Rgb(160, 160, 160);
+    draw_top_bar(f, chunks[0], app);
+    if tabs_height > 0 {
+        draw_host_tabs(f, chunks[1], app);
+    }
+    if app.overview {
+        let area = chunks[2].union(chunks[3]);
+        draw_overview(f, area, app);
+    } else if app.focus.is_some() {
+        draw_focus(f, chunks[2].union(chunks[3]), app);
+    } else {
+        if cpu_height > 0 {
+            draw_cpu_row(f, chunks[2], app);
+        }
+        draw_bottom_row(f, chunks[3], app);
+    }
+    if events_height > 0 {
+        draw_events(f, chunks[4], app);
+    }
+    if !footer.is_empty() {
+        draw_extra_panels(f, chunks[5], footer, Direction::Horizontal);
+    }
 
-const COLOR_ACCENT: Color = Color::Rgb(0, 255, 127);
+    match app.popup {
+        Some(Popup::AlertLog) => draw_alert_log(f, size, app),
+        Some(Popup::Process) => draw_process_detail(f, size, app),
+        Some(Popup::Perf) => draw_perf(f, size, app),
+        Some(Popup::Connect) => draw_connect(f, size, app),
+        Some(Popup::Containers) => draw_containers(f, size, app),
+        Some(Popup::Cgroups) => draw_cgroups(f, size, app),
+        Some(Popup::Pods) => draw_pods(f, size, app),
+        Some(Popup::Stats) => draw_stats(f, size, app),
+        Some(Popup::Delta) => draw_delta(f, size, app),
+        Some(Popup::Exits) => draw_exits(f, size, app),
+        Some(Popup::Help) => draw_help(f, size, app),
+        None => {}
+    }
+    if !app.truecolor() {
+        theme::downsample(f.buffer_mut());
+    }
+    if app.is_dimmed() {
+        screensaver::dim(f.buffer_mut());
+    }
+}
+
+/// Rows of the event log panel, borders included.
+const EVENTS_HEIGHT: u16 = 8;
+
+/// The event log panel of the host on screen, newest first.
+fn draw_events(f: &mut Frame, area: Rect, app: &App) {
+    let events = &app.host().events.entries;
+    let block = make_block(tr(" Events (e to hide) "));
+    if events.is_empty() {
+        let text = Line::styled(tr("Nothing has happened yet."), Style::default().fg(colors().text));
+        f.render_widget(Paragraph::new(text).block(block), area);
+        return;
+    }
+    let rows = block.inner(area).height as usize;
+    let lines: Vec<Line> = events
+        .iter()
+        .rev()
+        .take(rows)
+        .map(|e| {
+            let (text, color) = match &e.kind {
+                EventKind::Alert {
+                    rule,
+                    kind: AlertKind::Fired,
+                    severity,
+                } => {
+                    let color = match severity {
+                        Severity::Critical => colors().high,
+                        Severity::Warning => colors().warn,
+                    };
+                    (trf("Alert fired: {}", &[rule]), color)
+                }
+                EventKind::Alert { rule, .. } => (trf("Alert resolved: {}", &[rule]), colors().accent),
+                EventKind::Started { pid, name } => {
+                    (trf("Process started: {} ({})", &[name, pid]), colors().text)
+                }
+                EventKind::Exited { pid, name } => {
+                    (trf("Process exited: {} ({})", &[name, pid]), colors().text)
+                }
+                EventKind::Mounted(mount) => (trf("Mounted: {}", &[mount]), colors().accent),
+                EventKind::Unmounted(mount) => (trf("Unmounted: {}", &[mount]), colors().warn),
+                EventKind::TempSpike(from, to) => (
+                    trf(
+                        "CPU temperature jumped from {}°C to {}°C",
+                        &[&format!("{:.0}", from), &format!("{:.0}", to)],
+                    ),
+                    colors().high,
+                ),
+            };
+            Line::from(vec![
+                Span::styled(
+                    format!("{} ", e.at.format("%H:%M:%S")),
+                    Style::default().fg(Color::DarkGray),
+                ),
+                Span::styled(text, Style::default().fg(color)),
+            ])
+        })
+        .collect();
+    f.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+/// The single-process dashboard of `--pid` / `--name`.
+fn draw_focus(f: &mut Frame, area: Rect, app: &App) {
+    let Some(focus) = &app.focus else {
+        return;
+    };
+    let sys = app.sys();
+    let process = focus.process(sys);
+    let title = match (&focus.target, process) {
+        (_, Some(p)) => format!(" {} (PID {}, {}) ", p.name, p.pid, p.user),
+        (Target::Pid(pid), None) => trf(" PID {} is not running ", &[pid]),
+        (Target::Name(name), None) => trf(" Waiting for a process named \"{}\" ", &[name]),
+    };
+    let block = make_block(&title);
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),
+            Constraint::Percentage(45),
+            Constraint::Min(0),
+        ])
+        .split(inner);
+    let cmd = process.map(|p| p.cmd.to_string()).unwrap_or_default();
+    f.render_widget(
+        Paragraph::new(Line::styled(cmd, Style::default().fg(Color::DarkGray))),
+        chunks[0],
+    );
+
+    let charts = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(chunks[1]);
+    let cpu = focus.cpu_history.points();
+    let cpu_max = cpu.iter().map(|p| p.1).fold(100.0, f64::max);
+    draw_history_chart(f, charts[0], " CPU % ", &cpu, cpu_max, format!("{:.0}", cpu_max));
+    let mem = focus.mem_history.points();
+    let mem_max = mem.iter().map(|p| p.1).fold(1.0, f64::max) * 1.2;
+    draw_history_chart(f, charts[1], tr(" Memory "), &mem, mem_max, format_bytes(mem_max as u64));
+
+    let bottom = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(35), Constraint::Percentage(65)])
+        .split(chunks[2]);
+    let label = Style::default().fg(colors().accent).add_modifier(Modifier::BOLD);
+    let field = |name: &'static str, value: String| {
+        Line::from(vec![
+            Span::styled(format!("{:<12}", tr(name)), label),
+            Span::styled(value, Style::default().fg(colors().text)),
+        ])
+    };
+    let info = sys.focus.as_ref().filter(|i| Some(i.pid) == focus.pid);
+    let count = |n: Option<usize>| n.map_or("-".to_string(), |n| n.to_string());
+    let mut lines = vec![
+        field("CPU", process.map_or("-".into(), |p| format!("{:.1}%", p.cpu))),
+        field("Memory", process.map_or("-".into(), |p| format_bytes(p.mem_bytes))),
+        field("Threads", count(info.and_then(|i| i.threads))),
+        field("Open files", count(info.and_then(|i| i.open_files))),
+    ];
+    if let Some(info) = info {
+        lines.push(field(
+            "Read",
+            trf(
+                "{}/s ({} total)",
+                &[&format_bytes(focus.read_rate), &format_bytes(info.read_bytes)],
+            ),
+        ));
+        lines.push(field(
+            "Written",
+            trf(
+                "{}/s ({} total)",
+                &[&format_bytes(focus.write_rate), &format_bytes(info.written_bytes)],
+            ),
+        ));
+    }
+    f.render_widget(Paragraph::new(lines).block(make_block(tr(" Details "))), bottom[0]);
+
+    let tree = &app.host().tree;
+    let procs: HashMap<u32, &ProcessInfo> = sys.processes().iter().map(|p| (p.pid, p)).collect();
+    let rows: Vec<Row> = focus
+        .pid
+        .map(|pid| tree.descendants(pid))
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|(pid, depth)| {
+            let p = procs.get(&pid)?;
+            let indent = format!("{}└ ", "  ".repeat(depth - 1));
+            Some(Row::new(vec![
+                Cell::from(p.pid.to_string()),
+                Cell::from(format!("{}{}", indent, p.name)),
+                Cell::from(format!("{:.1}", p.cpu)),
+                Cell::from(format_bytes(p.mem_bytes)),
+            ]))
+        })
+        .collect();
+    let children_title = trf(" Children ({}) ", &[&rows.len()]);
+    let table = Table::new(
+        rows,
+        vec![
+            Constraint::Length(8),
+            Constraint::Min(16),
+            Constraint::Length(7),
+            Constraint::Length(9),
+        ],
+    )
+    .header(
+        Row::new(vec!["PID", tr("Name"), "CPU%", "Mem"]).style(
+            Style::default()
+                .bg(colors().header_bg)
+                .fg(colors().header_fg)
+                .add_modifier(Modifier::BOLD),
+        ),
+    )
+    .block(make_block(&children_title));
+    f.render_widget(table, bottom[1]);
+}
+
+/// A single-line chart of `data` from 0 to `y_max`, labelled with `top`.
+fn draw_history_chart(
+    f: &mut Frame,
+    area: Rect,
+    title: &str,
+    data: &[(f64, f64)],
+    y_max: f64,
+    top: String,
+) {
+    let block = make_block(title);
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+    let dataset = Dataset::default()
+        .marker(symbols::Marker::Braille)
+        .graph_type(GraphType::Line)
+        .style(Style::default().fg(colors().accent))
+        .data(data);
+    let chart = Chart::new(vec![dataset])
+        .x_axis(Axis::default().bounds([0.0, data.len().max(1) as f64]))
+        .y_axis(Axis::default().bounds([0.0, y_max]).labels(vec![
+            Span::styled("0", Style::default().fg(Color::DarkGray)),
+            Span::styled(top, Style::default().fg(Color::DarkGray)),
+        ]))
+        .style(Style::default().bg(colors().background));
+    f.render_widget(chart, inner);
+}
+
+/// An overview tab and one tab per host with its CPU and memory use, so
+/// all hosts can be followed at once; unreachable hosts in red.
+fn draw_host_tabs(f: &mut Frame, area: Rect, app: &App) {
+    let mut titles = vec![Line::styled(
+        tr("Overview"),
+        Style::default().fg(colors().text),
+    )];
+    titles.extend(app.hosts.iter().enumerate().map(|(i, host)| {
+        let style = if host.sys.offline.is_some() {
+            Style::default().fg(colors().high)
+        } else {
+            Style::default().fg(colors().text)
+        };
+        let mut title = format!("{} {}", i + 1, host.label());
+        if host.sys.offline.is_none() && host.sys.total_mem > 0 {
+            let mem = host.sys.used_mem as f64 / host.sys.total_mem as f64 * 100.0;
+            title.push(' ');
+            title.push_str(&trf(
+                "cpu {}% mem {}%",
+                &[&format!("{:.0}", host.sys.cpu_global), &format!("{:.0}", mem)],
+            ));
+        }
+        Line::styled(title, style)
+    }));
+    let selected = if app.overview {
+        0
+    } else {
+        app.active_host() + 1
+    };
+    let tabs = Tabs::new(titles)
+        .select(selected)
+        .style(Style::default().bg(colors().header_bg))
+        .highlight_style(
+            Style::default()
+                .fg(colors().accent)
+                .add_modifier(Modifier::BOLD | Modifier::REVERSED),
+        );
+    f.render_widget(tabs, area);
+}
+
+/// Width of one host card in the overview grid.
+const CARD_WIDTH: u16 = 40;
+
+/// A card per host with CPU, memory and the fullest disk as gauges and the
+/// network rates, laid out in as many columns as fit.
+fn draw_overview(f: &mut Frame, area: Rect, app: &App) {
+    let columns = (area.width / CARD_WIDTH).max(1) as usize;
+    let rows = app.hosts.len().div_ceil(columns);
+    let row_areas = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(vec![Constraint::Length(6); rows])
+        .split(area);
+
+    for (i, host) in app.hosts.iter().enumerate() {
+        let row = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![Constraint::Ratio(1, columns as u32); columns])
+            .split(row_areas[i / columns]);
+        let card = row[i % columns];
+
+        let sys = &host.sys;
+        let mut block = make_block(host.label());
+        if i == app.overview_selected {
+            block = block.border_style(Style::default().fg(colors().accent));
+        }
+        if sys.offline.is_some() {
+            block = block.border_style(Style::default().fg(colors().high));
+        }
+        let inner = block.inner(card);
+        f.render_widget(block, card);
+
+        let lines = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1); 4])
+            .split(inner);
+        let mem = if sys.total_mem > 0 {
+            sys.used_mem as f32 / sys.total_mem as f32 * 100.0
+        } else {
+            0.0
+        };
+        let disk = sys
+            .disks()
+            .iter()
+            .filter(|d| d.total > 0)
+            .map(|d| d.total.saturating_sub(d.available) as f32 / d.total as f32 * 100.0)
+            .fold(0.0, f32::max);
+        render_usage_bar(f, lines[0], "CPU".into(), sys.cpu_global);
+        render_usage_bar(f, lines[1], "Mem".into(), mem);
+        render_usage_bar(f, lines[2], tr("Disk").into(), disk);
+        f.render_widget(
+            Paragraph::new(trf(
+                "Net ↓ {}/s ↑ {}/s",
+                &[&format_bytes(sys.rx_rate), &format_bytes(sys.tx_rate)],
+            ))
+            .style(Style::default().fg(colors().text)),
+            lines[3],
+        );
+    }
+}
+
+fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
+    let vertical = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Percentage(percent_y),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ])
+        .split(area);
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(vertical[1])[1]
+}
+
+fn draw_alert_log(f: &mut Frame, area: Rect, app: &App) {
+    let area = centered_rect(70, 60, area);
+    f.render_widget(Clear, area);
+
+    let mut lines: Vec<Line> = Vec::new();
+    for (i, alert) in app.alerts.active().iter().enumerate() {
+        let color = match alert.severity {
+            Severity::Critical => colors().high,
+            Severity::Warning => colors().warn,
+        };
+        let mut style = Style::default().fg(color);
+        if i == app.alert_selected {
+            style = style.add_modifier(Modifier::REVERSED);
+        }
+        let ack = if alert.acked { tr(" [ack]") } else { "" };
+        lines.push(Line::styled(
+            trf(" ACTIVE   {}{}", &[&alert.name, &ack]),
+            style,
+        ));
+    }
+    let now = std::time::Instant::now();
+    for (name, until) in app.alerts.snoozed() {
+        let mins = until.saturating_duration_since(now).as_secs().div_ceil(60);
+        lines.push(Line::styled(
+            trf(" SNOOZED  {} ({}m left)", &[&name, &mins]),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    if !lines.is_empty() {
+        lines.push(Line::raw(""));
+    }
+
+    if app.alert_log.is_empty() {
+        lines.push(Line::styled(
+            tr("No alerts have fired yet."),
+            Style::default().fg(colors().text),
+        ));
+    } else {
+        lines.extend(app.alert_log.iter().rev().map(|e| {
+            let (label, color) = match (e.kind, e.severity) {
+                (AlertKind::Resolved, _) => (tr("RESOLVED"), colors().accent),
+                (AlertKind::Fired, Severity::Critical) => (tr("FIRED   "), colors().high),
+                (AlertKind::Fired, Severity::Warning) => (tr("FIRED   "), colors().warn),
+            };
+            Line::from(vec![
+                Span::styled(
+                    format!("{} ", e.at.format("%Y-%m-%d %H:%M:%S")),
+                    Style::default().fg(Color::DarkGray),
+                ),
+                Span::styled(label, Style::default().fg(color).add_modifier(Modifier::BOLD)),
+                Span::styled(
+                    trf(
+                        " {} (value {}, threshold {})",
+                        &[&e.rule, &format!("{:.1}", e.value), &e.threshold.to_string()],
+                    ),
+                    Style::default().fg(colors().text),
+                ),
+            ])
+        }));
+    }
+
+    f.render_widget(
+        Paragraph::new(lines).block(make_block(tr(
+            " Alerts (x acknowledge, s snooze, a/Esc close) ",
+        ))),
+        area,
+    );
+}
+
+fn draw_process_detail(f: &mut Frame, area: Rect, app: &App) {
+    let area = centered_rect(80, 70, area);
+    f.render_widget(Clear, area);
+    let Some(detail) = &app.detail else {
+        return;
+    };
+
+    let label = Style::default().fg(colors().accent).add_modifier(Modifier::BOLD);
+    let text = Style::default().fg(colors().text);
+    let field = |name: &'static str, value: String| {
+        Line::from(vec![
+            Span::styled(format!("{:<10}", tr(name)), label),
+            Span::styled(value, text),
+        ])
+    };
+    let mut lines = vec![
+        field("PID", detail.pid.to_string()),
+        field("Name", detail.name.clone()),
+        field("Exe", detail.exe.clone()),
+    ];
+    if app.detail_pending() {
+        lines.push(Line::styled(tr("Loading…"), Style::default().fg(Color::DarkGray)));
+    } else {
+        lines.push(field("Cwd", detail.cwd.clone()));
+        lines.push(field("Command", detail.cmdline.join(" ")));
+        lines.push(Line::raw(""));
+        lines.push(Line::styled(
+            trf("Environment ({})", &[&detail.environ.len()]),
+            label,
+        ));
+        lines.extend(
+            detail
+                .environ
+                .iter()
+                .map(|var| Line::styled(var.clone(), text)),
+        );
+    }
+
+    f.render_widget(
+        Paragraph::new(lines)
+            .wrap(ratatui::widgets::Wrap { trim: false })
+            .block(make_block(tr(" Process (Esc to close) "))),
+        area,
+    );
+}
+
+/// The monitor's own CPU and memory, and where its time goes.
+fn draw_perf(f: &mut Frame, area: Rect, app: &App) {
+    let area = centered_rect(50, 50, area);
+    f.render_widget(Clear, area);
+
+    let label = Style::default().fg(colors().accent).add_modifier(Modifier::BOLD);
+    let text = Style::default().fg(colors().text);
+    let field = |name: &'static str, value: String| {
+        Line::from(vec![
+            Span::styled(format!("{:<12}", tr(name)), label),
+            Span::styled(value, text),
+        ])
+    };
+    let ms = |d: Duration| format!("{:.2} ms", d.as_secs_f64() * 1000.0);
+    let timings = &app.sys().timings;
+
+    let mut lines = match app.own_process() {
+        Some(own) => vec![
+            field("CPU", format!("{:.1}%", own.cpu)),
+            field("RSS", format_bytes(own.mem_bytes)),
+        ],
+        None => vec![field("CPU", "-".into()), field("RSS", "-".into())],
+    };
+    lines.push(field("Interval", ms(app.effective_tick_rate())));
+    lines.push(Line::raw(""));
+    lines.push(Line::styled(tr("Last refresh"), label));
+    lines.push(field("CPU/memory", ms(timings.cpu_memory)));
+    lines.push(field("Network", ms(timings.network)));
+    lines.push(field("Processes", ms(timings.processes)));
+    lines.push(field("Disks", ms(timings.disks)));
+    lines.push(field("Sensors", ms(timings.sensors)));
+    lines.push(field("Total", ms(timings.total)));
+    lines.push(Line::raw(""));
+    lines.push(field("Frame", ms(app.frame_time())));
+
+    f.render_widget(
+        Paragraph::new(lines).block(make_block(tr(" Perf (P/Esc to close) "))),
+        area,
+    );
+}
+
+/// Every action with the keys bound to it, in two columns.
+fn draw_help(f: &mut Frame, area: Rect, app: &App) {
+    let area = centered_rect(80, 80, area);
+    f.render_widget(Clear, area);
+
+    let keymap = &app.config.keys;
+    let key_style = Style::default().fg(colors().accent).add_modifier(Modifier::BOLD);
+    let text = Style::default().fg(colors().text);
+    let half = Action::ALL.len().div_ceil(2);
+    let (left, right) = Action::ALL.split_at(half);
+    let cells = |action: Option<&Action>| match action {
+        Some(&action) => [
+            Cell::from(keymap.describe(action)).style(key_style),
+            Cell::from(tr(action.help())).style(text),
+        ],
+        None => [Cell::from(""), Cell::from("")],
+    };
+    let rows: Vec<Row> = (0..half)
+        .map(|i| Row::new(cells(left.get(i)).into_iter().chain(cells(right.get(i)))))
+        .collect();
+
+    let close: Vec<String> = keymap.keys(Action::Help).map(|k| k.to_string()).collect();
+    let title = trf(" Key bindings ({} to close) ", &[&close.join("/")]);
+    let table = Table::new(
+        rows,
+        vec![
+            Constraint::Length(18),
+            Constraint::Percentage(32),
+            Constraint::Length(18),
+            Constraint::Percentage(32),
+        ],
+    )
+    .block(make_block(&title));
+    f.render_widget(table, area);
+}
+
+/// Agents found through mDNS; Enter connects to the selected one.
+fn draw_connect(f: &mut Frame, area: Rect, app: &App) {
+    let area = centered_rect(60, 50, area);
+    f.render_widget(Clear, area);
+
+    let lines: Vec<Line> = if app.discovered.is_empty() {
+        let hint = if cfg!(feature = "mdns") {
+            tr("Searching for agents…")
+        } else {
+            tr("Discovery needs a build with the \"mdns\" feature")
+        };
+        vec![Line::styled(hint, Style::default().fg(Color::DarkGray))]
+    } else {
+        app.discovered
+            .iter()
+            .enumerate()
+            .map(|(i, agent)| {
+                let connected = app.hosts.iter().any(|h| h.name == agent.addr);
+                let mut style = Style::default().fg(colors().text);
+                if i == app.connect_selected {
+                    style = style.add_modifier(Modifier::REVERSED);
+                }
+                Line::styled(
+                    format!(
+                        "{:<24} {:<22}{}",
+                        agent.name,
+                        agent.addr,
+                        if connected { tr(" connected") } else { "" }
+                    ),
+                    style,
+                )
+            })
+            .collect()
+    };
+
+    f.render_widget(
+        Paragraph::new(lines).block(make_block(tr(" Connect (Enter connect, C/Esc close) "))),
+        area,
+    );
+}
+
+/// Running containers of the Docker daemon, local or remote, with the
+/// stop or kill waiting for confirmation in the last line.
+fn draw_containers(f: &mut Frame, area: Rect, app: &App) {
+    let area = centered_rect(90, 60, area);
+    f.render_widget(Clear, area);
+
+    let hint = |text: String| {
+        Paragraph::new(Line::styled(text, Style::default().fg(Color::DarkGray)))
+    };
+    let Some(containers) = &app.containers else {
+        let text = if cfg!(feature = "docker") {
+            tr("Asking the Docker daemon…")
+        } else {
+            tr("Containers need a build with the \"docker\" feature")
+        };
+        f.render_widget(hint(text.into()).block(make_block(tr(" Containers "))), area);
+        return;
+    };
+    let title = trf(" Containers on {} (D/Esc to close) ", &[&containers.host]);
+    let block = make_block(&title);
+    if let Some(err) = &containers.error {
+        let text = Paragraph::new(Line::styled(err.clone(), Style::default().fg(colors().high)));
+        f.render_widget(text.block(block), area);
+        return;
+    }
+    if containers.list.is_empty() {
+        f.render_widget(hint(tr("No running containers").into()).block(block), area);
+        return;
+    }
+
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(1)])
+        .split(inner);
+    let footer = match &app.container_confirm {
+        Some(request) => {
+            let prompt = match request.action {
+                ContainerAction::Stop => trf("Stop {}? y to confirm", &[&request.name]),
+                ContainerAction::Kill => trf("Kill {}? y to confirm", &[&request.name]),
+            };
+            Paragraph::new(Line::styled(prompt, Style::default().fg(colors().warn)))
+        }
+        None => hint(tr("s stop, k kill").into()),
+    };
+    f.render_widget(footer, chunks[1]);
+
+    // Each row's second line is a sparkline of its recent rates.
+    let rows: Vec<Row> = containers
+        .list
+        .iter()
+        .enumerate()
+        .map(|(i, c)| {
+            let mem = if c.mem_limit > 0 {
+                format!("{} / {}", format_bytes(c.mem_bytes), format_bytes(c.mem_limit))
+            } else {
+                format_bytes(c.mem_bytes)
+            };
+            let rates = app.container_rates.get(&c.id);
+            let spark = |history: Option<&History>, color: Color| {
+                let text = history.map_or(String::new(), |h| spark_text(&h.last(RATE_HISTORY)));
+                Line::styled(text, Style::default().fg(color))
+            };
+            let rate = |a: u64, b: u64| format!("{}/s {}/s", format_bytes(a), format_bytes(b));
+            Row::new(vec![
+                Cell::from(c.name.clone()),
+                Cell::from(c.image.clone()),
+                Cell::from(format!("{:.1}", c.cpu)),
+                Cell::from(mem),
+                Cell::from(vec![
+                    Line::from(rate(c.rx_rate, c.tx_rate)),
+                    spark(rates.map(|r| &r.net), colors().accent),
+                ]),
+                Cell::from(vec![
+                    Line::from(rate(c.read_rate, c.write_rate)),
+                    spark(rates.map(|r| &r.io), colors().warn),
+                ]),
+                Cell::from(c.status.clone()),
+            ])
+            .height(2)
+            .style(if i == app.container_selected {
+                Style::default().add_modifier(Modifier::REVERSED)
+            } else {
+                Style::default()
+            })
+        })
+        .collect();
+    let table = Table::new(
+        rows,
+        vec![
+            Constraint::Percentage(15),
+            Constraint::Percentage(15),
+            Constraint::Length(6),
+            Constraint::Length(20),
+            Constraint::Length(20),
+            Constraint::Length(20),
+            Constraint::Min(10),
+        ],
+    )
+    .header(
+        Row::new(vec![
+            tr("Name"),
+            tr("Image"),
+            "Cpu%",
+            "Mem",
+            tr("Net rx/tx"),
+            tr("Disk r/w"),
+            tr("Status"),
+        ]).style(
+            Style::default()
+                .bg(colors().header_bg)
+                .fg(colors().header_fg)
+                .add_modifier(Modifier::BOLD),
+        ),
+    );
+    f.render_widget(table, chunks[0]);
+}
+
+/// Min/avg/max/p95 of the active host since sysdash started.
+fn draw_stats(f: &mut Frame, area: Rect, app: &App) {
+    let area = centered_rect(60, 40, area);
+    f.render_widget(Clear, area);
+
+    let stats = &app.host().stats;
+    let registry = registry();
+    let rows: Vec<Row> = TRACKED
+        .iter()
+        .zip(&stats.stats)
+        .map(|((title, name, _), stat)| {
+            let unit = registry.get(name).map(|def| def.unit);
+            let cell = |v: Option<f64>| {
+                Cell::from(
+                    v.zip(unit)
+                        .map(|(v, unit)| unit.format(v))
+                        .unwrap_or_else(|| "-".into()),
+                )
+            };
+            Row::new(vec![
+                Cell::from(tr(title)).style(Style::default().fg(colors().accent)),
+                cell(stat.min()),
+                cell(stat.avg()),
+                cell(stat.max()),
+                cell(stat.percentile(95.0)),
+            ])
+        })
+        .collect();
+
+    let title = trf(
+        " Since {} ({}, {} samples), S/Esc to close ",
+        &[
+            &stats.since.format("%H:%M:%S").to_string(),
+            &format_duration_secs(stats.elapsed()),
+            &stats.samples.to_string(),
+        ],
+    );
+    let table = Table::new(
+        rows,
+        vec![
+            Constraint::Length(12),
+            Constraint::Length(12),
+            Constraint::Length(12),
+            Constraint::Length(12),
+            Constraint::Length(12),
+        ],
+    )
+    .header(
+        Row::new(vec!["", "Min", tr("Avg"), "Max", "p95"]).style(
+            Style::default()
+                .bg(colors().header_bg)
+                .fg(colors().header_fg)
+                .add_modifier(Modifier::BOLD),
+        ),
+    )
+    .block(make_block(&title));
+    f.render_widget(table, area);
+}
+
+/// What each process consumed and grew by since the baseline.
+fn draw_delta(f: &mut Frame, area: Rect, app: &App) {
+    let area = centered_rect(70, 60, area);
+    f.render_widget(Clear, area);
+
+    let Some(baseline) = &app.host().baseline else {
+        let hint = tr("Press b to mark a baseline, then B to compare against it");
+        let text = Paragraph::new(Line::styled(hint, Style::default().fg(Color::DarkGray)));
+        f.render_widget(text.block(make_block(" Delta ")), area);
+        return;
+    };
+    let cpu_time = |secs: f64| {
+        if secs < 60.0 {
+            format!("{:.1}s", secs)
+        } else {
+            format_duration_secs(secs as u64)
+        }
+    };
+    let growth = |bytes: i64| {
+        let sign = if bytes < 0 { "-" } else { "+" };
+        format!("{}{}", sign, format_bytes(bytes.unsigned_abs()))
+    };
+    let rows: Vec<Row> = baseline
+        .rows(app.sys().processes(), app.delta_sort)
+        .into_iter()
+        .map(|d| {
+            let name = if d.new {
+                Cell::from(trf("{} (new)", &[&d.name])).style(Style::default().fg(colors().warn))
+            } else {
+                Cell::from(d.name.to_string())
+            };
+            Row::new(vec![
+                Cell::from(d.pid.to_string()),
+                name,
+                Cell::from(cpu_time(d.cpu_secs)),
+                Cell::from(growth(d.mem_growth)),
+                Cell::from(format_bytes(d.mem_now)),
+            ])
+        })
+        .collect();
+
+    let (cpu_header, mem_header) = match app.delta_sort {
+        DeltaSort::CpuTime => (tr("CPU time ▼"), "Mem Δ"),
+        DeltaSort::MemGrowth => (tr("CPU time"), "Mem Δ ▼"),
+    };
+    let title = trf(
+        " Since {} ({}), net rx {} tx {} | m sort, b re-mark, B/Esc close ",
+        &[
+            &baseline.at.format("%H:%M:%S").to_string(),
+            &format_duration_secs(baseline.elapsed()),
+            &format_bytes(baseline.rx_bytes),
+            &format_bytes(baseline.tx_bytes),
+        ],
+    );
+    let table = Table::new(
+        rows,
+        vec![
+            Constraint::Length(8),
+            Constraint::Min(16),
+            Constraint::Length(12),
+            Constraint::Length(10),
+            Constraint::Length(10),
+        ],
+    )
+    .header(
+        Row::new(vec!["PID", tr("Name"), cpu_header, mem_header, "Mem"]).style(
+            Style::default()
+                .bg(colors().header_bg)
+                .fg(colors().header_fg)
+                .add_modifier(Modifier::BOLD),
+        ),
+    )
+    .block(make_block(&title));
+    f.render_widget(table, area);
+}
+
+/// The cgroup hierarchy of the host on screen as a tree, unfolded where
+/// the user asked.
+fn draw_cgroups(f: &mut Frame, area: Rect, app: &App) {
+    let area = centered_rect(70, 70, area);
+    f.render_widget(Clear, area);
+    let block = make_block(tr(" cgroups (G/Esc to close) "));
+
+    let list = &app.sys().cgroups;
+    let rows = cgroups::rows(list, &app.cgroup_expanded);
+    if rows.is_empty() {
+        let text = tr("Reading cgroups… (Linux with cgroup v2, local hosts only)");
+        let hint = Paragraph::new(Line::styled(text, Style::default().fg(Color::DarkGray)));
+        f.render_widget(hint.block(block), area);
+        return;
+    }
+    let table_rows: Vec<Row> = rows
+        .iter()
+        .map(|row| {
+            let cgroup = &list[row.index];
+            let marker = match (row.has_children, app.cgroup_expanded.contains(&cgroup.path)) {
+                (false, _) => "  ",
+                (true, false) => "▸ ",
+                (true, true) => "▾ ",
+            };
+            let indent = "  ".repeat(cgroup.depth());
+            Row::new(vec![
+                Cell::from(format!("{}{}{}", indent, marker, cgroup.name())),
+                Cell::from(format!("{:.1}", cgroup.cpu)),
+                Cell::from(format_bytes(cgroup.mem_bytes)),
+            ])
+        })
+        .collect();
+    let table = Table::new(
+        table_rows,
+        vec![Constraint::Min(20), Constraint::Length(7), Constraint::Length(9)],
+    )
+    .header(
+        Row::new(vec![tr("Name"), "CPU%", "Mem"]).style(
+            Style::default()
+                .bg(colors().header_bg)
+                .fg(colors().header_fg)
+                .add_modifier(Modifier::BOLD),
+        ),
+    )
+    .block(block)
+    .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    let mut state = TableState::default();
+    state.select(Some(app.cgroup_selected.min(rows.len() - 1)));
+    f.render_stateful_widget(table, area, &mut state);
+}
+
+/// Kubernetes pods, busiest first, each followed by its containers with
+/// the names of their processes.
+fn draw_pods(f: &mut Frame, area: Rect, app: &App) {
+    let area = centered_rect(80, 70, area);
+    f.render_widget(Clear, area);
+    let block = make_block(tr(" Pods (K/Esc to close) "));
+
+    let sys = app.sys();
+    let pods = cgroups::pods(&sys.cgroups);
+    if pods.is_empty() {
+        let text = if sys.cgroups.is_empty() {
+            tr("Reading cgroups… (Linux with cgroup v2, local hosts only)")
+        } else {
+            tr("No Kubernetes pods on this host")
+        };
+        let hint = Paragraph::new(Line::styled(text, Style::default().fg(Color::DarkGray)));
+        f.render_widget(hint.block(block), area);
+        return;
+    }
+    let name = |pid: u32| {
+        sys.processes()
+            .iter()
+            .find(|p| p.pid == pid)
+            .map_or_else(|| pid.to_string(), |p| p.name.to_string())
+    };
+    let mut rows = Vec::new();
+    for pod in &pods {
+        rows.push(
+            Row::new(vec![
+                format!("pod {}", pod.uid),
+                pod.qos.to_string(),
+                format!("{:.1}", pod.cpu),
+                format_bytes(pod.mem_bytes),
+            ])
+            .style(Style::default().add_modifier(Modifier::BOLD)),
+        );
+        for container in &pod.containers {
+            let mut names: Vec<String> = container.pids.iter().map(|&pid| name(pid)).collect();
+            names.dedup();
+            rows.push(Row::new(vec![
+                format!("  {}", container.id),
+                names.join(", "),
+                format!("{:.1}", container.cpu),
+                format_bytes(container.mem_bytes),
+            ]));
+        }
+    }
+    let table = Table::new(
+        rows,
+        vec![
+            Constraint::Length(42),
+            Constraint::Min(16),
+            Constraint::Length(7),
+            Constraint::Length(9),
+        ],
+    )
+    .header(
+        Row::new(vec![tr("Pod / container"), tr("QoS / processes"), "CPU%", "Mem"]).style(
+            Style::default()
+                .bg(colors().header_bg)
+                .fg(colors().header_fg)
+                .add_modifier(Modifier::BOLD),
+        ),
+    )
+    .block(block);
+    f.render_widget(table, area);
+}
 
-const COLOR_HIGH: Color = Color::Rgb(255, 85, 85);
+/// Processes that exited, newest first, with the names that exit most.
+fn draw_exits(f: &mut Frame, area: Rect, app: &App) {
+    let area = centered_rect(70, 60, area);
+    f.render_widget(Clear, area);
+    let exits = &app.host().exits;
 
-const COLOR_TEXT_MAIN: Color = Color::Rgb(192, 202, 245);
-const COLOR_HEADER_BG: Color = Color::Rgb(65, 72, 104);
-const COLOR_HEADER_FG: Color = Color::White;
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Min(0)])
+        .split(make_block("").inner(area));
+    let rows: Vec<Row> = exits
+        .entries
+        .iter()
+        .rev()
+        .map(|e| {
+            let lifetime = format!(
+                "{}{}",
+                if e.at_least { "≥" } else { "" },
+                format_duration_secs(e.lifetime.as_secs())
+            );
+            Row::new(vec![
+                Cell::from(e.at.format("%H:%M:%S").to_string()),
+                Cell::from(e.pid.to_string()),
+                Cell::from(e.name.to_string()),
+                Cell::from(format!("{:.1}", e.cpu)),
+                Cell::from(format_bytes(e.mem_bytes)),
+                Cell::from(lifetime),
+            ])
+        })
+        .collect();
+    let title = trf(" Exited processes ({}) | E/Esc close ", &[&exits.entries.len()]);
+    f.render_widget(make_block(&title), area);
 
-pub fn draw(f: &mut Frame, app: &mut App) {
-    let size = f.size();
+    let frequent: Vec<String> = exits
+        .counts()
+        .into_iter()
+        .filter(|(_, n)| *n > 1)
+        .take(5)
+        .map(|(name, n)| format!("{} ×{}", name, n))
+        .collect();
+    let summary = if frequent.is_empty() {
+        tr("No process exited more than once").to_string()
+    } else {
+        trf("Most exits: {}", &[&frequent.join(", ")])
+    };
+    f.render_widget(
+        Paragraph::new(Line::styled(summary, Style::default().fg(colors().warn))),
+        chunks[0],
+    );
 
-    let bg_block = Block::default().style(Style::default().bg(COLOR_BG));
-    f.render_widget(bg_block, size);
+    let table = Table::new(
+        rows,
+        vec![
+            Constraint::Length(9),
+            Constraint::Length(8),
+            Constraint::Min(16),
+            Constraint::Length(6),
+            Constraint::Length(9),
+            Constraint::Length(10),
+        ],
+    )
+    .header(
+        Row::new(vec![
+            tr("Time"),
+            "PID",
+            tr("Name"),
+            "CPU%",
+            "Mem",
+            tr("Lifetime"),
+        ]).style(
+            Style::default()
+                .bg(colors().header_bg)
+                .fg(colors().header_fg)
+                .add_modifier(Modifier::BOLD),
+        ),
+    );
+    f.render_widget(table, chunks[1]);
+}
 
-    let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Length(1),
-            Constraint::Percentage(30),
-            Constraint::Min(0),
-        ])
-        .spacing(0)
-        .split(size);
+/// Plugin and command widgets assigned to `slot`, as titled blocks of lines.
+fn extra_panels(app: &App, slot: Slot) -> Vec<(String, Vec<Line<'static>>)> {
+    let mut panels: Vec<(String, Vec<Line<'static>>)> = app
+        .plugins
+        .widgets_in(slot)
+        .into_iter()
+        .map(|w| {
+            let lines = w.lines.iter().map(|l| Line::from(l.clone())).collect();
+            (w.title.clone(), lines)
+        })
+        .collect();
 
-    draw_top_bar(f, chunks[0], app);
-    draw_cpu_row(f, chunks[1], app);
-    draw_bottom_row(f, chunks[2], app);
+    for widget in app.commands.iter().filter(|w| w.spec.slot == slot) {
+        let lines = widget
+            .lines()
+            .into_iter()
+            .map(|l| {
+                let color = widget
+                    .color_for(&l)
+                    .and_then(|c| c.parse::<Color>().ok())
+                    .unwrap_or(colors().text);
+                Line::styled(l, Style::default().fg(color))
+            })
+            .collect();
+        panels.push((widget.spec.title.clone(), lines));
+    }
+    panels
 }
 
 fn draw_top_bar(f: &mut Frame, area: Rect, app: &App) {
     let now = chrono::Local::now();
-    let time_str = now.format("%H:%M:%S").to_string();
-    let bat_str = if let Some(bat) = app.sys().battery_percentage() {
-        format!("BAT: {:.0}%", bat)
+    let time_str = app.clock.render(&now);
+    let bat_str = if let Some(bat) = &app.sys().battery {
+        let mut details = Vec::new();
+        if bat.charging {
+            details.push(tr("charging").to_string());
+        }
+        if let Some(health) = bat.health {
+            details.push(trf("health {}%", &[&format!("{:.0}", health)]));
+        }
+        if let Some(cycles) = bat.cycle_count {
+            details.push(trf("{} cycles", &[&cycles]));
+        }
+        if details.is_empty() {
+            format!("BAT: {:.0}%", bat.percent)
+        } else {
+            format!("BAT: {:.0}% ({})", bat.percent, details.join(", "))
+        }
     } else {
-        "BAT: N/A".to_string()
+        tr("BAT: N/A").to_string()
     };
 
-    let style = Style::default().bg(COLOR_BG).fg(COLOR_TEXT_MAIN);
+    // Alternate the background each second while a critical alert flashes.
+    let flash_on = app.is_flashing() && now.timestamp() % 2 == 0;
+    let bar_bg = if flash_on { colors().high } else { colors().background };
+    let style = Style::default().bg(bar_bg).fg(colors().text);
     let uptime = format_duration_secs(app.sys().uptime);
 
-    let text = Line::from(vec![
+    let mut text = Line::from(vec![
         Span::styled(
-            format!(" sysdash "),
-            style.add_modifier(Modifier::BOLD).fg(COLOR_ACCENT),
+            " sysdash ".to_string(),
+            style.add_modifier(Modifier::BOLD).fg(colors().accent),
         ),
         Span::raw(" | "),
         Span::styled(format!(" {} ", time_str), style),
         Span::raw(" | "),
-        Span::styled(format!(" {} ", bat_str), style),
-        Span::raw(" | "),
-        Span::styled(format!(" Uptime: {} ", uptime), style),
+        Span::styled(trf(" Uptime: {} ", &[&uptime]), style),
     ]);
+    let sys = app.sys();
+    // Batteries can't be read in restricted mode, so "N/A" would be noise.
+    if !sys.restricted || sys.battery.is_some() {
+        text.spans.insert(4, Span::styled(format!(" {} ", bat_str), style));
+        text.spans.insert(5, Span::raw(" | "));
+    }
+    if !sys.host.is_empty() {
+        text.spans.insert(2, Span::styled(format!(" {} ", sys.host), style));
+        text.spans.insert(3, Span::raw(" | "));
+    }
+    if let Some(sbc) = &sys.sbc {
+        let mut board = Vec::new();
+        if let Some(temp) = sbc.soc_temp {
+            board.push(format!("SoC {:.0}°C", temp));
+        }
+        if let Some(gpu) = sbc.gpu_mem {
+            board.push(format!("GPU {}", format_bytes(gpu)));
+        }
+        if !board.is_empty() {
+            text.spans.push(Span::raw(" | "));
+            text.spans.push(Span::styled(format!(" {} ", board.join(", ")), style));
+        }
+        if let Some(flags) = sbc.throttled.filter(|_| sbc.is_throttled()) {
+            text.spans.push(Span::raw(" | "));
+            text.spans.push(Span::styled(
+                format!(" {} ", throttle_reasons(flags).join(", ")),
+                style.fg(colors().warn).add_modifier(Modifier::BOLD),
+            ));
+        }
+    }
+    if let Some(err) = &sys.offline {
+        text.spans.push(Span::raw(" | "));
+        text.spans.push(Span::styled(
+            trf(" OFFLINE: {} ", &[err]),
+            style.fg(colors().high).add_modifier(Modifier::BOLD),
+        ));
+    }
+    if let Some(notice) = &app.notice {
+        text.spans.push(Span::raw(" | "));
+        text.spans.push(Span::styled(
+            format!(" {} ", notice),
+            style.fg(colors().warn).add_modifier(Modifier::BOLD),
+        ));
+    }
+    if let Some(replay) = app.replay() {
+        let at = chrono::DateTime::from_timestamp_millis(replay.current().at)
+            .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S"))
+            .map(|t| t.to_string())
+            .unwrap_or_default();
+        let position = replay.position() + 1;
+        text.spans.push(Span::raw(" | "));
+        text.spans.push(Span::styled(
+            trf(" REPLAY {}/{} {} ", &[&position, &replay.len(), &at]),
+            style.fg(colors().accent).add_modifier(Modifier::BOLD),
+        ));
+    }
+    if app.is_paused() {
+        text.spans.push(Span::raw(" | "));
+        text.spans.push(Span::styled(
+            tr(" PAUSED "),
+            style.fg(colors().warn).add_modifier(Modifier::BOLD),
+        ));
+    }
+    if app.is_idle() {
+        text.spans.push(Span::raw(" | "));
+        text.spans.push(Span::styled(
+            trf(" idle, every {}s ", &[&app.effective_tick_rate().as_secs()]),
+            style.fg(colors().border),
+        ));
+    }
+    let active = app.alerts.active();
+    if let Some(alert) = active.first() {
+        let color = match alert.severity {
+            Severity::Critical => colors().high,
+            Severity::Warning => colors().warn,
+        };
+        let more = if active.len() > 1 {
+            format!(" (+{})", active.len() - 1)
+        } else {
+            String::new()
+        };
+        text.spans.push(Span::raw(" | "));
+        let value = alert
+            .value
+            .map(|v| format!(" = {:.1}", v))
+            .unwrap_or_default();
+        text.spans.push(Span::styled(
+            trf(" ALERT: {}{}{} ", &[&alert.name, &value, &more]),
+            style.fg(color).add_modifier(Modifier::BOLD),
+        ));
+    } else if let Some(alert) = app.plugins.alerts.first() {
+        text.spans.push(Span::raw(" | "));
+        text.spans.push(Span::styled(
+            trf(" ALERT: {} ", &[alert]),
+            style.fg(colors().high).add_modifier(Modifier::BOLD),
+        ));
+    }
+    if let Some(err) = app.alerts.errors.first() {
+        text.spans.push(Span::raw(" | "));
+        text.spans.push(Span::styled(format!(" {} ", err), style.fg(colors().high)));
+    }
+    if let Some(err) = app.plugins.errors.first() {
+        text.spans.push(Span::raw(" | "));
+        text.spans.push(Span::styled(
+            trf(" plugin error: {} ", &[err]),
+            style.fg(colors().high),
+        ));
+    }
 
     f.render_widget(
         Paragraph::new(text)
@@ -78,7 +1315,16 @@ fn draw_top_bar(f: &mut Frame, area: Rect, app: &App) {
     );
 }
 
-fn draw_cpu_row(f: &mut Frame, area: Rect, app: &App) {
+fn draw_cpu_row(f: &mut Frame, area: Rect, app: &mut App) {
+    let panels = app.config.panels;
+    // No per-core figures where /proc/stat is off limits.
+    let cores = panels.cores && !app.sys().cpu_cores.is_empty();
+    match (panels.cpu, cores) {
+        (true, true) => {}
+        (true, false) => return draw_cpu_graph(f, area, app),
+        (false, true) => return draw_cpu_cores(f, area, app),
+        (false, false) => return,
+    }
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([Constraint::Percentage(70), Constraint::Percentage(30)])
@@ -90,87 +1336,238 @@ fn draw_cpu_row(f: &mut Frame, area: Rect, app: &App) {
 }
 
 fn draw_cpu_graph(f: &mut Frame, area: Rect, app: &App) {
-    let block = make_block(" CPU History ");
+    let firing = app.alerts.is_firing(&Metric::Cpu) || app.alerts.is_firing(&Metric::Temp);
+    let title = match (app.sys().restricted, app.projection) {
+        (false, false) => tr(" CPU History "),
+        (true, false) => tr(" CPU History (own processes) "),
+        (false, true) => tr(" CPU / Memory History (projected) "),
+        (true, true) => tr(" CPU (own processes) / Memory History (projected) "),
+    };
+    let block = alert_block(title, firing);
     let inner = block.inner(area);
     f.render_widget(block, area);
 
-    let data: Vec<(f64, f64)> = app
-        .cpu_history
-        .iter()
-        .enumerate()
-        .map(|(i, &v)| (i as f64, v as f64))
-        .collect();
+    let host = app.host();
+    let data = host.cpu_history.points();
+    let capacity = host.cpu_history.capacity() as f64;
 
-    let datasets = vec![Dataset::default()
+    let mut datasets = vec![Dataset::default()
         .name("Total")
         .marker(symbols::Marker::Braille)
         .graph_type(GraphType::Line)
-        .style(Style::default().fg(COLOR_ACCENT))
+        .style(Style::default().fg(colors().accent))
         .data(&data)];
 
+    // The memory line continues past "now" along its trend, over another
+    // quarter of the window.
+    let (mem, projected, x_max) = if app.projection {
+        let ahead = capacity / 4.0;
+        let mem = host.mem_history.points();
+        let now = mem.last().copied().unwrap_or((0.0, 0.0));
+        let secs_per_sample = HISTORY_WINDOW.as_secs_f64() / capacity;
+        let projected: Vec<(f64, f64)> = match host.mem_slope_percent() {
+            Some(slope) => (0..=ahead as usize)
+                .map(|i| {
+                    let y = now.1 + slope * secs_per_sample * i as f64;
+                    (now.0 + i as f64, y.clamp(0.0, 100.0))
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+        (mem, projected, capacity + ahead)
+    } else {
+        (Vec::new(), Vec::new(), capacity)
+    };
+    if app.projection {
+        datasets.push(
+            Dataset::default()
+                .name(tr("Memory"))
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(colors().text))
+                .data(&mem),
+        );
+        datasets.push(
+            Dataset::default()
+                .marker(symbols::Marker::Dot)
+                .graph_type(GraphType::Scatter)
+                .style(Style::default().fg(colors().warn))
+                .data(&projected),
+        );
+    }
+
     let chart = Chart::new(datasets)
-        .x_axis(Axis::default().bounds([0.0, 100.0]))
+        .x_axis(Axis::default().bounds([0.0, x_max]))
         .y_axis(Axis::default().bounds([0.0, 100.0]).labels(vec![
             Span::styled("0", Style::default().fg(Color::DarkGray)),
             Span::styled("100", Style::default().fg(Color::DarkGray)),
         ]))
-        .style(Style::default().bg(COLOR_BG));
+        .style(Style::default().bg(colors().background));
 
     f.render_widget(chart, inner);
 }
 
-fn draw_cpu_cores(f: &mut Frame, area: Rect, app: &App) {
-    let block = make_block(" Cores ");
-    let inner = block.inner(area);
-    f.render_widget(block, area);
+fn draw_cpu_cores(f: &mut Frame, area: Rect, app: &mut App) {
+    app.panel_areas.push((Panel::Cores, area));
+    let inner = make_block("").inner(area);
+    let len = app.sys().cpu_cores.len();
+    let (offset, range) = scroll_window(len, inner.height as usize, app.cores_scroll);
+    app.cores_scroll = offset;
 
-    let cores = &app.sys().cpu_cores;
-    let rows_max = inner.height as usize;
+    let title = trf(" Cores{} ", &[&range]);
+    let block = panel_block(
+        alert_block(&title, app.alerts.is_firing(&Metric::Cpu)),
+        app.panel == Panel::Cores,
+    );
+    f.render_widget(block, area);
 
-    let constraints = vec![Constraint::Length(1); rows_max];
-    let chunks = Layout::default()
+    let rows = Layout::default()
         .direction(Direction::Vertical)
-        .constraints(constraints)
+        .constraints(vec![Constraint::Length(1); inner.height as usize])
         .split(inner);
-
-    for (i, &usage) in cores.iter().take(rows_max).enumerate() {
-        if i >= chunks.len() {
-            break;
+    let sys = app.sys();
+    for (row, (i, &usage)) in rows.iter().zip(sys.cpu_cores.iter().enumerate().skip(offset)) {
+        match sys.core_temps.get(i).copied().flatten() {
+            Some(temp) => render_hot_bar(f, *row, format!("C{}", i), usage, temp, CORE_TEMP_HOT),
+            None => render_usage_bar(f, *row, format!("C{}", i), usage),
         }
-
-        render_usage_bar(f, chunks[i], format!("C{}", i), usage);
     }
 }
 
-fn draw_bottom_row(f: &mut Frame, area: Rect, app: &mut App) {
+/// Cores at this temperature show it in the alert color.
+const CORE_TEMP_HOT: f32 = 90.0;
+
+/// A usage bar with a temperature after it, in the alert color from `hot`
+/// and in the warning color from 10 degrees below.
+fn render_hot_bar(f: &mut Frame, area: Rect, label: String, percent: f32, temp: f32, hot: f32) {
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(35), Constraint::Percentage(65)])
-        .spacing(0)
+        .constraints([Constraint::Min(0), Constraint::Length(6)])
         .split(area);
+    render_usage_bar(f, chunks[0], label, percent);
+    let color = if temp >= hot {
+        colors().high
+    } else if temp >= hot - 10.0 {
+        colors().warn
+    } else {
+        colors().text
+    };
+    let text = Line::styled(format!(" {:>3.0}°C", temp), Style::default().fg(color));
+    f.render_widget(Paragraph::new(text), chunks[1]);
+}
+
+/// Where a list of `len` rows starts when `rows` fit and the user scrolled
+/// to `offset`, kept to full pages, and a title suffix with the rows shown
+/// and arrows for the hidden ones; empty when everything fits.
+fn scroll_window(len: usize, rows: usize, offset: usize) -> (usize, String) {
+    if len <= rows {
+        return (0, String::new());
+    }
+    let offset = offset.min(len - rows);
+    let up = if offset > 0 { "▲" } else { "" };
+    let down = if offset + rows < len { "▼" } else { "" };
+    let range = format!(" {}-{}/{} {}{}", offset + 1, offset + rows, len, up, down);
+    (offset, range)
+}
+
+/// Highlights the border of the panel the arrow keys scroll.
+fn panel_block(block: Block<'_>, focused: bool) -> Block<'_> {
+    if focused {
+        block.border_style(Style::default().fg(colors().accent))
+    } else {
+        block
+    }
+}
 
-    draw_resources(f, chunks[0], app);
-    draw_processes(f, chunks[1], app);
+fn draw_bottom_row(f: &mut Frame, area: Rect, app: &mut App) {
+    let panels = app.config.panels;
+    let sidebar = extra_panels(app, Slot::Sidebar);
+    let gpu = panels.gpu && !app.sys().gpus.is_empty();
+    let resources = panels.memory
+        || panels.disks
+        || panels.network
+        || gpu
+        || show_sensors(app)
+        || !sidebar.is_empty();
+    match (resources, panels.processes) {
+        (true, true) => {
+            let chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(35), Constraint::Percentage(65)])
+                .spacing(0)
+                .split(area);
+            draw_resources(f, chunks[0], app, sidebar);
+            draw_processes(f, chunks[1], app);
+        }
+        (true, false) => draw_resources(f, area, app, sidebar),
+        (false, true) => draw_processes(f, area, app),
+        (false, false) => {}
+    }
 }
 
-fn draw_resources(f: &mut Frame, area: Rect, app: &App) {
+/// Memory, disks, network, GPUs and sensors as enabled, then the plugin
+/// sidebar.
+fn draw_resources(
+    f: &mut Frame,
+    area: Rect,
+    app: &mut App,
+    sidebar: Vec<(String, Vec<Line<'static>>)>,
+) {
+    let panels = app.config.panels;
+    let mut shown: Vec<fn(&mut Frame, Rect, &mut App)> = Vec::new();
+    if panels.memory {
+        shown.push(|f, area, app| draw_memory(f, area, app));
+    }
+    if panels.disks {
+        shown.push(draw_disks);
+    }
+    if panels.network {
+        shown.push(|f, area, app| draw_network(f, area, app));
+    }
+    if panels.gpu && !app.sys().gpus.is_empty() {
+        shown.push(draw_gpu);
+    }
+    if show_sensors(app) {
+        shown.push(draw_sensors);
+    }
+    let count = shown.len() as u32 + u32::from(!sidebar.is_empty());
     let chunks = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Percentage(33),
-            Constraint::Percentage(33),
-            Constraint::Percentage(34),
-        ])
+        .constraints(vec![Constraint::Ratio(1, count); count as usize])
         .spacing(0)
         .split(area);
 
-    draw_memory(f, chunks[0], app);
-    draw_disks(f, chunks[1], app);
-    draw_network(f, chunks[2], app);
+    for (draw, chunk) in shown.into_iter().zip(chunks.iter()) {
+        draw(f, *chunk, app);
+    }
+    if !sidebar.is_empty() {
+        draw_extra_panels(f, chunks[count as usize - 1], sidebar, Direction::Vertical);
+    }
+}
+
+fn draw_extra_panels(
+    f: &mut Frame,
+    area: Rect,
+    panels: Vec<(String, Vec<Line<'static>>)>,
+    dir: Direction,
+) {
+    let chunks = Layout::default()
+        .direction(dir)
+        .constraints(vec![Constraint::Ratio(1, panels.len() as u32); panels.len()])
+        .split(area);
+
+    for ((title, lines), chunk) in panels.into_iter().zip(chunks.iter()) {
+        f.render_widget(
+            Paragraph::new(lines)
+                .block(make_block(&title))
+                .style(Style::default().fg(colors().text)),
+            *chunk,
+        );
+    }
 }
 
 fn draw_memory(f: &mut Frame, area: Rect, app: &App) {
-    let block = make_block(" Memory ");
+    let block = alert_block(tr(" Memory "), app.alerts.is_firing(&Metric::Mem));
     let inner = block.inner(area);
     f.render_widget(block, area);
 
@@ -183,51 +1580,96 @@ fn draw_memory(f: &mut Frame, area: Rect, app: &App) {
         0.0
     };
 
+    // Swap usage, then its activity where the platform counts it.
+    let swap_rows = if sys.total_swap > 0 { 1 } else { 0 };
+    let rates = sys.swap_rates.filter(|_| swap_rows > 0);
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(1),
             Constraint::Length(1),
+            Constraint::Length(swap_rows),
+            Constraint::Length(if rates.is_some() { 1 } else { 0 }),
             Constraint::Min(0),
         ])
         .split(inner);
 
-    let text = format!("{}/{}", format_bytes(used), format_bytes(total));
-    f.render_widget(
-        Paragraph::new(text).style(Style::default().fg(COLOR_TEXT_MAIN)),
-        chunks[0],
+    let mut text = Line::styled(
+        format!("{}/{}", format_bytes(used), format_bytes(total)),
+        Style::default().fg(colors().text),
     );
+    if let Some(eta) = app.host().mem_eta() {
+        text.spans.push(Span::styled(
+            trf(", exhausted in {}", &[&format_eta(eta)]),
+            Style::default().fg(colors().warn),
+        ));
+    }
+    f.render_widget(Paragraph::new(text), chunks[0]);
     render_usage_bar(f, chunks[1], "RAM".into(), percent);
+    if swap_rows == 0 {
+        return;
+    }
+
+    let swap_percent = (sys.used_swap as f64 / sys.total_swap as f64 * 100.0) as f32;
+    render_usage_bar(f, chunks[2], "Swap".into(), swap_percent);
+    let Some((swap_in, swap_out)) = rates else {
+        return;
+    };
+    let swapping = swap_in + swap_out > 0;
+    let activity = Line::styled(
+        trf("Swap in {} out {} pages/s", &[&swap_in, &swap_out]),
+        Style::default().fg(if swapping { colors().warn } else { colors().text }),
+    );
+    f.render_widget(Paragraph::new(activity), chunks[3]);
+    let data = app.host().swap_history.last(chunks[4].width as usize);
+    let spark = Sparkline::default()
+        .data(&data)
+        .style(Style::default().fg(colors().warn));
+    f.render_widget(spark, chunks[4]);
 }
 
-fn draw_disks(f: &mut Frame, area: Rect, app: &App) {
-    let block = make_block(" Disks ");
-    let inner = block.inner(area);
+fn draw_disks(f: &mut Frame, area: Rect, app: &mut App) {
+    app.panel_areas.push((Panel::Disks, area));
+    let inner = make_block("").inner(area);
+    let len = app.sys().disks().len();
+    let (offset, range) = scroll_window(len, inner.height as usize, app.disks_scroll);
+    app.disks_scroll = offset;
+
+    let title = trf(" Disks{} ", &[&range]);
+    let firing = app.alerts.is_firing(&Metric::Disk(String::new()));
+    let block = panel_block(alert_block(&title, firing), app.panel == Panel::Disks);
     f.render_widget(block, area);
 
     let disks = app.sys().disks();
-    let rows = inner.height as usize;
     let layout = Layout::default()
         .direction(Direction::Vertical)
-        .constraints(vec![Constraint::Length(1); rows])
+        .constraints(vec![Constraint::Length(1); inner.height as usize])
         .split(inner);
 
-    for (i, disk) in disks.iter().take(rows).enumerate() {
-        if i >= layout.len() {
-            break;
-        }
-        let used = disk.total - disk.available;
+    for (row, disk) in layout.iter().zip(disks.iter().skip(offset)) {
+        // Some FUSE and network mounts report more available than total.
+        let used = disk.total.saturating_sub(disk.available);
         let p = if disk.total > 0 {
             (used as f64 / disk.total as f64 * 100.0) as f32
         } else {
             0.0
         };
-        render_usage_bar(f, layout[i], disk.mount_point.clone(), p);
+        let label = match app.host().disk_trends.eta(&disk.mount_point, disk.available as f64) {
+            Some(eta) => trf("{} full in {}", &[&disk.mount_point, &format_eta(eta)]),
+            None => disk.mount_point.clone(),
+        };
+        match disk.temperature {
+            Some(temp) => render_hot_bar(f, *row, label, p, temp, DRIVE_TEMP_HOT),
+            None => render_usage_bar(f, *row, label, p),
+        }
     }
 }
 
+/// Past this, drives throttle (NVMe) or wear faster (SATA hard disks).
+const DRIVE_TEMP_HOT: f32 = 70.0;
+
 fn draw_network(f: &mut Frame, area: Rect, app: &App) {
-    let block = make_block(" Network ");
+    let block = make_block(tr(" Network "));
     let inner = block.inner(area);
     f.render_widget(block, area);
 
@@ -236,70 +1678,251 @@ fn draw_network(f: &mut Frame, area: Rect, app: &App) {
         .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
         .split(inner);
 
-    let rx_data: Vec<u64> = app.net_rx_history.iter().map(|&x| x).collect();
-    let tx_data: Vec<u64> = app.net_tx_history.iter().map(|&x| x).collect();
+    let width = inner.width as usize;
+    let rx_data = app.host().net_rx_history.last(width);
+    let tx_data = app.host().net_tx_history.last(width);
 
     let rx_spark = Sparkline::default()
         .block(
             Block::default()
                 .title(format!("RX: {}/s", format_bytes(app.sys().rx_rate)))
-                .title_style(Style::default().fg(COLOR_ACCENT)),
+                .title_style(Style::default().fg(colors().accent)),
         )
         .data(&rx_data)
-        .style(Style::default().fg(COLOR_ACCENT));
+        .style(Style::default().fg(colors().accent));
 
     let tx_spark = Sparkline::default()
         .block(
             Block::default()
                 .title(format!("TX: {}/s", format_bytes(app.sys().tx_rate)))
-                .title_style(Style::default().fg(COLOR_HIGH)),
+                .title_style(Style::default().fg(colors().high)),
         )
         .data(&tx_data)
-        .style(Style::default().fg(COLOR_HIGH));
+        .style(Style::default().fg(colors().high));
 
     f.render_widget(rx_spark, chunks[0]);
     f.render_widget(tx_spark, chunks[1]);
 }
 
-fn draw_processes(f: &mut Frame, area: Rect, app: &mut App) {
-    let block = make_block(" Processes ");
+/// Per GPU a line with its name, temperature and power draw, its
+/// utilization and VRAM bars, then the processes using it as far as they
+/// fit, most VRAM first.
+fn draw_gpu(f: &mut Frame, area: Rect, app: &mut App) {
+    app.panel_areas.push((Panel::Gpu, area));
+    let count = app.gpu_pids().len();
+    app.gpu_selected = app.gpu_selected.min(count.saturating_sub(1));
+    let focused = app.panel == Panel::Gpu;
+    let block = panel_block(make_block(tr(" GPU ")), focused);
+    let inner = block.inner(area);
+    f.render_widget(block, area);
 
-    let query = app.search_query.to_lowercase();
-    let mut procs: Vec<&ProcessInfo> = app
-        .sys()
-        .processes()
-        .iter()
-        .filter(|p| p.name.to_lowercase().contains(&query) || p.pid.to_string().contains(&query))
-        .collect();
+    let sys = app.sys();
+    let gpus = &sys.gpus;
+    // Index of the first process of each GPU among all of them.
+    let mut first = 0;
+    let slices = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(vec![Constraint::Ratio(1, gpus.len() as u32); gpus.len()])
+        .split(inner);
+    for (gpu, slice) in gpus.iter().zip(slices.iter()) {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![Constraint::Length(1); slice.height as usize])
+            .split(*slice);
+        let mut info = gpu.name.clone();
+        if let Some(temp) = gpu.temperature {
+            info.push_str(&format!("  {:.0}°C", temp));
+        }
+        if let Some(power) = gpu.power {
+            info.push_str(&format!("  {:.0} W", power));
+        }
+        let text = Style::default().fg(colors().text);
+        if let Some(row) = rows.first() {
+            f.render_widget(Paragraph::new(Line::styled(info, text)), *row);
+        }
+        if let (Some(row), Some(busy)) = (rows.get(1), gpu.utilization) {
+            render_usage_bar(f, *row, "GPU".into(), busy);
+        }
+        if let (Some(row), Some(vram)) = (rows.get(2), gpu.mem_percent()) {
+            let label = format!(
+                "VRAM {}/{}",
+                format_bytes(gpu.mem_used),
+                format_bytes(gpu.mem_total)
+            );
+            render_usage_bar(f, *row, label, vram);
+        }
+        for (i, (process, row)) in gpu.processes.iter().zip(rows.iter().skip(3)).enumerate() {
+            // Processes in another PID namespace, e.g. a container's.
+            let name = sys
+                .processes()
+                .iter()
+                .find(|p| p.pid == process.pid)
+                .map_or("?", |p| &p.name);
+            let mem = process.mem.map(format_bytes).unwrap_or_default();
+            let width = (row.width as usize).saturating_sub(17);
+            let line = format!("{:>7} {:<width$.width$} {:>8}", process.pid, name, mem);
+            let style = if focused && first + i == app.gpu_selected {
+                text.add_modifier(Modifier::REVERSED)
+            } else {
+                text
+            };
+            f.render_widget(Paragraph::new(Line::styled(line, style)), *row);
+        }
+        first += gpu.processes.len();
+    }
+}
 
-    procs.sort_by(|a, b| {
-        let ord = match app.sort_col {
-            SortColumn::Pid => a.pid.cmp(&b.pid),
-            SortColumn::Name => a.name.cmp(&b.name),
-            SortColumn::User => a.user.cmp(&b.user),
-            SortColumn::Cpu => a
-                .cpu
-                .partial_cmp(&b.cpu)
-                .unwrap_or(std::cmp::Ordering::Equal),
-            SortColumn::Mem => a.mem_bytes.cmp(&b.mem_bytes),
-        };
-        if app.sort_desc {
-            ord.reverse()
+fn show_sensors(app: &App) -> bool {
+    app.config.panels.sensors && sensor_rows(app.sys()) > 0
+}
+
+fn draw_sensors(f: &mut Frame, area: Rect, app: &mut App) {
+    app.panel_areas.push((Panel::Sensors, area));
+    let inner = make_block("").inner(area);
+    let width = inner.width as usize;
+    let sys = app.sys();
+    let text = Style::default().fg(colors().text);
+    let mut lines = Vec::new();
+    if let Some(watts) = sys.cpu_power {
+        lines.push(Line::from(vec![
+            Span::styled("CPU", text.add_modifier(Modifier::BOLD)),
+            Span::styled(format!("  {:.1} W", watts), text),
+        ]));
+        let history = app.host().cpu_power_history.last(width);
+        lines.push(Line::styled(
+            spark_text(&history),
+            Style::default().fg(colors().accent),
+        ));
+    }
+    if let Some(bat) = &sys.battery {
+        let state = if bat.charging {
+            tr("charging")
         } else {
-            ord
+            tr("discharging")
+        };
+        lines.push(Line::from(vec![
+            Span::styled(tr("Battery"), text.add_modifier(Modifier::BOLD)),
+            Span::styled(format!("  {:.0}%  {}", bat.percent, state), text),
+        ]));
+        let mut rate = Vec::new();
+        if let Some(power) = bat.power {
+            rate.push(format!("{:.1} W", power));
         }
-    });
+        if let Some(secs) = bat.time_left {
+            let time = format!("{}h {:02}m", secs / 3600, secs % 3600 / 60);
+            rate.push(if bat.charging {
+                trf("{} to full", &[&time])
+            } else {
+                trf("{} left", &[&time])
+            });
+        }
+        let mut wear = Vec::new();
+        if let Some(health) = bat.health {
+            wear.push(trf("health {}%", &[&format!("{:.0}", health)]));
+        }
+        if let Some(cycles) = bat.cycle_count {
+            wear.push(trf("{} cycles", &[&cycles]));
+        }
+        if let Some(temp) = bat.temperature {
+            wear.push(format!("{:.0}°C", temp));
+        }
+        for details in [rate, wear] {
+            if !details.is_empty() {
+                lines.push(Line::styled(format!("  {}", details.join("  ")), text));
+            }
+        }
+    }
+    if !sys.fans.is_empty() {
+        lines.push(Line::styled(tr("Fans"), text.add_modifier(Modifier::BOLD)));
+        let label_width = sys.fans.iter().map(|fan| fan.label.chars().count()).max().unwrap_or(0);
+        for fan in &sys.fans {
+            // A stopped fan may be one that failed, or one idling on purpose.
+            let style = if fan.rpm == 0 {
+                Style::default().fg(Color::DarkGray)
+            } else {
+                text
+            };
+            lines.push(Line::styled(
+                format!("  {:<label_width$}  {:>5} RPM", fan.label, fan.rpm),
+                style,
+            ));
+        }
+    }
+    let (offset, range) = scroll_window(lines.len(), inner.height as usize, app.sensors_scroll);
+    app.sensors_scroll = offset;
 
-    let rows: Vec<Row> = procs
+    let title = trf(" Sensors{} ", &[&range]);
+    let block = panel_block(make_block(&title), app.panel == Panel::Sensors);
+    let paragraph = Paragraph::new(lines).block(block).scroll((offset as u16, 0));
+    f.render_widget(paragraph, area);
+}
+
+fn draw_processes(f: &mut Frame, area: Rect, app: &mut App) {
+    app.panel_areas.push((Panel::Processes, area));
+    let title = match (app.tree_view, app.sys().restricted) {
+        (true, false) => tr(" Processes (tree) "),
+        (false, false) => tr(" Processes "),
+        (true, true) => tr(" Own processes (tree) "),
+        (false, true) => tr(" Own processes "),
+    };
+    let mut title = title.to_string();
+    if app.aggregating() {
+        title.push_str(tr("(with children) "));
+    }
+    if !app.search_query.is_empty() {
+        title.push_str(&trf("(filter: {}) ", &[&app.search_query]));
+    }
+    let shown = app.visible_processes().len();
+    if app.matched_processes() > shown {
+        title.push_str(&trf(
+            "(top {} of {}, A shows all) ",
+            &[&shown, &app.matched_processes()],
+        ));
+    }
+    let block = make_block(&title);
+    if app.is_dimmed() && app.screensaver.blank_processes {
+        f.render_widget(block, area);
+        return;
+    }
+
+    // Borders and the header.
+    let window = app.process_window(area.height.saturating_sub(3) as usize);
+    let mut state = TableState::default().with_selected(
+        app.table_state
+            .selected()
+            .map(|i| i.saturating_sub(window.start)),
+    );
+    let procs = app.visible_processes();
+    let depths = app.visible_depths();
+    let watchdog = &app.alerts.watchdog;
+    let limits = &app.sys().limits;
+
+    let rows: Vec<Row> = procs[window.clone()]
         .iter()
-        .map(|p| {
+        .zip(window.clone())
+        .map(|(p, i)| {
+            let (cpu, mem) = app.usage(p);
+            let indent = match depths.get(i) {
+                Some(&depth) if depth > 0 => format!("{}└ ", "  ".repeat(depth - 1)),
+                _ => String::new(),
+            };
+            let limit = match limits.get(&p.pid) {
+                Some(limit) => format!(" ≤{}%", limit),
+                None => String::new(),
+            };
+            let name = if watchdog.is_watched(p) {
+                Cell::from(format!("{}*{}{}", indent, p.name, limit))
+                    .style(Style::default().fg(colors().warn))
+            } else {
+                Cell::from(format!("{}{}{}", indent, p.name, limit))
+            };
             Row::new(vec![
                 Cell::from(p.pid.to_string()),
-                Cell::from(p.name.clone()),
+                name,
                 Cell::from(p.cmd.chars().take(20).collect::<String>()),
-                Cell::from(p.user.clone()),
-                Cell::from(format_bytes(p.mem_bytes)),
-                Cell::from(format!("{:.1}", p.cpu)),
+                Cell::from(p.user.to_string()),
+                Cell::from(format_bytes(mem)),
+                Cell::from(format!("{:.1}", cpu)),
             ])
         })
         .collect();
@@ -321,11 +1944,11 @@ fn draw_processes(f: &mut Frame, area: Rect, app: &mut App) {
         ],
     )
     .header(
-        Row::new(vec!["PID", "Prog", "Command", "User", "MemB", "Cpu%"])
+        Row::new(vec!["PID", "Prog", tr("Command"), tr("User"), "MemB", "Cpu%"])
             .style(
                 Style::default()
-                    .bg(COLOR_HEADER_BG)
-                    .fg(COLOR_HEADER_FG)
+                    .bg(colors().header_bg)
+                    .fg(colors().header_fg)
                     .add_modifier(Modifier::BOLD),
             )
             .bottom_margin(0),
@@ -333,7 +1956,7 @@ fn draw_processes(f: &mut Frame, area: Rect, app: &mut App) {
     .block(block)
     .highlight_style(highlight_style);
 
-    f.render_stateful_widget(table, area, &mut app.table_state);
+    f.render_stateful_widget(table, area, &mut state);
 }
 
 fn render_usage_bar(f: &mut Frame, area: Rect, label: String, percent: f32) {
@@ -344,9 +1967,9 @@ fn render_usage_bar(f: &mut Frame, area: Rect, label: String, percent: f32) {
         .gauge_style(
             Style::default()
                 .fg(if percent > 80.0 {
-                    COLOR_HIGH
+                    colors().high
                 } else {
-                    COLOR_ACCENT
+                    colors().accent
                 })
                 .bg(Color::DarkGray),
         )
@@ -357,16 +1980,378 @@ fn render_usage_bar(f: &mut Frame, area: Rect, label: String, percent: f32) {
     f.render_widget(gauge, area);
 }
 
+/// `data` as a one-line sparkline of block characters, scaled to its own
+/// peak; zero is blank so idle stretches stand out.
+fn spark_text(data: &[u64]) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let max = data.iter().copied().max().unwrap_or(0).max(1);
+    data.iter()
+        .map(|&v| match v {
+            0 => ' ',
+            v => BARS[((v * 8).div_ceil(max) as usize).clamp(1, 8) - 1],
+        })
+        .collect()
+}
+
 fn make_block(title: &str) -> Block<'_> {
     Block::default()
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(COLOR_BORDER))
+        .border_style(Style::default().fg(colors().border))
         .title(Span::styled(
             format!(" {} ", title),
             Style::default()
-                .bg(COLOR_HEADER_BG)
-                .fg(COLOR_HEADER_FG)
+                .bg(colors().header_bg)
+                .fg(colors().header_fg)
                 .add_modifier(Modifier::BOLD),
         ))
-        .style(Style::default().bg(COLOR_BG))
+        .style(Style::default().bg(colors().background))
+}
+
+/// A panel block whose border turns red while one of its alerts is active.
+fn alert_block(title: &str, firing: bool) -> Block<'_> {
+    let block = make_block(title);
+    if firing {
+        block.border_style(Style::default().fg(colors().high).add_modifier(Modifier::BOLD))
+    } else {
+        block
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::source::{MockSource, SystemSource};
+    use crate::sys::{ProcessInfo, SysCache};
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use ratatui::{backend::TestBackend, Terminal};
+
+    fn proc(pid: u32, name: &str, cpu: f32) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            name: name.into(),
+            user: "alice".into(),
+            cmd: "".into(),
+            cpu,
+            mem_bytes: 1024,
+            parent: None,
+        }
+    }
+
+    fn render(app: &mut App) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(120, 40)).unwrap();
+        terminal.draw(|f| draw(f, app)).unwrap();
+        terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|c| c.symbol())
+            .collect()
+    }
+
+    #[test]
+    fn test_draw_processes_in_sort_order() {
+        let snapshot = SysCache::default().with_processes(vec![
+            proc(10, "idle-daemon", 0.5),
+            proc(11, "busy-worker", 75.0),
+        ]);
+        let mut app = App::new(Config::default());
+        app.on_refresh(MockSource::new([snapshot]).collect());
+
+        let screen = render(&mut app);
+        let busy = screen.find("busy-worker").unwrap();
+        let idle = screen.find("idle-daemon").unwrap();
+        assert!(busy < idle);
+        assert!(screen.contains("alice"));
+    }
+
+    #[test]
+    fn test_scroll_cores() {
+        let mut snapshot = SysCache::default();
+        snapshot.cpu_cores = (0..64).map(|i| i as f32).collect();
+        let mut app = App::new(Config::default());
+        app.on_refresh(MockSource::new([snapshot]).collect());
+
+        let screen = render(&mut app);
+        assert!(screen.contains("Cores 1-10/64 ▼"));
+        assert!(!screen.contains("C10 "));
+
+        app.cores_scroll = 100;
+        let screen = render(&mut app);
+        assert!(screen.contains("Cores 55-64/64 ▲"));
+        assert!(screen.contains("C63 "));
+        assert_eq!(app.cores_scroll, 54);
+    }
+
+    #[test]
+    fn test_drive_temps() {
+        use crate::sys::DiskInfo;
+
+        let disk = |mount: &str, temperature| DiskInfo {
+            _name: String::new(),
+            mount_point: mount.into(),
+            total: 100,
+            available: 40,
+            temperature,
+        };
+        let snapshot =
+            SysCache::default().with_disks(vec![disk("/", Some(41.8)), disk("/mnt/nfs", None)]);
+        let mut app = App::new(Config::default());
+        app.on_refresh(MockSource::new([snapshot]).collect());
+
+        let screen = render(&mut app);
+        assert!(screen.contains("/ 60.0%"));
+        assert!(screen.contains("/mnt/nfs 60.0%"));
+        assert!(screen.contains("  42°C"));
+        assert_eq!(screen.matches("°C").count(), 1);
+    }
+
+    #[test]
+    fn test_core_temps() {
+        let mut snapshot = SysCache::default();
+        snapshot.cpu_cores = vec![10.0, 20.0, 30.0];
+        snapshot.core_temps = vec![Some(48.4), None, Some(93.0)];
+        let mut app = App::new(Config::default());
+        app.on_refresh(MockSource::new([snapshot]).collect());
+
+        let screen = render(&mut app);
+        assert!(screen.contains("C0 10.0%"));
+        assert!(screen.contains("  48°C"));
+        assert!(screen.contains("  93°C"));
+        // C1 has no reading.
+        assert_eq!(screen.matches("°C").count(), 2);
+    }
+
+    #[test]
+    fn test_container_sparklines() {
+        use crate::containers::{ContainerInfo, Containers};
+
+        let mut app = App::new(Config::default());
+        for rx in [0, 100, 800] {
+            app.on_containers(Containers {
+                host: "local".into(),
+                list: vec![ContainerInfo {
+                    id: "c1".into(),
+                    name: "web".into(),
+                    rx_rate: rx,
+                    ..ContainerInfo::default()
+                }],
+                error: None,
+                notice: None,
+            });
+        }
+        app.popup = Some(Popup::Containers);
+
+        let screen = render(&mut app);
+        assert!(screen.contains("Disk r/w"));
+        assert!(screen.contains("s stop, k kill"));
+        assert!(screen.contains(&format!("{}▁█", " ".repeat(RATE_HISTORY - 2))));
+        assert_eq!(spark_text(&[0, 1, 4, 8]), " ▁▄█");
+    }
+
+    #[test]
+    fn test_swap_activity() {
+        let mut snapshot = SysCache::default();
+        snapshot.total_mem = 8 << 30;
+        snapshot.total_swap = 2 << 30;
+        snapshot.used_swap = 1 << 30;
+        snapshot.swap_rates = Some((12, 340));
+        let mut app = App::new(Config::default());
+        app.on_refresh(MockSource::new([snapshot]).collect());
+
+        let screen = render(&mut app);
+        assert!(screen.contains("Swap 50.0%"));
+        assert!(screen.contains("Swap in 12 out 340 pages/s"));
+        assert_eq!(app.host().swap_history.latest(), 352);
+    }
+
+    #[test]
+    fn test_gpu_panel() {
+        use crate::sys::gpu::{GpuInfo, GpuProcess};
+
+        let mut app = App::new(Config::default());
+        app.on_refresh(MockSource::new([SysCache::default()]).collect());
+        assert!(!render(&mut app).contains(" GPU "));
+
+        let mut snapshot = SysCache::default().with_processes(vec![proc(42, "blender", 80.0)]);
+        snapshot.gpus = vec![GpuInfo {
+            name: "RTX 4070".into(),
+            utilization: Some(42.0),
+            mem_used: 3 << 30,
+            mem_total: 12 << 30,
+            temperature: Some(61.0),
+            power: Some(95.4),
+            processes: vec![
+                GpuProcess {
+                    pid: 42,
+                    mem: Some(2 << 30),
+                },
+                GpuProcess { pid: 99, mem: None },
+            ],
+        }];
+        app.on_refresh(MockSource::new([snapshot]).collect());
+        let screen = render(&mut app);
+        assert!(screen.contains("RTX 4070  61°C  95 W"));
+        assert!(screen.contains("GPU 42.0%"));
+        assert!(screen.contains("VRAM 3.0G/12.0G 25.0%"));
+
+        // Room for the processes once the other panels are hidden.
+        app.config.panels.memory = false;
+        app.config.panels.disks = false;
+        app.config.panels.network = false;
+        let screen = render(&mut app);
+        assert!(screen.contains("     42 blender "));
+        assert!(screen.contains("     99 ? "));
+        let row = screen.find("     42 blender").unwrap();
+        assert!(screen[row..].contains("2.0G"));
+    }
+
+    #[test]
+    fn test_pods_popup() {
+        use crate::cgroups::CgroupInfo;
+
+        let mut snapshot = SysCache::default().with_processes(vec![proc(7, "nginx", 3.0)]);
+        let pod = "kubepods.slice/kubepods-besteffort.slice/kubepods-besteffort-pod1f2e.slice";
+        snapshot.cgroups = vec![
+            CgroupInfo {
+                path: pod.into(),
+                cpu: 3.0,
+                mem_bytes: 64 << 20,
+                pids: Vec::new(),
+            },
+            CgroupInfo {
+                path: format!("{}/cri-containerd-0123456789abcdef.scope", pod),
+                cpu: 3.0,
+                mem_bytes: 64 << 20,
+                pids: vec![7],
+            },
+        ];
+        let mut app = App::new(Config::default());
+        app.on_refresh(MockSource::new([snapshot]).collect());
+        app.popup = Some(Popup::Pods);
+        let screen = render(&mut app);
+        assert!(screen.contains("pod 1f2e"));
+        assert!(screen.contains("besteffort"));
+        assert!(screen.contains("  0123456789ab"));
+        assert!(screen.contains("nginx"));
+    }
+
+    #[test]
+    fn test_sensors_panel() {
+        use crate::sys::BatteryInfo;
+
+        let mut app = App::new(Config::default());
+        app.on_refresh(MockSource::new([SysCache::default()]).collect());
+        assert!(!render(&mut app).contains(" Sensors "));
+
+        let mut snapshot = SysCache::default();
+        snapshot.battery = Some(BatteryInfo {
+            percent: 64.6,
+            charging: false,
+            cycle_count: Some(212),
+            health: Some(80.0),
+            temperature: None,
+            power: Some(9.54),
+            time_left: Some(2 * 3600 + 5 * 60),
+        });
+        app.on_refresh(MockSource::new([snapshot]).collect());
+        let screen = render(&mut app);
+        assert!(screen.contains(" Sensors "));
+        assert!(screen.contains("Battery  65%  discharging"));
+        assert!(screen.contains("9.5 W  2h 05m left"));
+        assert!(screen.contains("health 80%  212 cycles"));
+
+        app.config.panels.sensors = false;
+        assert!(!render(&mut app).contains(" Sensors "));
+    }
+
+    #[test]
+    fn test_fans() {
+        use crate::sys::FanInfo;
+
+        let mut sys = SysCache::default();
+        sys.fans = vec![
+            FanInfo {
+                label: "CPU Fan".into(),
+                rpm: 1210,
+            },
+            FanInfo {
+                label: "nct6798 fan3".into(),
+                rpm: 0,
+            },
+        ];
+        let mut app = App::new(Config::default());
+        app.on_refresh(MockSource::new([sys]).collect());
+        let screen = render(&mut app);
+        assert!(screen.contains(" Sensors "));
+        assert!(screen.contains("CPU Fan        1210 RPM"));
+        assert!(screen.contains("nct6798 fan3      0 RPM"));
+    }
+
+    #[test]
+    fn test_scroll_sensors() {
+        use crate::sys::FanInfo;
+
+        let mut sys = SysCache::default();
+        sys.fans = (0..40)
+            .map(|i| FanInfo {
+                label: format!("fan{}", i),
+                rpm: 1000 + i,
+            })
+            .collect();
+        let mut app = App::new(Config::default());
+        app.on_refresh(MockSource::new([sys]).collect());
+        let screen = render(&mut app);
+        assert!(screen.contains("Sensors 1-"));
+        assert!(screen.contains("/41 ▼"));
+        assert!(!screen.contains("fan39"));
+
+        app.sensors_scroll = 100;
+        let screen = render(&mut app);
+        assert!(screen.contains("/41 ▲"));
+        assert!(screen.contains("fan39   1039 RPM"));
+        assert!(!screen.contains("Fans"));
+    }
+
+    #[test]
+    fn test_cpu_power() {
+        let mut app = App::new(Config::default());
+        for watts in [12.0, 48.2] {
+            let mut sys = SysCache::default();
+            sys.cpu_power = Some(watts);
+            app.on_refresh(MockSource::new([sys]).collect());
+        }
+        assert_eq!(app.host().cpu_power_history.last(2), [12, 48]);
+        assert!(render(&mut app).contains("CPU  48.2 W"));
+    }
+
+    #[test]
+    fn test_hidden_panels() {
+        let mut config = Config::default();
+        config.panels.cpu = false;
+        config.panels.network = false;
+        let mut app = App::new(config);
+        app.on_refresh(MockSource::new([SysCache::default()]).collect());
+
+        let screen = render(&mut app);
+        assert!(!screen.contains("CPU History"));
+        assert!(!screen.contains("Network"));
+        assert!(screen.contains("Memory") && screen.contains("Processes"));
+    }
+
+    #[test]
+    fn test_help_shows_bindings() {
+        let config = Config {
+            keys: toml::from_str("kill = \"x\"").unwrap(),
+            ..Config::default()
+        };
+        let mut app = App::new(config);
+        app.on_key(KeyEvent::new(KeyCode::Char('?'), KeyModifiers::empty()));
+        assert_eq!(app.popup, Some(Popup::Help));
+
+        let screen = render(&mut app);
+        assert!(screen.contains("│x                  Kill the process"));
+        assert!(screen.contains("q, Q, F10, Ctrl+c"));
+    }
 }
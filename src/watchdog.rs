@@ -0,0 +1,289 @@
+//! Process watchdog: alerts when a watched process exits, restarts under a new
+//! PID, or stays above a CPU/memory limit. Watches come from `[[watch]]`
+//! entries in `alerts.toml` or are toggled at runtime with `w`:
+//!
+//! ```toml
+//! [[watch]]
+//! name = "nginx"     # or pid = 1234
+//! cpu = 80           # percent, optional
+//! mem = 512          # MiB, optional
+//! for = 10           # seconds over a limit before alerting
+//! ```
+
+use crate::alerts::{ActiveAlert, AlertEvent, AlertKind, Hold, Severity};
+use crate::sys::ProcessInfo;
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum WatchTarget {
+    Name(String),
+    Pid(u32),
+}
+
+#[derive(Deserialize)]
+pub(crate) struct RawWatch {
+    name: Option<String>,
+    pid: Option<u32>,
+    cpu: Option<f32>,
+    mem: Option<u64>,
+    #[serde(rename = "for", default)]
+    duration: u64,
+}
+
+pub struct Watch {
+    pub target: WatchTarget,
+    pub cpu: Option<f32>,
+    pub mem_bytes: Option<u64>,
+    pub duration: Duration,
+    pids: Vec<u32>,
+    seen: bool,
+    exited: bool,
+    cpu_limit: Hold,
+    mem_limit: Hold,
+}
+
+impl TryFrom<RawWatch> for Watch {
+    type Error = String;
+
+    fn try_from(raw: RawWatch) -> Result<Self, String> {
+        let target = match (raw.name, raw.pid) {
+            (Some(name), None) => WatchTarget::Name(name),
+            (None, Some(pid)) => WatchTarget::Pid(pid),
+            _ => return Err("a watch needs exactly one of name or pid".into()),
+        };
+        let mut watch = Watch::new(target);
+        watch.cpu = raw.cpu;
+        watch.mem_bytes = raw.mem.map(|mib| mib * 1024 * 1024);
+        watch.duration = Duration::from_secs(raw.duration);
+        Ok(watch)
+    }
+}
+
+impl Watch {
+    pub fn new(target: WatchTarget) -> Self {
+        Self {
+            target,
+            cpu: None,
+            mem_bytes: None,
+            duration: Duration::ZERO,
+            pids: Vec::new(),
+            seen: false,
+            exited: false,
+            cpu_limit: Hold::default(),
+            mem_limit: Hold::default(),
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match &self.target {
+            WatchTarget::Name(name) => format!("proc:{}", name),
+            WatchTarget::Pid(pid) => format!("pid:{}", pid),
+        }
+    }
+
+    fn matches(&self, p: &ProcessInfo) -> bool {
+        match &self.target {
+            WatchTarget::Name(name) => *p.name == **name,
+            WatchTarget::Pid(pid) => p.pid == *pid,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Watchdog {
+    pub watches: Vec<Watch>,
+}
+
+impl Watchdog {
+    pub fn is_watched(&self, p: &ProcessInfo) -> bool {
+        self.watches.iter().any(|w| w.matches(p))
+    }
+
+    /// Starts watching `p` by name (so restarts are caught), or stops if it
+    /// is already watched. Returns whether it is watched afterwards.
+    pub fn toggle(&mut self, p: &ProcessInfo) -> bool {
+        let before = self.watches.len();
+        self.watches.retain(|w| !w.matches(p));
+        if self.watches.len() < before {
+            return false;
+        }
+        self.watches
+            .push(Watch::new(WatchTarget::Name(p.name.to_string())));
+        true
+    }
+
+    pub fn active(&self) -> Vec<ActiveAlert> {
+        let mut out = Vec::new();
+        for w in &self.watches {
+            let label = w.label();
+            let conditions = [
+                (w.exited, "exited", Severity::Critical),
+                (w.cpu_limit.active, "cpu", Severity::Warning),
+                (w.mem_limit.active, "mem", Severity::Warning),
+            ];
+            for (active, what, severity) in conditions {
+                if active {
+                    out.push(ActiveAlert {
+                        name: format!("{} {}", label, what),
+                        metric: label.clone(),
+                        severity,
+                        value: None,
+                        acked: false,
+                    });
+                }
+            }
+        }
+        out
+    }
+
+    pub fn evaluate(&mut self, procs: &[ProcessInfo], now: Instant) -> Vec<AlertEvent> {
+        let mut events = Vec::new();
+        for w in self.watches.iter_mut() {
+            let matching: Vec<&ProcessInfo> = procs.iter().filter(|p| w.matches(p)).collect();
+            let mut pids: Vec<u32> = matching.iter().map(|p| p.pid).collect();
+            pids.sort_unstable();
+            let label = w.label();
+            let event = |kind, severity, what: &str, value: f64, threshold: f64| AlertEvent {
+                rule: format!("{} {}", label, what),
+                metric: label.clone(),
+                value,
+                threshold,
+                severity,
+                kind,
+                at: chrono::Local::now(),
+            };
+
+            if pids.is_empty() {
+                if w.seen && !w.exited {
+                    w.exited = true;
+                    events.push(event(
+                        AlertKind::Fired,
+                        Severity::Critical,
+                        "exited",
+                        0.0,
+                        0.0,
+                    ));
+                }
+            } else {
+                let restarted = w.seen && !w.pids.iter().any(|p| pids.contains(p));
+                if w.exited {
+                    w.exited = false;
+                    events.push(event(
+                        AlertKind::Resolved,
+                        Severity::Critical,
+                        "exited",
+                        1.0,
+                        0.0,
+                    ));
+                }
+                if restarted {
+                    events.push(event(
+                        AlertKind::Fired,
+                        Severity::Warning,
+                        "restarted",
+                        pids[0] as f64,
+                        0.0,
+                    ));
+                }
+                w.seen = true;
+                w.pids = pids;
+            }
+
+            let cpu: f32 = matching.iter().map(|p| p.cpu).sum();
+            let mem: u64 = matching.iter().map(|p| p.mem_bytes).sum();
+            let duration = w.duration;
+            if let Some(limit) = w.cpu {
+                if let Some(kind) = w.cpu_limit.update(cpu > limit, now, duration) {
+                    events.push(event(
+                        kind,
+                        Severity::Warning,
+                        "cpu",
+                        cpu as f64,
+                        limit as f64,
+                    ));
+                }
+            }
+            if let Some(limit) = w.mem_bytes {
+                if let Some(kind) = w.mem_limit.update(mem > limit, now, duration) {
+                    events.push(event(
+                        kind,
+                        Severity::Warning,
+                        "mem",
+                        mem as f64,
+                        limit as f64,
+                    ));
+                }
+            }
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proc(pid: u32, name: &str, cpu: f32) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            name: name.into(),
+            user: "root".into(),
+            cmd: "".into(),
+            cpu,
+            mem_bytes: 1024,
+            parent: None,
+        }
+    }
+
+    #[test]
+    fn test_exit_and_restart() {
+        let mut dog = Watchdog::default();
+        assert!(dog.toggle(&proc(10, "nginx", 0.0)));
+        let t0 = Instant::now();
+
+        assert!(dog.evaluate(&[proc(10, "nginx", 0.0)], t0).is_empty());
+        let exited = dog.evaluate(&[], t0);
+        assert_eq!(exited[0].rule, "proc:nginx exited");
+        assert_eq!(exited[0].kind, AlertKind::Fired);
+        assert_eq!(dog.active().len(), 1);
+
+        let back = dog.evaluate(&[proc(11, "nginx", 0.0)], t0);
+        assert_eq!(back[0].kind, AlertKind::Resolved);
+        assert_eq!(back[1].rule, "proc:nginx restarted");
+        assert!(dog.active().is_empty());
+    }
+
+    #[test]
+    fn test_cpu_limit_for_duration() {
+        let mut watch = Watch::new(WatchTarget::Pid(7));
+        watch.cpu = Some(50.0);
+        watch.duration = Duration::from_secs(5);
+        let mut dog = Watchdog {
+            watches: vec![watch],
+        };
+        let t0 = Instant::now();
+        assert!(dog.evaluate(&[proc(7, "job", 90.0)], t0).is_empty());
+        let fired = dog.evaluate(&[proc(7, "job", 90.0)], t0 + Duration::from_secs(5));
+        assert_eq!(fired[0].rule, "pid:7 cpu");
+        let resolved = dog.evaluate(&[proc(7, "job", 1.0)], t0 + Duration::from_secs(6));
+        assert_eq!(resolved[0].kind, AlertKind::Resolved);
+    }
+
+    #[test]
+    fn test_toggle_and_parse() {
+        let mut dog = Watchdog::default();
+        let p = proc(1, "sshd", 0.0);
+        assert!(dog.toggle(&p));
+        assert!(dog.is_watched(&p));
+        assert!(!dog.toggle(&p));
+        assert!(!dog.is_watched(&p));
+
+        let raw: RawWatch = toml::from_str("pid = 5\nmem = 2\nfor = 3").unwrap();
+        let watch = Watch::try_from(raw).unwrap();
+        assert_eq!(watch.target, WatchTarget::Pid(5));
+        assert_eq!(watch.mem_bytes, Some(2 * 1024 * 1024));
+        let raw: RawWatch = toml::from_str("name = \"a\"\npid = 5").unwrap();
+        assert!(Watch::try_from(raw).is_err());
+    }
+}
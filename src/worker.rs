@@ -0,0 +1,220 @@
+//! Background collector: refreshes system data in its own task, with the
+//! blocking sysinfo calls on tokio's blocking pool, so slow process
+//! enumeration never stalls drawing or key handling.
+
+use crate::source::SystemSource;
+use crate::sys::{Collector, ProcessDetail, RefreshIntervals, SysCache};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task;
+use tokio::time;
+
+enum Request {
+    Kill(u32),
+    Renice(u32, i32),
+    Limit(u32, Option<u32>),
+    Interval(Duration),
+    Focus(Option<u32>),
+    Cgroups(bool),
+    Detail(u32, oneshot::Sender<Option<ProcessDetail>>),
+}
+
+/// Handle to the collector task; dropping it stops the task after its
+/// current refresh.
+pub struct Worker {
+    tx: mpsc::UnboundedSender<Request>,
+}
+
+impl Worker {
+    /// Refreshes every `interval`, slower subsystems as `intervals` says, and
+    /// passes each snapshot to `deliver`. The task exits once `deliver`
+    /// returns false or the handle is dropped. Must be called from within a
+    /// tokio runtime.
+    pub fn spawn<F>(interval: Duration, intervals: RefreshIntervals, deliver: F) -> Self
+    where
+        F: FnMut(SysCache) -> bool + Send + 'static,
+    {
+        Self::spawn_with(
+            move || Collector::with_intervals(intervals, interval),
+            interval,
+            deliver,
+        )
+    }
+
+    /// Like [`spawn`](Self::spawn) for any source; `make` runs on the blocking
+    /// pool, so an expensive setup doesn't delay the first frame.
+    pub fn spawn_with<S, M, F>(make: M, mut interval: Duration, mut deliver: F) -> Self
+    where
+        S: SystemSource + Send + 'static,
+        M: FnOnce() -> S + Send + 'static,
+        F: FnMut(SysCache) -> bool + Send + 'static,
+    {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let Ok(mut source) = task::spawn_blocking(make).await else {
+                return;
+            };
+            loop {
+                let Some((back, snapshot)) = blocking(source, |s| s.collect()).await else {
+                    return;
+                };
+                source = back;
+                if !deliver(snapshot) {
+                    return;
+                }
+                let next = time::sleep(interval);
+                tokio::pin!(next);
+                loop {
+                    tokio::select! {
+                        _ = &mut next => break,
+                        request = rx.recv() => match request {
+                            // A remote source waits for the agent's answer,
+                            // so actions also run on the blocking pool.
+                            Some(Request::Kill(pid)) => {
+                                let Some((back, ())) =
+                                    blocking(source, move |s| s.kill_process(pid)).await
+                                else {
+                                    return;
+                                };
+                                source = back;
+                            }
+                            Some(Request::Renice(pid, delta)) => {
+                                let Some((back, ())) =
+                                    blocking(source, move |s| s.renice_process(pid, delta)).await
+                                else {
+                                    return;
+                                };
+                                source = back;
+                            }
+                            Some(Request::Limit(pid, percent)) => {
+                                let Some((back, ())) =
+                                    blocking(source, move |s| s.limit_process(pid, percent)).await
+                                else {
+                                    return;
+                                };
+                                source = back;
+                            }
+                            Some(Request::Detail(pid, reply)) => {
+                                let Some((back, detail)) =
+                                    blocking(source, move |s| s.process_detail(pid)).await
+                                else {
+                                    return;
+                                };
+                                source = back;
+                                let _ = reply.send(detail);
+                            }
+                            Some(Request::Focus(pid)) => source.set_focus(pid),
+                            Some(Request::Cgroups(on)) => source.set_cgroups(on),
+                            Some(Request::Interval(new)) => {
+                                interval = new;
+                                source.set_tick(new);
+                                break;
+                            }
+                            None => return,
+                        },
+                    }
+                }
+            }
+        });
+        Self { tx }
+    }
+
+    pub fn kill_process(&self, pid: u32) {
+        let _ = self.tx.send(Request::Kill(pid));
+    }
+
+    pub fn renice_process(&self, pid: u32, delta: i32) {
+        let _ = self.tx.send(Request::Renice(pid, delta));
+    }
+
+    /// Caps `pid` at `percent` of one core; `None` lifts the cap.
+    pub fn limit_process(&self, pid: u32, percent: Option<u32>) {
+        let _ = self.tx.send(Request::Limit(pid, percent));
+    }
+
+    /// Follows `pid` closely from the next refresh on.
+    pub fn set_focus(&self, pid: Option<u32>) {
+        let _ = self.tx.send(Request::Focus(pid));
+    }
+
+    /// Reads the cgroup hierarchy from the next process refresh on, while
+    /// `on`.
+    pub fn set_cgroups(&self, on: bool) {
+        let _ = self.tx.send(Request::Cgroups(on));
+    }
+
+    /// Asks for the details of `pid`; the answer arrives on the returned
+    /// channel once the worker is between refreshes.
+    pub fn process_detail(&self, pid: u32) -> oneshot::Receiver<Option<ProcessDetail>> {
+        let (reply, rx) = oneshot::channel();
+        let _ = self.tx.send(Request::Detail(pid, reply));
+        rx
+    }
+
+    /// Changes the refresh interval, refreshing once right away.
+    pub fn set_interval(&self, interval: Duration) {
+        let _ = self.tx.send(Request::Interval(interval));
+    }
+}
+
+/// Runs `f` against the source on the blocking pool and hands the source
+/// back with the result; `None` means `f` panicked.
+pub(crate) async fn blocking<S, T, F>(mut source: S, f: F) -> Option<(S, T)>
+where
+    S: SystemSource + Send + 'static,
+    T: Send + 'static,
+    F: FnOnce(&mut S) -> T + Send + 'static,
+{
+    task::spawn_blocking(move || {
+        let value = f(&mut source);
+        (source, value)
+    })
+    .await
+    .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_kill_reaches_source() {
+        use crate::source::MockSource;
+        use crate::sys::ProcessInfo;
+
+        let proc = ProcessInfo {
+            pid: 42,
+            name: "job".into(),
+            user: "u".into(),
+            cmd: "".into(),
+            cpu: 0.0,
+            mem_bytes: 0,
+            parent: None,
+        };
+        let snapshot = SysCache::default().with_processes(vec![proc]);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let worker = Worker::spawn_with(
+            move || MockSource::new([snapshot]),
+            Duration::from_secs(3600),
+            move |sys| tx.send(sys.processes().len()).is_ok(),
+        );
+        let wait = Duration::from_secs(5);
+        assert_eq!(time::timeout(wait, rx.recv()).await.unwrap(), Some(1));
+        worker.kill_process(42);
+        worker.set_interval(Duration::from_secs(3600));
+        assert_eq!(time::timeout(wait, rx.recv()).await.unwrap(), Some(0));
+    }
+
+    #[tokio::test]
+    async fn test_delivers_snapshots_until_refused() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let intervals = RefreshIntervals::default();
+        let _worker = Worker::spawn(Duration::from_millis(10), intervals, move |sys| {
+            tx.send(sys.total_mem).is_ok()
+        });
+        let wait = Duration::from_secs(5);
+        assert!(time::timeout(wait, rx.recv()).await.unwrap().unwrap() > 0);
+        assert!(time::timeout(wait, rx.recv()).await.unwrap().is_some());
+        drop(rx);
+    }
+}